@@ -18,7 +18,10 @@ fn main() -> Result<(), impl std::error::Error> {
         },
     );
 
-    let mut count = 0;
+    let count = ctx.create_signal(0);
+    ctx.observe(count, |ctx, count| {
+        ctx.set_title(format!("Count = {count}"))
+    });
 
     let (border_default, shadow_default, first_frame_pad) =
         if let Ok(desktop) = std::env::var("XDG_CURRENT_DESKTOP") {
@@ -60,8 +63,8 @@ fn main() -> Result<(), impl std::error::Error> {
                         background_color: clr!(white),
                     },
                     children: [
-                        count_label = Label {
-                            text: "Count = 0",
+                        Label {
+                            text: signal(count, |count| format!("Count = {count}")),
                             style: TextStyle {
                                 color: clr!(risd_blue),
                                 font_size: 32.0,
@@ -71,11 +74,7 @@ fn main() -> Result<(), impl std::error::Error> {
                         },
                         Button {
                             text: "increment +1",
-                            on_click: move |ctx, _| {
-                                count += 1;
-                                ctx.set_label_text(count_label, format!("Count = {count}"));
-                                ctx.set_title(format!("Count = {count}"));
-                            },
+                            on_click: move |ctx, _| ctx.update_signal(count, |count| *count += 1),
                             style: TextStyle {
                                 font_size: 14.0,
                                 font_family: FamilyOwned::Name("Fantasque Sans Mono".into()),
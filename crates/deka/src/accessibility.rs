@@ -0,0 +1,175 @@
+//! Accessibility preferences the built-in components follow: less motion,
+//! more contrast and larger pointer targets.
+
+use heka::CapsuleRef;
+use heka::color::Color;
+use heka::sizing::{Enlargement, Padding, SizeSpec};
+
+use crate::Context;
+
+/// Width of the focus ring, and in high contrast
+const FOCUS_RING_WIDTH: u32 = 2;
+const HIGH_CONTRAST_FOCUS_RING_WIDTH: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccessibilityPrefs {
+    /// Transitions only there for the eye jump to their end, see
+    /// `Context::set_reduced_motion`
+    pub reduced_motion: bool,
+    /// Borders are drawn in the text color, and the focused element always
+    /// shows a thicker focus ring
+    pub high_contrast: bool,
+    /// Smallest width and height, in pixels, of the elements reacting to
+    /// the pointer. Smaller elements fitting their content get padding,
+    /// ones with a fixed size get a larger one, when laid out: their
+    /// styles are left as set.
+    pub min_target_size: Option<u32>,
+}
+
+impl AccessibilityPrefs {
+    /// Preferences set in the desktop, where they can be read: the GTK
+    /// settings on Linux. Defaults elsewhere.
+    pub fn from_system() -> Self {
+        let mut prefs = Self::default();
+
+        #[cfg(target_os = "linux")]
+        {
+            let config = std::env::var_os("XDG_CONFIG_HOME")
+                .map(std::path::PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config"))
+                });
+            let settings = config
+                .and_then(|config| {
+                    std::fs::read_to_string(config.join("gtk-3.0/settings.ini")).ok()
+                })
+                .unwrap_or_default();
+            let mut theme = std::env::var("GTK_THEME").unwrap_or_default();
+
+            for line in settings.lines() {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                match key.trim() {
+                    "gtk-enable-animations" => {
+                        prefs.reduced_motion = matches!(value.trim(), "false" | "0");
+                    }
+                    "gtk-theme-name" if theme.is_empty() => theme = value.trim().to_string(),
+                    _ => {}
+                }
+            }
+            prefs.high_contrast = theme.contains("HighContrast");
+        }
+
+        prefs
+    }
+}
+
+impl Context {
+    /// Makes the built-in components follow `prefs`
+    pub fn set_accessibility_prefs(&mut self, prefs: AccessibilityPrefs) {
+        self.set_reduced_motion(prefs.reduced_motion);
+        self.accessibility = prefs;
+        // Targets are enlarged (or given back their size) on layout
+        self.root_frame.set_dirty(&mut self.root);
+        self.request_redraw();
+    }
+
+    pub fn accessibility_prefs(&self) -> AccessibilityPrefs {
+        AccessibilityPrefs {
+            reduced_motion: self.reduced_motion(),
+            ..self.accessibility
+        }
+    }
+
+    /// Width of the ring around the focused element, 0 when it has none.
+    /// It shows after moving the focus with the keyboard.
+    pub(crate) fn focus_ring_width(&self) -> u32 {
+        if self.accessibility.high_contrast {
+            HIGH_CONTRAST_FOCUS_RING_WIDTH
+        } else if self.focus_visible {
+            FOCUS_RING_WIDTH
+        } else {
+            0
+        }
+    }
+
+    /// Color of borders and of the focus ring in high contrast, the one
+    /// of the text
+    pub(crate) fn contrast_color(&self) -> Color {
+        #[cfg(feature = "text")]
+        return self.theme.text_color;
+        #[cfg(not(feature = "text"))]
+        Color::black
+    }
+
+    /// Enlarges the pointer targets smaller than `min_target_size`, and
+    /// takes back the enlargement of the ones that no longer need it. Runs
+    /// after layout, their styles are left as the app set them. Returns
+    /// whether an enlargement changed.
+    pub(crate) fn fit_hit_targets(&mut self) -> bool {
+        let min = self.accessibility.min_target_size.unwrap_or(0);
+        let mut targets: Vec<CapsuleRef> = if min > 0 {
+            self.click_callbacks
+                .keys()
+                .chain(self.mouse_button_callbacks.keys().map(|(cref, _)| cref))
+                .chain(self.enlarged_targets.iter())
+                .copied()
+                .collect()
+        } else {
+            self.enlarged_targets.iter().copied().collect()
+        };
+        targets.sort();
+        targets.dedup();
+
+        let mut changed = false;
+        for cref in targets {
+            let (Some(rect), Some(style)) = (self.root.get_rect(cref), self.root.get_style(cref))
+            else {
+                self.enlarged_targets.remove(&cref);
+                continue;
+            };
+            let current = self.root.enlargement(cref);
+            // Size the target would have without its padding enlargement
+            let extra = current.unwrap_or_default().extra_padding;
+            let (left, right) = fit_side(
+                style.width,
+                rect.w - (extra.left + extra.right).min(rect.w),
+                min,
+            );
+            let (top, bottom) = fit_side(
+                style.height,
+                rect.h - (extra.top + extra.bottom).min(rect.h),
+                min,
+            );
+
+            let enlargement = (min > 0).then_some(Enlargement {
+                min_size: min,
+                extra_padding: Padding::new(left, right, top, bottom),
+            });
+            match enlargement {
+                Some(_) => self.enlarged_targets.insert(cref),
+                None => self.enlarged_targets.remove(&cref),
+            };
+            if enlargement != current {
+                self.root.set_enlargement(cref, enlargement);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Paddings added at both ends of a side of a target laid out `size`
+/// pixels long without them, so it is at least `min` long. Only sizes
+/// following the content get padding, fixed sizes grow to `min` with
+/// `Enlargement::min_size`.
+fn fit_side(spec: SizeSpec, size: u32, min: u32) -> (u32, u32) {
+    match spec {
+        SizeSpec::Fit | SizeSpec::Auto => {
+            let missing = min.saturating_sub(size);
+            (missing / 2, missing - missing / 2)
+        }
+        _ => (0, 0),
+    }
+}
@@ -5,8 +5,9 @@ use std::sync::Arc;
 use vulkano::{
     Validated, VulkanError, VulkanLibrary,
     command_buffer::{
-        AutoCommandBufferBuilder, CommandBufferUsage, RenderPassBeginInfo, SubpassBeginInfo,
-        SubpassContents, allocator::StandardCommandBufferAllocator,
+        AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer,
+        RenderPassBeginInfo, SubpassBeginInfo, SubpassContents,
+        allocator::StandardCommandBufferAllocator,
     },
     descriptor_set::{
         DescriptorSet, WriteDescriptorSet, allocator::StandardDescriptorSetAllocator,
@@ -37,50 +38,121 @@ use vulkano::{
     },
     render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
     swapchain::{
-        CompositeAlpha, Surface, Swapchain, SwapchainCreateInfo, SwapchainPresentInfo,
-        acquire_next_image,
+        CompositeAlpha, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
+        SwapchainPresentInfo, acquire_next_image,
     },
     sync::{self, GpuFuture, future::FenceSignalFuture},
 };
 
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
-    event::WindowEvent,
-    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
     window::Window,
 };
 
 use log::{debug, warn};
 
-use super::{Context, renderer::gui::GuiRenderer};
-use crate::events::{SystemEvent, WindowCommand};
-use crate::renderer::{gui::utils::TVertex, shaders};
+/// Loop cadence while the window is hidden and has pending work
+const HIDDEN_WAKE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+use super::{
+    Context, FrameStats,
+    renderer::gui::{GuiRenderer, SceneSnapshot},
+};
+use crate::events::{GesturePhase, SystemEvent, WindowCommand};
+use crate::render_thread::RenderThread;
+use crate::renderer::{
+    atlas::{Atlas, TextureUpdate},
+    gui::utils::TVertex,
+    shaders,
+};
 
 pub struct Application {
     instance: Arc<Instance>,
     device: Arc<Device>,
     queue: Arc<Queue>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
     command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
     sampler: Arc<Sampler>,
-    rcx: Option<RenderContext>,
-    gui_renderer: GuiRenderer,
+    window: Option<Arc<Window>>,
+    presenter: Option<Presenter>,
+    /// Glyphs rasterized so far, scenes are built against it
+    atlas: Atlas,
+    proxy: EventLoopProxy<()>,
     ctx: Context,
 
     last_click: Option<(winit::dpi::PhysicalPosition<f64>, winit::event::MouseButton)>,
     last_click_time: std::time::Instant,
+    /// The window is fully hidden by other windows (or minimized)
+    occluded: bool,
+}
+
+/// Where the scenes are recorded and presented
+enum Presenter {
+    /// On the event loop, as soon as they are built
+    Inline(Box<Renderer>),
+    Threaded(RenderThread),
+}
+
+impl Presenter {
+    /// Whether a new scene would be taken right away
+    fn is_ready(&mut self) -> bool {
+        match self {
+            Presenter::Inline(_) => true,
+            Presenter::Threaded(thread) => thread.is_ready(),
+        }
+    }
+
+    fn draw(&mut self, scene: SceneSnapshot) {
+        match self {
+            Presenter::Inline(renderer) => renderer.draw(scene),
+            Presenter::Threaded(thread) => thread.submit(scene),
+        }
+    }
+
+    fn stats(&self) -> FrameStats {
+        match self {
+            Presenter::Inline(renderer) => renderer.stats,
+            Presenter::Threaded(thread) => thread.stats(),
+        }
+    }
+}
+
+/// Records and presents scenes. Holds no reference to the `Context`, so it
+/// can move to the render thread.
+pub(crate) struct Renderer {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
+    descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
+    sampler: Arc<Sampler>,
+    rcx: RenderContext,
+    gui_renderer: GuiRenderer,
+    /// Glyph copies of scenes that were dropped, recorded with the next frame
+    pending_uploads: Vec<TextureUpdate>,
+    pub(crate) stats: FrameStats,
 }
 
 struct RenderContext {
-    window: Arc<Window>,
     swapchain: Arc<Swapchain>,
     render_pass: Arc<RenderPass>,
     framebuffers: Vec<Arc<Framebuffer>>,
     pipeline: Arc<GraphicsPipeline>,
     viewport: Viewport,
     recreate_swapchain: bool,
-    fences: Vec<Option<Arc<FenceSignalFuture<Box<dyn GpuFuture>>>>>,
+    fences: Vec<Option<Arc<FenceSignalFuture<Box<dyn GpuFuture + Send + Sync>>>>>,
+}
+
+/// A frame recorded but not submitted yet
+struct PreparedFrame {
+    image_index: u32,
+    acquire_future: SwapchainAcquireFuture,
+    command_buffer: Arc<PrimaryAutoCommandBuffer>,
+    /// When the swapchain image was asked for
+    acquired_at: std::time::Instant,
 }
 
 fn window_size_dependent_setup(
@@ -181,7 +253,7 @@ impl Application {
 
         let queue = queues.next().unwrap();
         let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
-        let gui_renderer = GuiRenderer::new(memory_allocator.clone());
+        let atlas = Atlas::new(memory_allocator.clone());
 
         let command_buffer_allocator = Arc::new(StandardCommandBufferAllocator::new(
             device.clone(),
@@ -204,20 +276,275 @@ impl Application {
         )
         .unwrap();
 
-        let rcx = None;
-
         Application {
             instance,
             device,
             queue,
+            memory_allocator,
             command_buffer_allocator,
             descriptor_set_allocator,
             sampler,
-            gui_renderer,
-            rcx,
+            window: None,
+            presenter: None,
+            atlas,
+            proxy: event_loop.create_proxy(),
             ctx,
             last_click: None,
             last_click_time: std::time::Instant::now(),
+            occluded: false,
+        }
+    }
+
+    /// Gives back the context once the event loop is done with it
+    pub fn into_context(self) -> Context {
+        self.ctx
+    }
+
+    /// Lays the UI out and turns it into a scene, `None` if there is
+    /// nothing to draw on.
+    fn build_scene(&mut self) -> Option<SceneSnapshot> {
+        let window_size = self.window.as_ref()?.inner_size();
+
+        if self.occluded || window_size.width == 0 || window_size.height == 0 {
+            return None;
+        }
+
+        self.ctx.compute_layout();
+        let commands = self.ctx.render();
+
+        if commands.is_empty() {
+            debug!("No draw commands generated!");
+        }
+
+        Some(SceneSnapshot::build(
+            &commands,
+            &mut self.ctx,
+            &mut self.atlas,
+            window_size.into(),
+        ))
+    }
+
+    /// Builds and hands over a new frame, unless the render thread is
+    /// still busy with the last one. It wakes the loop up once done.
+    fn redraw(&mut self) {
+        if !self.presenter.as_mut().is_some_and(Presenter::is_ready) {
+            return;
+        }
+        if let Some(scene) = self.build_scene()
+            && let Some(presenter) = &mut self.presenter
+        {
+            presenter.draw(scene);
+        }
+    }
+
+    /// Lays out, rasterizes the glyphs and records the first frame while the
+    /// window is still hidden, then shows the window with it. The window is
+    /// never seen empty.
+    fn warm_start(&mut self, renderer: &mut Renderer) {
+        let frame = self
+            .build_scene()
+            .and_then(|scene| renderer.prepare_frame(scene));
+        if let Some(window) = &self.window {
+            window.set_visible(true);
+        }
+        if let Some(frame) = frame {
+            renderer.present_frame(frame);
+        }
+    }
+}
+
+impl Renderer {
+    /// Records the commands of a scene, `None` if the frame was dropped
+    fn prepare_frame(&mut self, scene: SceneSnapshot) -> Option<PreparedFrame> {
+        let SceneSnapshot {
+            vertices,
+            indices,
+            uploads,
+            pages,
+            size,
+            scale,
+            clear_color,
+        } = scene;
+        // Copies of dropped scenes go first, later ones may overwrite them
+        self.pending_uploads.extend(uploads);
+
+        let rcx = &mut self.rcx;
+        if rcx.recreate_swapchain || rcx.swapchain.image_extent() != size {
+            let (new_swapchain, new_images) = rcx
+                .swapchain
+                .recreate(SwapchainCreateInfo {
+                    image_extent: size,
+                    ..rcx.swapchain.create_info()
+                })
+                .expect("failed to recreate swapchain");
+
+            rcx.swapchain = new_swapchain;
+            rcx.framebuffers = window_size_dependent_setup(&new_images, &rcx.render_pass);
+            rcx.viewport.extent = [size[0] as f32, size[1] as f32];
+            rcx.recreate_swapchain = false;
+            self.gui_renderer.resize(new_images.len());
+            rcx.fences.resize(new_images.len(), None);
+            self.stats.swapchain_recreations += 1;
+        }
+
+        let acquired_at = std::time::Instant::now();
+        let (image_index, suboptimal, acquire_future) =
+            match acquire_next_image(rcx.swapchain.clone(), None).map_err(Validated::unwrap) {
+                Ok(r) => r,
+                Err(VulkanError::OutOfDate) => {
+                    rcx.recreate_swapchain = true;
+                    self.stats.dropped_frames += 1;
+                    return None;
+                }
+                Err(e) => panic!("[error::vulkan]: failed to acquire next image: {e}"),
+            };
+
+        self.stats.suboptimal = suboptimal;
+        if suboptimal {
+            rcx.recreate_swapchain = true;
+            self.stats.suboptimal_frames += 1;
+        }
+
+        // Wait for all fences to ensure we can safely update resources (like Atlas)
+        // that might be shared across frames.
+        for fence in &mut rcx.fences {
+            if let Some(image_fence) = fence {
+                image_fence.wait(None).unwrap();
+                image_fence.cleanup_finished();
+            }
+        }
+
+        let mut builder = AutoCommandBufferBuilder::primary(
+            self.command_buffer_allocator.clone(),
+            self.queue.queue_family_index(),
+            CommandBufferUsage::OneTimeSubmit,
+        )
+        .unwrap();
+
+        self.gui_renderer.upload_scene(
+            image_index as usize,
+            vertices,
+            indices,
+            std::mem::take(&mut self.pending_uploads),
+            &pages,
+            &mut builder,
+        );
+
+        let scissor = Scissor {
+            offset: [rcx.viewport.offset[0] as u32, rcx.viewport.offset[1] as u32],
+            extent: [rcx.viewport.extent[0] as u32, rcx.viewport.extent[1] as u32],
+        };
+
+        builder
+            .begin_render_pass(
+                RenderPassBeginInfo {
+                    clear_values: vec![
+                        Some(clear_color.into()), // Color
+                    ],
+                    ..RenderPassBeginInfo::framebuffer(
+                        rcx.framebuffers[image_index as usize].clone(),
+                    )
+                },
+                SubpassBeginInfo {
+                    contents: SubpassContents::Inline,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .set_viewport(0, [rcx.viewport.clone()].into_iter().collect())
+            .unwrap()
+            .set_scissor(0, [scissor].into_iter().collect())
+            .unwrap()
+            .bind_pipeline_graphics(rcx.pipeline.clone())
+            .unwrap()
+            .push_constants(
+                rcx.pipeline.layout().clone(),
+                0,
+                shaders::rectvs::PushConstants {
+                    // Vertices are in layout units
+                    screen_size: [size[0] as f32 / scale, size[1] as f32 / scale],
+                },
+            )
+            .unwrap();
+
+        let layout = rcx.pipeline.layout().set_layouts().get(0).unwrap();
+        let descriptor_sets: Vec<_> = pages
+            .iter()
+            .map(|page| {
+                DescriptorSet::new(
+                    self.descriptor_set_allocator.clone(),
+                    layout.clone(),
+                    [WriteDescriptorSet::image_view_sampler(
+                        0,
+                        ImageView::new_default(page.clone()).unwrap(),
+                        self.sampler.clone(),
+                    )],
+                    [],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        self.gui_renderer.render(
+            image_index as usize,
+            &mut builder,
+            &rcx.pipeline.layout(),
+            &descriptor_sets,
+        );
+
+        builder.end_render_pass(Default::default()).unwrap();
+
+        let command_buffer = builder.build().unwrap();
+        Some(PreparedFrame {
+            image_index,
+            acquire_future,
+            command_buffer,
+            acquired_at,
+        })
+    }
+
+    /// Submits a prepared frame and presents it
+    fn present_frame(&mut self, frame: PreparedFrame) {
+        let rcx = &mut self.rcx;
+        let PreparedFrame {
+            image_index,
+            acquire_future,
+            command_buffer,
+            acquired_at,
+        } = frame;
+        let logic_future = sync::now(self.device.clone())
+            .join(acquire_future)
+            .then_execute(self.queue.clone(), command_buffer)
+            .unwrap()
+            .then_swapchain_present(
+                self.queue.clone(),
+                SwapchainPresentInfo::swapchain_image_index(rcx.swapchain.clone(), image_index),
+            )
+            .boxed_send_sync();
+
+        let fence_future = logic_future.then_signal_fence_and_flush();
+
+        match fence_future.map_err(Validated::unwrap) {
+            Ok(future) => {
+                rcx.fences[image_index as usize] = Some(Arc::new(future));
+                self.stats.frames_presented += 1;
+                self.stats.last_present_latency = Some(acquired_at.elapsed());
+            }
+            Err(VulkanError::OutOfDate) => {
+                rcx.recreate_swapchain = true;
+                self.stats.dropped_frames += 1;
+                // For safe recovery, we can just clear the fence or keep the old one
+                // rcx.fences[image_index as usize] = None;
+            }
+            Err(e) => {
+                panic!("[error::vulkan]: failed to flush future: {e}");
+            }
+        }
+    }
+
+    pub(crate) fn draw(&mut self, scene: SceneSnapshot) {
+        if let Some(frame) = self.prepare_frame(scene) {
+            self.present_frame(frame);
         }
     }
 }
@@ -232,7 +559,10 @@ impl ApplicationHandler for Application {
                 self.ctx.attr.size.1,
             ))
             .with_decorations(false)
-            .with_transparent(true);
+            .with_transparent(true)
+            .with_blur(self.ctx.attr.blur)
+            // Shown by `warm_start` once the first frame is ready
+            .with_visible(false);
 
         #[cfg(target_os = "linux")]
         {
@@ -292,7 +622,8 @@ impl ApplicationHandler for Application {
             .unwrap()
         };
 
-        self.gui_renderer.resize(images.len());
+        let mut gui_renderer = GuiRenderer::new(self.memory_allocator.clone());
+        gui_renderer.resize(images.len());
 
         let render_pass = vulkano::single_pass_renderpass!(
             self.device.clone(),
@@ -390,15 +721,32 @@ impl ApplicationHandler for Application {
         let recreate_swapchain = false;
         let fences = vec![None; images.len()];
 
-        self.rcx = Some(RenderContext {
-            window,
-            swapchain,
-            render_pass,
-            framebuffers,
-            pipeline,
-            viewport,
-            recreate_swapchain,
-            fences,
+        let mut renderer = Renderer {
+            device: self.device.clone(),
+            queue: self.queue.clone(),
+            command_buffer_allocator: self.command_buffer_allocator.clone(),
+            descriptor_set_allocator: self.descriptor_set_allocator.clone(),
+            sampler: self.sampler.clone(),
+            rcx: RenderContext {
+                swapchain,
+                render_pass,
+                framebuffers,
+                pipeline,
+                viewport,
+                recreate_swapchain,
+                fences,
+            },
+            gui_renderer,
+            pending_uploads: Vec::new(),
+            stats: FrameStats::default(),
+        };
+        self.window = Some(window);
+        self.warm_start(&mut renderer);
+
+        self.presenter = Some(if self.ctx.attr.render_thread {
+            Presenter::Threaded(RenderThread::spawn(renderer, self.proxy.clone()))
+        } else {
+            Presenter::Inline(Box::new(renderer))
         });
     }
 
@@ -408,12 +756,37 @@ impl ApplicationHandler for Application {
         _window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
-        let rcx = self.rcx.as_mut().unwrap();
-
         match event {
-            WindowEvent::CloseRequested => {
+            // Vetoed close requests fall through to the no-op arm
+            WindowEvent::CloseRequested if self.ctx.close_requested() => {
                 event_loop.exit();
             }
+            WindowEvent::MouseWheel { delta, phase, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => PhysicalPosition::new(
+                        x as f64 * crate::SCROLL_LINE_HEIGHT,
+                        y as f64 * crate::SCROLL_LINE_HEIGHT,
+                    ),
+                    MouseScrollDelta::PixelDelta(delta) => delta,
+                };
+                // Wheels only send `Moved`, touchpads start and end their scrolls
+                let phase = GesturePhase::from(phase);
+                if phase == GesturePhase::Begin {
+                    self.ctx.process_event(SystemEvent::ScrollPhase(phase));
+                }
+                if delta.x != 0.0 || delta.y != 0.0 {
+                    self.ctx.process_event(SystemEvent::Scroll(delta));
+                }
+                if phase == GesturePhase::End {
+                    self.ctx.process_event(SystemEvent::ScrollPhase(phase));
+                }
+            }
+            WindowEvent::PinchGesture { delta, phase, .. } => {
+                self.ctx.process_event(SystemEvent::Pinch {
+                    delta,
+                    phase: phase.into(),
+                });
+            }
             WindowEvent::CursorMoved {
                 device_id: _,
                 position,
@@ -460,217 +833,99 @@ impl ApplicationHandler for Application {
                     logical_key: event.logical_key,
                     text: event.text,
                     pressed: event.state.is_pressed(),
+                    repeat: event.repeat,
                 });
             }
 
-            WindowEvent::Resized(PhysicalSize { width, height }) => {
-                rcx.recreate_swapchain = true;
-                self.ctx.process_event(SystemEvent::Resize(width, height));
+            WindowEvent::Focused(false) => {
+                // The release of a held key goes to the newly focused window
+                self.ctx.stop_key_repeat();
+                self.ctx.shift_held = false;
+                self.ctx.drag_pan_key(false);
+                self.ctx.cancel_drag();
             }
-            WindowEvent::RedrawRequested => {
-                let window_size = rcx.window.inner_size();
-
-                if window_size.width == 0 || window_size.height == 0 {
-                    return;
-                }
-
-                if rcx.recreate_swapchain {
-                    let (new_swapchain, new_images) = rcx
-                        .swapchain
-                        .recreate(SwapchainCreateInfo {
-                            image_extent: window_size.into(),
-                            ..rcx.swapchain.create_info()
-                        })
-                        .expect("failed to recreate swapchain");
-
-                    rcx.swapchain = new_swapchain;
-                    rcx.framebuffers = window_size_dependent_setup(&new_images, &rcx.render_pass);
-                    rcx.viewport.extent = window_size.into();
-                    rcx.recreate_swapchain = false;
-                    self.gui_renderer.resize(new_images.len());
-                    rcx.fences.resize(new_images.len(), None);
-                }
-
-                let (image_index, suboptimal, acquire_future) = match acquire_next_image(
-                    rcx.swapchain.clone(),
-                    None,
-                )
-                .map_err(Validated::unwrap)
-                {
-                    Ok(r) => r,
-                    Err(VulkanError::OutOfDate) => {
-                        rcx.recreate_swapchain = true;
-                        return;
-                    }
-                    Err(e) => panic!("[error::vulkan]: failed to acquire next image: {e}"),
-                };
-
-                if suboptimal {
-                    rcx.recreate_swapchain = true;
-                }
-
-                // Wait for all fences to ensure we can safely update resources (like Atlas)
-                // that might be shared across frames.
-                for fence in &mut rcx.fences {
-                    if let Some(image_fence) = fence {
-                        image_fence.wait(None).unwrap();
-                        image_fence.cleanup_finished();
-                    }
-                }
-
-                let mut builder = AutoCommandBufferBuilder::primary(
-                    self.command_buffer_allocator.clone(),
-                    self.queue.queue_family_index(),
-                    CommandBufferUsage::OneTimeSubmit,
-                )
-                .unwrap();
-
-                self.ctx.compute_layout();
-                let commands = self.ctx.render();
 
-                if commands.is_empty() {
-                    debug!("Frame {}: No draw commands generated!", image_index);
+            WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                if !occluded && let Some(window) = &self.window {
+                    window.request_redraw();
                 }
+            }
 
-                self.gui_renderer.upload_draw_commands(
-                    image_index as usize,
-                    &commands,
-                    &mut self.ctx,
-                    &mut builder,
-                );
-
-                let scissor = Scissor {
-                    offset: [rcx.viewport.offset[0] as u32, rcx.viewport.offset[1] as u32],
-                    extent: [rcx.viewport.extent[0] as u32, rcx.viewport.extent[1] as u32],
-                };
-
-                builder
-                    .begin_render_pass(
-                        RenderPassBeginInfo {
-                            clear_values: vec![
-                                Some([0., 0., 0., 0.0].into()), // Color
-                            ],
-                            ..RenderPassBeginInfo::framebuffer(
-                                rcx.framebuffers[image_index as usize].clone(),
-                            )
-                        },
-                        SubpassBeginInfo {
-                            contents: SubpassContents::Inline,
-                            ..Default::default()
-                        },
-                    )
-                    .unwrap()
-                    .set_viewport(0, [rcx.viewport.clone()].into_iter().collect())
-                    .unwrap()
-                    .set_scissor(0, [scissor].into_iter().collect())
-                    .unwrap()
-                    .bind_pipeline_graphics(rcx.pipeline.clone())
-                    .unwrap()
-                    .push_constants(
-                        rcx.pipeline.layout().clone(),
-                        0,
-                        shaders::rectvs::PushConstants {
-                            screen_size: [window_size.width as f32, window_size.height as f32],
-                        },
-                    )
-                    .unwrap();
-
-                let layout = rcx.pipeline.layout().set_layouts().get(0).unwrap();
-                let descriptor_set = DescriptorSet::new(
-                    self.descriptor_set_allocator.clone(),
-                    layout.clone(),
-                    [WriteDescriptorSet::image_view_sampler(
-                        0,
-                        ImageView::new_default(self.gui_renderer.atlas.texture.clone()).unwrap(),
-                        self.sampler.clone(),
-                    )],
-                    [],
-                )
-                .unwrap();
-
-                self.gui_renderer.render(
-                    image_index as usize,
-                    &mut builder,
-                    &rcx.pipeline.layout(),
-                    &descriptor_set,
-                );
-
-                builder.end_render_pass(Default::default()).unwrap();
-
-                let command_buffer = builder.build().unwrap();
-
-                let logic_future = sync::now(self.device.clone())
-                    .join(acquire_future)
-                    .then_execute(self.queue.clone(), command_buffer)
-                    .unwrap()
-                    .then_swapchain_present(
-                        self.queue.clone(),
-                        SwapchainPresentInfo::swapchain_image_index(
-                            rcx.swapchain.clone(),
-                            image_index,
-                        ),
-                    )
-                    .boxed();
-
-                let fence_future = logic_future.then_signal_fence_and_flush();
-
-                match fence_future.map_err(Validated::unwrap) {
-                    Ok(future) => {
-                        rcx.fences[image_index as usize] = Some(Arc::new(future));
-                    }
-                    Err(VulkanError::OutOfDate) => {
-                        rcx.recreate_swapchain = true;
-                        // For safe recovery, we can just clear the fence or keep the old one
-                        // rcx.fences[image_index as usize] = None;
-                    }
-                    Err(e) => {
-                        panic!("[error::vulkan]: failed to flush future: {e}");
-                    }
+            // The swapchain follows the size of the next scene
+            WindowEvent::Resized(PhysicalSize { width, height }) => {
+                if let Some(window) = &self.window {
+                    self.ctx.set_maximized(window.is_maximized());
                 }
+                self.ctx.process_event(SystemEvent::Resize(width, height));
             }
+            WindowEvent::RedrawRequested => self.redraw(),
             _ => {}
         }
     }
 
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, _event: ()) {
+        // A `UiHandle` queued a task
+        self.ctx.run_pending_tasks();
+    }
+
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(presenter) = &self.presenter {
+            self.ctx.frame_stats = presenter.stats();
+        }
+        self.ctx.run_pending_tasks();
+        let next_replayed = self.ctx.pump_replay();
+        let next_timer = self.ctx.run_due_timers();
+
+        // Cursor moves of the whole batch are coalesced into one
+        self.ctx.dispatch_pointer_move();
+
         let commands: Vec<WindowCommand> = self.ctx.commands.drain(..).collect();
         for cmd in commands {
             match cmd {
                 WindowCommand::SetTitle(title) => {
-                    if let Some(rcx) = &self.rcx {
-                        rcx.window.set_title(&title);
+                    if let Some(window) = &self.window {
+                        window.set_title(&title);
                     }
                 }
                 WindowCommand::SetSize(width, height) => {
-                    if let Some(rcx) = &self.rcx {
-                        let _ = rcx
-                            .window
-                            .request_inner_size(PhysicalSize::new(width, height));
+                    if let Some(window) = &self.window {
+                        let _ = window.request_inner_size(PhysicalSize::new(width, height));
                     }
                 }
                 WindowCommand::SetResizable(resizable) => {
-                    if let Some(rcx) = &self.rcx {
-                        rcx.window.set_resizable(resizable);
+                    if let Some(window) = &self.window {
+                        window.set_resizable(resizable);
                     }
                 }
                 WindowCommand::SetDecorations(decorations) => {
-                    if let Some(rcx) = &self.rcx {
-                        rcx.window.set_decorations(decorations);
+                    if let Some(window) = &self.window {
+                        window.set_decorations(decorations);
+                    }
+                }
+                WindowCommand::SetBlur(blur) => {
+                    if let Some(window) = &self.window {
+                        window.set_blur(blur);
                     }
                 }
                 WindowCommand::Maximize => {
-                    if let Some(rcx) = &self.rcx {
-                        rcx.window.set_maximized(true);
+                    if let Some(window) = &self.window {
+                        window.set_maximized(true);
                     }
                 }
                 WindowCommand::Minimize => {
-                    if let Some(rcx) = &self.rcx {
-                        rcx.window.set_minimized(true);
+                    if let Some(window) = &self.window {
+                        window.set_minimized(true);
                     }
                 }
                 WindowCommand::DragWindow => {
-                    if let Some(rcx) = &self.rcx {
-                        let _ = rcx.window.drag_window();
+                    if let Some(window) = &self.window {
+                        let _ = window.drag_window();
+                    }
+                }
+                WindowCommand::SetCursor(icon) => {
+                    if let Some(window) = &self.window {
+                        window.set_cursor(icon);
                     }
                 }
                 WindowCommand::Quit => {
@@ -679,10 +934,18 @@ impl ApplicationHandler for Application {
             }
         }
 
-        if self.ctx.is_dirty() {
-            let rcx = self.rcx.as_mut().unwrap();
-            rcx.window.request_redraw();
-            event_loop.set_control_flow(ControlFlow::Poll);
+        // A busy render thread wakes the loop up once its frame is presented
+        if self.ctx.is_dirty() && self.presenter.as_mut().is_some_and(Presenter::is_ready) {
+            let window = self.window.as_ref().unwrap();
+            if self.occluded || window.is_minimized() == Some(true) {
+                // Nothing would be seen: keep the pending frame until the window shows up again
+                event_loop.set_control_flow(ControlFlow::wait_duration(HIDDEN_WAKE_INTERVAL));
+            } else {
+                window.request_redraw();
+                event_loop.set_control_flow(ControlFlow::Poll);
+            }
+        } else if let Some(next) = next_replayed.into_iter().chain(next_timer).min() {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(next));
         } else {
             event_loop.set_control_flow(ControlFlow::Wait);
         }
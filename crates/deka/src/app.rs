@@ -0,0 +1,142 @@
+#[cfg(feature = "text")]
+use std::path::PathBuf;
+
+use crate::{Context, Theme, WindowAttr};
+
+/// A configured `Context`, ready to run
+pub struct App {
+    ctx: Context,
+}
+
+impl App {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    /// Starts the event loop. See `Context::run`.
+    pub fn run(self) -> Result<(), impl std::error::Error> {
+        self.ctx.run()
+    }
+
+    /// Starts the event loop and returns the context once it exits.
+    /// See `Context::run_until_exit`.
+    pub fn run_until_exit(self) -> Result<Context, winit::error::EventLoopError> {
+        self.ctx.run_until_exit()
+    }
+
+    /// The underlying context, to keep building the UI before running
+    pub fn context(&mut self) -> &mut Context {
+        &mut self.ctx
+    }
+}
+
+/// Configuration of an `App`.
+///
+/// ```rust,ignore
+/// App::builder()
+///     .title("Hello")
+///     .size(800, 600)
+///     .theme(Theme::default())
+///     .build(|ctx| {
+///         ctx.new_label("Hello, World!", None::<Element>, None);
+///     })
+///     .run()
+/// ```
+#[derive(Default)]
+pub struct AppBuilder {
+    attr: WindowAttr,
+    theme: Theme,
+    #[cfg(feature = "text")]
+    fonts: Vec<Vec<u8>>,
+    #[cfg(feature = "text")]
+    font_files: Vec<PathBuf>,
+}
+
+impl AppBuilder {
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.attr.title = title.into();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.attr.size = (width, height);
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.attr.resizable = resizable;
+        self
+    }
+
+    pub fn app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.attr.app_id = app_id.into();
+        self
+    }
+
+    /// Color the window is cleared with, see `Context::set_clear_color`
+    pub fn clear_color(mut self, color: heka::color::Color) -> Self {
+        self.attr.clear_color = color;
+        self
+    }
+
+    /// Records and presents frames on a dedicated thread. Events, callbacks
+    /// and layout stay on the main thread, which hands each laid out frame
+    /// over and builds the next one at most one frame ahead. Input keeps
+    /// being handled while a slow GPU is busy with command buffers.
+    pub fn render_thread(mut self, enabled: bool) -> Self {
+        self.attr.render_thread = enabled;
+        self
+    }
+
+    /// Rounds the corners of the window, see `Context::set_corner_radius`
+    pub fn corner_radius(mut self, radius: u32) -> Self {
+        self.attr.corner_radius = radius;
+        self
+    }
+
+    /// Blurs what is behind the window, see `Context::set_blur`
+    pub fn blur(mut self, blur: bool) -> Self {
+        self.attr.blur = blur;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Fonts to load from memory, on top of the system fonts
+    #[cfg(feature = "text")]
+    pub fn fonts(mut self, fonts: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        self.fonts.extend(fonts);
+        self
+    }
+
+    /// A font file to load, on top of the system fonts
+    #[cfg(feature = "text")]
+    pub fn font_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.font_files.push(path.into());
+        self
+    }
+
+    /// Creates the context and builds the UI in `ui`
+    pub fn build(self, ui: impl FnOnce(&mut Context)) -> App {
+        let (width, height) = self.attr.size;
+        let mut ctx = Context::new(width, height, self.attr);
+
+        #[cfg(feature = "text")]
+        for data in self.fonts {
+            ctx.load_font_data(data);
+        }
+        #[cfg(feature = "text")]
+        for path in self.font_files {
+            if let Err(e) = ctx.load_font_file(&path) {
+                log::warn!("Unable to load font {}: {e}", path.display());
+            }
+        }
+        ctx.set_theme(self.theme);
+
+        ui(&mut ctx);
+        App { ctx }
+    }
+}
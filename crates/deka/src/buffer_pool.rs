@@ -0,0 +1,116 @@
+//! Shaped buffers shared by the labels showing the same short text in the
+//! same style, like the "Edit" and "Delete" buttons of every row of a list.
+//!
+//! A shared buffer is shaped once, as a label fitting its text shows it.
+//! Labels keep sharing it as long as the layout gives them that room: one
+//! that has to wrap its text, or whose text or style changes, gets a buffer
+//! of its own (copy-on-write).
+
+use std::collections::HashMap;
+
+use cosmic_text::{Buffer, FontSystem};
+use heka::DataRef;
+
+use crate::TextStyle;
+use crate::elements::{Label, fill_buffer};
+
+/// Texts longer than this (in bytes) are not shared, the texts repeated
+/// across a UI are short
+pub(crate) const SHARED_TEXT_MAX_LEN: usize = 64;
+
+/// A buffer and the labels using it
+struct Share {
+    users: usize,
+    /// Size of the text as shaped
+    size: (u32, u32),
+}
+
+#[derive(Default)]
+pub(crate) struct BufferPool {
+    /// Shared buffers by the text they show, with the style they show it in
+    by_text: HashMap<String, Vec<(TextStyle, DataRef)>>,
+    shares: HashMap<DataRef, Share>,
+}
+
+impl BufferPool {
+    /// Whether labels showing `text` in `style` can share a buffer.
+    /// Cutting the text to `max_lines` depends on the width of each label.
+    pub(crate) fn accepts(text: &str, style: &TextStyle) -> bool {
+        !text.is_empty() && text.len() <= SHARED_TEXT_MAX_LEN && style.max_lines.is_none()
+    }
+
+    /// A buffer showing `text` in `style` for one more label, shaped now if
+    /// no other label shows it. Returns it with the size of the text, or
+    /// `None` if the text is not shared.
+    pub(crate) fn acquire(
+        &mut self,
+        root: &mut heka::Root,
+        font_system: &mut FontSystem,
+        text: &str,
+        style: &TextStyle,
+    ) -> Option<(DataRef, (u32, u32))> {
+        if !Self::accepts(text, style) {
+            return None;
+        }
+
+        let found = self.by_text.get(text).and_then(|buffers| {
+            buffers
+                .iter()
+                .find(|(shared_style, _)| shared_style == style)
+                .map(|(_, buffer_ref)| *buffer_ref)
+        });
+        if let Some(buffer_ref) = found
+            && let Some(share) = self.shares.get_mut(&buffer_ref)
+        {
+            share.users += 1;
+            return Some((buffer_ref, share.size));
+        }
+
+        let mut buffer = Buffer::new(font_system, style.as_cosmic_metrics());
+        fill_buffer(&mut buffer, font_system, text, style);
+        buffer.set_size(font_system, None, None);
+        buffer.shape_until_scroll(font_system, true);
+        let size = Label::measure_buffer(&buffer);
+        // As the layout leaves the buffer of a label fitting its text
+        buffer.set_size(font_system, Some(size.0 as f32), None);
+        buffer.shape_until_scroll(font_system, true);
+
+        let buffer_ref = root.set_binding(buffer);
+        self.by_text
+            .entry(text.to_string())
+            .or_default()
+            .push((style.clone(), buffer_ref));
+        self.shares.insert(buffer_ref, Share { users: 1, size });
+        Some((buffer_ref, size))
+    }
+
+    /// A label stops using `buffer_ref`, which is freed with its last label.
+    /// Returns false if the buffer is not shared.
+    pub(crate) fn release(&mut self, root: &mut heka::Root, buffer_ref: DataRef) -> bool {
+        let Some(share) = self.shares.get_mut(&buffer_ref) else {
+            return false;
+        };
+        share.users -= 1;
+        if share.users == 0 {
+            self.shares.remove(&buffer_ref);
+            self.by_text.retain(|_, buffers| {
+                buffers.retain(|(_, shared)| *shared != buffer_ref);
+                !buffers.is_empty()
+            });
+            root.remove_binding(buffer_ref);
+        }
+        true
+    }
+
+    /// New labels get buffers of their own shaping, the current ones are
+    /// freed with their last label. For when fonts are loaded.
+    pub(crate) fn forget(&mut self) {
+        self.by_text.clear();
+    }
+
+    /// Number of buffers shared by labels
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.shares.len()
+    }
+}
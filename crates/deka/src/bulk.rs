@@ -0,0 +1,149 @@
+//! Creating many children of an element at once, like the rows of a long
+//! list: the layout tree is told about them in a single change.
+
+use heka::Frame;
+use heka::Style;
+
+use crate::elements::{Canvas, FrameElement, Panel};
+#[cfg(feature = "text")]
+use crate::elements::{Label, Sharing};
+use crate::{Context, Element, ElementRef};
+#[cfg(feature = "text")]
+use crate::{LabelRef, TextStyle};
+
+/// An element for `Context::extend` to create
+#[derive(Debug, Clone)]
+pub enum ElementSpec {
+    Panel(Style),
+    Canvas(Style),
+    /// A label in `style`, or in the default style of labels with the text
+    /// properties of the elements above, see `Context::set_text_defaults`
+    #[cfg(feature = "text")]
+    Label {
+        text: String,
+        style: Option<TextStyle>,
+    },
+}
+
+/// Data of a child before its frame exists
+enum Pending {
+    Panel,
+    Canvas,
+    #[cfg(feature = "text")]
+    Label {
+        text: String,
+        style: TextStyle,
+        /// Takes its text properties from the elements above
+        inherits: bool,
+        buffer: (heka::DataRef, Option<Sharing>),
+    },
+}
+
+impl Context {
+    /// Creates a child of `parent_frame` for each spec, in order. The layout
+    /// is marked dirty once for all of them, and they are measured on the
+    /// next compute.
+    pub fn extend(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        specs: impl IntoIterator<Item = ElementSpec>,
+    ) -> Vec<Element> {
+        let parent = match parent_frame {
+            Some(pf) => Frame::define(pf.raw()),
+            None => self.root_frame,
+        };
+        #[cfg(feature = "text")]
+        let mut label_style = None;
+
+        let (frame_styles, pending): (Vec<_>, Vec<_>) = specs
+            .into_iter()
+            .map(|spec| match spec {
+                ElementSpec::Panel(style) => ((style, None), Pending::Panel),
+                ElementSpec::Canvas(style) => ((style, None), Pending::Canvas),
+                #[cfg(feature = "text")]
+                ElementSpec::Label { text, style } => {
+                    // Looked up once for every label in the default style
+                    let (style, inherits) = match style {
+                        Some(style) => (style, false),
+                        None => {
+                            let (_, style) = label_style.get_or_insert_with(|| {
+                                let base = self
+                                    .default_text_style::<Label>()
+                                    .unwrap_or_else(|| self.theme.text_style());
+                                let style = self.inherit_text_style(parent.get_ref(), &base);
+                                (base, style)
+                            });
+                            (style.clone(), true)
+                        }
+                    };
+                    let buffer = Label::buffer_for(
+                        &mut self.root,
+                        &mut self.font_system,
+                        &mut self.buffer_pool,
+                        &text,
+                        &style,
+                        None,
+                    );
+                    let frame_style = (Label::frame_style(), Some(buffer.0));
+                    (
+                        frame_style,
+                        Pending::Label {
+                            text,
+                            style,
+                            inherits,
+                            buffer,
+                        },
+                    )
+                }
+            })
+            .unzip();
+
+        let frames = self.root.add_frame_children(&parent, frame_styles);
+        self.elements.reserve(frames.len());
+        frames
+            .into_iter()
+            .zip(pending)
+            .map(|(frame, pending)| {
+                let element: Box<dyn FrameElement> = match pending {
+                    Pending::Panel => Box::new(Panel { frame }),
+                    Pending::Canvas => Box::new(Canvas::new(frame)),
+                    #[cfg(feature = "text")]
+                    Pending::Label {
+                        text,
+                        style,
+                        inherits,
+                        buffer,
+                    } => {
+                        let mut label =
+                            Label::with_frame(&mut self.root, frame, text, style, buffer);
+                        label.base_style = label_style
+                            .as_ref()
+                            .filter(|_| inherits)
+                            .map(|(base, _)| base.clone());
+                        Box::new(label)
+                    }
+                };
+                self.elements.insert(frame.get_ref(), element);
+                Element(frame.get_ref())
+            })
+            .collect()
+    }
+
+    /// Creates a label child of `parent_frame` for each text, in the default
+    /// style of labels. See `extend`.
+    #[cfg(feature = "text")]
+    pub fn new_labels<S: ToString>(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        texts: impl IntoIterator<Item = S>,
+    ) -> Vec<LabelRef> {
+        let specs = texts.into_iter().map(|text| ElementSpec::Label {
+            text: text.to_string(),
+            style: None,
+        });
+        self.extend(parent_frame, specs)
+            .into_iter()
+            .map(|element| LabelRef(element.raw()))
+            .collect()
+    }
+}
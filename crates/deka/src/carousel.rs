@@ -0,0 +1,427 @@
+//! Carousels: a scroll view showing one page at a time, swiped through
+//! with the pointer. It snaps to the pages and springs back at the ends,
+//! the way elastic snapping scroll views do, and dots along the bottom
+//! follow the page in view.
+
+use heka::position::{Insets, JustifyContent, Offset, Overflow, Position};
+use heka::sizing::{Border, SizeSpec};
+use heka::{CapsuleRef, Style, clr, flow, layout, make_style, size};
+use winit::dpi::PhysicalPosition;
+use winit::event::MouseButton;
+
+use crate::elements::Carousel;
+use crate::events::PageChangeEvent;
+use crate::{CarouselRef, Context, ElementRef, PanelRef, ScrollSnap, ScrollViewRef};
+
+/// Distance the cursor goes sideways from the press before it swipes, so
+/// that clicks on the pages stay clicks
+const SWIPE_THRESHOLD: f64 = 6.0;
+
+/// Fraction of the width a swipe goes to turn the page
+const SWIPE_TURN: f64 = 0.2;
+
+const DOT_SIZE: u32 = 8;
+
+/// A press on a carousel, and the swipe it turned into
+pub(crate) struct Swipe {
+    carousel: CapsuleRef,
+    view: CapsuleRef,
+    start: PhysicalPosition<f64>,
+    /// Scroll position at the press
+    start_offset: f32,
+    /// Page in view at the press
+    page: usize,
+    /// The cursor went past the threshold
+    started: bool,
+}
+
+impl Context {
+    /// Creates a `Carousel` sized by `style`, showing one page at a time.
+    /// Pages are added with `add_carousel_page`.
+    pub fn new_carousel(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> CarouselRef {
+        let frame = self.new_panel(
+            parent_frame,
+            Style {
+                overflow: Overflow::Hidden,
+                ..style
+            },
+        );
+        let view = self.new_scroll_view(
+            Some(frame),
+            make_style!(
+                width: size!(100%),
+                height: size!(100%),
+                // Pages are paged through, not scrolled with a scrollbar
+                overflow: Overflow::Scroll,
+                background_color: clr!(transparent),
+            ),
+        );
+        if let Some(content) = self.scroll_view_content(view) {
+            heka::Frame::define(content.raw()).update_style(&mut self.root, |style| {
+                style.height = size!(100%);
+                style.flow = flow!(row);
+            });
+        }
+        self.set_overscroll(view, true);
+        self.set_scroll_snap(view, ScrollSnap::Start);
+
+        let dots = self.new_panel(
+            Some(frame),
+            make_style!(
+                // Stretched between the insets, the dots centered in it
+                width: size!(auto),
+                height: size!(fit),
+                position: Position::Absolute(Insets {
+                    bottom: Some(Offset::Pixel(8)),
+                    left: Some(Offset::Pixel(0)),
+                    right: Some(Offset::Pixel(0)),
+                    ..Default::default()
+                }),
+                layout: layout!(flex),
+                flow: flow!(row),
+                justify_content: JustifyContent::Center,
+                gap: 6,
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let cref = frame.raw();
+        self.elements.insert(
+            cref,
+            Box::new(Carousel {
+                frame: heka::Frame::define(cref),
+                view,
+                dots,
+                page: 0,
+                page_width: 0,
+                realign: false,
+            }),
+        );
+        CarouselRef(cref)
+    }
+
+    pub fn get_carousel(&self, carousel: CarouselRef) -> Option<&Carousel> {
+        let element = self.elements.get(&carousel.raw())?;
+        element.as_any().downcast_ref::<Carousel>()
+    }
+
+    /// Adds a page after the last one, as large as the carousel. Its
+    /// children go in the returned panel.
+    pub fn add_carousel_page(&mut self, carousel: CarouselRef) -> Option<PanelRef> {
+        let carousel = self.get_carousel(carousel)?;
+        let width = carousel.page_width;
+        let content = self.scroll_view_content(carousel.view)?;
+        Some(self.new_panel(
+            Some(content),
+            make_style!(
+                width: SizeSpec::Pixel(width),
+                height: size!(100%),
+                layout: layout!(flex),
+                flow: flow!(column),
+                background_color: clr!(transparent),
+            ),
+        ))
+    }
+
+    /// The pages of a carousel, in order
+    pub fn carousel_pages(&self, carousel: CarouselRef) -> Vec<PanelRef> {
+        self.get_carousel(carousel)
+            .and_then(|carousel| self.scroll_view_content(carousel.view))
+            .and_then(|content| self.root.get_capsule(content.raw()))
+            .map(|content| content.children().iter().copied().map(PanelRef).collect())
+            .unwrap_or_default()
+    }
+
+    /// Page mostly in view
+    pub fn carousel_page(&self, carousel: CarouselRef) -> usize {
+        self.get_carousel(carousel).map_or(0, Carousel::page)
+    }
+
+    /// Shows a page of the carousel, sliding to it when `animated`
+    pub fn set_carousel_page(&mut self, carousel: CarouselRef, page: usize, animated: bool) {
+        let count = self.carousel_pages(carousel).len();
+        let Some(carousel) = self.get_carousel(carousel) else {
+            return;
+        };
+        let view = carousel.view;
+        let offset = (page.min(count.saturating_sub(1)) as u32 * carousel.page_width) as f32;
+        if animated {
+            self.animate_scroll_to(view.raw(), (offset, 0.0));
+        } else {
+            self.scroll_to(view, (offset, 0.0));
+        }
+    }
+
+    /// Called when the carousel shows another page: once the page is
+    /// mostly in view, while it slides in
+    pub fn on_page_change<F>(&mut self, carousel: CarouselRef, callback: F)
+    where
+        F: FnMut(&mut Context, &PageChangeEvent) + 'static,
+    {
+        self.page_change_callbacks
+            .insert(carousel.raw(), Box::new(callback));
+    }
+
+    /// Carousel a scroll view shows the pages of
+    fn carousel_of(&self, view: CapsuleRef) -> Option<CapsuleRef> {
+        let parent = self.root.get_capsule(view)?.parent_ref?;
+        self.elements
+            .get(&parent)
+            .filter(|element| element.as_any().is::<Carousel>())
+            .map(|_| parent)
+    }
+
+    /// Updates the page and the dots of the carousels whose view moved
+    pub(crate) fn follow_carousel_pages(&mut self, scrolled: &[CapsuleRef]) {
+        for view in scrolled {
+            let Some(cref) = self.carousel_of(*view) else {
+                continue;
+            };
+            let count = self.carousel_pages(CarouselRef(cref)).len();
+            let (offset, _) = self.scrolled_to(*view);
+            let Some(carousel) = self.get_carousel(CarouselRef(cref)) else {
+                continue;
+            };
+            let page = match carousel.page_width {
+                0 => 0,
+                width => (offset / width as f32).round().max(0.0) as usize,
+            }
+            .min(count.saturating_sub(1));
+            if page == carousel.page {
+                continue;
+            }
+
+            let previous = carousel.page;
+            self.with_component_mut(cref, |carousel: &mut Carousel, _| carousel.page = page);
+            self.paint_dots(cref);
+            if let Some(mut callback) = self.page_change_callbacks.remove(&cref) {
+                let event = PageChangeEvent { page, previous };
+                self.guarded_on(cref, |ctx| callback(ctx, &event));
+                self.page_change_callbacks.entry(cref).or_insert(callback);
+            }
+        }
+    }
+
+    /// Highlights the dot of the page in view
+    fn paint_dots(&mut self, cref: CapsuleRef) {
+        let Some(carousel) = self.get_carousel(CarouselRef(cref)) else {
+            return;
+        };
+        let page = carousel.page;
+        let Some(dots) = self.root.get_capsule(carousel.dots.raw()) else {
+            return;
+        };
+        let (accent, muted) = (self.theme.accent_color, self.theme.muted_color);
+        for (index, dot) in dots.children().to_vec().into_iter().enumerate() {
+            let color = if index == page { accent } else { muted };
+            heka::Frame::define(dot).update_style(&mut self.root, |style| {
+                style.background_color = color;
+            });
+        }
+    }
+
+    /// Sizes the pages of the carousels to their viewport, and gives them
+    /// one dot per page. Returns whether anything changed.
+    pub(crate) fn fit_carousels(&mut self) -> bool {
+        let carousels: Vec<CapsuleRef> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| element.as_any().is::<Carousel>())
+            .map(|(cref, _)| *cref)
+            .collect();
+
+        let mut changed = false;
+        for cref in carousels {
+            let pages = self.carousel_pages(CarouselRef(cref));
+            let Some(carousel) = self.get_carousel(CarouselRef(cref)) else {
+                continue;
+            };
+            let (view, dots) = (carousel.view, carousel.dots);
+            let Some(width) = self.root.get_content_rect(view.raw()).map(|rect| rect.w) else {
+                continue;
+            };
+
+            if width != carousel.page_width {
+                self.with_component_mut(cref, |carousel: &mut Carousel, _| {
+                    carousel.page_width = width;
+                    carousel.realign = true;
+                });
+            }
+            for page in &pages {
+                let sized = self
+                    .root
+                    .get_style(page.raw())
+                    .is_some_and(|style| style.width == SizeSpec::Pixel(width));
+                if !sized {
+                    heka::Frame::define(page.raw()).update_style(&mut self.root, |style| {
+                        style.width = SizeSpec::Pixel(width);
+                    });
+                    changed = true;
+                }
+            }
+
+            let shown = self
+                .root
+                .get_capsule(dots.raw())
+                .map_or(0, |dots| dots.children().len());
+            if shown != pages.len() {
+                self.set_dot_count(cref, dots, pages.len());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn set_dot_count(&mut self, cref: CapsuleRef, dots: PanelRef, count: usize) {
+        let shown: Vec<CapsuleRef> = self
+            .root
+            .get_capsule(dots.raw())
+            .map(|dots| dots.children().to_vec())
+            .unwrap_or_default();
+        for dot in shown.iter().skip(count) {
+            self.remove_element(crate::Element(*dot));
+        }
+        for index in shown.len()..count {
+            let dot = self.new_panel(
+                Some(dots),
+                make_style!(
+                    width: SizeSpec::Pixel(DOT_SIZE),
+                    height: SizeSpec::Pixel(DOT_SIZE),
+                    border: Border {
+                        size: 0,
+                        radius: DOT_SIZE / 2,
+                        color: clr!(transparent),
+                    },
+                ),
+            );
+            self.on_click(dot, move |ctx, _| {
+                ctx.set_carousel_page(CarouselRef(cref), index, true);
+            });
+        }
+        self.paint_dots(cref);
+    }
+
+    /// Scrolls the carousels whose pages changed width back to their page.
+    /// Returns whether any moved.
+    pub(crate) fn align_carousels(&mut self) -> bool {
+        let realigned: Vec<(CapsuleRef, ScrollViewRef, f32)> = self
+            .elements
+            .iter()
+            .filter_map(|(cref, element)| {
+                let carousel = element.as_any().downcast_ref::<Carousel>()?;
+                carousel.realign.then(|| {
+                    let offset = (carousel.page as u32 * carousel.page_width) as f32;
+                    (*cref, carousel.view, offset)
+                })
+            })
+            .collect();
+
+        let mut moved = false;
+        for (cref, view, offset) in realigned {
+            self.with_component_mut(cref, |carousel: &mut Carousel, _| carousel.realign = false);
+            let (_, y) = self.root.scroll_offset(view.raw());
+            moved |= self.root.set_scroll_offset(view.raw(), offset, y);
+        }
+        moved
+    }
+
+    /// Remembers a press on a carousel, a swipe may start from it
+    pub(crate) fn carousel_press(&mut self, button: MouseButton) {
+        self.swipe = None;
+        // Elements following the pointer themselves keep it
+        if button != MouseButton::Left || self.pointer_capture.is_some() {
+            return;
+        }
+        let Some((cref, view, page)) = self.hit_candidates().into_iter().find_map(|cref| {
+            let carousel = self.get_carousel(CarouselRef(cref))?;
+            Some((cref, carousel.view.raw(), carousel.page))
+        }) else {
+            return;
+        };
+        self.swipe = Some(Swipe {
+            carousel: cref,
+            view,
+            start: self.mouse_pos,
+            start_offset: self.scrolled_to(view).0,
+            page,
+            started: false,
+        });
+    }
+
+    /// Moves the pages along with the cursor once it went sideways past
+    /// the threshold. Returns whether a swipe is in progress.
+    pub(crate) fn carousel_swipe(&mut self) -> bool {
+        let pos = self.mouse_pos;
+        let Some(swipe) = &mut self.swipe else {
+            return false;
+        };
+        let (dx, dy) = (pos.x - swipe.start.x, pos.y - swipe.start.y);
+        if !swipe.started {
+            if dx.hypot(dy) < SWIPE_THRESHOLD {
+                return false;
+            }
+            if dx.abs() < dy.abs() {
+                // Going up or down, not a swipe
+                self.swipe = None;
+                return false;
+            }
+            swipe.started = true;
+        }
+
+        let (view, target) = (swipe.view, swipe.start_offset - dx as f32);
+        let (at, _) = self.scrolled_to(view);
+        if self.scroll_container_by(view, target - at, 0.0, false) {
+            // The cursor may be above another element now
+            self.pointer_moved = true;
+            self.dispatch_scroll_offsets();
+        }
+        true
+    }
+
+    /// Turns the page when the swipe went far enough, or slides the page
+    /// back. Returns whether a swipe was in progress, the release is then
+    /// no click.
+    pub(crate) fn carousel_release(&mut self) -> bool {
+        let Some(swipe) = self.swipe.take().filter(|swipe| swipe.started) else {
+            return false;
+        };
+        let carousel = CarouselRef(swipe.carousel);
+        let width = self
+            .get_carousel(carousel)
+            .map_or(0.0, |carousel| carousel.page_width as f64);
+        let dx = self.mouse_pos.x - swipe.start.x;
+
+        let page = if dx < -width * SWIPE_TURN {
+            swipe.page + 1
+        } else if dx > width * SWIPE_TURN {
+            swipe.page.saturating_sub(1)
+        } else {
+            swipe.page
+        };
+        self.set_carousel_page(carousel, page, true);
+        true
+    }
+
+    /// Whether a swipe moves the scroll view
+    pub(crate) fn swiping(&self, view: CapsuleRef) -> bool {
+        self.swipe
+            .as_ref()
+            .is_some_and(|swipe| swipe.started && swipe.view == view)
+    }
+
+    /// Forgets the swipe on a carousel removed from the tree
+    pub(crate) fn forget_carousel(&mut self, cref: CapsuleRef) {
+        self.page_change_callbacks.remove(&cref);
+        if self
+            .swipe
+            .as_ref()
+            .is_some_and(|swipe| swipe.carousel == cref)
+        {
+            self.swipe = None;
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Line, bar and pie charts drawn on a `Canvas`
+
+use std::time::Duration;
+
+use heka::color::Color;
+use heka::{Frame, Style, clr, layout, size};
+
+use crate::clock::AnimationClock;
+use crate::elements::{FrameElement, Paint, Scene, Shape, ShapeId};
+use crate::{CanvasRef, Context, Element, ElementRef, TextStyle};
+
+/// Time taken by data changes to settle
+const ANIMATION: Duration = Duration::from_millis(300);
+
+/// Room left around the plot for the tick labels
+const AXIS_LEFT: f32 = 40.0;
+const AXIS_BOTTOM: f32 = 20.0;
+const PLOT_MARGIN: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+    /// Slices of the first series
+    Pie,
+}
+
+/// Named values, one per chart label
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f32>,
+    pub color: Color,
+}
+
+impl Series {
+    pub fn new(name: impl Into<String>, values: Vec<f32>, color: Color) -> Self {
+        Self {
+            name: name.into(),
+            values,
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChartRef(pub(crate) heka::CapsuleRef);
+impl From<ChartRef> for Element {
+    fn from(v: ChartRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for ChartRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+/// Chart component.
+///
+/// Holds the data and rebuilds the scene of its canvas whenever the data,
+/// its size or the hovered value change.
+pub struct Chart {
+    pub(crate) frame: heka::Frame,
+    canvas: CanvasRef,
+    kind: ChartKind,
+    labels: Vec<String>,
+    series: Vec<Series>,
+    /// Values the current animation starts from
+    from: Vec<Vec<f32>>,
+    /// On the animation clock
+    animation_start: Option<Duration>,
+    /// (series, index) under the cursor, with the cursor position
+    hovered: Option<((usize, usize), [f32; 2])>,
+    /// Value each pickable shape stands for
+    hit_regions: Vec<(ShapeId, (usize, usize))>,
+    /// Canvas size the scene was built for
+    built_size: Option<(u32, u32)>,
+    needs_rebuild: bool,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Chart {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[CHART]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Chart {
+    #[inline]
+    pub fn kind(&self) -> ChartKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn series(&self) -> &[Series] {
+        &self.series
+    }
+
+    #[inline]
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Animation progress in 0..=1, eased out
+    fn progress(&self, clock: &AnimationClock) -> f32 {
+        let Some(start) = self.animation_start else {
+            return 1.0;
+        };
+        let t = clock.fraction(start, ANIMATION);
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    /// Values as currently displayed
+    fn shown_values(&self, t: f32) -> Vec<Vec<f32>> {
+        self.series
+            .iter()
+            .enumerate()
+            .map(|(s, series)| {
+                series
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, to)| {
+                        let from = self
+                            .from
+                            .get(s)
+                            .and_then(|values| values.get(i))
+                            .copied()
+                            .unwrap_or(0.0);
+                        from + (to - from) * t
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn build(
+        &mut self,
+        scene: &mut Scene,
+        (width, height): (u32, u32),
+        values: &[Vec<f32>],
+        theme_text: &TextStyle,
+    ) {
+        scene.clear();
+        self.hit_regions.clear();
+
+        let (width, height) = (width as f32, height as f32);
+        let small_text = TextStyle {
+            font_size: (theme_text.font_size - 3.0).max(8.0),
+            ..theme_text.clone()
+        };
+
+        match self.kind {
+            ChartKind::Pie => self.build_pie(scene, width, height, values),
+            ChartKind::Line | ChartKind::Bar => {
+                self.build_axes(scene, width, height, values, &small_text)
+            }
+        }
+
+        if let Some(((s, i), pos)) = self.hovered
+            && let (Some(series), Some(value)) = (
+                self.series.get(s),
+                self.series.get(s).and_then(|series| series.values.get(i)),
+            )
+        {
+            let label = self.labels.get(i).map(String::as_str).unwrap_or_default();
+            let text = if label.is_empty() {
+                format!("{}: {value}", series.name)
+            } else {
+                format!("{} ({label}): {value}", series.name)
+            };
+            let (x, y) = (pos[0] + 12.0, pos[1] + 12.0);
+            let w = text.chars().count() as f32 * small_text.font_size * 0.6 + 8.0;
+
+            scene.add(Shape::Rect {
+                x,
+                y,
+                w,
+                h: small_text.font_size * 1.2 + 6.0,
+                radius: 3.0,
+                paint: Paint {
+                    fill: clr!(0xFFFFFFEE),
+                    stroke: series.color,
+                    stroke_width: 1.0,
+                },
+            });
+            scene.add(Shape::Text {
+                x: x + 4.0,
+                y: y + 3.0,
+                text,
+                style: small_text.clone(),
+            });
+        }
+    }
+
+    /// Grid, tick labels and the line or bar series
+    fn build_axes(
+        &mut self,
+        scene: &mut Scene,
+        width: f32,
+        height: f32,
+        values: &[Vec<f32>],
+        tick_text: &TextStyle,
+    ) {
+        let plot_x = AXIS_LEFT;
+        let plot_y = PLOT_MARGIN;
+        let plot_w = (width - AXIS_LEFT - PLOT_MARGIN).max(1.0);
+        let plot_h = (height - AXIS_BOTTOM - PLOT_MARGIN).max(1.0);
+
+        let (min, max) = values
+            .iter()
+            .flatten()
+            .fold((0.0f32, 0.0f32), |(min, max), v| (min.min(*v), max.max(*v)));
+        let step = nice_step(max - min, 5);
+        let (low, high) = ((min / step).floor() * step, (max / step).ceil() * step);
+        let high = if high > low { high } else { low + step };
+        let to_y = |v: f32| plot_y + plot_h - (v - low) / (high - low) * plot_h;
+
+        // Horizontal grid with tick labels
+        let mut tick = low;
+        while tick <= high + step / 2.0 {
+            let y = to_y(tick);
+            scene.add(Shape::Path {
+                points: vec![[plot_x, y], [plot_x + plot_w, y]],
+                closed: false,
+                paint: Paint::stroke(clr!(0x00000022), 1.0),
+            });
+            scene.add(Shape::Text {
+                x: 2.0,
+                y: y - tick_text.font_size * 0.6,
+                text: format_tick(tick, step),
+                style: tick_text.clone(),
+            });
+            tick += step;
+        }
+
+        let count = values
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+            .max(self.labels.len());
+        if count == 0 {
+            return;
+        }
+        let slot = plot_w / count as f32;
+
+        for (i, label) in self.labels.iter().enumerate() {
+            let estimated_width = label.chars().count() as f32 * tick_text.font_size * 0.5;
+            scene.add(Shape::Text {
+                x: plot_x + slot * (i as f32 + 0.5) - estimated_width / 2.0,
+                y: plot_y + plot_h + 4.0,
+                text: label.clone(),
+                style: tick_text.clone(),
+            });
+        }
+
+        match self.kind {
+            ChartKind::Line => {
+                for (s, series) in self.series.iter().enumerate() {
+                    let points: Vec<[f32; 2]> = values[s]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| [plot_x + slot * (i as f32 + 0.5), to_y(*v)])
+                        .collect();
+
+                    scene.add(Shape::Path {
+                        points: points.clone(),
+                        closed: false,
+                        paint: Paint::stroke(series.color, 2.0),
+                    });
+                    for (i, [cx, cy]) in points.into_iter().enumerate() {
+                        let id = scene.add(Shape::Circle {
+                            cx,
+                            cy,
+                            r: 4.0,
+                            paint: Paint::fill(series.color),
+                        });
+                        self.hit_regions.push((id, (s, i)));
+                    }
+                }
+            }
+            ChartKind::Bar => {
+                let group = slot * 0.8;
+                let bar = group / self.series.len().max(1) as f32;
+                let zero = to_y(0.0f32.clamp(low, high));
+
+                for (s, series) in self.series.iter().enumerate() {
+                    for (i, v) in values[s].iter().enumerate() {
+                        let top = to_y(*v);
+                        let id = scene.add(Shape::Rect {
+                            x: plot_x + slot * i as f32 + (slot - group) / 2.0 + bar * s as f32,
+                            y: top.min(zero),
+                            w: (bar - 2.0).max(1.0),
+                            h: (top - zero).abs(),
+                            radius: 0.0,
+                            paint: Paint::fill(series.color),
+                        });
+                        self.hit_regions.push((id, (s, i)));
+                    }
+                }
+            }
+            ChartKind::Pie => {}
+        }
+    }
+
+    /// Slices of the first series, as fans around the center
+    fn build_pie(&mut self, scene: &mut Scene, width: f32, height: f32, values: &[Vec<f32>]) {
+        let Some(values) = values.first() else {
+            return;
+        };
+        let total: f32 = values.iter().map(|v| v.max(0.0)).sum();
+        if total <= 0.0 {
+            return;
+        }
+
+        let center = [width / 2.0, height / 2.0];
+        let radius = (width.min(height) / 2.0 - PLOT_MARGIN).max(1.0);
+        let color = self.series[0].color;
+
+        let mut angle = -std::f32::consts::FRAC_PI_2;
+        for (i, v) in values.iter().enumerate() {
+            let sweep = v.max(0.0) / total * std::f32::consts::TAU;
+            if sweep <= 0.0 {
+                continue;
+            }
+
+            let steps = ((sweep / 0.1).ceil() as usize).max(2);
+            let mut points = vec![center];
+            points.extend((0..=steps).map(|k| {
+                let a = angle + sweep * k as f32 / steps as f32;
+                [center[0] + radius * a.cos(), center[1] + radius * a.sin()]
+            }));
+
+            // Shades of the series color tell slices apart
+            let shade = color.lerp(Color::white, i as f32 / values.len() as f32 * 0.7);
+            let id = scene.add(Shape::Path {
+                points,
+                closed: true,
+                paint: Paint {
+                    fill: shade,
+                    stroke: Color::white,
+                    stroke_width: 1.0,
+                },
+            });
+            self.hit_regions.push((id, (0, i)));
+            angle += sweep;
+        }
+    }
+}
+
+/// Round tick spacing (1, 2 or 5 times a power of ten) giving about `count` ticks
+fn nice_step(range: f32, count: u32) -> f32 {
+    if range <= 0.0 {
+        return 1.0;
+    }
+    let raw = range / count as f32;
+    let magnitude = 10f32.powf(raw.log10().floor());
+    let normalized = raw / magnitude;
+    let nice = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+fn format_tick(value: f32, step: f32) -> String {
+    if step >= 1.0 {
+        format!("{value:.0}")
+    } else {
+        let decimals = (-step.log10().floor()) as usize;
+        format!("{value:.decimals$}")
+    }
+}
+
+impl Context {
+    /// Creates a new `Chart` component. Its size comes from `style`.
+    pub fn new_chart(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        kind: ChartKind,
+        style: Style,
+    ) -> ChartRef {
+        let parent = if let Some(pf) = parent_frame {
+            &Frame::define(pf.raw())
+        } else {
+            &self.root_frame
+        };
+
+        let chart_frame = self.root.add_frame_child(parent, None);
+        chart_frame.update_style(&mut self.root, |s| {
+            *s = style;
+            s.layout = layout!(flex);
+        });
+        let chart_ref = ChartRef(chart_frame.get_ref());
+
+        let canvas = self.new_canvas(
+            Some(chart_ref),
+            heka::make_style!(
+                width: size!(100%),
+                height: size!(100%),
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let chart = Chart {
+            frame: chart_frame,
+            canvas,
+            kind,
+            labels: Vec::new(),
+            series: Vec::new(),
+            from: Vec::new(),
+            animation_start: None,
+            hovered: None,
+            hit_regions: Vec::new(),
+            built_size: None,
+            needs_rebuild: true,
+        };
+        self.elements.insert(chart_ref.raw(), Box::new(chart));
+
+        self.on_pointer_move(canvas, move |ctx, event| {
+            let local = [event.local_pos.x as f32, event.local_pos.y as f32];
+            let shape = ctx.get_canvas(canvas).and_then(|canvas| canvas.pick(local));
+            ctx.with_chart(chart_ref, |chart| {
+                let hovered = shape.and_then(|shape| {
+                    let (_, value) = chart.hit_regions.iter().find(|(id, _)| *id == shape)?;
+                    Some((*value, local))
+                });
+                if hovered != chart.hovered {
+                    chart.hovered = hovered;
+                    chart.needs_rebuild = true;
+                }
+            });
+        });
+        self.on_hover(canvas, move |ctx, event| {
+            if !event.hovered {
+                ctx.with_chart(chart_ref, |chart| {
+                    chart.needs_rebuild |= chart.hovered.take().is_some();
+                });
+            }
+        });
+
+        chart_ref
+    }
+
+    pub fn get_chart(&self, element: ChartRef) -> Option<&Chart> {
+        let chart = self.elements.get(&element.raw())?;
+        chart.as_any().downcast_ref::<Chart>()
+    }
+
+    /// Replaces the data of a chart, animating from the displayed values.
+    pub fn set_chart_data(&mut self, element: ChartRef, labels: Vec<String>, series: Vec<Series>) {
+        let clock = self.clock;
+        self.with_chart(element, |chart| {
+            chart.from = chart.shown_values(chart.progress(&clock));
+            chart.labels = labels;
+            chart.series = series;
+            chart.animation_start = Some(clock.now());
+            chart.hovered = None;
+            chart.needs_rebuild = true;
+        });
+    }
+
+    fn with_chart(&mut self, element: ChartRef, op: impl FnOnce(&mut Chart)) {
+        if let Some(chart) = self
+            .elements
+            .get_mut(&element.raw())
+            .and_then(|element| element.as_any_mut().downcast_mut::<Chart>())
+        {
+            op(chart);
+            self.request_redraw();
+        }
+    }
+
+    /// Rebuilds the scenes of the charts whose data, size or hovered value
+    /// changed since the last frame. Runs after layout.
+    pub(crate) fn update_charts(&mut self) {
+        let charts: Vec<heka::CapsuleRef> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| element.as_any().is::<Chart>())
+            .map(|(cref, _)| *cref)
+            .collect();
+
+        let text_style = self.theme.text_style();
+        for cref in charts {
+            self.with_component_mut(cref, |chart: &mut Chart, ctx| {
+                let Some(content) = ctx.root.get_content_rect(chart.canvas.raw()) else {
+                    return;
+                };
+                let size = (content.w, content.h);
+                if !chart.needs_rebuild
+                    && chart.animation_start.is_none()
+                    && chart.built_size == Some(size)
+                {
+                    return;
+                }
+
+                let t = chart.progress(&ctx.clock);
+                let values = chart.shown_values(t);
+                ctx.update_canvas(chart.canvas, |scene| {
+                    chart.build(scene, size, &values, &text_style)
+                });
+
+                chart.built_size = Some(size);
+                chart.needs_rebuild = false;
+                if t >= 1.0 {
+                    chart.animation_start = None;
+                } else if ctx.clock.running() {
+                    ctx.request_redraw();
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! The clock animations sample: wall time, scaled for slow motion and
+//! standing still while animations are paused. It also holds the time the
+//! context runs at, see `Context::now`.
+
+use std::time::{Duration, Instant};
+
+use crate::Context;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AnimationClock {
+    /// Animation time at `anchor`
+    elapsed: Duration,
+    anchor: Instant,
+    scale: f32,
+    paused: bool,
+    /// Transitions end as soon as they start
+    reduced_motion: bool,
+    /// Wall time of the session being replayed, see `Context::replay`
+    replay_time: Option<Instant>,
+}
+
+impl AnimationClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            anchor: Instant::now(),
+            scale: 1.0,
+            paused: false,
+            reduced_motion: false,
+            replay_time: None,
+        }
+    }
+
+    /// Wall time, or the time of the session being replayed
+    pub(crate) fn wall(&self) -> Instant {
+        self.replay_time.unwrap_or_else(Instant::now)
+    }
+
+    /// Runs on `time` instead of the wall clock, `None` goes back to it.
+    /// The animation time goes on from where it is.
+    pub(crate) fn set_replay_time(&mut self, time: Option<Instant>) {
+        self.elapsed = self.now();
+        self.replay_time = time;
+        self.anchor = self.wall();
+    }
+
+    /// Animation time since the clock was created
+    pub(crate) fn now(&self) -> Duration {
+        if self.paused {
+            return self.elapsed;
+        }
+        self.elapsed
+            + self
+                .wall()
+                .saturating_duration_since(self.anchor)
+                .mul_f32(self.scale)
+    }
+
+    /// Part of an animation of `duration` started at `start` that has run,
+    /// in 0..=1
+    pub(crate) fn fraction(&self, start: Duration, duration: Duration) -> f32 {
+        if self.reduced_motion || duration.is_zero() {
+            return 1.0;
+        }
+        (self.now().saturating_sub(start).as_secs_f32() / duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Whether the time moves, animations in progress need more frames
+    pub(crate) fn running(&self) -> bool {
+        !self.paused && self.scale > 0.0
+    }
+
+    /// Keeps the time reached so far, before the scale or pause changes
+    fn rebase(&mut self) {
+        self.elapsed = self.now();
+        self.anchor = self.wall();
+    }
+}
+
+impl Context {
+    /// The time the context runs at: the wall clock, or the time of the
+    /// session `replay` is running. Timers, gestures and animations read it.
+    #[inline]
+    pub fn now(&self) -> Instant {
+        self.clock.wall()
+    }
+
+    /// Speed of the animations: 1 is real time, 0.25 four times slower.
+    /// Negative values count as 0, which stops the time like
+    /// `pause_animations`.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.clock.rebase();
+        self.clock.scale = if scale.is_finite() {
+            scale.max(0.0)
+        } else {
+            1.0
+        };
+        self.request_redraw();
+    }
+
+    #[inline]
+    pub fn time_scale(&self) -> f32 {
+        self.clock.scale
+    }
+
+    /// Freezes the animations where they are, until `resume_animations`
+    pub fn pause_animations(&mut self) {
+        self.clock.rebase();
+        self.clock.paused = true;
+    }
+
+    pub fn resume_animations(&mut self) {
+        self.clock.rebase();
+        self.clock.paused = false;
+        self.request_redraw();
+    }
+
+    #[inline]
+    pub fn animations_paused(&self) -> bool {
+        self.clock.paused
+    }
+
+    /// Turns off the transitions that are only there for the eye (gauges
+    /// and charts moving to their new values), which jump to their end.
+    /// For users who are bothered by motion, or to save battery.
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.clock.reduced_motion = reduced;
+        self.request_redraw();
+    }
+
+    #[inline]
+    pub fn reduced_motion(&self) -> bool {
+        self.clock.reduced_motion
+    }
+
+    /// Time of the animation clock, to drive custom animations the way the
+    /// built-in ones are: slowed down by `set_time_scale` and stopped by
+    /// `pause_animations`
+    #[inline]
+    pub fn animation_time(&self) -> Duration {
+        self.clock.now()
+    }
+}
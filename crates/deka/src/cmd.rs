@@ -1,15 +1,18 @@
 use super::Context;
-use super::TextStyle;
-use super::renderer::gui::utils::TVertex;
+use super::elements::ImageData;
+use super::renderer::gui::utils::{DISABLED, NO_CLIP, TVertex};
+#[cfg(feature = "text")]
+use super::{TextDecoration, TextStyle};
 use crate::renderer::atlas::{Atlas, TextureUpdate};
+#[cfg(feature = "text")]
 use cosmic_text::Buffer;
-use heka::{Space, color::Color};
+use heka::{color::Color, geometry::Rect};
 
 #[derive(Debug, Clone)]
 pub enum DrawCommand {
     /// A rectangle with optional fill and stroke.
     Rect {
-        space: Space,
+        rect: Rect,
         z_index: u32,
         fill_color: Color,
         border_radius: u32,
@@ -19,27 +22,92 @@ pub enum DrawCommand {
         shadow_blur: f32,
     },
     /// A block of text.
+    #[cfg(feature = "text")]
     Text {
-        space: Space,
+        rect: Rect,
         buffer_ref: heka::DataRef,
         style: TextStyle,
         z_index: u32,
     },
-    // `Image { ... }`, `Svg { ... }`, etc.
+    /// Flat-colored triangles, given as points and triangle indices.
+    Mesh {
+        points: Vec<[f32; 2]>,
+        indices: Vec<u32>,
+        color: Color,
+        z_index: u32,
+    },
+    /// An image stretched over `rect`. `uv` is the part of the image shown,
+    /// as `[left, top, right, bottom]` from 0 to 1, and `tint` multiplies
+    /// its pixels.
+    Image {
+        rect: Rect,
+        texture_ref: heka::DataRef,
+        uv: [f32; 4],
+        tint: Color,
+        z_index: u32,
+    },
+    /// Another command, only drawn inside `clip`.
+    Clipped {
+        clip: Rect,
+        command: Box<DrawCommand>,
+    },
+    /// Another command, faded and greyed out as part of a disabled element.
+    Disabled { command: Box<DrawCommand> },
+    // `Svg { ... }`, etc.
 }
 
 impl DrawCommand {
+    /// Whether the command can change any pixel. Fully transparent or
+    /// empty commands are not worth their vertices.
+    pub fn is_visible(&self) -> bool {
+        match self {
+            DrawCommand::Rect {
+                rect,
+                fill_color,
+                stroke_color,
+                stroke_width,
+                shadow_color,
+                shadow_blur,
+                ..
+            } => {
+                !rect.is_empty()
+                    && (fill_color.a > 0
+                        || (stroke_color.a > 0 && *stroke_width > 0)
+                        || (shadow_color.a > 0 && *shadow_blur > 0.0))
+            }
+            // Glyphs are not cut to the rectangle of the text, it may be empty
+            #[cfg(feature = "text")]
+            DrawCommand::Text { style, .. } => style.color.a > 0,
+            DrawCommand::Mesh { indices, color, .. } => color.a > 0 && !indices.is_empty(),
+            DrawCommand::Image { rect, tint, .. } => !rect.is_empty() && tint.a > 0,
+            DrawCommand::Clipped { clip, command } => !clip.is_empty() && command.is_visible(),
+            DrawCommand::Disabled { command } => command.is_visible(),
+        }
+    }
+
+    /// Style of the text the command draws, if it draws one
+    #[cfg(feature = "text")]
+    pub(crate) fn text_style_mut(&mut self) -> Option<&mut TextStyle> {
+        match self {
+            DrawCommand::Text { style, .. } => Some(style),
+            DrawCommand::Clipped { command, .. } | DrawCommand::Disabled { command } => {
+                command.text_style_mut()
+            }
+            _ => None,
+        }
+    }
+
     pub fn rect_vertices(
-        space: &Space,
+        rect: &Rect,
         color: &Color,
         radius: u32,
         stroke_width: u32,
         blur: f32,
     ) -> [TVertex; 4] {
-        let mut w = space.width.unwrap_or(0) as f32;
-        let mut h = space.height.unwrap_or(0) as f32;
-        let mut x = space.x as f32;
-        let mut y = space.y as f32;
+        let mut w = rect.w as f32;
+        let mut h = rect.h as f32;
+        let mut x = rect.x as f32;
+        let mut y = rect.y as f32;
 
         if blur > 0.0 {
             let expansion = blur * 2.0;
@@ -71,6 +139,8 @@ impl DrawCommand {
                 stroke_width: s,
                 blur,
                 obj_type: 0,
+                clip: NO_CLIP,
+                page: 0,
             },
             // Bottom-Left
             TVertex {
@@ -82,6 +152,8 @@ impl DrawCommand {
                 stroke_width: s,
                 blur,
                 obj_type: 0,
+                clip: NO_CLIP,
+                page: 0,
             },
             // Top-Right
             TVertex {
@@ -93,6 +165,8 @@ impl DrawCommand {
                 stroke_width: s,
                 blur,
                 obj_type: 0,
+                clip: NO_CLIP,
+                page: 0,
             },
             // Bottom-Right
             TVertex {
@@ -104,10 +178,71 @@ impl DrawCommand {
                 stroke_width: s,
                 blur,
                 obj_type: 0,
+                clip: NO_CLIP,
+                page: 0,
             },
         ]
     }
 
+    /// A plain filled quad, used for text decorations
+    #[cfg(feature = "text")]
+    fn line_vertices(x: f32, y: f32, w: f32, h: f32, color: [f32; 4]) -> [TVertex; 4] {
+        let vertex = |position, uv| TVertex {
+            position,
+            color,
+            uv,
+            size: [w, h],
+            radius: 0.0,
+            stroke_width: 0.0,
+            blur: 0.0,
+            obj_type: 0,
+            clip: NO_CLIP,
+            page: 0,
+        };
+
+        [
+            vertex([x, y], [0.0, 0.0]),
+            vertex([x, y + h], [0.0, 1.0]),
+            vertex([x + w, y], [1.0, 0.0]),
+            vertex([x + w, y + h], [1.0, 1.0]),
+        ]
+    }
+
+    /// Decoration lines of one layout run, as (top, thickness) in layout units.
+    /// Offsets and thickness come from the metrics of the run's first font.
+    #[cfg(feature = "text")]
+    pub(crate) fn decoration_lines(
+        ctx: &mut Context,
+        run: &cosmic_text::LayoutRun,
+        decoration: TextDecoration,
+        baseline: f32,
+        scale: f32,
+    ) -> Vec<(f32, f32)> {
+        let Some(glyph) = run.glyphs.first() else {
+            return vec![];
+        };
+        let Some(font) = ctx.font_system.get_font(glyph.font_id, glyph.font_weight) else {
+            return vec![];
+        };
+        let metrics = font.as_swash().metrics(&[]).scale(glyph.font_size);
+
+        // At least one device pixel thick, lines sit on the device-pixel grid
+        let thickness = (metrics.stroke_size * scale).round().max(1.0) / scale;
+        let snap = |y: f32| (y * scale).round() / scale;
+
+        let mut lines = Vec::with_capacity(3);
+        if decoration.contains(TextDecoration::UNDERLINE) {
+            lines.push((snap(baseline - metrics.underline_offset), thickness));
+        }
+        if decoration.contains(TextDecoration::STRIKETHROUGH) {
+            lines.push((snap(baseline - metrics.strikeout_offset), thickness));
+        }
+        if decoration.contains(TextDecoration::OVERLINE) {
+            lines.push((snap(baseline - metrics.ascent), thickness));
+        }
+        lines
+    }
+
     pub fn to_geometry(
         &self,
         ctx: &mut Context,
@@ -115,8 +250,33 @@ impl DrawCommand {
         uploads: &mut Vec<TextureUpdate>,
     ) -> (Vec<TVertex>, Vec<u32>) {
         match self {
+            DrawCommand::Clipped { clip, command } => {
+                let (mut vertices, indices) = command.to_geometry(ctx, atlas, uploads);
+                let [x0, y0, x1, y1] = [
+                    clip.x as f32,
+                    clip.y as f32,
+                    clip.x as f32 + clip.w as f32,
+                    clip.y as f32 + clip.h as f32,
+                ];
+                for vertex in &mut vertices {
+                    vertex.clip = [
+                        vertex.clip[0].max(x0),
+                        vertex.clip[1].max(y0),
+                        vertex.clip[2].min(x1),
+                        vertex.clip[3].min(y1),
+                    ];
+                }
+                (vertices, indices)
+            }
+            DrawCommand::Disabled { command } => {
+                let (mut vertices, indices) = command.to_geometry(ctx, atlas, uploads);
+                for vertex in &mut vertices {
+                    vertex.obj_type |= DISABLED;
+                }
+                (vertices, indices)
+            }
             DrawCommand::Rect {
-                space,
+                rect,
                 fill_color,
                 stroke_color,
                 z_index: _,
@@ -144,7 +304,7 @@ impl DrawCommand {
                 // Draw Shadow (if visible)
                 if shadow_color.a > 0 && *shadow_blur > 0.0 {
                     add_quad(Self::rect_vertices(
-                        space,
+                        rect,
                         shadow_color,
                         *border_radius,
                         0,
@@ -155,7 +315,7 @@ impl DrawCommand {
                 // Draw Fill (if visible)
                 if fill_color.a > 0 {
                     add_quad(Self::rect_vertices(
-                        space,
+                        rect,
                         fill_color,
                         *border_radius,
                         0, // Fill has 0 stroke width
@@ -166,7 +326,7 @@ impl DrawCommand {
                 // Draw Stroke (if visible and has width)
                 if stroke_color.a > 0 && *stroke_width > 0 {
                     add_quad(Self::rect_vertices(
-                        space,
+                        rect,
                         stroke_color,
                         *border_radius,
                         *stroke_width,
@@ -176,9 +336,76 @@ impl DrawCommand {
 
                 (vertices, indices)
             }
+            DrawCommand::Mesh {
+                points,
+                indices,
+                color,
+                z_index: _,
+            } => {
+                let color_arr: [f32; 4] = (*color).into();
+                let vertices = points
+                    .iter()
+                    .map(|p| TVertex {
+                        position: *p,
+                        color: color_arr,
+                        uv: [0.0, 0.0],
+                        size: [0.0, 0.0],
+                        radius: 0.0,
+                        stroke_width: 0.0,
+                        blur: 0.0,
+                        obj_type: 2,
+                        clip: NO_CLIP,
+                        page: 0,
+                    })
+                    .collect();
+
+                (vertices, indices.clone())
+            }
+            DrawCommand::Image {
+                rect,
+                texture_ref,
+                uv: [u0, v0, u1, v1],
+                tint,
+                z_index: _,
+            } => {
+                let Some(image) = ctx.get_buffer::<ImageData>(*texture_ref) else {
+                    return (vec![], vec![]);
+                };
+                let Some(page) =
+                    atlas.image_page(&image.pixels, image.width(), image.height(), uploads)
+                else {
+                    return (vec![], vec![]);
+                };
+
+                let color: [f32; 4] = (*tint).into();
+                let [x, y, w, h] = [rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32];
+                let vertex = |position, uv| TVertex {
+                    position,
+                    color,
+                    uv,
+                    size: [w, h],
+                    radius: 0.0,
+                    stroke_width: 0.0,
+                    blur: 0.0,
+                    obj_type: 3,
+                    clip: NO_CLIP,
+                    page: page as u32,
+                };
+
+                (
+                    vec![
+                        vertex([x, y], [*u0, *v0]),
+                        vertex([x, y + h], [*u0, *v1]),
+                        vertex([x + w, y], [*u1, *v0]),
+                        vertex([x + w, y + h], [*u1, *v1]),
+                    ],
+                    vec![0, 1, 2, 2, 1, 3],
+                )
+            }
+            #[cfg(feature = "text")]
             DrawCommand::Text {
                 buffer_ref,
-                space,
+                rect,
                 style,
                 z_index: _,
             } => {
@@ -189,27 +416,33 @@ impl DrawCommand {
 
                 // Color from style
                 let color_arr: [f32; 4] = style.color.into();
+                let scale = ctx.scale_factor * ctx.ui_scale;
 
                 let mut vertices = vec![];
                 let mut indices = vec![];
 
                 for run in buffer.layout_runs() {
                     for glyph in run.glyphs.iter() {
-                        let phys =
-                            glyph.physical((space.x as f32, space.y as f32 + run.line_y), 1.0);
+                        // Glyphs are rasterized at device pixels: the cache key carries
+                        // the scaled font size, so each scale gets its own atlas entries.
+                        let phys = glyph.physical(
+                            (rect.x as f32 * scale, (rect.y as f32 + run.line_y) * scale),
+                            scale,
+                        );
 
                         let image = ctx
                             .swash_cache
                             .get_image(&mut ctx.font_system, phys.cache_key);
 
                         if let Some(image) = image {
-                            if let Some((ax, ay, is_new)) = atlas.allocate(
+                            if let Some((page, ax, ay, is_new)) = atlas.allocate(
                                 phys.cache_key,
                                 image.placement.width,
                                 image.placement.height,
                             ) {
                                 if is_new {
                                     uploads.push(TextureUpdate {
+                                        page,
                                         x: ax,
                                         y: ay,
                                         width: image.placement.width,
@@ -218,10 +451,11 @@ impl DrawCommand {
                                     });
                                 }
 
-                                let x = phys.x as f32 + image.placement.left as f32;
-                                let y = phys.y as f32 - image.placement.top as f32;
-                                let w = image.placement.width as f32;
-                                let h = image.placement.height as f32;
+                                // Snapped to the device-pixel grid, then back to layout units
+                                let x = (phys.x + image.placement.left) as f32 / scale;
+                                let y = (phys.y - image.placement.top) as f32 / scale;
+                                let w = image.placement.width as f32 / scale;
+                                let h = image.placement.height as f32 / scale;
 
                                 // UVs
                                 let u0 = ax as f32 / atlas.width as f32;
@@ -240,6 +474,8 @@ impl DrawCommand {
                                     stroke_width: 0.0,
                                     blur: 0.0,
                                     obj_type: 1,
+                                    clip: NO_CLIP,
+                                    page: page as u32,
                                 });
                                 vertices.push(TVertex {
                                     position: [x, y + h],
@@ -250,6 +486,8 @@ impl DrawCommand {
                                     stroke_width: 0.0,
                                     blur: 0.0,
                                     obj_type: 1,
+                                    clip: NO_CLIP,
+                                    page: page as u32,
                                 });
                                 vertices.push(TVertex {
                                     position: [x + w, y],
@@ -260,6 +498,8 @@ impl DrawCommand {
                                     stroke_width: 0.0,
                                     blur: 0.0,
                                     obj_type: 1,
+                                    clip: NO_CLIP,
+                                    page: page as u32,
                                 });
                                 vertices.push(TVertex {
                                     position: [x + w, y + h],
@@ -270,6 +510,8 @@ impl DrawCommand {
                                     stroke_width: 0.0,
                                     blur: 0.0,
                                     obj_type: 1,
+                                    clip: NO_CLIP,
+                                    page: page as u32,
                                 });
 
                                 indices.extend([
@@ -283,6 +525,39 @@ impl DrawCommand {
                             }
                         }
                     }
+
+                    if style.decoration.is_none() || run.glyphs.is_empty() {
+                        continue;
+                    }
+
+                    let start = run.glyphs.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+                    let end = run
+                        .glyphs
+                        .iter()
+                        .map(|g| g.x + g.w)
+                        .fold(f32::MIN, f32::max);
+                    let baseline = rect.y as f32 + run.line_y;
+
+                    for (top, thickness) in
+                        Self::decoration_lines(ctx, &run, style.decoration, baseline, scale)
+                    {
+                        let start_v = vertices.len() as u32;
+                        vertices.extend(Self::line_vertices(
+                            rect.x as f32 + start,
+                            top,
+                            end - start,
+                            thickness,
+                            color_arr,
+                        ));
+                        indices.extend([
+                            start_v,
+                            start_v + 1,
+                            start_v + 2,
+                            start_v + 2,
+                            start_v + 1,
+                            start_v + 3,
+                        ]);
+                    }
                 }
 
                 (vertices, indices)
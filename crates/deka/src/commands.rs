@@ -0,0 +1,285 @@
+//! Reversible UI mutations, for undo/redo support
+
+use std::any::Any;
+
+#[cfg(feature = "text")]
+use crate::TextInputRef;
+use crate::{CheckboxRef, Context};
+
+/// Undo steps kept by default
+const DEFAULT_LIMIT: usize = 100;
+
+/// A mutation that can be reverted and applied again
+pub trait Command: 'static {
+    fn undo(&mut self, ctx: &mut Context);
+    fn redo(&mut self, ctx: &mut Context);
+
+    /// Absorbs a command that directly follows this one (typing in the same
+    /// input...). Returns whether `next` was merged.
+    fn merge(&mut self, _next: &dyn Command) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Whether a text edit added or removed characters
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEditKind {
+    Insert,
+    Delete,
+}
+
+/// The text of a `TextInput` changed
+#[cfg(feature = "text")]
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub input: TextInputRef,
+    pub kind: TextEditKind,
+    pub before: String,
+    pub after: String,
+    /// Selection of the input around the edit, as (anchor, cursor)
+    pub(crate) selection_before: (usize, usize),
+    pub(crate) selection_after: (usize, usize),
+}
+
+#[cfg(feature = "text")]
+impl Command for TextEdit {
+    fn undo(&mut self, ctx: &mut Context) {
+        ctx.set_input_text(self.input, &self.before);
+        ctx.set_text_selection(self.input, self.selection_before);
+    }
+
+    fn redo(&mut self, ctx: &mut Context) {
+        ctx.set_input_text(self.input, &self.after);
+        ctx.set_text_selection(self.input, self.selection_after);
+    }
+
+    /// Consecutive insertions (or deletions) in the same input form one step
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        match next.as_any().downcast_ref::<TextEdit>() {
+            Some(next) if next.input == self.input && next.kind == self.kind => {
+                self.after = next.after.clone();
+                self.selection_after = next.selection_after;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A checkbox was toggled
+#[derive(Debug, Clone, Copy)]
+pub struct Toggle {
+    pub checkbox: CheckboxRef,
+}
+
+impl Command for Toggle {
+    fn undo(&mut self, ctx: &mut Context) {
+        ctx.toggle_checkbox(self.checkbox);
+    }
+
+    fn redo(&mut self, ctx: &mut Context) {
+        ctx.toggle_checkbox(self.checkbox);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Commands undone (or redone) together
+type Step = Vec<Box<dyn Command>>;
+
+/// Undo and redo stacks of a `Context`
+pub(crate) struct History {
+    pub(crate) enabled: bool,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// Step being built by an open group, with the group nesting depth
+    group: Option<(Step, usize)>,
+    /// Commands are being undone or redone: mutations are not recorded
+    replaying: bool,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            group: None,
+            replaying: false,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl History {
+    #[inline]
+    pub(crate) fn is_recording(&self) -> bool {
+        self.enabled && !self.replaying
+    }
+
+    pub(crate) fn push(&mut self, command: Box<dyn Command>) {
+        if !self.is_recording() {
+            return;
+        }
+        self.redo.clear();
+
+        if let Some((step, _)) = &mut self.group {
+            Self::append(step, command);
+            return;
+        }
+
+        let merged = self
+            .undo
+            .last_mut()
+            .is_some_and(|last| last.len() == 1 && last[0].merge(command.as_ref()));
+        if !merged {
+            self.undo.push(vec![command]);
+            if self.undo.len() > self.limit {
+                self.undo.remove(0);
+            }
+        }
+    }
+
+    fn append(step: &mut Step, command: Box<dyn Command>) {
+        let merged = step
+            .last_mut()
+            .is_some_and(|last| last.merge(command.as_ref()));
+        if !merged {
+            step.push(command);
+        }
+    }
+
+    pub(crate) fn begin_group(&mut self) {
+        match &mut self.group {
+            Some((_, depth)) => *depth += 1,
+            None => self.group = Some((Vec::new(), 1)),
+        }
+    }
+
+    pub(crate) fn end_group(&mut self) {
+        let Some((_, depth)) = &mut self.group else {
+            return;
+        };
+        *depth -= 1;
+        if *depth > 0 {
+            return;
+        }
+
+        if let Some((step, _)) = self.group.take()
+            && !step.is_empty()
+        {
+            self.undo.push(step);
+            if self.undo.len() > self.limit {
+                self.undo.remove(0);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+    }
+}
+
+impl Context {
+    /// Starts (or stops) recording built-in widget mutations (text edits,
+    /// checkbox toggles) for `undo` and `redo`.
+    pub fn enable_undo(&mut self, enabled: bool) {
+        self.history.enabled = enabled;
+        if !enabled {
+            self.history.clear();
+        }
+    }
+
+    /// Number of undo steps kept, the oldest ones are dropped first
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.history.limit = limit.max(1);
+        let excess = self.history.undo.len().saturating_sub(self.history.limit);
+        self.history.undo.drain(..excess);
+    }
+
+    /// Records an application command next to the widget ones.
+    /// The command is expected to be already applied.
+    pub fn record_command(&mut self, command: impl Command) {
+        self.history.push(Box::new(command));
+    }
+
+    /// Records every command pushed by `op` as a single undo step
+    pub fn undo_group<R>(&mut self, op: impl FnOnce(&mut Context) -> R) -> R {
+        self.begin_undo_group();
+        let result = op(self);
+        self.end_undo_group();
+        result
+    }
+
+    /// Commands recorded until the matching `end_undo_group` form a single undo step.
+    /// Groups can be nested.
+    pub fn begin_undo_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    pub fn end_undo_group(&mut self) {
+        self.history.end_group();
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Reverts the last step. Returns whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let Some(mut step) = self.history.undo.pop() else {
+            return false;
+        };
+
+        self.history.replaying = true;
+        self.guarded(|ctx| {
+            for command in step.iter_mut().rev() {
+                command.undo(ctx);
+            }
+        });
+        self.history.replaying = false;
+
+        self.history.redo.push(step);
+        true
+    }
+
+    /// Applies the last undone step again. Returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(mut step) = self.history.redo.pop() else {
+            return false;
+        };
+
+        self.history.replaying = true;
+        self.guarded(|ctx| {
+            for command in step.iter_mut() {
+                command.redo(ctx);
+            }
+        });
+        self.history.replaying = false;
+
+        self.history.undo.push(step);
+        true
+    }
+
+    pub fn clear_undo_history(&mut self) {
+        self.history.clear();
+    }
+}
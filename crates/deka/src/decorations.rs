@@ -0,0 +1,170 @@
+//! Decorations: small boxes drawn next to an element (bullets, required
+//! marks, count badges) without a frame of their own.
+
+#[cfg(feature = "text")]
+use cosmic_text::Buffer;
+use heka::decoration::{Decoration, DecorationId};
+#[cfg(feature = "text")]
+use heka::geometry::Rect;
+
+#[cfg(feature = "text")]
+use crate::TextStyle;
+#[cfg(feature = "text")]
+use crate::elements::fill_buffer;
+use crate::{Context, ElementRef, cmd};
+
+/// Room left around the text of a text decoration
+#[cfg(feature = "text")]
+const TEXT_DECORATION_PADDING: u32 = 2;
+
+/// Text drawn in a decoration
+#[cfg(feature = "text")]
+pub(crate) struct DecorationText {
+    /// Element the decoration belongs to
+    owner: heka::CapsuleRef,
+    style: TextStyle,
+    /// Size of the shaped text
+    size: (u32, u32),
+}
+
+impl Context {
+    /// Draws `decoration` next to an element. Returns `None` if the element
+    /// is gone.
+    pub fn add_decoration(
+        &mut self,
+        element: impl ElementRef,
+        decoration: Decoration,
+    ) -> Option<DecorationId> {
+        let id = self.root.add_decoration(element.raw(), decoration)?;
+        self.redraw_requested = true;
+        Some(id)
+    }
+
+    /// Draws `text` in a decoration next to an element (an asterisk after a
+    /// required field, the count of a badge...). The decoration grows to fit
+    /// the text when its `width` or `height` is too small.
+    #[cfg(feature = "text")]
+    pub fn add_text_decoration(
+        &mut self,
+        element: impl ElementRef,
+        text: impl AsRef<str>,
+        text_style: TextStyle,
+        decoration: Decoration,
+    ) -> Option<DecorationId> {
+        self.root.get_capsule(element.raw())?;
+
+        let mut buffer = Buffer::new(&mut self.font_system, text_style.as_cosmic_metrics());
+        buffer.set_size(&mut self.font_system, None, None);
+        fill_buffer(
+            &mut buffer,
+            &mut self.font_system,
+            text.as_ref(),
+            &text_style,
+        );
+        buffer.shape_until_scroll(&mut self.font_system, true);
+
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max)
+            .ceil() as u32;
+        let height = buffer
+            .layout_runs()
+            .last()
+            .map(|run| run.line_top + run.line_height)
+            .unwrap_or(0.0)
+            .ceil() as u32;
+
+        let padding = 2 * (TEXT_DECORATION_PADDING + decoration.border.size);
+        let decoration = Decoration {
+            width: decoration.width.max(width + padding),
+            height: decoration.height.max(height + padding),
+            data_ref: Some(self.root.set_binding(buffer)),
+            ..decoration
+        };
+        let id = self.add_decoration(element, decoration)?;
+        self.decoration_texts.insert(
+            id,
+            DecorationText {
+                owner: element.raw(),
+                style: text_style,
+                size: (width, height),
+            },
+        );
+        Some(id)
+    }
+
+    /// Removes a decoration, freeing its text if it has one.
+    /// Returns whether it existed.
+    pub fn remove_decoration(&mut self, id: DecorationId) -> bool {
+        #[cfg_attr(not(feature = "text"), allow(unused_variables))]
+        let Some(decoration) = self.root.remove_decoration(id) else {
+            return false;
+        };
+        #[cfg(feature = "text")]
+        if self.decoration_texts.remove(&id).is_some()
+            && let Some(data_ref) = decoration.data_ref
+        {
+            self.root.remove_binding(data_ref);
+        }
+        self.redraw_requested = true;
+        true
+    }
+
+    /// Forgets the texts of the decorations of a removed element.
+    /// Their buffers go away with its frame.
+    #[cfg(feature = "text")]
+    pub(crate) fn drop_decoration_texts(&mut self, element: heka::CapsuleRef) {
+        self.decoration_texts
+            .retain(|_, text| text.owner != element);
+    }
+
+    /// Draw commands of the decorations of an element, with their priority
+    #[cfg_attr(not(feature = "text"), allow(unused_variables))]
+    pub(crate) fn decoration_commands(
+        &self,
+        element: heka::CapsuleRef,
+        z_index: u32,
+    ) -> Vec<(u8, cmd::DrawCommand)> {
+        let mut commands = Vec::new();
+
+        for (id, rect, decoration) in self.root.decorations(element) {
+            commands.push((
+                0,
+                cmd::DrawCommand::Rect {
+                    rect,
+                    fill_color: decoration.background_color,
+                    stroke_color: decoration.border.color,
+                    z_index,
+                    border_radius: decoration.border.radius,
+                    stroke_width: decoration.border.size,
+                    shadow_color: heka::color::Color::transparent,
+                    shadow_blur: 0.0,
+                },
+            ));
+
+            #[cfg(feature = "text")]
+            if let (Some(buffer_ref), Some(text)) =
+                (decoration.data_ref, self.decoration_texts.get(&id))
+            {
+                // Centered in the decoration
+                let (width, height) = text.size;
+                commands.push((
+                    1,
+                    cmd::DrawCommand::Text {
+                        rect: Rect::new(
+                            rect.x + (rect.w as i32 - width as i32) / 2,
+                            rect.y + (rect.h as i32 - height as i32) / 2,
+                            width,
+                            height,
+                        ),
+                        buffer_ref,
+                        style: text.style.clone(),
+                        z_index,
+                    },
+                ));
+            }
+        }
+        commands
+    }
+}
@@ -0,0 +1,66 @@
+//! Disabled elements: they and their children are drawn faded and greyed
+//! out, and take no pointer events nor the focus, without every component
+//! having disabled colors and checks of its own.
+
+use heka::CapsuleRef;
+
+use crate::{Context, ElementRef};
+
+impl Context {
+    /// Disables an element along with its children, or enables it back.
+    /// Disabled elements let the pointer through like non-interactive ones
+    /// (see `set_interactive`), are skipped by the focus, and the renderer
+    /// fades and greys them out.
+    pub fn set_enabled(&mut self, element: impl ElementRef, enabled: bool) {
+        let cref = element.raw();
+        let changed = if enabled {
+            self.disabled.remove(&cref)
+        } else {
+            self.disabled.insert(cref)
+        };
+        if !changed {
+            return;
+        }
+
+        if !enabled {
+            if self
+                .focused_element
+                .is_some_and(|focused| self.is_disabled(focused))
+            {
+                self.focused_element = None;
+                #[cfg(feature = "text")]
+                self.update_caret();
+            }
+            if self
+                .pointer_capture
+                .is_some_and(|captured| self.is_disabled(captured))
+            {
+                self.pointer_capture = None;
+            }
+        }
+        // Hovers follow what the pointer reaches now
+        self.update_hover();
+        self.request_redraw();
+    }
+
+    /// Whether the element and all its parents are enabled
+    #[inline]
+    pub fn is_enabled(&self, element: impl ElementRef) -> bool {
+        !self.is_disabled(element.raw())
+    }
+
+    /// Whether `cref` or one of its parents is disabled
+    pub(crate) fn is_disabled(&self, cref: CapsuleRef) -> bool {
+        if self.disabled.is_empty() {
+            return false;
+        }
+        let mut current = Some(cref);
+        while let Some(cref) = current {
+            if self.disabled.contains(&cref) {
+                return true;
+            }
+            current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        }
+        false
+    }
+}
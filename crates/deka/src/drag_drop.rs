@@ -0,0 +1,321 @@
+//! Drag and drop: elements carry a value to the targets they are dropped
+//! on. While dragged, a ghost of the element follows the cursor over
+//! everything else, and the target under it that takes the value is
+//! highlighted.
+
+use std::any::{Any, TypeId};
+use std::rc::Rc;
+
+use heka::CapsuleRef;
+use heka::color::Color;
+use heka::position::{Offset, PointerEvents, Position};
+use heka::sizing::SizeSpec;
+use heka::{Style, make_style};
+use winit::dpi::PhysicalPosition;
+use winit::event::MouseButton;
+
+use crate::events::DropEvent;
+use crate::{Context, Element, ElementRef, PanelRef};
+
+/// Layer holding the ghost, above the popups
+const GHOST_LAYER: &str = "deka-drag-ghost";
+const GHOST_LAYER_ORDER: i32 = 300;
+
+/// Distance the cursor goes from the press before a drag starts, so that
+/// clicks on draggable elements stay clicks
+const DRAG_THRESHOLD: f64 = 4.0;
+
+/// Opacity of the snapshot ghosts
+const GHOST_OPACITY: u8 = 160;
+
+/// Fills the ghost panel of a `DragPreview::Build`
+type BuildGhost = Rc<dyn Fn(&mut Context, PanelRef)>;
+
+/// What follows the cursor while an element is dragged
+#[derive(Clone, Default)]
+pub enum DragPreview {
+    /// A translucent snapshot of the element
+    #[default]
+    Snapshot,
+    /// The elements `build` adds to the (transparent) ghost panel
+    Build(BuildGhost),
+    /// Nothing, only the target is highlighted
+    None,
+}
+
+/// An element that can be dragged, see `Context::set_draggable`
+pub(crate) struct DragSource {
+    payload: Rc<dyn Any>,
+    preview: DragPreview,
+    /// Top-left corner of the ghost from the cursor. By default the ghost
+    /// keeps the point of the element that was grabbed under the cursor.
+    offset: Option<PhysicalPosition<f64>>,
+}
+
+/// A press on a draggable element, and the drag it turned into
+pub(crate) struct DragSession {
+    source: CapsuleRef,
+    payload: Rc<dyn Any>,
+    start: PhysicalPosition<f64>,
+    /// Top-left corner of the ghost from the cursor
+    offset: PhysicalPosition<f64>,
+    /// The cursor went past the threshold
+    started: bool,
+    ghost: Option<CapsuleRef>,
+    /// Element under the cursor taking the payload
+    target: Option<CapsuleRef>,
+}
+
+impl Context {
+    /// Lets `element` be dragged onto the drop targets taking a `T` (see
+    /// `on_drop`), carrying `payload` to them. A drag starts once the
+    /// cursor moved a few pixels with the left button pressed on the
+    /// element, it then gets no click. Escape cancels it.
+    pub fn set_draggable<T: 'static>(&mut self, element: impl ElementRef, payload: T) {
+        self.drag_sources.insert(
+            element.raw(),
+            DragSource {
+                payload: Rc::new(payload),
+                preview: DragPreview::default(),
+                offset: None,
+            },
+        );
+    }
+
+    /// Stops `element` from being dragged
+    pub fn unset_draggable(&mut self, element: impl ElementRef) {
+        self.drag_sources.remove(&element.raw());
+    }
+
+    /// Changes what follows the cursor while `element` is dragged
+    pub fn set_drag_preview(&mut self, element: impl ElementRef, preview: DragPreview) {
+        if let Some(source) = self.drag_sources.get_mut(&element.raw()) {
+            source.preview = preview;
+        }
+    }
+
+    /// Puts the top-left corner of the ghost of `element` at `offset` from
+    /// the cursor, or (with `None`) keeps the point that was grabbed under
+    /// the cursor
+    pub fn set_drag_offset(
+        &mut self,
+        element: impl ElementRef,
+        offset: Option<PhysicalPosition<f64>>,
+    ) {
+        if let Some(source) = self.drag_sources.get_mut(&element.raw()) {
+            source.offset = offset;
+        }
+    }
+
+    /// Called when an element carrying a `T` is dropped on `target`.
+    /// While such an element is dragged over it, the target is highlighted.
+    pub fn on_drop<T: 'static, F>(&mut self, target: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &DropEvent) + 'static,
+    {
+        let cref = target.raw();
+        self.drop_types.insert(cref, TypeId::of::<T>());
+        self.drop_callbacks.insert(cref, Box::new(callback));
+    }
+
+    /// The element being dragged, if a drag started
+    pub fn dragged(&self) -> Option<Element> {
+        self.drag
+            .as_ref()
+            .filter(|drag| drag.started)
+            .map(|drag| Element(drag.source))
+    }
+
+    /// The drop target under the dragged element, highlighted
+    pub(crate) fn drop_target(&self) -> Option<CapsuleRef> {
+        self.drag.as_ref().and_then(|drag| drag.target)
+    }
+
+    /// Remembers a press on a draggable element, a drag may start from it
+    pub(crate) fn drag_press(&mut self, button: MouseButton) {
+        self.drag = None;
+        if button != MouseButton::Left {
+            return;
+        }
+        let Some((cref, source)) = self
+            .hit_candidates()
+            .into_iter()
+            .find_map(|cref| Some((cref, self.drag_sources.get(&cref)?)))
+        else {
+            return;
+        };
+
+        let offset = source.offset.unwrap_or_else(|| {
+            let rect = self.root.get_rect(cref).unwrap_or_default();
+            PhysicalPosition::new(
+                rect.x as f64 - self.mouse_pos.x,
+                rect.y as f64 - self.mouse_pos.y,
+            )
+        });
+        self.drag = Some(DragSession {
+            source: cref,
+            payload: source.payload.clone(),
+            start: self.mouse_pos,
+            offset,
+            started: false,
+            ghost: None,
+            target: None,
+        });
+    }
+
+    /// Starts the drag past the threshold, then moves the ghost and finds
+    /// the target under the cursor
+    pub(crate) fn drag_move(&mut self) {
+        let pos = self.mouse_pos;
+        let Some(drag) = &self.drag else {
+            return;
+        };
+        if !drag.started {
+            if (pos.x - drag.start.x).hypot(pos.y - drag.start.y) < DRAG_THRESHOLD {
+                return;
+            }
+            self.start_drag();
+        }
+
+        let Some(drag) = &self.drag else {
+            return;
+        };
+        if let Some(ghost) = drag.ghost {
+            let (x, y) = (pos.x + drag.offset.x, pos.y + drag.offset.y);
+            heka::Frame::define(ghost).update_style(&mut self.root, |style| {
+                style.position = Position::Fixed {
+                    x: Offset::Pixel(x.round() as i32),
+                    y: Offset::Pixel(y.round() as i32),
+                };
+            });
+        }
+
+        let type_id = Any::type_id(&*drag.payload);
+        let target = self
+            .hit_candidates()
+            .into_iter()
+            .find(|cref| self.drop_types.get(cref) == Some(&type_id));
+        if let Some(drag) = &mut self.drag {
+            drag.target = target;
+        }
+        self.request_redraw();
+    }
+
+    fn start_drag(&mut self) {
+        let Some(drag) = &mut self.drag else {
+            return;
+        };
+        drag.started = true;
+        let source = drag.source;
+        let Some(preview) = self
+            .drag_sources
+            .get(&source)
+            .map(|source| source.preview.clone())
+        else {
+            return;
+        };
+
+        let layer = self
+            .layer(GHOST_LAYER)
+            .unwrap_or_else(|| self.create_layer(GHOST_LAYER, GHOST_LAYER_ORDER));
+        // The ghost is never under the cursor, targets are
+        let style = make_style!(
+            position: Position::Fixed {
+                x: Offset::Pixel(0),
+                y: Offset::Pixel(0),
+            },
+            pointer_events: PointerEvents::None,
+        );
+
+        let ghost = match preview {
+            DragPreview::Snapshot => {
+                let rect = self.root.get_rect(source).unwrap_or_default();
+                self.snapshot_element(Element(source)).and_then(|pixels| {
+                    let style = Style {
+                        width: SizeSpec::Pixel(rect.w),
+                        height: SizeSpec::Pixel(rect.h),
+                        ..style
+                    };
+                    let image = self.new_image(pixels, Some(layer), style).ok()?;
+                    self.set_image_tint(image, Color::new(255, 255, 255, GHOST_OPACITY));
+                    Some(image.raw())
+                })
+            }
+            DragPreview::Build(build) => {
+                let panel = self.new_panel(Some(layer), style);
+                self.guarded(|ctx| build(ctx, panel));
+                Some(panel.raw())
+            }
+            DragPreview::None => None,
+        };
+        if let Some(drag) = &mut self.drag {
+            drag.ghost = ghost;
+        }
+    }
+
+    /// Drops the dragged element on the target under the cursor.
+    /// Returns whether a drag was in progress, the release is then no
+    /// click.
+    pub(crate) fn drag_release(&mut self) -> bool {
+        let Some(drag) = self.drag.take() else {
+            return false;
+        };
+        if !drag.started {
+            return false;
+        }
+        self.end_drag(&drag);
+
+        if let Some(target) = drag.target
+            && let Some(mut callback) = self.drop_callbacks.remove(&target)
+        {
+            let event = DropEvent {
+                source: Element(drag.source),
+                pos: self.mouse_pos,
+                local_pos: self.local_mouse_pos(target),
+                payload: drag.payload,
+            };
+            self.guarded_on(target, |ctx| callback(ctx, &event));
+            self.drop_callbacks.entry(target).or_insert(callback);
+        }
+        true
+    }
+
+    /// Stops the drag in progress without dropping it.
+    /// Returns whether there was one.
+    pub(crate) fn cancel_drag(&mut self) -> bool {
+        match self.drag.take() {
+            Some(drag) if drag.started => {
+                self.end_drag(&drag);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn end_drag(&mut self, drag: &DragSession) {
+        if let Some(ghost) = drag.ghost {
+            self.remove_element(Element(ghost));
+        }
+        self.request_redraw();
+    }
+
+    /// Forgets what an element removed from the tree had to do with
+    /// dragging
+    pub(crate) fn forget_drag_element(&mut self, cref: CapsuleRef) {
+        self.drag_sources.remove(&cref);
+        self.drop_types.remove(&cref);
+        self.drop_callbacks.remove(&cref);
+        let Some(drag) = &mut self.drag else {
+            return;
+        };
+        if drag.target == Some(cref) {
+            drag.target = None;
+        }
+        if drag.ghost == Some(cref) {
+            drag.ghost = None;
+        }
+        if drag.source == cref {
+            self.cancel_drag();
+        }
+    }
+}
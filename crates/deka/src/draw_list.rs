@@ -0,0 +1,293 @@
+//! Draw lists: what painters draw, built shape by shape with a transform
+//! and a clip that can be saved and restored, instead of draw commands
+//! made by hand.
+
+#[cfg(feature = "text")]
+use cosmic_text::Buffer;
+use heka::CapsuleRef;
+use heka::color::Color;
+use heka::geometry::Rect;
+
+#[cfg(feature = "text")]
+use crate::TextStyle;
+use crate::cmd::DrawCommand;
+use crate::elements::Paint;
+#[cfg(feature = "text")]
+use crate::elements::fill_buffer;
+use crate::{Context, ElementRef};
+
+/// Fills the draw list of an element, given the size of its content box
+pub(crate) type Painter = Box<dyn Fn(&mut DrawList, [f32; 2])>;
+
+/// Maps list units to the window: `window = offset + p * scale`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Transform {
+    offset: [f32; 2],
+    scale: f32,
+}
+
+impl Transform {
+    fn apply(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            self.offset[0] + p[0] * self.scale,
+            self.offset[1] + p[1] * self.scale,
+        ]
+    }
+
+    /// The window rectangle covered by `size` at `pos`
+    fn rect(&self, pos: [f32; 2], size: [f32; 2]) -> Rect {
+        let [x, y] = self.apply(pos);
+        Rect {
+            x: x.round() as i32,
+            y: y.round() as i32,
+            w: (size[0] * self.scale).round().max(0.0) as u32,
+            h: (size[1] * self.scale).round().max(0.0) as u32,
+        }
+    }
+}
+
+/// Shapes to draw, in order, each over the previous ones.
+///
+/// Positions and sizes go through the current transform, set with
+/// `translate` and `scale`, and shapes are cut to the current clip, set
+/// with `clip`. `save` and `restore` bring both back to what they were.
+#[derive(Debug, Clone)]
+pub struct DrawList {
+    commands: Vec<DrawCommand>,
+    z_index: u32,
+    transform: Transform,
+    clip: Option<Rect>,
+    saved: Vec<(Transform, Option<Rect>)>,
+}
+
+impl DrawList {
+    /// An empty list in window coordinates
+    pub fn new(z_index: u32) -> Self {
+        Self {
+            commands: Vec::new(),
+            z_index,
+            transform: Transform {
+                offset: [0.0, 0.0],
+                scale: 1.0,
+            },
+            clip: None,
+            saved: Vec::new(),
+        }
+    }
+
+    /// Remembers the transform and the clip, see `restore`
+    pub fn save(&mut self) {
+        self.saved.push((self.transform, self.clip));
+    }
+
+    /// Goes back to the transform and the clip of the last `save`
+    pub fn restore(&mut self) {
+        if let Some((transform, clip)) = self.saved.pop() {
+            self.transform = transform;
+            self.clip = clip;
+        }
+    }
+
+    /// Moves the origin of what is drawn next
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.transform.offset = self.transform.apply([dx, dy]);
+    }
+
+    /// Scales what is drawn next around the origin. Text keeps its size.
+    pub fn scale(&mut self, factor: f32) {
+        self.transform.scale *= factor;
+    }
+
+    /// Cuts what is drawn next to `size` at `pos`, inside the current clip
+    pub fn clip(&mut self, pos: [f32; 2], size: [f32; 2]) {
+        let rect = self.transform.rect(pos, size);
+        self.clip = Some(match self.clip {
+            Some(clip) => clip.intersection(&rect).unwrap_or_default(),
+            None => rect,
+        });
+    }
+
+    /// A rectangle of `size` at `pos`, with corners rounded by `radius`
+    pub fn push_rect(&mut self, pos: [f32; 2], size: [f32; 2], radius: f32, paint: Paint) {
+        let scale = self.transform.scale;
+        self.push(DrawCommand::Rect {
+            rect: self.transform.rect(pos, size),
+            z_index: self.z_index,
+            fill_color: paint.fill,
+            border_radius: (radius * scale).round().max(0.0) as u32,
+            stroke_color: paint.stroke,
+            stroke_width: (paint.stroke_width * scale).round().max(0.0) as u32,
+            shadow_color: Color::transparent,
+            shadow_blur: 0.0,
+        });
+    }
+
+    /// The blurred shadow of a rectangle of `size` at `pos`
+    pub fn push_shadow(
+        &mut self,
+        pos: [f32; 2],
+        size: [f32; 2],
+        radius: f32,
+        color: Color,
+        blur: f32,
+    ) {
+        let scale = self.transform.scale;
+        self.push(DrawCommand::Rect {
+            rect: self.transform.rect(pos, size),
+            z_index: self.z_index,
+            fill_color: Color::transparent,
+            border_radius: (radius * scale).round().max(0.0) as u32,
+            stroke_color: Color::transparent,
+            stroke_width: 0,
+            shadow_color: color,
+            shadow_blur: blur * scale,
+        });
+    }
+
+    /// Flat-colored triangles, three `indices` of `points` each
+    pub fn push_mesh(&mut self, points: &[[f32; 2]], indices: &[u32], color: Color) {
+        self.push(DrawCommand::Mesh {
+            points: points.iter().map(|p| self.transform.apply(*p)).collect(),
+            indices: indices.to_vec(),
+            color,
+            z_index: self.z_index,
+        });
+    }
+
+    /// Text shaped with `Context::shape_text`, its top-left corner at `pos`
+    #[cfg(feature = "text")]
+    pub fn push_text(&mut self, pos: [f32; 2], text: &ShapedText) {
+        let [x, y] = self.transform.apply(pos);
+        self.push(DrawCommand::Text {
+            rect: Rect {
+                x: x.round() as i32,
+                y: y.round() as i32,
+                w: text.size[0].ceil() as u32,
+                h: text.size[1].ceil() as u32,
+            },
+            buffer_ref: text.buffer_ref,
+            style: text.style.clone(),
+            z_index: self.z_index,
+        });
+    }
+
+    /// An image stretched over `size` at `pos`, its pixels multiplied by
+    /// `tint`. `texture` is the data of an `Image` element, see
+    /// `FrameElement::data_ref`.
+    pub fn push_image(
+        &mut self,
+        pos: [f32; 2],
+        size: [f32; 2],
+        texture: heka::DataRef,
+        tint: Color,
+    ) {
+        self.push(DrawCommand::Image {
+            rect: self.transform.rect(pos, size),
+            texture_ref: texture,
+            uv: [0.0, 0.0, 1.0, 1.0],
+            tint,
+            z_index: self.z_index,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    fn push(&mut self, command: DrawCommand) {
+        let command = match self.clip {
+            Some(clip) => DrawCommand::Clipped {
+                clip,
+                command: Box::new(command),
+            },
+            None => command,
+        };
+        self.commands.push(command);
+    }
+
+    pub(crate) fn into_commands(self) -> Vec<DrawCommand> {
+        self.commands
+    }
+}
+
+/// Text shaped once for painters to draw, see `Context::shape_text`
+#[cfg(feature = "text")]
+#[derive(Debug)]
+pub struct ShapedText {
+    buffer_ref: heka::DataRef,
+    size: [f32; 2],
+    style: TextStyle,
+}
+
+#[cfg(feature = "text")]
+impl ShapedText {
+    /// Width and height of the text
+    #[inline]
+    pub fn size(&self) -> [f32; 2] {
+        self.size
+    }
+}
+
+impl Context {
+    /// Draws what `painter` adds to a draw list over the background of
+    /// `element`, on every frame. The list starts at the top-left corner of
+    /// the content box of the element, whose size `painter` gets.
+    /// Call `request_redraw` when what it draws changes.
+    pub fn set_painter<F>(&mut self, element: impl ElementRef, painter: F)
+    where
+        F: Fn(&mut DrawList, [f32; 2]) + 'static,
+    {
+        self.painters.insert(element.raw(), Box::new(painter));
+        self.request_redraw();
+    }
+
+    pub fn remove_painter(&mut self, element: impl ElementRef) {
+        if self.painters.remove(&element.raw()).is_some() {
+            self.request_redraw();
+        }
+    }
+
+    /// Commands of the painter of `cref`, if it has one
+    pub(crate) fn painter_commands(&self, cref: CapsuleRef, z_index: u32) -> Vec<DrawCommand> {
+        let Some(painter) = self.painters.get(&cref) else {
+            return Vec::new();
+        };
+        let Some(content) = self.root.get_content_rect(cref) else {
+            return Vec::new();
+        };
+        let mut list = DrawList::new(z_index);
+        list.translate(content.x as f32, content.y as f32);
+        painter(&mut list, [content.w as f32, content.h as f32]);
+        list.into_commands()
+    }
+
+    /// Shapes `text` for `DrawList::push_text`. The text stays until given
+    /// to `free_shaped_text`.
+    #[cfg(feature = "text")]
+    pub fn shape_text(&mut self, text: impl AsRef<str>, style: TextStyle) -> ShapedText {
+        let mut buffer = Buffer::new(&mut self.font_system, style.as_cosmic_metrics());
+        buffer.set_size(&mut self.font_system, None, None);
+        fill_buffer(&mut buffer, &mut self.font_system, text.as_ref(), &style);
+        buffer.shape_until_scroll(&mut self.font_system, true);
+
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max);
+        let height = buffer
+            .layout_runs()
+            .last()
+            .map(|run| run.line_top + run.line_height)
+            .unwrap_or(0.0);
+        ShapedText {
+            buffer_ref: self.root.set_binding(buffer),
+            size: [width, height],
+            style,
+        }
+    }
+
+    #[cfg(feature = "text")]
+    pub fn free_shaped_text(&mut self, text: ShapedText) {
+        self.root.remove_binding(text.buffer_ref);
+    }
+}
@@ -0,0 +1,197 @@
+use super::FrameElement;
+use crate::{Context, Element, ElementRef, LabelRef, PanelRef, Theme};
+use cosmic_text::Weight;
+use heka::color::Color;
+use heka::position::{Offset, Position};
+use heka::sizing::{Border, SizeSpec};
+use heka::{align, justify, layout};
+
+/// Diameter preset of an avatar, see `Theme::avatar_sizes`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+impl AvatarSize {
+    pub fn diameter(&self, theme: &Theme) -> u32 {
+        let [small, medium, large] = theme.avatar_sizes;
+        match self {
+            AvatarSize::Small => small,
+            AvatarSize::Medium => medium,
+            AvatarSize::Large => large,
+        }
+    }
+}
+
+/// Presence shown in the dot at the bottom right of an avatar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarStatus {
+    Online,
+    Away,
+    Busy,
+    Offline,
+}
+
+impl AvatarStatus {
+    fn color(&self, theme: &Theme) -> Color {
+        match self {
+            AvatarStatus::Online => Color::Hex(0x2EB67DFF),
+            AvatarStatus::Away => Color::Hex(0xF2A93BFF),
+            AvatarStatus::Busy => theme.error_color,
+            AvatarStatus::Offline => theme.muted_color,
+        }
+    }
+}
+
+/// Round avatar showing the initials of a name on a background color
+/// derived from it, with an optional status dot
+pub struct Avatar {
+    pub(crate) frame: heka::Frame,
+    name: String,
+    diameter: u32,
+    label: LabelRef,
+    status: Option<AvatarStatus>,
+    /// Created with the first status
+    status_dot: Option<PanelRef>,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Avatar {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[AVATAR]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Avatar {
+    pub(crate) fn new(
+        ctx: &mut Context,
+        parent_frame: Option<impl ElementRef>,
+        name: String,
+        size: AvatarSize,
+    ) -> Self {
+        let parent = if let Some(pf) = parent_frame {
+            &heka::Frame::define(pf.raw())
+        } else {
+            &ctx.root_frame
+        };
+
+        let diameter = size.diameter(&ctx.theme);
+        let frame = ctx.root.add_frame_child(parent, None);
+        frame.update_style(&mut ctx.root, |style| {
+            style.width = SizeSpec::Pixel(diameter);
+            style.height = SizeSpec::Pixel(diameter);
+            style.border = Border {
+                size: 0,
+                radius: diameter / 2,
+                color: Color::transparent,
+            };
+            style.background_color = background_color(&name);
+            style.layout = layout!(flex);
+            style.justify_content = justify!(center);
+            style.align_items = align!(center);
+        });
+
+        let text_style = crate::TextStyle {
+            color: Color::white,
+            font_size: (diameter as f32 * 0.4).max(8.0),
+            weight: Weight::SEMIBOLD,
+            ..ctx.theme.text_style()
+        };
+        let label = ctx.new_label(
+            initials(&name),
+            Some(Element(frame.get_ref())),
+            Some(text_style),
+        );
+
+        Self {
+            frame,
+            name,
+            diameter,
+            label,
+            status: None,
+            status_dot: None,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn status(&self) -> Option<AvatarStatus> {
+        self.status
+    }
+
+    /// The label holding the initials
+    #[inline]
+    pub fn label(&self) -> LabelRef {
+        self.label
+    }
+
+    pub(crate) fn set_status(&mut self, ctx: &mut Context, status: Option<AvatarStatus>) {
+        self.status = status;
+        let color = status.map_or(Color::transparent, |status| status.color(&ctx.theme));
+
+        let dot_diameter = (self.diameter / 4).max(8);
+        let dot = *self.status_dot.get_or_insert_with(|| {
+            let corner = Offset::Pixel(self.diameter as i32 - dot_diameter as i32);
+            // Out of the flow, over the bottom right of the circle
+            let position = Position::Fixed {
+                x: corner,
+                y: corner,
+            };
+            ctx.new_panel(
+                Some(Element(self.frame.get_ref())),
+                heka::make_style!(
+                    width: SizeSpec::Pixel(dot_diameter),
+                    height: SizeSpec::Pixel(dot_diameter),
+                    position: position,
+                ),
+            )
+        });
+
+        heka::Frame::define(dot.raw()).update_style(&mut ctx.root, |style| {
+            style.background_color = color;
+            style.border = Border {
+                size: 2,
+                radius: dot_diameter / 2,
+                color: if status.is_some() {
+                    Color::white
+                } else {
+                    Color::transparent
+                },
+            };
+        });
+    }
+}
+
+/// First letters of the first two words, uppercased
+fn initials(name: &str) -> String {
+    let initials: String = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    if initials.is_empty() {
+        String::from("?")
+    } else {
+        initials
+    }
+}
+
+/// Stable color for a name, readable under white initials
+fn background_color(name: &str) -> Color {
+    // FNV-1a, the hue only has to be stable across runs
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    Color::from_hsl((hash % 360) as f32, 0.55, 0.45)
+}
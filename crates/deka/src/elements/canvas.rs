@@ -0,0 +1,690 @@
+#[cfg(feature = "text")]
+use std::collections::HashMap;
+
+use super::FrameElement;
+#[cfg(feature = "text")]
+use crate::TextStyle;
+use crate::cmd::DrawCommand;
+use heka::color::Color;
+use heka::geometry::Rect;
+
+/// Handle to a shape of a `Scene`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShapeId(u32);
+
+/// Fill and outline of a shape. Transparent colors are not drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paint {
+    pub fill: Color,
+    pub stroke: Color,
+    pub stroke_width: f32,
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Self {
+            fill: Color::black,
+            stroke: Color::transparent,
+            stroke_width: 0.0,
+        }
+    }
+}
+
+impl Paint {
+    pub fn fill(color: Color) -> Self {
+        Self {
+            fill: color,
+            ..Default::default()
+        }
+    }
+
+    pub fn stroke(color: Color, width: f32) -> Self {
+        Self {
+            fill: Color::transparent,
+            stroke: color,
+            stroke_width: width,
+        }
+    }
+}
+
+/// Retained canvas item, in scene units
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        radius: f32,
+        paint: Paint,
+    },
+    Circle {
+        cx: f32,
+        cy: f32,
+        r: f32,
+        paint: Paint,
+    },
+    /// Circular arc starting at the `start` angle and running `sweep`
+    /// radians, clockwise on screen from 0 pointing right. The stroke is a
+    /// band centered on the radius, the fill is the wedge down to the center.
+    Arc {
+        cx: f32,
+        cy: f32,
+        r: f32,
+        start: f32,
+        sweep: f32,
+        paint: Paint,
+    },
+    /// Polyline, filled when closed. Fills assume a convex outline.
+    Path {
+        points: Vec<[f32; 2]>,
+        closed: bool,
+        paint: Paint,
+    },
+    /// Text whose top-left corner sits at (x, y)
+    #[cfg(feature = "text")]
+    Text {
+        x: f32,
+        y: f32,
+        text: String,
+        style: TextStyle,
+    },
+}
+
+/// Axis-aligned area in scene units
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SceneRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl SceneRect {
+    pub fn contains(&self, p: [f32; 2]) -> bool {
+        p[0] >= self.x && p[0] <= self.x + self.w && p[1] >= self.y && p[1] <= self.y + self.h
+    }
+
+    pub fn union(&self, other: &SceneRect) -> SceneRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        SceneRect {
+            x,
+            y,
+            w: (self.x + self.w).max(other.x + other.w) - x,
+            h: (self.y + self.h).max(other.y + other.h) - y,
+        }
+    }
+}
+
+/// Shapes of a `Canvas`, drawn in insertion order
+#[derive(Debug, Default)]
+pub struct Scene {
+    shapes: Vec<(ShapeId, Shape)>,
+    next_id: u32,
+    /// Measured size of text shapes
+    #[cfg(feature = "text")]
+    pub(crate) text_sizes: HashMap<ShapeId, [f32; 2]>,
+    /// Shapes added or updated since the last sync, to shape their text
+    #[cfg(feature = "text")]
+    pub(crate) changed: Vec<ShapeId>,
+    /// Shapes removed since the last sync, to free their text
+    #[cfg(feature = "text")]
+    pub(crate) removed: Vec<ShapeId>,
+    /// Area that changed since the last sync
+    pub(crate) damage: Option<SceneRect>,
+}
+
+impl Scene {
+    pub fn add(&mut self, shape: Shape) -> ShapeId {
+        let id = ShapeId(self.next_id);
+        self.next_id += 1;
+
+        self.shapes.push((id, shape));
+        self.touch(id);
+        id
+    }
+
+    /// Edits a shape in place. Returns whether it exists.
+    pub fn update(&mut self, id: ShapeId, op: impl FnOnce(&mut Shape)) -> bool {
+        self.damage_shape(id);
+        let Some((_, shape)) = self.shapes.iter_mut().find(|(sid, _)| *sid == id) else {
+            return false;
+        };
+        op(shape);
+        self.touch(id);
+        true
+    }
+
+    pub fn remove(&mut self, id: ShapeId) -> Option<Shape> {
+        self.damage_shape(id);
+        let index = self.shapes.iter().position(|(sid, _)| *sid == id)?;
+        #[cfg(feature = "text")]
+        {
+            self.text_sizes.remove(&id);
+            self.removed.push(id);
+        }
+        Some(self.shapes.remove(index).1)
+    }
+
+    pub fn clear(&mut self) {
+        let ids: Vec<ShapeId> = self.shapes.iter().map(|(id, _)| *id).collect();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// Moves a shape above all the others
+    pub fn raise(&mut self, id: ShapeId) {
+        if let Some(index) = self.shapes.iter().position(|(sid, _)| *sid == id) {
+            let entry = self.shapes.remove(index);
+            self.shapes.push(entry);
+            self.damage_shape(id);
+        }
+    }
+
+    pub fn get(&self, id: ShapeId) -> Option<&Shape> {
+        self.shapes
+            .iter()
+            .find(|(sid, _)| *sid == id)
+            .map(|(_, shape)| shape)
+    }
+
+    /// Shapes from the bottom to the top
+    pub fn shapes(&self) -> impl Iterator<Item = (ShapeId, &Shape)> {
+        self.shapes.iter().map(|(id, shape)| (*id, shape))
+    }
+
+    /// Area covered by a shape, strokes included
+    pub fn bounds(&self, id: ShapeId) -> Option<SceneRect> {
+        let shape = self.get(id)?;
+        let (bounds, stroke) = match shape {
+            Shape::Rect {
+                x, y, w, h, paint, ..
+            } => (
+                SceneRect {
+                    x: *x,
+                    y: *y,
+                    w: *w,
+                    h: *h,
+                },
+                paint.stroke_width,
+            ),
+            // Arcs get the bounds of their whole circle
+            Shape::Circle { cx, cy, r, paint }
+            | Shape::Arc {
+                cx, cy, r, paint, ..
+            } => (
+                SceneRect {
+                    x: cx - r,
+                    y: cy - r,
+                    w: r * 2.0,
+                    h: r * 2.0,
+                },
+                paint.stroke_width,
+            ),
+            Shape::Path { points, paint, .. } => {
+                let first = *points.first()?;
+                let bounds = points.iter().fold(
+                    SceneRect {
+                        x: first[0],
+                        y: first[1],
+                        ..Default::default()
+                    },
+                    |acc, p| {
+                        acc.union(&SceneRect {
+                            x: p[0],
+                            y: p[1],
+                            ..Default::default()
+                        })
+                    },
+                );
+                (bounds, paint.stroke_width)
+            }
+            #[cfg(feature = "text")]
+            Shape::Text { x, y, .. } => {
+                let [w, h] = self.text_sizes.get(&id).copied().unwrap_or_default();
+                (SceneRect { x: *x, y: *y, w, h }, 0.0)
+            }
+        };
+
+        let half = stroke / 2.0;
+        Some(SceneRect {
+            x: bounds.x - half,
+            y: bounds.y - half,
+            w: bounds.w + stroke,
+            h: bounds.h + stroke,
+        })
+    }
+
+    /// Topmost shape under a point in scene units
+    pub fn pick(&self, p: [f32; 2]) -> Option<ShapeId> {
+        self.shapes
+            .iter()
+            .rev()
+            .find(|(id, shape)| self.hit(*id, shape, p))
+            .map(|(id, _)| *id)
+    }
+
+    fn hit(&self, id: ShapeId, shape: &Shape, p: [f32; 2]) -> bool {
+        match shape {
+            Shape::Circle { cx, cy, r, paint } => {
+                let reach = r + paint.stroke_width / 2.0;
+                (p[0] - cx).powi(2) + (p[1] - cy).powi(2) <= reach * reach
+            }
+            Shape::Arc {
+                cx,
+                cy,
+                r,
+                start,
+                sweep,
+                paint,
+            } => {
+                let (dx, dy) = (p[0] - cx, p[1] - cy);
+                if !within_sweep(dy.atan2(dx), *start, *sweep) {
+                    return false;
+                }
+                let distance = (dx * dx + dy * dy).sqrt();
+                let reach = (paint.stroke_width / 2.0).max(2.0);
+                (distance - r).abs() <= reach || (paint.fill.a > 0 && distance <= *r)
+            }
+            Shape::Path {
+                points,
+                closed,
+                paint,
+            } => {
+                let reach = (paint.stroke_width / 2.0).max(2.0);
+                let near_segment =
+                    segments(points, *closed).any(|(a, b)| distance_to_segment(p, a, b) <= reach);
+                near_segment || (*closed && paint.fill.a > 0 && inside_polygon(p, points))
+            }
+            Shape::Rect { .. } => self.bounds(id).is_some_and(|bounds| bounds.contains(p)),
+            #[cfg(feature = "text")]
+            Shape::Text { .. } => self.bounds(id).is_some_and(|bounds| bounds.contains(p)),
+        }
+    }
+
+    fn touch(&mut self, id: ShapeId) {
+        #[cfg(feature = "text")]
+        if !self.changed.contains(&id) {
+            self.changed.push(id);
+        }
+        self.damage_shape(id);
+    }
+
+    fn damage_shape(&mut self, id: ShapeId) {
+        if let Some(bounds) = self.bounds(id) {
+            self.add_damage(bounds);
+        }
+    }
+
+    pub(crate) fn add_damage(&mut self, area: SceneRect) {
+        self.damage = Some(match self.damage {
+            Some(damage) => damage.union(&area),
+            None => area,
+        });
+    }
+}
+
+/// Maps scene units to the canvas content box: `local = pan + scene * zoom`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasTransform {
+    pub pan: [f32; 2],
+    pub zoom: f32,
+}
+
+impl Default for CanvasTransform {
+    fn default() -> Self {
+        Self {
+            pan: [0.0, 0.0],
+            zoom: 1.0,
+        }
+    }
+}
+
+impl CanvasTransform {
+    #[inline]
+    pub fn to_local(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            self.pan[0] + p[0] * self.zoom,
+            self.pan[1] + p[1] * self.zoom,
+        ]
+    }
+
+    #[inline]
+    pub fn to_scene(&self, p: [f32; 2]) -> [f32; 2] {
+        [
+            (p[0] - self.pan[0]) / self.zoom,
+            (p[1] - self.pan[1]) / self.zoom,
+        ]
+    }
+}
+
+/// Canvas component.
+///
+/// Draws a retained `Scene` of shapes under a pan/zoom transform.
+pub struct Canvas {
+    pub(crate) frame: heka::Frame,
+    pub(crate) scene: Scene,
+    pub(crate) transform: CanvasTransform,
+    /// cosmic-text buffers of the text shapes, stored in heka's `Allocator`
+    #[cfg(feature = "text")]
+    pub(crate) text_buffers: HashMap<ShapeId, heka::DataRef>,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Canvas {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[CANVAS]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Canvas {
+    pub(crate) fn new(frame: heka::Frame) -> Self {
+        Self {
+            frame,
+            scene: Scene::default(),
+            transform: CanvasTransform::default(),
+            #[cfg(feature = "text")]
+            text_buffers: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    #[inline]
+    pub fn transform(&self) -> CanvasTransform {
+        self.transform
+    }
+
+    /// Topmost shape under a point relative to the canvas content box
+    pub fn pick(&self, local: [f32; 2]) -> Option<ShapeId> {
+        self.scene.pick(self.transform.to_scene(local))
+    }
+
+    /// Window area covered by a scene area, given the canvas content box
+    pub(crate) fn window_rect(&self, content: Rect, area: SceneRect) -> Rect {
+        let [x0, y0] = self.transform.to_local([area.x, area.y]);
+        let [x1, y1] = self.transform.to_local([area.x + area.w, area.y + area.h]);
+        Rect::from_corners(
+            content.x + x0.floor() as i32,
+            content.y + y0.floor() as i32,
+            content.x + x1.ceil() as i32,
+            content.y + y1.ceil() as i32,
+        )
+    }
+
+    /// Draw commands of the visible shapes, bottom to top
+    pub(crate) fn draw_commands(&self, content: Rect, z_index: u32) -> Vec<DrawCommand> {
+        let zoom = self.transform.zoom;
+        let origin = [content.x as f32, content.y as f32];
+        let to_window = |p: [f32; 2]| {
+            let [x, y] = self.transform.to_local(p);
+            [origin[0] + x, origin[1] + y]
+        };
+
+        let mut commands = Vec::new();
+        for (id, shape) in self.scene.shapes() {
+            let Some(bounds) = self.scene.bounds(id) else {
+                continue;
+            };
+            if !self.window_rect(content, bounds).intersects(&content) {
+                continue;
+            }
+
+            match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    w,
+                    h,
+                    radius,
+                    paint,
+                } => {
+                    commands.push(Self::rect_command(
+                        to_window([*x, *y]),
+                        [w * zoom, h * zoom],
+                        radius * zoom,
+                        paint,
+                        zoom,
+                        z_index,
+                    ));
+                }
+                Shape::Circle { cx, cy, r, paint } => {
+                    commands.push(Self::rect_command(
+                        to_window([cx - r, cy - r]),
+                        [r * 2.0 * zoom, r * 2.0 * zoom],
+                        r * zoom,
+                        paint,
+                        zoom,
+                        z_index,
+                    ));
+                }
+                Shape::Arc {
+                    cx,
+                    cy,
+                    r,
+                    start,
+                    sweep,
+                    paint,
+                } => {
+                    let center = to_window([*cx, *cy]);
+                    let r = r * zoom;
+
+                    if paint.fill.a > 0 {
+                        let mut points = vec![center];
+                        points.extend(arc_points(center, r, *start, *sweep));
+                        let indices = (1..points.len() as u32 - 1)
+                            .flat_map(|i| [0, i, i + 1])
+                            .collect();
+                        commands.push(DrawCommand::Mesh {
+                            points,
+                            indices,
+                            color: paint.fill,
+                            z_index,
+                        });
+                    }
+
+                    if paint.stroke.a > 0 && paint.stroke_width > 0.0 {
+                        let half = paint.stroke_width * zoom / 2.0;
+                        let outer = arc_points(center, r + half, *start, *sweep);
+                        let inner = arc_points(center, (r - half).max(0.0), *start, *sweep);
+                        // Quad strip between the two edges
+                        let indices = (0..outer.len() as u32 - 1)
+                            .flat_map(|i| {
+                                let j = i + outer.len() as u32;
+                                [i, j, i + 1, i + 1, j, j + 1]
+                            })
+                            .collect();
+                        commands.push(DrawCommand::Mesh {
+                            points: outer.into_iter().chain(inner).collect(),
+                            indices,
+                            color: paint.stroke,
+                            z_index,
+                        });
+                    }
+                }
+                Shape::Path {
+                    points,
+                    closed,
+                    paint,
+                } => {
+                    let points: Vec<[f32; 2]> = points.iter().map(|p| to_window(*p)).collect();
+
+                    if *closed && paint.fill.a > 0 && points.len() >= 3 {
+                        // Triangle fan, fine for convex outlines
+                        let indices = (1..points.len() as u32 - 1)
+                            .flat_map(|i| [0, i, i + 1])
+                            .collect();
+                        commands.push(DrawCommand::Mesh {
+                            points: points.clone(),
+                            indices,
+                            color: paint.fill,
+                            z_index,
+                        });
+                    }
+
+                    if paint.stroke.a > 0 && paint.stroke_width > 0.0 {
+                        commands.push(Self::stroke_command(
+                            &points,
+                            *closed,
+                            paint.stroke,
+                            paint.stroke_width * zoom,
+                            z_index,
+                        ));
+                    }
+                }
+                #[cfg(feature = "text")]
+                Shape::Text { x, y, style, .. } => {
+                    let Some(buffer_ref) = self.text_buffers.get(&id) else {
+                        continue;
+                    };
+                    let [w, h] = self.scene.text_sizes.get(&id).copied().unwrap_or_default();
+                    let [wx, wy] = to_window([*x, *y]);
+                    commands.push(DrawCommand::Text {
+                        rect: Rect {
+                            x: wx.round() as i32,
+                            y: wy.round() as i32,
+                            w: (w * zoom).ceil() as u32,
+                            h: (h * zoom).ceil() as u32,
+                        },
+                        buffer_ref: *buffer_ref,
+                        style: style.clone(),
+                        z_index,
+                    });
+                }
+            }
+        }
+
+        commands
+    }
+
+    fn rect_command(
+        pos: [f32; 2],
+        size: [f32; 2],
+        radius: f32,
+        paint: &Paint,
+        zoom: f32,
+        z_index: u32,
+    ) -> DrawCommand {
+        DrawCommand::Rect {
+            rect: Rect {
+                x: pos[0].round() as i32,
+                y: pos[1].round() as i32,
+                w: size[0].round().max(0.0) as u32,
+                h: size[1].round().max(0.0) as u32,
+            },
+            z_index,
+            fill_color: paint.fill,
+            border_radius: radius.round().max(0.0) as u32,
+            stroke_color: paint.stroke,
+            stroke_width: (paint.stroke_width * zoom).round().max(0.0) as u32,
+            shadow_color: Color::transparent,
+            shadow_blur: 0.0,
+        }
+    }
+
+    /// One quad per segment, `width` wide
+    fn stroke_command(
+        points: &[[f32; 2]],
+        closed: bool,
+        color: Color,
+        width: f32,
+        z_index: u32,
+    ) -> DrawCommand {
+        let half = width / 2.0;
+        let mut quad_points = Vec::new();
+        let mut indices = Vec::new();
+
+        for (a, b) in segments(points, closed) {
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let len = (dx * dx + dy * dy).sqrt();
+            if len == 0.0 {
+                continue;
+            }
+            let n = [-dy / len * half, dx / len * half];
+
+            let start = quad_points.len() as u32;
+            quad_points.extend([
+                [a[0] + n[0], a[1] + n[1]],
+                [a[0] - n[0], a[1] - n[1]],
+                [b[0] + n[0], b[1] + n[1]],
+                [b[0] - n[0], b[1] - n[1]],
+            ]);
+            indices.extend([start, start + 1, start + 2, start + 2, start + 1, start + 3]);
+        }
+
+        DrawCommand::Mesh {
+            points: quad_points,
+            indices,
+            color,
+            z_index,
+        }
+    }
+}
+
+/// Consecutive point pairs, the last one joining the first when closed
+fn segments(points: &[[f32; 2]], closed: bool) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+    let closing = match (closed, points.first(), points.last()) {
+        (true, Some(first), Some(last)) if points.len() > 2 => Some((*last, *first)),
+        _ => None,
+    };
+    points.windows(2).map(|w| (w[0], w[1])).chain(closing)
+}
+
+/// Points along an arc, close enough for the chords to pass for a curve
+fn arc_points(center: [f32; 2], r: f32, start: f32, sweep: f32) -> Vec<[f32; 2]> {
+    let sweep = sweep.clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+    let steps = ((sweep.abs() / 0.05).ceil() as usize).max(2);
+    (0..=steps)
+        .map(|k| {
+            let a = start + sweep * k as f32 / steps as f32;
+            [center[0] + r * a.cos(), center[1] + r * a.sin()]
+        })
+        .collect()
+}
+
+/// Whether `angle` falls between `start` and `start + sweep`
+fn within_sweep(angle: f32, start: f32, sweep: f32) -> bool {
+    use std::f32::consts::TAU;
+    if sweep.abs() >= TAU {
+        return true;
+    }
+    let (from, span) = if sweep < 0.0 {
+        (start + sweep, -sweep)
+    } else {
+        (start, sweep)
+    };
+    (angle - from).rem_euclid(TAU) <= span
+}
+
+fn distance_to_segment(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 {
+        0.0
+    } else {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / len_sq).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (a[0] + t * dx, a[1] + t * dy);
+    ((p[0] - cx).powi(2) + (p[1] - cy).powi(2)).sqrt()
+}
+
+/// Even-odd rule
+fn inside_polygon(p: [f32; 2], points: &[[f32; 2]]) -> bool {
+    let mut inside = false;
+    for (a, b) in segments(points, true) {
+        if (a[1] > p[1]) != (b[1] > p[1])
+            && p[0] < (b[0] - a[0]) * (p[1] - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -0,0 +1,45 @@
+use super::FrameElement;
+use crate::{PanelRef, ScrollViewRef};
+
+/// Carousel component
+///
+/// Shows one page at a time, each as wide as the carousel. Pages are
+/// swiped through, scrolled sideways, or picked with the dots along the
+/// bottom.
+pub struct Carousel {
+    pub(crate) frame: heka::Frame,
+    /// Scrolled sideways, its content holds the pages
+    pub(crate) view: ScrollViewRef,
+    /// Row of dots, one per page
+    pub(crate) dots: PanelRef,
+    /// Page mostly in view
+    pub(crate) page: usize,
+    /// Width the pages were given, the one of the viewport
+    pub(crate) page_width: u32,
+    /// The pages changed width, the scroll position follows once laid out
+    pub(crate) realign: bool,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Carousel {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[CAROUSEL]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Carousel {
+    /// Page mostly in view
+    #[inline]
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    /// The scroll view the pages are in
+    #[inline]
+    pub fn view(&self) -> ScrollViewRef {
+        self.view
+    }
+}
@@ -1,10 +1,13 @@
 use super::FrameElement;
+use crate::Theme;
 use heka::color::Color;
 
 /// Checkbox component
 pub struct Checkbox {
     pub(crate) frame: heka::Frame,
     pub checked: bool,
+    checked_color: Color,
+    unchecked_color: Color,
 }
 
 #[rustfmt::skip]
@@ -22,6 +25,8 @@ impl Checkbox {
         root: &mut heka::Root,
         parent_frame: Option<&heka::Frame>,
         initial_checked: bool,
+        theme: &Theme,
+        default_style: Option<heka::Style>,
     ) -> Self {
         let frame = if let Some(parent) = parent_frame {
             root.add_frame_child(parent, None)
@@ -29,35 +34,42 @@ impl Checkbox {
             root.add_frame(None)
         };
 
-        let checked_color = if initial_checked {
-            Color::new(100, 100, 255, 255)
-        } else {
-            Color::new(200, 200, 200, 255)
-        };
+        let unchecked_color = default_style.map_or(theme.muted_color, |s| s.background_color);
+        let checked_color = theme.accent_color;
 
         frame.update_style(root, |style| {
-            style.width = heka::sizing::SizeSpec::Pixel(20);
-            style.height = heka::sizing::SizeSpec::Pixel(20);
-            style.background_color = checked_color;
-            style.border = heka::sizing::Border {
-                size: 2,
-                radius: 4,
-                color: Color::new(50, 50, 50, 255),
+            if let Some(default_style) = default_style {
+                *style = default_style;
+            } else {
+                style.width = heka::sizing::SizeSpec::Pixel(20);
+                style.height = heka::sizing::SizeSpec::Pixel(20);
+                style.border = heka::sizing::Border {
+                    size: 2,
+                    radius: 4,
+                    color: Color::new(50, 50, 50, 255),
+                };
+            }
+            style.background_color = if initial_checked {
+                checked_color
+            } else {
+                unchecked_color
             };
         });
 
         Self {
             frame,
             checked: initial_checked,
+            checked_color,
+            unchecked_color,
         }
     }
 
     pub fn toggle(&mut self, root: &mut heka::Root) {
         self.checked = !self.checked;
         let checked_color = if self.checked {
-            Color::new(100, 100, 255, 255)
+            self.checked_color
         } else {
-            Color::new(200, 200, 200, 255)
+            self.unchecked_color
         };
 
         self.frame.update_style(root, |style| {
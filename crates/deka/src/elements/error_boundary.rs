@@ -0,0 +1,37 @@
+use std::rc::Rc;
+
+use super::FrameElement;
+use crate::{Context, PanelRef};
+
+/// Builds the children of an `ErrorBoundary` in the panel it is given
+type BuildSubtree = Rc<dyn Fn(&mut Context, PanelRef)>;
+
+/// ErrorBoundary component
+///
+/// Holds a subtree built by a closure. When building it or one of the
+/// callbacks of its elements panics, the subtree gives way to a message
+/// and a button building it again.
+pub struct ErrorBoundary {
+    pub(crate) frame: heka::Frame,
+    pub(crate) build: BuildSubtree,
+    /// Message of the panic the fallback shows
+    pub(crate) error: Option<String>,
+}
+
+#[rustfmt::skip]
+impl FrameElement for ErrorBoundary {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[ERROR_BOUNDARY]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl ErrorBoundary {
+    /// Message of the panic the fallback shows, while it does
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
@@ -0,0 +1,204 @@
+use super::FrameElement;
+use crate::{Context, Element, ElementRef, LabelRef, PanelRef, TextStyle};
+use heka::{align, clr, flow, justify, layout, make_style, size};
+
+/// Space between the label column and the fields
+const COLUMN_GAP: u32 = 8;
+
+/// Form component.
+///
+/// Lays out rows of (label, field) pairs. Labels are right-aligned in a
+/// shared column as wide as the widest label of the form.
+pub struct Form {
+    pub(crate) frame: heka::Frame,
+    pub(crate) rows: Vec<FormRow>,
+    /// Current width of the label column
+    pub(crate) column_width: u32,
+}
+
+pub(crate) struct FormRow {
+    /// Holds the field row and, below it, the error row
+    entry: PanelRef,
+    label_cell: PanelRef,
+    label: LabelRef,
+    field: heka::CapsuleRef,
+    error: Option<FormError>,
+}
+
+struct FormError {
+    row: PanelRef,
+    /// Keeps the message under the field, past the label column
+    spacer: PanelRef,
+    label: LabelRef,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Form {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[FORM]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Form {
+    pub(crate) fn new(ctx: &mut Context, parent_frame: Option<impl ElementRef>) -> Self {
+        let parent = if let Some(pf) = parent_frame {
+            &heka::Frame::define(pf.raw())
+        } else {
+            &ctx.root_frame
+        };
+
+        let frame = ctx.root.add_frame_child(parent, None);
+        frame.update_style(&mut ctx.root, |style| {
+            style.width = size!(fit);
+            style.height = size!(fit);
+            style.layout = layout!(flex);
+            style.flow = flow!(column);
+            style.gap = 6;
+        });
+
+        Self {
+            frame,
+            rows: Vec::new(),
+            column_width: 0,
+        }
+    }
+
+    /// Adds a (label, field) row at the end of the form.
+    /// The field is moved into the form.
+    pub(crate) fn add_row(
+        &mut self,
+        ctx: &mut Context,
+        label_text: String,
+        field: heka::CapsuleRef,
+    ) -> LabelRef {
+        let entry = ctx.new_panel(
+            Some(Element(self.frame.get_ref())),
+            make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                layout: layout!(flex),
+                flow: flow!(column),
+                gap: 2,
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let row = ctx.new_panel(
+            Some(entry),
+            make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                layout: layout!(flex),
+                flow: flow!(row),
+                align_items: align!(center),
+                gap: COLUMN_GAP,
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let label_cell = ctx.new_panel(Some(row), self.cell_style());
+        let label = ctx.new_label(label_text, Some(label_cell), None);
+
+        let row_frame = heka::Frame::define(row.raw());
+        ctx.root.set_parent(heka::Frame::define(field), row_frame);
+
+        self.rows.push(FormRow {
+            entry,
+            label_cell,
+            label,
+            field,
+            error: None,
+        });
+
+        label
+    }
+
+    /// Shows `message` under `field`, or removes the error row with `None`.
+    pub(crate) fn set_error(
+        &mut self,
+        ctx: &mut Context,
+        field: heka::CapsuleRef,
+        message: Option<String>,
+    ) {
+        let cell_style = self.cell_style();
+        let Some(row) = self.rows.iter_mut().find(|row| row.field == field) else {
+            return;
+        };
+
+        match (message, &row.error) {
+            (Some(message), Some(error)) => ctx.set_label_text(error.label, message),
+            (Some(message), None) => {
+                let error_row = ctx.new_panel(
+                    Some(row.entry),
+                    make_style!(
+                        width: size!(fit),
+                        height: size!(fit),
+                        layout: layout!(flex),
+                        flow: flow!(row),
+                        gap: COLUMN_GAP,
+                        background_color: clr!(transparent),
+                    ),
+                );
+                let spacer = ctx.new_panel(Some(error_row), cell_style);
+                let error_style = TextStyle {
+                    color: ctx.theme.error_color,
+                    font_size: ctx.theme.font_size - 2.0,
+                    ..ctx.theme.text_style()
+                };
+                let label = ctx.new_label(message, Some(error_row), Some(error_style));
+
+                row.error = Some(FormError {
+                    row: error_row,
+                    spacer,
+                    label,
+                });
+            }
+            (None, Some(_)) => {
+                if let Some(error) = row.error.take() {
+                    ctx.remove_element_tree(error.row.raw());
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Sizes the label column to the widest measured label.
+    /// Only touches the cells when the width changed, which is reported.
+    pub(crate) fn align(&mut self, root: &mut heka::Root) -> bool {
+        let width = self
+            .rows
+            .iter()
+            .filter_map(|row| root.get_space(row.label.raw())?.width)
+            .max()
+            .unwrap_or(0);
+
+        if width == self.column_width {
+            return false;
+        }
+        self.column_width = width;
+
+        for row in &self.rows {
+            let cells = std::iter::once(row.label_cell).chain(row.error.as_ref().map(|e| e.spacer));
+            for cell in cells {
+                heka::Frame::define(cell.raw()).update_style(root, |style| {
+                    style.width = heka::sizing::SizeSpec::Pixel(width);
+                });
+            }
+        }
+        true
+    }
+
+    fn cell_style(&self) -> heka::Style {
+        make_style!(
+            width: heka::sizing::SizeSpec::Pixel(self.column_width),
+            height: size!(fit),
+            layout: layout!(flex),
+            flow: flow!(row),
+            justify_content: justify!(end),
+            background_color: clr!(transparent),
+        )
+    }
+}
@@ -0,0 +1,225 @@
+use super::FrameElement;
+use heka::color::Color;
+use heka::content::{Constraints, ContentProvider};
+use std::any::Any;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Pixels of an image, as rows of straight alpha RGBA
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    width: u32,
+    height: u32,
+    /// The renderer keeps the texture of the image while they are alive
+    pub(crate) pixels: Arc<[u8]>,
+}
+
+impl ImageData {
+    /// Wraps `width * height * 4` bytes of RGBA, `None` if there are not
+    /// as many
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        (width > 0 && height > 0 && pixels.len() == width as usize * height as usize * 4).then(
+            || Self {
+                width,
+                height,
+                pixels: pixels.into(),
+            },
+        )
+    }
+
+    /// Decodes a PNG file
+    pub fn from_png(bytes: &[u8]) -> Result<Self, ImageError> {
+        let (width, height, pixels) = crate::png::decode(bytes)?;
+        Ok(Self {
+            width,
+            height,
+            pixels: pixels.into(),
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// Where the pixels of an `Image` come from
+#[derive(Debug, Clone)]
+pub enum ImageSource {
+    /// A PNG file
+    Path(PathBuf),
+    /// The content of a PNG file
+    Bytes(Vec<u8>),
+    /// Decoded pixels
+    Data(ImageData),
+}
+
+impl ImageSource {
+    pub fn load(self) -> Result<ImageData, ImageError> {
+        match self {
+            ImageSource::Path(path) => ImageData::from_png(&std::fs::read(path)?),
+            ImageSource::Bytes(bytes) => ImageData::from_png(&bytes),
+            ImageSource::Data(data) => Ok(data),
+        }
+    }
+}
+
+impl From<PathBuf> for ImageSource {
+    fn from(path: PathBuf) -> Self {
+        ImageSource::Path(path)
+    }
+}
+
+impl From<&std::path::Path> for ImageSource {
+    fn from(path: &std::path::Path) -> Self {
+        ImageSource::Path(path.to_path_buf())
+    }
+}
+
+impl From<&str> for ImageSource {
+    fn from(path: &str) -> Self {
+        ImageSource::Path(path.into())
+    }
+}
+
+impl From<Vec<u8>> for ImageSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        ImageSource::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for ImageSource {
+    fn from(bytes: &[u8]) -> Self {
+        ImageSource::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<ImageData> for ImageSource {
+    fn from(data: ImageData) -> Self {
+        ImageSource::Data(data)
+    }
+}
+
+/// Why an image could not be loaded
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    /// The file is not a PNG file, or a corrupt one
+    Decode(String),
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Io(e) => write!(f, "unable to read the image: {e}"),
+            ImageError::Decode(message) => write!(f, "unable to decode the image: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImageError::Io(e) => Some(e),
+            ImageError::Decode(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ImageError {
+    fn from(e: std::io::Error) -> Self {
+        ImageError::Io(e)
+    }
+}
+
+/// Image component, stretched over its content box
+pub struct Image {
+    pub(crate) frame: heka::Frame,
+    /// The handle to the `ImageData`, which is stored in heka's `Allocator`
+    pub(crate) data_ref: heka::DataRef,
+    width: u32,
+    height: u32,
+    /// Multiplies the pixels, white leaves them as they are
+    pub(crate) tint: Color,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Image {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { Some(self.data_ref) }
+    fn name(&self) -> &str { "[IMAGE]" }
+
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+/// Sizes an image to its pixels. Given one side only, the other follows
+/// the aspect ratio.
+struct ImageContent {
+    width: u32,
+    height: u32,
+}
+
+impl ContentProvider for ImageContent {
+    fn measure(
+        &mut self,
+        constraints: Constraints,
+        _data: Option<&mut dyn Any>,
+        _context: &mut dyn Any,
+    ) -> (u32, u32) {
+        let fixed_width = constraints.max_width.filter(|_| constraints.fixed_width);
+        let (width, height) = (self.width as u64, self.height as u64);
+        match (fixed_width, constraints.max_height) {
+            (Some(w), None) => (w, (w as u64 * height / width) as u32),
+            (None, Some(h)) => ((h as u64 * width / height) as u32, h),
+            (Some(w), Some(h)) => (w, h),
+            (None, None) => (self.width, self.height),
+        }
+    }
+}
+
+impl Image {
+    pub(crate) fn new(
+        root: &mut heka::Root,
+        parent_frame: &heka::Frame,
+        data: ImageData,
+        style: heka::Style,
+    ) -> Self {
+        let (width, height) = (data.width, data.height);
+        let data_ref = root.set_binding(data);
+        let frame = root.add_frame_child(parent_frame, Some(data_ref));
+        frame.update_style(root, |s| {
+            *s = style;
+        });
+        root.set_content_provider(frame.get_ref(), ImageContent { width, height });
+
+        Self {
+            frame,
+            data_ref,
+            width,
+            height,
+            tint: Color::white,
+        }
+    }
+
+    /// Size of the image in pixels
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    #[inline]
+    pub fn tint(&self) -> Color {
+        self.tint
+    }
+}
@@ -1,7 +1,12 @@
 use super::FrameElement;
 use crate::TextStyle;
+use crate::buffer_pool::BufferPool;
 use cosmic_text::{Attrs, Buffer, FontSystem, Shaping};
 use heka::color::Color;
+use heka::content::{Constraints, ContentProvider};
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// Label component
 pub struct Label {
@@ -12,9 +17,17 @@ pub struct Label {
     /// The handle to the cosmic-text buffer, which is
     /// stored in heka's `Allocator`
     pub(crate) buffer_ref: heka::DataRef,
+    /// Set while the buffer is shared with other labels, see `BufferPool`
+    pub(crate) shared: Option<Sharing>,
+    /// Job of the shaping thread `text` waits for, the buffer still holds
+    /// the previous text
+    pub(crate) pending_shape: Option<u64>,
 
     /// Label Text style
     pub text_style: TextStyle,
+    /// Style before the text properties taken from the elements above,
+    /// `None` once the label was given a style of its own
+    pub(crate) base_style: Option<TextStyle>,
 }
 
 #[rustfmt::skip]
@@ -27,6 +40,105 @@ impl FrameElement for Label {
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
 }
 
+/// A buffer shared with other labels
+#[derive(Clone)]
+pub(crate) struct Sharing {
+    /// Size of the text as the buffer is shaped
+    size: (u32, u32),
+    /// Shaped by the layout once the label needs a buffer of its own,
+    /// see `Label::unshare`
+    own: Rc<RefCell<Option<Buffer>>>,
+}
+
+/// Measures the label buffer during layout.
+/// Expects the label's `Buffer` as frame data and the `FontSystem` as context.
+struct TextContent {
+    /// Full text of the label, the buffer may only hold part of it
+    /// once cut to `max_lines`
+    text: String,
+    style: TextStyle,
+    shared: Option<Sharing>,
+    /// Baseline of the first line as of the last measure
+    baseline: Option<f32>,
+}
+
+impl ContentProvider for TextContent {
+    fn measure(
+        &mut self,
+        constraints: Constraints,
+        data: Option<&mut dyn Any>,
+        context: &mut dyn Any,
+    ) -> (u32, u32) {
+        let Some(font_system) = context.downcast_mut::<FontSystem>() else {
+            return (0, 0);
+        };
+
+        let mut own;
+        let buffer = match &self.shared {
+            // Other labels show the shared buffer, it is left as it is
+            Some(shared)
+                if !constraints.fixed_width
+                    && constraints.max_width.is_none_or(|w| w >= shared.size.0)
+                    && constraints.max_height.is_none_or(|h| h >= shared.size.1) =>
+            {
+                self.baseline = data
+                    .and_then(|data| data.downcast_ref::<Buffer>())
+                    .and_then(first_baseline);
+                return shared.size;
+            }
+            Some(shared) => {
+                own = shared.own.borrow_mut();
+                own.get_or_insert_with(|| {
+                    let mut buffer = Buffer::new(font_system, self.style.as_cosmic_metrics());
+                    fill_buffer(&mut buffer, font_system, &self.text, &self.style);
+                    buffer
+                })
+            }
+            None => match data.and_then(|data| data.downcast_mut::<Buffer>()) {
+                Some(buffer) => buffer,
+                None => return (0, 0),
+            },
+        };
+
+        // Wraps within the width the layout offers
+        buffer.set_size(
+            font_system,
+            constraints.max_width.map(|w| w as f32),
+            constraints.max_height.map(|h| h as f32),
+        );
+        buffer.shape_until_scroll(font_system, true);
+
+        if let Some(max_lines) = self.style.max_lines {
+            // The previous measure may have cut the text for another width
+            fill_buffer(buffer, font_system, &self.text, &self.style);
+            truncate_lines(buffer, font_system, &self.style, max_lines.max(1) as usize);
+        }
+
+        self.baseline = first_baseline(buffer);
+        let (width, height) = Label::measure_buffer(buffer);
+        // A frame fitting its text is as wide as its longest line:
+        // aligning within the room of the parent would move the text out of it
+        if constraints.max_width.is_some() && !constraints.fixed_width {
+            buffer.set_size(
+                font_system,
+                Some(width as f32),
+                constraints.max_height.map(|h| h as f32),
+            );
+            buffer.shape_until_scroll(font_system, true);
+        }
+        (width, height)
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.baseline
+    }
+}
+
+/// Distance from the top of the text to the baseline of its first line
+fn first_baseline(buffer: &Buffer) -> Option<f32> {
+    buffer.layout_runs().next().map(|run| run.line_y)
+}
+
 impl Label {
     pub(crate) fn new(
         root: &mut heka::Root,
@@ -34,47 +146,90 @@ impl Label {
         text: String,
         text_style: TextStyle,
         font_system: &mut FontSystem,
+        pool: &mut BufferPool,
     ) -> Self {
-        let metrics = text_style.as_cosmic_metrics();
-        let attrs = text_style.as_cosmic_attrs();
-
-        let mut buffer = Buffer::new(font_system, metrics);
-        buffer.set_text(
-            font_system,
-            &text,
-            &Attrs {
-                family: text_style.font_family.as_family(),
-                ..attrs
-            },
-            Shaping::Advanced,
-            Some(text_style.align),
-        );
-
-        buffer.shape_until_scroll(font_system, true);
-
-        let (measured_width, measured_height) = Self::measure_buffer(&buffer);
-
-        let buffer_ref = root.set_binding(buffer);
+        let (buffer_ref, shared) =
+            Self::buffer_for(root, font_system, pool, &text, &text_style, None);
         let frame = if let Some(parent) = parent_frame {
             root.add_frame_child(parent, Some(buffer_ref))
         } else {
             root.add_frame(Some(buffer_ref))
         };
 
-        frame.update_style(root, |style| {
-            style.width = heka::sizing::SizeSpec::Fit;
-            style.height = heka::sizing::SizeSpec::Fit;
-            style.intrinsic_width = Some(measured_width);
-            style.intrinsic_height = Some(measured_height);
-            style.background_color = Color::new(0, 0, 0, 0);
-            // style.background_color = Color::new(70, 230, 230, 200);
-        });
+        frame.update_style(root, |style| *style = Self::frame_style());
+        Self::with_frame(root, frame, text, text_style, (buffer_ref, shared))
+    }
+
+    /// Style of the frame of a label: fitting its text, see-through
+    pub(crate) fn frame_style() -> heka::Style {
+        heka::Style {
+            width: heka::sizing::SizeSpec::Fit,
+            height: heka::sizing::SizeSpec::Fit,
+            background_color: Color::new(0, 0, 0, 0),
+            // background_color: Color::new(70, 230, 230, 200),
+            ..Default::default()
+        }
+    }
 
-        Self {
+    /// A label showing `text` in `frame`, created apart with the buffer
+    /// given by `buffer_for` as data
+    pub(crate) fn with_frame(
+        root: &mut heka::Root,
+        frame: heka::Frame,
+        text: String,
+        text_style: TextStyle,
+        (buffer_ref, shared): (heka::DataRef, Option<Sharing>),
+    ) -> Self {
+        let label = Self {
             frame,
             text,
             buffer_ref,
+            shared,
+            pending_shape: None,
             text_style,
+            base_style: None,
+        };
+        root.set_content_provider(frame.get_ref(), label.content());
+        label
+    }
+
+    /// A buffer showing `text` in `style`: the shared one if the text is
+    /// shared, else `own` (or a new buffer) shaped with it
+    pub(crate) fn buffer_for(
+        root: &mut heka::Root,
+        font_system: &mut FontSystem,
+        pool: &mut BufferPool,
+        text: &str,
+        style: &TextStyle,
+        own: Option<heka::DataRef>,
+    ) -> (heka::DataRef, Option<Sharing>) {
+        if let Some((buffer_ref, size)) = pool.acquire(root, font_system, text, style) {
+            if let Some(own) = own {
+                root.remove_binding(own);
+            }
+            let shared = Sharing {
+                size,
+                own: Rc::default(),
+            };
+            return (buffer_ref, Some(shared));
+        }
+
+        let buffer_ref = own.unwrap_or_else(|| {
+            root.set_binding(Buffer::new(font_system, style.as_cosmic_metrics()))
+        });
+        if let Some(buffer) = root.get_binding_mut::<Buffer>(buffer_ref) {
+            buffer.set_metrics(font_system, style.as_cosmic_metrics());
+            fill_buffer(buffer, font_system, text, style);
+        }
+        (buffer_ref, None)
+    }
+
+    fn content(&self) -> TextContent {
+        TextContent {
+            text: self.text.clone(),
+            style: self.text_style.clone(),
+            shared: self.shared.clone(),
+            baseline: None,
         }
     }
 
@@ -82,6 +237,7 @@ impl Label {
         &mut self,
         root: &mut heka::Root,
         font_system: &mut FontSystem,
+        pool: &mut BufferPool,
         new_text: String,
     ) {
         if self.text == new_text {
@@ -89,13 +245,14 @@ impl Label {
         }
 
         self.text = new_text;
-        self.remeasure_and_push(root, font_system);
+        self.remeasure_and_push(root, font_system, pool);
     }
 
     pub(crate) fn set_style(
         &mut self,
         root: &mut heka::Root,
         font_system: &mut FontSystem,
+        pool: &mut BufferPool,
         new_style: TextStyle,
     ) {
         if self.text_style == new_style {
@@ -103,7 +260,7 @@ impl Label {
         }
 
         self.text_style = new_style;
-        self.remeasure_and_push(root, font_system);
+        self.remeasure_and_push(root, font_system, pool);
     }
 
     #[inline]
@@ -111,7 +268,7 @@ impl Label {
         &self.text
     }
 
-    fn measure_buffer(buffer: &Buffer) -> (u32, u32) {
+    pub(crate) fn measure_buffer(buffer: &Buffer) -> (u32, u32) {
         let measured_width = buffer
             .layout_runs()
             .map(|run| run.line_w)
@@ -133,37 +290,140 @@ impl Label {
         )
     }
 
+    /// Shows a buffer shaped on a shaping thread with `text`
+    pub(crate) fn set_buffer(
+        &mut self,
+        root: &mut heka::Root,
+        pool: &mut BufferPool,
+        shaped: Buffer,
+    ) {
+        self.pending_shape = None;
+        if self.shared.take().is_some() {
+            pool.release(root, self.buffer_ref);
+            self.buffer_ref = root.set_binding(shaped);
+            root.rebind_data(self.frame.get_ref(), Some(self.buffer_ref));
+        } else if let Some(buffer) = root.get_binding_mut::<Buffer>(self.buffer_ref) {
+            *buffer = shaped;
+        } else {
+            return;
+        }
+        root.set_content_provider(self.frame.get_ref(), self.content());
+        root.invalidate_content(self.frame.get_ref());
+    }
+
     pub(crate) fn remeasure_and_push(
         &mut self,
         root: &mut heka::Root,
         font_system: &mut FontSystem,
+        pool: &mut BufferPool,
     ) {
-        if let Some(buffer) = root.get_binding_mut::<Buffer>(self.buffer_ref) {
-            let attrs = self.text_style.as_cosmic_attrs();
-            let metrics = self.text_style.as_cosmic_metrics();
-            buffer.set_metrics(font_system, metrics);
+        // Shaped here, whatever a shaping thread is doing
+        self.pending_shape = None;
+        // A shared buffer is left to the other labels
+        let previous = self.buffer_ref;
+        let own = self.shared.is_none().then_some(previous);
+        let (buffer_ref, shared) =
+            Self::buffer_for(root, font_system, pool, &self.text, &self.text_style, own);
+        if self.shared.is_some() {
+            pool.release(root, previous);
+        }
+        (self.buffer_ref, self.shared) = (buffer_ref, shared);
+        root.rebind_data(self.frame.get_ref(), Some(self.buffer_ref));
 
-            buffer.set_text(
-                font_system,
-                &self.text,
-                &Attrs {
-                    family: self.text_style.font_family.as_family(),
-                    ..attrs
-                },
-                Shaping::Advanced,
-                Some(self.text_style.align),
-            );
+        root.set_content_provider(self.frame.get_ref(), self.content());
+        // `TextContent` measures it again on the next compute
+        root.invalidate_content(self.frame.get_ref());
+    }
 
-            buffer.shape_until_scroll(font_system, true);
+    /// Gives the label the buffer of its own the layout shaped, once it
+    /// had the text wrap. Returns whether it did.
+    pub(crate) fn unshare(&mut self, root: &mut heka::Root, pool: &mut BufferPool) -> bool {
+        // The shaping thread brings a buffer of its own
+        if self.pending_shape.is_some() {
+            return false;
+        }
+        let Some(buffer) = self
+            .shared
+            .as_ref()
+            .and_then(|shared| shared.own.borrow_mut().take())
+        else {
+            return false;
+        };
 
-            let (measured_width, measured_height) = Self::measure_buffer(buffer);
+        pool.release(root, self.buffer_ref);
+        self.shared = None;
+        self.buffer_ref = root.set_binding(buffer);
+        root.rebind_data(self.frame.get_ref(), Some(self.buffer_ref));
 
-            self.frame.update_style(root, |style| {
-                style.intrinsic_width = Some(measured_width);
-                style.intrinsic_height = Some(measured_height);
-            });
+        root.set_content_provider(self.frame.get_ref(), self.content());
+        root.invalidate_content(self.frame.get_ref());
+        true
+    }
+}
+
+/// Shapes `text` into `buffer` as `style` displays it
+pub(crate) fn fill_buffer(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    text: &str,
+    style: &TextStyle,
+) {
+    buffer.set_wrap(font_system, style.as_cosmic_wrap());
+    set_buffer_text(buffer, font_system, &style.display_text(text), style);
+}
+
+fn set_buffer_text(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    text: &str,
+    style: &TextStyle,
+) {
+    buffer.set_text(
+        font_system,
+        text,
+        &Attrs {
+            family: style.font_family.as_family(),
+            ..style.as_cosmic_attrs()
+        },
+        Shaping::Advanced,
+        Some(style.align),
+    );
+    buffer.shape_until_scroll(font_system, true);
+}
+
+/// Cuts the text of `buffer` to `max_lines` lines, ending the last one
+/// with an ellipsis
+fn truncate_lines(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    style: &TextStyle,
+    max_lines: usize,
+) {
+    let Some(last) = buffer.layout_runs().nth(max_lines - 1) else {
+        return;
+    };
+    if buffer.layout_runs().count() <= max_lines {
+        return;
+    }
+    let (last_line, end) = (
+        last.line_i,
+        last.glyphs.iter().map(|glyph| glyph.end).max().unwrap_or(0),
+    );
+
+    // Text of the lines shown, as laid out
+    let mut kept = String::new();
+    for line in &buffer.lines[..last_line] {
+        kept.push_str(line.text());
+        kept.push('\n');
+    }
+    kept.push_str(&buffer.lines[last_line].text()[..end]);
 
-            self.frame.set_dirty(root);
+    // Room for the ellipsis is made one character at a time
+    loop {
+        let cut = format!("{}…", kept.trim_end());
+        set_buffer_text(buffer, font_system, &cut, style);
+        if buffer.layout_runs().count() <= max_lines || kept.pop().is_none() {
+            break;
         }
     }
 }
@@ -0,0 +1,47 @@
+use super::FrameElement;
+use crate::LabelRef;
+
+/// Hyperlink component: a label that opens its URL when activated
+pub struct Link {
+    /// The clickable frame holding the label
+    pub(crate) frame: heka::Frame,
+    pub(crate) url: String,
+    /// The handle to the child label
+    pub child_label: LabelRef,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Link {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[LINK]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Link {
+    #[inline]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Opens `url` with the default handler of the system
+#[cfg(feature = "open-url")]
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    use std::process::Command;
+
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(url).spawn().map(|_| ())
+}
@@ -1,16 +1,54 @@
 use std::any::Any;
 
+#[cfg(feature = "text")]
+pub use avatar::{Avatar, AvatarSize, AvatarStatus};
+#[cfg(feature = "text")]
 pub use button::Button;
+pub use canvas::{Canvas, CanvasTransform, Paint, Scene, SceneRect, Shape, ShapeId};
+pub use carousel::Carousel;
 pub use checkbox::Checkbox;
+#[cfg(feature = "text")]
+pub use error_boundary::ErrorBoundary;
+#[cfg(feature = "text")]
+pub use form::Form;
+pub use image::{Image, ImageData, ImageError, ImageSource};
+#[cfg(feature = "text")]
 pub use label::Label;
+#[cfg(feature = "text")]
+pub(crate) use label::{Sharing, fill_buffer};
+#[cfg(feature = "text")]
+pub use link::Link;
+#[cfg(feature = "open-url")]
+pub use link::open_url;
 pub use panel::Panel;
+pub use scroll_view::ScrollView;
+#[cfg(feature = "text")]
 pub use text_input::TextInput;
+#[cfg(feature = "text")]
+pub use toolbar::{Breadcrumb, Toolbar};
 
+#[cfg(feature = "text")]
+mod avatar;
+#[cfg(feature = "text")]
 mod button;
+mod canvas;
+mod carousel;
 mod checkbox;
+#[cfg(feature = "text")]
+mod error_boundary;
+#[cfg(feature = "text")]
+mod form;
+mod image;
+#[cfg(feature = "text")]
 mod label;
+#[cfg(feature = "text")]
+mod link;
 mod panel;
+mod scroll_view;
+#[cfg(feature = "text")]
 mod text_input;
+#[cfg(feature = "text")]
+mod toolbar;
 
 pub trait FrameElement: 'static {
     fn get_frame(&self) -> heka::Frame;
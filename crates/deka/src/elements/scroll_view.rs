@@ -0,0 +1,46 @@
+use super::FrameElement;
+use crate::{PanelRef, ScrollSnap};
+
+/// ScrollView component
+///
+/// A viewport over a content panel, scrolled with the wheel or by dragging
+/// the thumbs of its scrollbars. Children go in the content panel, which
+/// grows with them.
+pub struct ScrollView {
+    pub(crate) frame: heka::Frame,
+    pub(crate) content: PanelRef,
+    /// Can be pulled past the ends of its content, see `Context::set_overscroll`
+    pub(crate) overscroll: bool,
+    pub(crate) snap: ScrollSnap,
+    /// Distance the content was pulled past its ends, the overscroll drawn
+    /// is less
+    pub(crate) stretch: (f32, f32),
+}
+
+#[rustfmt::skip]
+impl FrameElement for ScrollView {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[SCROLL_VIEW]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl ScrollView {
+    /// The panel holding the scrolled children
+    #[inline]
+    pub fn content(&self) -> PanelRef {
+        self.content
+    }
+
+    #[inline]
+    pub fn overscroll(&self) -> bool {
+        self.overscroll
+    }
+
+    #[inline]
+    pub fn scroll_snap(&self) -> ScrollSnap {
+        self.snap
+    }
+}
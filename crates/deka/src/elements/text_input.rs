@@ -1,11 +1,55 @@
-use super::FrameElement;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use super::{FrameElement, Paint};
+use crate::cmd::DrawCommand;
+use crate::commands::{TextEdit, TextEditKind};
 use crate::events::KeyEvent;
-use crate::{Context, Element, ElementRef, LabelRef};
+use crate::{ButtonRef, Context, DrawList, Element, ElementRef, LabelRef, TextInputRef};
+use cosmic_text::Buffer;
+use heka::color::Color;
+use heka::geometry::Rect;
+
+/// Width of the caret
+const CARET_WIDTH: u32 = 2;
 
 /// TextInput component
+///
+/// Edits its text at a cursor, moved with the arrow, Home and End keys.
+/// Holding Shift while moving it selects the text in between, which typing
+/// replaces. The caret and the selection highlight are drawn with the
+/// input, their colors and blink come from the theme when the input is
+/// created.
+///
+/// A masked input (passwords...) shows a character in place of each of
+/// its own, see `Context::set_input_mask`. Its edits are not kept for
+/// undo.
 pub struct TextInput {
     pub(crate) frame: heka::Frame,
     pub(crate) label: LabelRef,
+    /// Byte index of the caret in the text
+    cursor: usize,
+    /// Byte index of the other end of the selection, the cursor when
+    /// nothing is selected
+    pub(crate) anchor: usize,
+    /// Shows the selection, and the caret when on
+    focused: bool,
+    caret_shown: bool,
+    pub(crate) caret_color: Color,
+    pub(crate) selection_color: Color,
+    pub(crate) caret_blink: Option<Duration>,
+    /// Keeps the caret on while typing
+    last_edit: Option<Instant>,
+    /// Shown in place of each character
+    pub(crate) mask: Option<char>,
+    /// The text of a masked input, its label shows the mask
+    pub(crate) value: String,
+    /// The text of a masked input is shown as is for now
+    pub(crate) revealed: bool,
+    /// Toggles `revealed`
+    pub(crate) reveal_button: Option<ButtonRef>,
+    /// The selection of a masked input can be copied
+    pub(crate) masked_copy: bool,
 }
 
 #[rustfmt::skip]
@@ -31,26 +75,218 @@ impl TextInput {
         };
 
         let input_frame = ctx.root.add_frame_child(parent, None);
+        let (input_color, border_color) = (ctx.theme.input_color, ctx.theme.border_color);
+        let defaults = ctx.default_style::<TextInput>().cloned();
 
         input_frame.update_style(&mut ctx.root, |style| {
+            if let Some(defaults) = &defaults {
+                *style = defaults.style;
+                return;
+            }
             style.width = heka::sizing::SizeSpec::Pixel(200);
             style.height = heka::sizing::SizeSpec::Pixel(30);
             style.padding = heka::sizing::Padding::all(5);
-            style.background_color = heka::color::Color::new(255, 255, 255, 255);
+            style.background_color = input_color;
             style.border = heka::sizing::Border {
                 size: 1,
                 radius: 2,
-                color: heka::color::Color::new(150, 150, 150, 255),
+                color: border_color,
             };
             style.layout = heka::position::LayoutStrategy::Flex;
         });
 
-        let label = ctx.new_label(initial_text, Some(Element(input_frame.get_ref())), None);
+        // Typing starts at the end of the text
+        let cursor = initial_text.len();
+        let label = ctx.new_label(
+            initial_text,
+            Some(Element(input_frame.get_ref())),
+            defaults.and_then(|defaults| defaults.text_style),
+        );
 
         Self {
             frame: input_frame,
             label,
+            cursor,
+            anchor: cursor,
+            focused: false,
+            caret_shown: false,
+            caret_color: ctx.theme.caret_color,
+            selection_color: ctx.theme.selection_color,
+            caret_blink: ctx.theme.caret_blink,
+            last_edit: None,
+            mask: None,
+            value: String::new(),
+            revealed: false,
+            reveal_button: None,
+            masked_copy: false,
+        }
+    }
+
+    /// Character shown in place of each of the text, if masked
+    #[inline]
+    pub fn mask(&self) -> Option<char> {
+        self.mask
+    }
+
+    /// Whether the text of a masked input is shown as is
+    #[inline]
+    pub fn revealed(&self) -> bool {
+        self.revealed
+    }
+
+    /// The text of the input, not the mask shown
+    pub(crate) fn text<'a>(&'a self, ctx: &'a Context) -> &'a str {
+        if self.mask.is_some() {
+            &self.value
+        } else {
+            ctx.get_label_text(self.label)
+        }
+    }
+
+    /// Replaces the text, shown masked if the input is
+    pub(crate) fn set_text(&mut self, ctx: &mut Context, text: &str) {
+        if self.mask.is_none() {
+            ctx.set_label_text(self.label, text);
+            return;
+        }
+        self.value = text.to_string();
+        ctx.set_label_text(self.label, self.shown_text(ctx));
+    }
+
+    /// What the label shows for the text
+    pub(crate) fn shown_text(&self, ctx: &Context) -> String {
+        match self.hidden_by() {
+            Some(mask) => std::iter::repeat_n(mask, self.value.chars().count()).collect(),
+            None => self.text(ctx).to_string(),
+        }
+    }
+
+    /// The mask, while it hides the text
+    fn hidden_by(&self) -> Option<char> {
+        self.mask.filter(|_| !self.revealed)
+    }
+
+    /// Byte index in the shown text of the byte `index` of the text
+    fn shown_index(&self, index: usize) -> usize {
+        match self.hidden_by() {
+            Some(mask) => self.value[..index].chars().count() * mask.len_utf8(),
+            None => index,
+        }
+    }
+
+    #[inline]
+    pub fn caret_color(&self) -> Color {
+        self.caret_color
+    }
+
+    /// Highlight behind the selected text
+    #[inline]
+    pub fn selection_color(&self) -> Color {
+        self.selection_color
+    }
+
+    #[inline]
+    pub fn caret_blink(&self) -> Option<Duration> {
+        self.caret_blink
+    }
+
+    /// Byte index of the caret in the text
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Byte range of the selected text, empty when nothing is selected
+    pub fn selection(&self) -> Range<usize> {
+        self.anchor.min(self.cursor)..self.anchor.max(self.cursor)
+    }
+
+    /// Moves the cursor to `cursor` and the other end of the selection to
+    /// `anchor`, both kept on a character of `text`
+    pub(crate) fn select(&mut self, text: &str, anchor: usize, cursor: usize) {
+        self.anchor = floor_boundary(text, anchor);
+        self.cursor = floor_boundary(text, cursor);
+    }
+
+    /// Shows the caret and the selection, or hides them when the input
+    /// loses the focus
+    pub(crate) fn set_focused(&mut self, ctx: &mut Context, focused: bool) {
+        self.focused = focused;
+        self.show_caret(ctx, focused);
+    }
+
+    pub(crate) fn show_caret(&mut self, ctx: &mut Context, shown: bool) {
+        self.caret_shown = shown;
+        ctx.request_redraw();
+    }
+
+    /// Turns the caret on or off, on every blink. It stays on while typing.
+    pub(crate) fn blink(&mut self, ctx: &mut Context) {
+        let typing = self
+            .last_edit
+            .zip(self.caret_blink)
+            .is_some_and(|(edit, blink)| edit.elapsed() < blink);
+        self.show_caret(ctx, typing || !self.caret_shown);
+    }
+
+    /// The selection highlight, under the text, and the caret, over it,
+    /// for the text shaped in `buffer` and laid out in `text`.
+    /// Returns them with their draw priority.
+    pub(crate) fn draw_commands(
+        &self,
+        buffer: &Buffer,
+        text: Rect,
+        z_index: u32,
+    ) -> Vec<(u8, DrawCommand)> {
+        if !self.focused {
+            return Vec::new();
+        }
+        // Under the text and over it, from the top-left corner of the text
+        let mut highlight = DrawList::new(z_index);
+        let mut caret = DrawList::new(z_index);
+        for list in [&mut highlight, &mut caret] {
+            list.translate(text.x as f32, text.y as f32);
+        }
+
+        let starts = line_starts(buffer);
+        let selection = self.selection();
+        let selection = self.shown_index(selection.start)..self.shown_index(selection.end);
+        if !selection.is_empty() {
+            for run in buffer.layout_runs() {
+                let start = starts[run.line_i];
+                let (left, right) = run
+                    .glyphs
+                    .iter()
+                    .filter(|glyph| {
+                        selection.start <= start + glyph.start && start + glyph.end <= selection.end
+                    })
+                    .fold((f32::MAX, f32::MIN), |(left, right), glyph| {
+                        (left.min(glyph.x), right.max(glyph.x + glyph.w))
+                    });
+                if left < right {
+                    highlight.push_rect(
+                        [left, run.line_top],
+                        [right - left, run.line_height],
+                        0.0,
+                        Paint::fill(self.selection_color),
+                    );
+                }
+            }
+        }
+
+        if self.caret_shown {
+            let (x, top, height) = caret_position(buffer, &starts, self.shown_index(self.cursor))
+                .unwrap_or((0.0, 0.0, buffer.metrics().line_height));
+            caret.push_rect(
+                [x, top],
+                [CARET_WIDTH as f32, height],
+                0.0,
+                Paint::fill(self.caret_color),
+            );
         }
+        let highlight = highlight.into_commands().into_iter().map(|c| (0, c));
+        let caret = caret.into_commands().into_iter().map(|c| (2, c));
+        highlight.chain(caret).collect()
     }
 
     pub fn handle_key(&mut self, ctx: &mut Context, event: &KeyEvent) {
@@ -58,20 +294,160 @@ impl TextInput {
             return;
         }
 
-        use winit::keyboard::Key;
-        match &event.logical_key {
-            Key::Named(winit::keyboard::NamedKey::Backspace) => {
-                let mut text = ctx.get_label_text(self.label).to_string();
-                text.pop();
-                ctx.set_label_text(self.label, text);
+        use winit::keyboard::{Key, NamedKey};
+        let before = self.text(ctx).to_string();
+        // The text may have changed since the last key, by an undo
+        self.select(&before, self.anchor, self.cursor);
+        let extend = ctx.shift_held;
+        let selection = self.selection();
+
+        let (kind, range, inserted) = match &event.logical_key {
+            Key::Named(NamedKey::ArrowLeft) => {
+                let to = if selection.is_empty() || extend {
+                    previous_boundary(&before, self.cursor)
+                } else {
+                    selection.start
+                };
+                return self.move_cursor(ctx, to, extend);
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                let to = if selection.is_empty() || extend {
+                    next_boundary(&before, self.cursor)
+                } else {
+                    selection.end
+                };
+                return self.move_cursor(ctx, to, extend);
+            }
+            Key::Named(NamedKey::Home) => return self.move_cursor(ctx, 0, extend),
+            Key::Named(NamedKey::End) => return self.move_cursor(ctx, before.len(), extend),
+            Key::Named(NamedKey::Backspace) => {
+                let range = if selection.is_empty() {
+                    previous_boundary(&before, self.cursor)..self.cursor
+                } else {
+                    selection
+                };
+                (TextEditKind::Delete, range, "")
+            }
+            Key::Named(NamedKey::Delete) => {
+                let range = if selection.is_empty() {
+                    self.cursor..next_boundary(&before, self.cursor)
+                } else {
+                    selection
+                };
+                (TextEditKind::Delete, range, "")
             }
             _ => {
-                if let Some(text_to_append) = &event.text {
-                    let mut text = ctx.get_label_text(self.label).to_string();
-                    text.push_str(text_to_append.as_str());
-                    ctx.set_label_text(self.label, text);
-                }
+                // Enter, Tab and shortcuts don't type
+                let Some(typed) = event
+                    .text
+                    .as_ref()
+                    .filter(|typed| !typed.chars().any(char::is_control))
+                else {
+                    return;
+                };
+                (TextEditKind::Insert, selection, typed.as_str())
             }
+        };
+
+        self.last_edit = Some(ctx.now());
+        self.show_caret(ctx, true);
+
+        if range.is_empty() && inserted.is_empty() {
+            return;
+        }
+        let selection_before = (self.anchor, self.cursor);
+        let mut after = before.clone();
+        after.replace_range(range.clone(), inserted);
+        self.cursor = range.start + inserted.len();
+        self.anchor = self.cursor;
+
+        let input = TextInputRef(self.frame.get_ref());
+        self.set_text(ctx, &after);
+        // The history would keep masked texts in the clear
+        if self.mask.is_none() {
+            ctx.history.push(Box::new(TextEdit {
+                input,
+                kind,
+                before,
+                after: after.clone(),
+                selection_before,
+                selection_after: (self.cursor, self.cursor),
+            }));
+        }
+        ctx.emit_text_change(input, after);
+    }
+
+    /// Moves the cursor to `to`, and the selection with it unless `extend`
+    fn move_cursor(&mut self, ctx: &mut Context, to: usize, extend: bool) {
+        self.cursor = to;
+        if !extend {
+            self.anchor = to;
+        }
+        self.last_edit = Some(ctx.now());
+        self.show_caret(ctx, true);
+    }
+}
+
+/// The character boundary at or before `index`
+fn floor_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn previous_boundary(text: &str, index: usize) -> usize {
+    text[..index]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+fn next_boundary(text: &str, index: usize) -> usize {
+    text[index..]
+        .chars()
+        .next()
+        .map_or(index, |c| index + c.len_utf8())
+}
+
+/// Byte index in the text where each line of `buffer` starts
+fn line_starts(buffer: &Buffer) -> Vec<usize> {
+    let mut start = 0;
+    buffer
+        .lines
+        .iter()
+        .map(|line| {
+            let line_start = start;
+            start += line.text().len() + line.ending().as_str().len();
+            line_start
+        })
+        .collect()
+}
+
+/// Where the caret sits before the byte `index` of the text: its x, and
+/// the top and height of its line. `None` if the text is not laid out.
+fn caret_position(buffer: &Buffer, starts: &[usize], index: usize) -> Option<(f32, f32, f32)> {
+    let line_i = starts.iter().rposition(|start| *start <= index)?;
+    let index = index - starts[line_i];
+
+    let mut end = None;
+    for run in buffer.layout_runs().filter(|run| run.line_i == line_i) {
+        if let Some(glyph) = run
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start <= index && index < glyph.end)
+        {
+            let x = if glyph.level.is_rtl() {
+                glyph.x + glyph.w
+            } else {
+                glyph.x
+            };
+            return Some((x, run.line_top, run.line_height));
         }
+        // Past the last glyph of the line
+        let x = run.glyphs.last().map_or(0.0, |glyph| glyph.x + glyph.w);
+        end = Some((x, run.line_top, run.line_height));
     }
+    end
 }
@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+
+use super::FrameElement;
+use crate::{ButtonRef, ClickEvent, Context, Element, ElementRef, LabelRef, PanelRef};
+use heka::position::{Offset, Overflow, Position};
+use heka::{Style, flow, layout, make_style, size};
+
+/// Layer holding the opened overflow menus, above the main UI
+const MENU_LAYER: &str = "deka-overflow-menus";
+const MENU_LAYER_ORDER: i32 = 100;
+
+/// Which items go to the menu first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collapse {
+    /// The last ones, the "…" button closes the row
+    End,
+    /// The ones after the first, the "…" button takes their place
+    Middle,
+}
+
+struct RowItem {
+    button: ButtonRef,
+    text: String,
+    /// In front of the item, breadcrumbs only
+    separator: Option<LabelRef>,
+}
+
+/// Row of buttons moving the ones that do not fit into a "…" menu.
+///
+/// Widths are measured while the items are shown and kept for when they
+/// are not. Items left out are moved under `stash`, a panel of no size
+/// clipping its children, so they are neither drawn nor hit.
+pub(crate) struct OverflowRow {
+    pub(crate) frame: heka::Frame,
+    collapse: Collapse,
+    items: Vec<RowItem>,
+    more: ButtonRef,
+    /// In front of `more`, breadcrumbs only
+    more_separator: Option<LabelRef>,
+    stash: PanelRef,
+    /// Outer widths of the frames of the row, as last shown
+    widths: HashMap<heka::CapsuleRef, u32>,
+    /// Frames in the row, in order
+    shown: Vec<heka::CapsuleRef>,
+    /// Items in the menu
+    hidden: Vec<usize>,
+    menu: Option<PanelRef>,
+}
+
+impl OverflowRow {
+    fn new(
+        ctx: &mut Context,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+        collapse: Collapse,
+    ) -> Self {
+        let parent = if let Some(pf) = parent_frame {
+            &heka::Frame::define(pf.raw())
+        } else {
+            &ctx.root_frame
+        };
+
+        let frame = ctx.root.add_frame_child(parent, None);
+        frame.update_style(&mut ctx.root, |s| {
+            *s = style;
+            s.layout = layout!(flex);
+            s.flow = flow!(row);
+        });
+        let bar = Element(frame.get_ref());
+
+        let origin = Position::Fixed {
+            x: Offset::Pixel(0),
+            y: Offset::Pixel(0),
+        };
+        let stash = ctx.new_panel(
+            Some(bar),
+            make_style!(
+                width: size!(0),
+                height: size!(0),
+                position: origin,
+                overflow: Overflow::Hidden,
+            ),
+        );
+
+        let more_separator = (collapse == Collapse::Middle).then(|| separator(ctx, bar));
+        let bar_ref = frame.get_ref();
+        let more = ctx.new_button(
+            "…",
+            Some(bar),
+            move |ctx, _| ctx.toggle_overflow_menu(bar_ref),
+            None,
+        );
+        keep_width(ctx, more.raw());
+
+        Self {
+            frame,
+            collapse,
+            items: Vec::new(),
+            more,
+            more_separator,
+            stash,
+            widths: HashMap::new(),
+            shown: Vec::new(),
+            hidden: Vec::new(),
+            menu: None,
+        }
+    }
+
+    /// Adds a button at the end of the row
+    fn push<F>(&mut self, ctx: &mut Context, text: String, on_click: F) -> ButtonRef
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        let bar = Element(self.frame.get_ref());
+        let separator = (self.collapse == Collapse::Middle && !self.items.is_empty())
+            .then(|| separator(ctx, bar));
+        let button = ctx.new_button(text.clone(), Some(bar), on_click, None);
+        keep_width(ctx, button.raw());
+
+        self.items.push(RowItem {
+            button,
+            text,
+            separator,
+        });
+        button
+    }
+
+    /// Removes the items from `len` on
+    fn truncate(&mut self, ctx: &mut Context, len: usize) {
+        if len >= self.items.len() {
+            return;
+        }
+        self.close_menu(ctx);
+        for item in self.items.drain(len..) {
+            self.widths.remove(&item.button.raw());
+            self.shown.retain(|cref| *cref != item.button.raw());
+            ctx.remove_element(item.button);
+            if let Some(separator) = item.separator {
+                self.widths.remove(&separator.raw());
+                self.shown.retain(|cref| *cref != separator.raw());
+                ctx.remove_element(separator);
+            }
+        }
+        self.hidden.retain(|&i| i < len);
+    }
+
+    #[inline]
+    fn buttons(&self) -> Vec<ButtonRef> {
+        self.items.iter().map(|item| item.button).collect()
+    }
+
+    /// Shows what fits of the row, after a layout.
+    /// Returns whether the row changed and has to be laid out again.
+    fn fit(&mut self, ctx: &mut Context) -> bool {
+        let bar = self.frame.get_ref();
+        let (Some(content), Some(style)) =
+            (ctx.root.get_content_rect(bar), ctx.root.get_style(bar))
+        else {
+            return false;
+        };
+
+        let in_row: Vec<heka::CapsuleRef> = ctx
+            .root
+            .get_capsule(bar)
+            .map(|capsule| capsule.children().to_vec())
+            .unwrap_or_default();
+        for cref in in_row {
+            if let Some(width) = outer_width(&ctx.root, cref) {
+                self.widths.insert(cref, width);
+            }
+        }
+
+        let Some((shown_items, collapsed)) = self.arrange(content.w, style.gap) else {
+            // Not measured yet
+            return false;
+        };
+
+        let mut row = Vec::new();
+        let push_item = |row: &mut Vec<heka::CapsuleRef>, item: &RowItem| {
+            row.extend(item.separator.map(|separator| separator.raw()));
+            row.push(item.button.raw());
+        };
+        match self.collapse {
+            Collapse::End => {
+                for &i in &shown_items {
+                    push_item(&mut row, &self.items[i]);
+                }
+                if collapsed {
+                    row.push(self.more.raw());
+                }
+            }
+            Collapse::Middle => {
+                for (n, &i) in shown_items.iter().enumerate() {
+                    push_item(&mut row, &self.items[i]);
+                    if n == 0 && collapsed {
+                        row.extend(self.more_separator.map(|separator| separator.raw()));
+                        row.push(self.more.raw());
+                    }
+                }
+            }
+        }
+
+        if row == self.shown {
+            return false;
+        }
+
+        // Items moving in or out of the menu leave it outdated
+        self.close_menu(ctx);
+
+        for &cref in &row {
+            ctx.root.set_parent(heka::Frame::define(cref), self.frame);
+        }
+        let stash = heka::Frame::define(self.stash.raw());
+        for cref in self.frames() {
+            if !row.contains(&cref) {
+                ctx.root.set_parent(heka::Frame::define(cref), stash);
+            }
+        }
+
+        self.hidden = (0..self.items.len())
+            .filter(|i| !shown_items.contains(i))
+            .collect();
+        self.shown = row;
+        true
+    }
+
+    /// Every frame the row manages
+    fn frames(&self) -> Vec<heka::CapsuleRef> {
+        let items = self.items.iter().flat_map(|item| {
+            item.separator
+                .map(|separator| separator.raw())
+                .into_iter()
+                .chain([item.button.raw()])
+        });
+        self.more_separator
+            .map(|separator| separator.raw())
+            .into_iter()
+            .chain([self.more.raw()])
+            .chain(items)
+            .collect()
+    }
+
+    /// Items to show in `available` pixels, in order, and whether the
+    /// "…" button is needed. `None` while some width is unknown.
+    fn arrange(&self, available: u32, gap: u32) -> Option<(Vec<usize>, bool)> {
+        let width = |cref: heka::CapsuleRef| self.widths.get(&cref).copied();
+        // Width of an item with the separator in front of it
+        let cost = |item: &RowItem| -> Option<u32> {
+            let separator = match item.separator {
+                Some(separator) => width(separator.raw())? + gap,
+                None => 0,
+            };
+            Some(width(item.button.raw())? + separator)
+        };
+        let costs = self.items.iter().map(cost).collect::<Option<Vec<u32>>>()?;
+        let more = width(self.more.raw())?
+            + match self.more_separator {
+                Some(separator) => width(separator.raw())? + gap,
+                None => 0,
+            };
+
+        // Width of `count` units, gaps included
+        let span = |widths: u32, count: usize| widths + gap * count.saturating_sub(1) as u32;
+
+        let all: Vec<usize> = (0..costs.len()).collect();
+        if span(costs.iter().sum(), costs.len()) <= available {
+            return Some((all, false));
+        }
+
+        match self.collapse {
+            Collapse::End => {
+                let mut total = more;
+                let mut count = 0;
+                for &cost in &costs {
+                    if span(total + cost, count + 2) > available {
+                        break;
+                    }
+                    total += cost;
+                    count += 1;
+                }
+                Some(((0..count).collect(), true))
+            }
+            Collapse::Middle => {
+                // The first and the last items stay, the current location
+                // being the last one
+                let n = costs.len();
+                if n < 3 {
+                    return Some((all, false));
+                }
+                let mut total = costs[0] + more + costs[n - 1];
+                let mut first_tail = n - 1;
+                while first_tail > 1 {
+                    let cost = costs[first_tail - 1];
+                    if span(total + cost, n - first_tail + 3) > available {
+                        break;
+                    }
+                    total += cost;
+                    first_tail -= 1;
+                }
+                let shown = std::iter::once(0).chain(first_tail..n).collect();
+                Some((shown, true))
+            }
+        }
+    }
+
+    /// Opens the menu listing the hidden items under the "…" button,
+    /// or closes it
+    fn toggle_menu(&mut self, ctx: &mut Context) {
+        if self.menu.is_some() {
+            self.close_menu(ctx);
+            return;
+        }
+        let Some(anchor) = ctx.root.get_rect(self.more.raw()) else {
+            return;
+        };
+
+        let layer = ctx
+            .layer(MENU_LAYER)
+            .unwrap_or_else(|| ctx.create_layer(MENU_LAYER, MENU_LAYER_ORDER));
+        let position = Position::Fixed {
+            x: Offset::Pixel(anchor.x),
+            y: Offset::Pixel(anchor.y + anchor.h as i32),
+        };
+        let border = heka::sizing::Border {
+            size: 1,
+            radius: 5,
+            color: ctx.theme.border_color,
+        };
+        let menu = ctx.new_panel(
+            Some(layer),
+            make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                position: position,
+                layout: layout!(flex),
+                flow: flow!(column),
+                padding: heka::pad!(2),
+                gap: 2,
+                border: border,
+                background_color: ctx.theme.surface_color,
+            ),
+        );
+
+        let bar = self.frame.get_ref();
+        for &i in &self.hidden {
+            let item = self.items[i].button.raw();
+            ctx.new_button(
+                self.items[i].text.clone(),
+                Some(menu),
+                move |ctx, _| {
+                    ctx.with_overflow_row(bar, |row, ctx| row.close_menu(ctx));
+                    ctx.activate(item);
+                },
+                None,
+            );
+        }
+        self.menu = Some(menu);
+    }
+
+    fn close_menu(&mut self, ctx: &mut Context) {
+        if let Some(menu) = self.menu.take() {
+            ctx.remove_element(menu);
+        }
+    }
+}
+
+/// Keeps the natural width of a frame in the row, it is what gets measured
+fn keep_width(ctx: &mut Context, cref: heka::CapsuleRef) {
+    heka::Frame::define(cref).update_style(&mut ctx.root, |style| style.flex_shrink = 0.0);
+}
+
+fn separator(ctx: &mut Context, bar: Element) -> LabelRef {
+    let text_style = crate::TextStyle {
+        color: ctx.theme.muted_color,
+        ..ctx.theme.text_style()
+    };
+    let label = ctx.new_label("›", Some(bar), Some(text_style));
+    keep_width(ctx, label.raw());
+    label
+}
+
+/// Laid out width of a frame, margins included
+fn outer_width(root: &heka::Root, cref: heka::CapsuleRef) -> Option<u32> {
+    let width = root.get_space(cref)?.width?;
+    let margin = root.get_style(cref)?.margin;
+    Some(width + margin.left + margin.right)
+}
+
+/// Toolbar component.
+///
+/// A row of buttons. The ones at the end that do not fit in its width go to
+/// a menu opened by a "…" button closing the row.
+pub struct Toolbar {
+    pub(crate) row: OverflowRow,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Toolbar {
+    fn get_frame(&self) -> heka::Frame { self.row.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[TOOLBAR]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Toolbar {
+    pub(crate) fn new(
+        ctx: &mut Context,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> Self {
+        Self {
+            row: OverflowRow::new(ctx, parent_frame, style, Collapse::End),
+        }
+    }
+
+    pub(crate) fn add_item<F>(&mut self, ctx: &mut Context, text: String, on_click: F) -> ButtonRef
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.row.push(ctx, text, on_click)
+    }
+
+    /// The buttons of the toolbar, shown or not
+    #[inline]
+    pub fn items(&self) -> Vec<ButtonRef> {
+        self.row.buttons()
+    }
+}
+
+/// Breadcrumb component.
+///
+/// A path of buttons separated by "›". When it does not fit in its width,
+/// the items after the first one go to a menu opened by a "…" button in
+/// their place, the last item staying in view.
+pub struct Breadcrumb {
+    pub(crate) row: OverflowRow,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Breadcrumb {
+    fn get_frame(&self) -> heka::Frame { self.row.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[BREADCRUMB]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Breadcrumb {
+    pub(crate) fn new(
+        ctx: &mut Context,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> Self {
+        Self {
+            row: OverflowRow::new(ctx, parent_frame, style, Collapse::Middle),
+        }
+    }
+
+    pub(crate) fn push<F>(&mut self, ctx: &mut Context, text: String, on_click: F) -> ButtonRef
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.row.push(ctx, text, on_click)
+    }
+
+    pub(crate) fn truncate(&mut self, ctx: &mut Context, len: usize) {
+        self.row.truncate(ctx, len);
+    }
+
+    /// The buttons of the path, from the first one, shown or not
+    #[inline]
+    pub fn items(&self) -> Vec<ButtonRef> {
+        self.row.buttons()
+    }
+}
+
+impl Context {
+    /// Runs `op` on the row of a toolbar or breadcrumb
+    pub(crate) fn with_overflow_row(
+        &mut self,
+        cref: heka::CapsuleRef,
+        op: impl FnOnce(&mut OverflowRow, &mut Context),
+    ) {
+        let Some(element) = self.elements.get(&cref) else {
+            return;
+        };
+        if element.as_any().is::<Toolbar>() {
+            self.with_component_mut::<Toolbar>(cref, |toolbar, ctx| op(&mut toolbar.row, ctx));
+        } else if element.as_any().is::<Breadcrumb>() {
+            self.with_component_mut::<Breadcrumb>(cref, |breadcrumb, ctx| {
+                op(&mut breadcrumb.row, ctx)
+            });
+        }
+    }
+
+    fn toggle_overflow_menu(&mut self, bar: heka::CapsuleRef) {
+        self.with_overflow_row(bar, |row, ctx| {
+            row.toggle_menu(ctx);
+            ctx.request_redraw();
+        });
+    }
+
+    /// Collapses the toolbars and breadcrumbs to their width.
+    /// Returns whether any of them changed.
+    pub(crate) fn fit_overflow_rows(&mut self) -> bool {
+        let rows: Vec<heka::CapsuleRef> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| {
+                element.as_any().is::<Toolbar>() || element.as_any().is::<Breadcrumb>()
+            })
+            .map(|(cref, _)| *cref)
+            .collect();
+
+        let mut changed = false;
+        for cref in rows {
+            self.with_overflow_row(cref, |row, ctx| changed |= row.fit(ctx));
+        }
+        changed
+    }
+}
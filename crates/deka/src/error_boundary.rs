@@ -0,0 +1,158 @@
+//! Error boundaries: a panic building a part of the UI, or in one of the
+//! callbacks of its elements, replaces that part with a fallback saying
+//! what went wrong and offering to build it again, rather than leaving it
+//! half updated.
+
+use std::rc::Rc;
+
+use heka::{CapsuleRef, Style, clr, flow, layout, make_style, size};
+
+use crate::elements::ErrorBoundary;
+use crate::events::ErrorEvent;
+use crate::{Context, Element, ElementRef, ErrorBoundaryRef, PanelRef};
+
+impl Context {
+    /// Creates an `ErrorBoundary` sized by `style`, and fills it with
+    /// `build`. If `build` or a callback of an element inside panics, the
+    /// elements are removed and a message with a retry button, building
+    /// them again, shows instead. The panic still goes to the hook of
+    /// `on_callback_panic`.
+    pub fn new_error_boundary<F>(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+        build: F,
+    ) -> ErrorBoundaryRef
+    where
+        F: Fn(&mut Context, PanelRef) + 'static,
+    {
+        let cref = self.new_panel(parent_frame, style).raw();
+        self.elements.insert(
+            cref,
+            Box::new(ErrorBoundary {
+                frame: heka::Frame::define(cref),
+                build: Rc::new(build),
+                error: None,
+            }),
+        );
+        self.build_boundary(cref);
+        ErrorBoundaryRef(cref)
+    }
+
+    pub fn get_error_boundary(&self, boundary: ErrorBoundaryRef) -> Option<&ErrorBoundary> {
+        let element = self.elements.get(&boundary.raw())?;
+        element.as_any().downcast_ref::<ErrorBoundary>()
+    }
+
+    /// Called when the boundary catches a panic, before its fallback shows
+    pub fn on_error<F>(&mut self, boundary: ErrorBoundaryRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ErrorEvent) + 'static,
+    {
+        self.error_callbacks
+            .insert(boundary.raw(), Box::new(callback));
+    }
+
+    /// Builds the subtree of the boundary again in place of its fallback,
+    /// or of the subtree itself
+    pub fn retry_error_boundary(&mut self, boundary: ErrorBoundaryRef) {
+        let cref = boundary.raw();
+        let Some(boundary) = self.elements.get_mut(&cref) else {
+            return;
+        };
+        if let Some(boundary) = boundary.as_any_mut().downcast_mut::<ErrorBoundary>() {
+            boundary.error = None;
+        }
+        self.clear_boundary(cref);
+        self.build_boundary(cref);
+    }
+
+    fn build_boundary(&mut self, cref: CapsuleRef) {
+        let Some(build) = self
+            .get_error_boundary(ErrorBoundaryRef(cref))
+            .map(|boundary| boundary.build.clone())
+        else {
+            return;
+        };
+        self.guarded_on(cref, |ctx| build(ctx, PanelRef(cref)));
+    }
+
+    /// Removes the children of the boundary
+    fn clear_boundary(&mut self, cref: CapsuleRef) {
+        let children = self
+            .root
+            .get_capsule(cref)
+            .map(|capsule| capsule.children().to_vec())
+            .unwrap_or_default();
+        for child in children {
+            self.remove_element(Element(child));
+        }
+    }
+
+    /// Shows the fallback of the boundary around `cref`, which panicked
+    /// with `message`. Returns whether there was one.
+    pub(crate) fn catch_in_boundary(&mut self, cref: CapsuleRef, message: &str) -> bool {
+        let mut current = Some(cref);
+        let boundary = loop {
+            let Some(cref) = current else {
+                return false;
+            };
+            if self.get_error_boundary(ErrorBoundaryRef(cref)).is_some() {
+                break cref;
+            }
+            current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        };
+
+        if let Some(mut callback) = self.error_callbacks.remove(&boundary) {
+            let event = ErrorEvent {
+                message: message.to_string(),
+            };
+            // A panicking error callback goes to the boundary around this one
+            let parent = self.root.get_capsule(boundary).and_then(|c| c.parent_ref);
+            let outer = std::mem::replace(&mut self.guarded_element, parent);
+            self.guarded(|ctx| callback(ctx, &event));
+            self.guarded_element = outer;
+            self.error_callbacks.entry(boundary).or_insert(callback);
+        }
+        if let Some(element) = self.elements.get_mut(&boundary)
+            && let Some(boundary) = element.as_any_mut().downcast_mut::<ErrorBoundary>()
+        {
+            boundary.error = Some(message.to_string());
+        }
+        self.clear_boundary(boundary);
+        self.show_fallback(boundary, message);
+        self.request_redraw();
+        true
+    }
+
+    fn show_fallback(&mut self, cref: CapsuleRef, message: &str) {
+        let fallback = self.new_panel(
+            Some(Element(cref)),
+            make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                layout: layout!(flex),
+                flow: flow!(column),
+                padding: heka::pad!(8),
+                gap: 6,
+                background_color: clr!(transparent),
+            ),
+        );
+        let text_style = crate::TextStyle {
+            color: self.theme.error_color,
+            ..self.theme.text_style()
+        };
+        self.new_label(
+            format!("Something went wrong: {message}"),
+            Some(fallback),
+            Some(text_style),
+        );
+        let boundary = ErrorBoundaryRef(cref);
+        self.new_button(
+            "Retry",
+            Some(fallback),
+            move |ctx, _| ctx.retry_error_boundary(boundary),
+            None,
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! Tracing of event dispatch, to find out why an element did not get an
+//! event: each event is logged with the elements under the cursor, the
+//! ones whose callbacks ran and the focus changes it caused.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use heka::CapsuleRef;
+#[cfg(feature = "text")]
+use heka::position::PointerEvents;
+#[cfg(feature = "text")]
+use heka::{Style, flow, layout};
+
+use crate::events::SystemEvent;
+use crate::{Context, Element};
+#[cfg(feature = "text")]
+use crate::{ElementRef, LabelRef, PanelRef, TextStyle};
+
+/// Events kept for `traced_events` and the event log panels
+const TRACE_CAPACITY: usize = 200;
+
+/// Lines of an event log panel, the newest event first
+#[cfg(feature = "text")]
+const EVENT_LOG_LINES: usize = 16;
+
+/// What happened to an element under the cursor during an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitStatus {
+    /// One of its callbacks ran
+    Handled,
+    /// Had no callback for the event, which went on to the elements below
+    Passed,
+    /// Below the element that handled the event
+    NotReached,
+    /// Skipped, see `Context::set_enabled`
+    Disabled,
+    /// Skipped, see `Context::set_interactive`
+    NonInteractive,
+}
+
+/// An element an event went through
+#[derive(Debug, Clone)]
+pub struct TracedElement {
+    pub element: Element,
+    /// Kind of the element and its id, like `[BUTTON] 4@0`
+    pub name: String,
+    pub status: HitStatus,
+}
+
+/// An event as it was dispatched, see `Context::trace_events`
+#[derive(Debug, Clone)]
+pub struct TracedEvent {
+    /// The event, like `release Left at (120, 48)`
+    pub description: String,
+    /// Elements under the cursor for pointer events, topmost first, or the
+    /// focused one for the others, then the other elements that handled it
+    pub chain: Vec<TracedElement>,
+    /// Part of deka that took the event before the elements could, like
+    /// the scrollbars or the drag and drop
+    pub taken_by: Option<&'static str>,
+    /// Focused element before and after the event, if it changed
+    pub focus_change: Option<(Option<Element>, Option<Element>)>,
+}
+
+impl TracedEvent {
+    /// Whether a callback of an element ran for the event
+    pub fn handled(&self) -> bool {
+        self.chain
+            .iter()
+            .any(|element| element.status == HitStatus::Handled)
+    }
+}
+
+impl fmt::Display for TracedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)?;
+        let handlers: Vec<&str> = self
+            .chain
+            .iter()
+            .filter(|element| element.status == HitStatus::Handled)
+            .map(|element| element.name.as_str())
+            .collect();
+        match self.taken_by {
+            Some(part) => write!(f, " -> taken by the {part}")?,
+            None if handlers.is_empty() => write!(f, " -> unhandled")?,
+            None => write!(f, " -> handled by {}", handlers.join(", "))?,
+        }
+
+        let hit: Vec<String> = self
+            .chain
+            .iter()
+            .map(|element| {
+                let status = match element.status {
+                    HitStatus::Handled => "handled",
+                    HitStatus::Passed => "passed",
+                    HitStatus::NotReached => "not reached",
+                    HitStatus::Disabled => "disabled",
+                    HitStatus::NonInteractive => "non-interactive",
+                };
+                format!("{} ({status})", element.name)
+            })
+            .collect();
+        if !hit.is_empty() {
+            write!(f, " | {}", hit.join(" > "))?;
+        }
+
+        if let Some((from, to)) = &self.focus_change {
+            let name = |element: &Option<Element>| match element {
+                Some(element) => format!("{:?}", element.0),
+                None => "nothing".to_string(),
+            };
+            write!(f, " | focus {} -> {}", name(from), name(to))?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracing state, while `Context::trace_events` is on
+#[derive(Default)]
+pub(crate) struct EventTrace {
+    /// The event being dispatched
+    current: Option<PendingTrace>,
+    events: VecDeque<TracedEvent>,
+}
+
+struct PendingTrace {
+    description: String,
+    /// Whether the elements under the cursor are part of the trace
+    pointer: bool,
+    /// Cursor moves are only kept if they did something
+    quiet: bool,
+    handlers: Vec<CapsuleRef>,
+    taken_by: Option<&'static str>,
+    focused: Option<CapsuleRef>,
+}
+
+fn describe(event: &SystemEvent) -> Option<(String, bool)> {
+    let at = |pos: &winit::dpi::PhysicalPosition<f64>| format!("({:.0}, {:.0})", pos.x, pos.y);
+    Some(match event {
+        SystemEvent::Click {
+            pos,
+            button,
+            pressed,
+            double_click,
+        } => {
+            let action = if *pressed { "press" } else { "release" };
+            let double = if *double_click { " (double)" } else { "" };
+            (format!("{action} {button:?}{double} at {}", at(pos)), true)
+        }
+        SystemEvent::CursorMoved(pos) => (format!("move to {}", at(pos)), true),
+        SystemEvent::Keyboard {
+            logical_key,
+            pressed,
+            repeat,
+            ..
+        } => {
+            let action = if *pressed { "down" } else { "up" };
+            let repeat = if *repeat { " (repeat)" } else { "" };
+            (format!("key {logical_key:?} {action}{repeat}"), false)
+        }
+        SystemEvent::Scroll(delta) => (format!("scroll by ({:.1}, {:.1})", delta.x, delta.y), true),
+        SystemEvent::ScrollPhase(phase) => (format!("scroll {phase:?}"), true),
+        SystemEvent::Pinch { delta, phase } => (format!("pinch {delta:.3} {phase:?}"), true),
+        SystemEvent::Resize(w, h) => (format!("resize to {w}x{h}"), false),
+        SystemEvent::RequestRedraw => return None,
+    })
+}
+
+impl Context {
+    /// Logs every event dispatched from now on (at the debug level, target
+    /// `deka::events`): the elements under the cursor and what each did
+    /// with it, the callbacks that ran and the focus changes. The last
+    /// events are kept, see `traced_events` and `new_event_log`.
+    pub fn trace_events(&mut self, enabled: bool) {
+        match enabled {
+            true if self.trace.is_none() => self.trace = Some(Box::default()),
+            false => self.trace = None,
+            _ => {}
+        }
+    }
+
+    pub fn tracing_events(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// The last events traced, the oldest first
+    pub fn traced_events(&self) -> impl DoubleEndedIterator<Item = &TracedEvent> {
+        self.trace.iter().flat_map(|trace| trace.events.iter())
+    }
+
+    /// Starts tracing `event`, about to be handled
+    pub(crate) fn trace_start(&mut self, event: &SystemEvent) {
+        let focused = self.focused_element;
+        let Some(trace) = &mut self.trace else {
+            return;
+        };
+        trace.current = describe(event).map(|(description, pointer)| PendingTrace {
+            description,
+            pointer,
+            quiet: matches!(event, SystemEvent::CursorMoved(_)),
+            handlers: Vec::new(),
+            taken_by: None,
+            focused,
+        });
+    }
+
+    /// Notes that `cref` handles the traced event: one of its callbacks
+    /// runs, or it scrolls
+    pub(crate) fn trace_handler(&mut self, cref: CapsuleRef) {
+        if let Some(current) = self.trace.as_mut().and_then(|trace| trace.current.as_mut())
+            && !current.handlers.contains(&cref)
+        {
+            current.handlers.push(cref);
+        }
+    }
+
+    /// Notes that `part` of deka took the traced event
+    pub(crate) fn trace_taken(&mut self, part: &'static str) {
+        if let Some(current) = self.trace.as_mut().and_then(|trace| trace.current.as_mut()) {
+            current.taken_by.get_or_insert(part);
+        }
+    }
+
+    /// Logs the traced event once handled
+    pub(crate) fn trace_end(&mut self) {
+        let Some(current) = self.trace.as_mut().and_then(|trace| trace.current.take()) else {
+            return;
+        };
+        let focus_change = (current.focused != self.focused_element).then(|| {
+            (
+                current.focused.map(Element),
+                self.focused_element.map(Element),
+            )
+        });
+        if current.quiet
+            && current.handlers.is_empty()
+            && current.taken_by.is_none()
+            && focus_change.is_none()
+        {
+            return;
+        }
+
+        let mut chain = Vec::new();
+        if current.pointer {
+            let pos = self.mouse_pos;
+            let mut reached = false;
+            for cref in self.root.hit_test(pos.x.ceil() as i32, pos.y.ceil() as i32) {
+                let status = if self.non_interactive.contains(&cref) {
+                    HitStatus::NonInteractive
+                } else if self.is_disabled(cref) {
+                    HitStatus::Disabled
+                } else if current.handlers.contains(&cref) {
+                    reached = true;
+                    HitStatus::Handled
+                } else if reached {
+                    HitStatus::NotReached
+                } else {
+                    HitStatus::Passed
+                };
+                chain.push(self.traced_element(cref, status));
+            }
+        } else if let Some(cref) = current.focused
+            && !current.handlers.contains(&cref)
+        {
+            // The keys go to the focused element, with or without a callback
+            chain.push(self.traced_element(cref, HitStatus::Passed));
+        }
+        for &cref in &current.handlers {
+            if !chain.iter().any(|element| element.element.0 == cref) {
+                chain.push(self.traced_element(cref, HitStatus::Handled));
+            }
+        }
+
+        let event = TracedEvent {
+            description: current.description,
+            chain,
+            taken_by: current.taken_by,
+            focus_change,
+        };
+        log::debug!(target: "deka::events", "{event}");
+        if let Some(trace) = &mut self.trace {
+            if trace.events.len() == TRACE_CAPACITY {
+                trace.events.pop_front();
+            }
+            trace.events.push_back(event);
+        }
+        #[cfg(feature = "text")]
+        self.refresh_event_logs();
+    }
+
+    fn traced_element(&self, cref: CapsuleRef, status: HitStatus) -> TracedElement {
+        let kind = self
+            .elements
+            .get(&cref)
+            .map_or("[FRAME]", |element| element.name());
+        TracedElement {
+            element: Element(cref),
+            name: format!("{kind} {cref:?}"),
+            status,
+        }
+    }
+
+    /// Creates a panel listing the last traced events as a column, the
+    /// newest on top. The pointer goes through it to the elements below.
+    /// Events are only traced once `trace_events` is on.
+    #[cfg(feature = "text")]
+    pub fn new_event_log(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> PanelRef {
+        let panel = self.new_panel(
+            parent_frame,
+            Style {
+                layout: layout!(flex),
+                flow: flow!(column),
+                pointer_events: PointerEvents::None,
+                ..style
+            },
+        );
+        let text_style = TextStyle {
+            font_size: 11.0,
+            ..self.theme.text_style()
+        };
+        let lines = (0..EVENT_LOG_LINES)
+            .map(|_| self.new_label("", Some(panel), Some(text_style.clone())))
+            .collect();
+        self.event_logs.insert(panel.raw(), lines);
+        self.refresh_event_logs();
+        panel
+    }
+
+    /// Shows the last traced events in the event log panels
+    #[cfg(feature = "text")]
+    fn refresh_event_logs(&mut self) {
+        if self.event_logs.is_empty() {
+            return;
+        }
+        let texts: Vec<String> = self
+            .traced_events()
+            .rev()
+            .take(EVENT_LOG_LINES)
+            .map(ToString::to_string)
+            .collect();
+        let labels: Vec<LabelRef> = self.event_logs.values().flatten().copied().collect();
+        for (i, label) in labels.into_iter().enumerate() {
+            let text = texts.get(i % EVENT_LOG_LINES).map_or("", String::as_str);
+            if self.get_label_text(label) != text {
+                self.set_label_text(label, text);
+            }
+        }
+    }
+}
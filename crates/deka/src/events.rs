@@ -2,7 +2,10 @@ use winit::{dpi::PhysicalPosition, event::MouseButton, keyboard::SmolStr};
 
 #[derive(Debug, Clone, Copy)]
 pub struct ClickEvent {
+    /// Cursor position in the window
     pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
     pub button: MouseButton,
     pub double_click: bool,
 }
@@ -10,6 +13,174 @@ pub struct ClickEvent {
 #[derive(Debug, Clone, Copy)]
 pub struct HoverEvent {
     pub hovered: bool,
+    /// Cursor position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointerMoveEvent {
+    /// Cursor position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+    /// Cursor movement since the last dispatched move
+    pub delta: PhysicalPosition<f64>,
+    /// Whether the element holds the pointer capture (a button was pressed on it)
+    pub captured: bool,
+}
+
+/// The cursor moved with a button held, after a press on the element
+#[derive(Debug, Clone, Copy)]
+pub struct DragEvent {
+    /// Cursor position in the window when the button was pressed
+    pub start: PhysicalPosition<f64>,
+    /// Cursor position in the window
+    pub current: PhysicalPosition<f64>,
+    /// Cursor movement since the last drag event
+    pub delta: PhysicalPosition<f64>,
+    pub button: MouseButton,
+}
+
+/// A dragged element was dropped on the element, see `Context::on_drop`
+#[derive(Clone)]
+pub struct DropEvent {
+    /// Element that was dragged
+    pub source: crate::Element,
+    /// Cursor position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+    pub(crate) payload: std::rc::Rc<dyn std::any::Any>,
+}
+
+impl DropEvent {
+    /// The value the dragged element carries, see `Context::set_draggable`
+    pub fn payload<T: 'static>(&self) -> Option<&T> {
+        self.payload.downcast_ref()
+    }
+}
+
+impl std::fmt::Debug for DropEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DropEvent")
+            .field("source", &self.source)
+            .field("pos", &self.pos)
+            .field("local_pos", &self.local_pos)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Stage of a touchpad gesture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GesturePhase {
+    Begin,
+    Update,
+    /// The fingers left the touchpad, or the gesture was cancelled
+    End,
+}
+
+impl From<winit::event::TouchPhase> for GesturePhase {
+    fn from(phase: winit::event::TouchPhase) -> Self {
+        use winit::event::TouchPhase;
+        match phase {
+            TouchPhase::Started => GesturePhase::Begin,
+            TouchPhase::Moved => GesturePhase::Update,
+            TouchPhase::Ended | TouchPhase::Cancelled => GesturePhase::End,
+        }
+    }
+}
+
+/// Wheel or touchpad scroll over an element, see `Context::on_scroll`
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollEvent {
+    /// Cursor position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+    /// Scroll amount in pixels, positive towards the top-left.
+    /// Zero for `Begin` and `End`.
+    pub delta: PhysicalPosition<f64>,
+    /// Mouse wheels only send updates, touchpads begin and end their scrolls
+    pub phase: GesturePhase,
+    /// The fingers left the touchpad and the scroll carries on by inertia
+    pub momentum: bool,
+}
+
+/// A scroll container moved, see `Context::on_scroll_offset`
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollOffsetEvent {
+    /// Scroll position, in pixels from the top-left of the content
+    pub offset: (f32, f32),
+    /// Largest scroll position of the container
+    pub max_offset: (f32, f32),
+}
+
+/// A panic caught by an error boundary, see `Context::on_error`
+#[cfg(feature = "text")]
+#[derive(Debug, Clone)]
+pub struct ErrorEvent {
+    pub message: String,
+}
+
+/// A carousel shows another page, see `Context::on_page_change`
+#[derive(Debug, Clone, Copy)]
+pub struct PageChangeEvent {
+    /// Page mostly in view now
+    pub page: usize,
+    pub previous: usize,
+}
+
+/// Touchpad pinch over an element, see `Context::on_pinch`
+#[derive(Debug, Clone, Copy)]
+pub struct PinchEvent {
+    /// Cursor position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Cursor position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+    /// Change of magnification, positive when zooming in.
+    /// Scale by `1.0 + delta` to follow the fingers.
+    pub delta: f64,
+    pub phase: GesturePhase,
+}
+
+/// A position over an element, see `Context::element_at`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointInElement {
+    /// Position in the window
+    pub pos: PhysicalPosition<f64>,
+    /// Position relative to the element's content box
+    pub local_pos: PhysicalPosition<f64>,
+}
+
+/// Click on a shape of a `Canvas`
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeEvent {
+    pub shape: crate::elements::ShapeId,
+    /// Cursor position in scene units
+    pub scene_pos: [f32; 2],
+    pub click: ClickEvent,
+}
+
+/// The text of a `TextInput` was edited
+#[cfg(feature = "text")]
+#[derive(Debug, Clone)]
+pub struct TextChangeEvent {
+    pub input: crate::TextInputRef,
+    /// Text after the edit
+    pub text: String,
+}
+
+/// Interactions that may deserve audible feedback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InteractionSound {
+    /// A button was activated
+    Click,
+    /// A two-state element (checkbox...) changed state
+    Toggle,
+    /// A validation error was shown
+    Error,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +188,8 @@ pub struct KeyEvent {
     pub logical_key: winit::keyboard::Key,
     pub text: Option<SmolStr>,
     pub pressed: bool,
+    /// The key is held down and pressed again, see `Context::set_key_repeat`
+    pub repeat: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -25,9 +198,11 @@ pub enum WindowCommand {
     SetSize(u32, u32),
     SetResizable(bool),
     SetDecorations(bool),
+    SetBlur(bool),
     Maximize,
     Minimize,
     DragWindow,
+    SetCursor(winit::window::CursorIcon),
     Quit,
 }
 
@@ -44,6 +219,17 @@ pub enum SystemEvent {
         logical_key: winit::keyboard::Key,
         text: Option<SmolStr>,
         pressed: bool,
+        repeat: bool,
+    },
+    /// Mouse wheel or touchpad scroll, in pixels.
+    /// Positive values scroll towards the top-left.
+    Scroll(PhysicalPosition<f64>),
+    /// Start or end of a touchpad scroll, around its `Scroll` events
+    ScrollPhase(GesturePhase),
+    /// Touchpad pinch, see `PinchEvent`
+    Pinch {
+        delta: f64,
+        phase: GesturePhase,
     },
     Resize(u32, u32),
     RequestRedraw,
@@ -0,0 +1,174 @@
+//! Focus scopes: while a modal or a popup is open, the keyboard focus
+//! stays in it, and goes back where it was once it closes.
+
+use heka::CapsuleRef;
+
+use crate::{Context, Element, ElementRef, FOCUS_SCROLL_MARGIN};
+
+/// An element keeping the focus inside, see `Context::trap_focus`
+pub(crate) struct FocusScope {
+    element: CapsuleRef,
+    /// Focused when the scope was opened, focused again when it closes
+    previous: Option<CapsuleRef>,
+}
+
+impl Context {
+    /// Keeps the focus within `scope`, a modal or a popup: Tab, the arrow
+    /// keys of spatial navigation and `set_focus` only reach the elements
+    /// inside it, and keys only go to them. Its first focusable element is
+    /// focused, unless the focus already is inside.
+    ///
+    /// Scopes may be open together: the one stacked highest (by layer,
+    /// then z-index, then the last opened) holds the focus.
+    pub fn trap_focus(&mut self, scope: impl ElementRef) {
+        let element = scope.raw();
+        if self
+            .focus_scopes
+            .iter()
+            .any(|scope| scope.element == element)
+        {
+            return;
+        }
+        self.focus_scopes.push(FocusScope {
+            element,
+            previous: self.focused_element,
+        });
+
+        if self.active_focus_scope() == Some(element)
+            && !self
+                .focused_element
+                .is_some_and(|focused| self.in_focus_scope(focused))
+        {
+            let first = self.focus_order().first().copied();
+            self.move_focus(first);
+        }
+    }
+
+    /// Lets the focus out of `scope`. If it held the focus, the element
+    /// focused when it was trapped gets it back, when it still exists.
+    /// Removing the scope element releases it too.
+    /// Returns false if `scope` was not trapping the focus.
+    pub fn release_focus(&mut self, scope: impl ElementRef) -> bool {
+        let element = scope.raw();
+        let Some(index) = self
+            .focus_scopes
+            .iter()
+            .position(|scope| scope.element == element)
+        else {
+            return false;
+        };
+
+        let was_active = self.active_focus_scope() == Some(element);
+        let scope = self.focus_scopes.remove(index);
+        if was_active {
+            let previous = scope.previous.filter(|previous| {
+                self.elements.contains_key(previous) && self.in_focus_scope(*previous)
+            });
+            self.move_focus(previous);
+        }
+        true
+    }
+
+    /// The scope holding the focus, if any
+    pub(crate) fn active_focus_scope(&self) -> Option<CapsuleRef> {
+        self.focus_scopes
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, scope)| {
+                let z_index = self
+                    .root
+                    .get_style(scope.element)
+                    .map_or(0, |style| style.z_index);
+                (self.root.layer_order(scope.element), z_index, *index)
+            })
+            .map(|(_, scope)| scope.element)
+    }
+
+    /// Whether the focus can go to `cref`: it is inside the active scope,
+    /// or no scope is active
+    pub(crate) fn in_focus_scope(&self, cref: CapsuleRef) -> bool {
+        let Some(scope) = self.active_focus_scope() else {
+            return true;
+        };
+        let mut current = Some(cref);
+        while let Some(cref) = current {
+            if cref == scope {
+                return true;
+            }
+            current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        }
+        false
+    }
+
+    /// Focusable elements of the active scope (or of the whole UI) in tree
+    /// order, the order Tab goes through them
+    fn focus_order(&self) -> Vec<CapsuleRef> {
+        let focusable = |cref: &CapsuleRef| {
+            self.click_callbacks.contains_key(cref)
+                || self.keyboard_callbacks.contains_key(cref)
+                || self.mouse_button_callbacks.keys().any(|(c, _)| c == cref)
+        };
+
+        let mut stack: Vec<CapsuleRef> = match self.active_focus_scope() {
+            Some(scope) => vec![scope],
+            None => {
+                // Top-most layers last, as they are stacked
+                let layers = self.root.layers();
+                let mut tops: Vec<_> = layers.iter().map(|(frame, _)| frame.get_ref()).collect();
+                tops.insert(0, self.root_frame.get_ref());
+                tops.into_iter().rev().collect()
+            }
+        };
+
+        let mut order = Vec::new();
+        while let Some(cref) = stack.pop() {
+            // Nothing of a disabled subtree takes the focus
+            if self.disabled.contains(&cref) {
+                continue;
+            }
+            if focusable(&cref) {
+                order.push(cref);
+            }
+            if let Some(capsule) = self.root.get_capsule(cref) {
+                stack.extend(capsule.children().iter().rev());
+            }
+        }
+        order
+    }
+
+    /// Moves the focus to the next focusable element, or the previous one
+    /// if `backward`, coming back to the first after the last.
+    /// Returns whether there is any.
+    pub(crate) fn tab_focus(&mut self, backward: bool) -> bool {
+        let order = self.focus_order();
+        let len = order.len();
+        if len == 0 {
+            return false;
+        }
+
+        let current = self
+            .focused_element
+            .and_then(|focused| order.iter().position(|cref| *cref == focused));
+        let next = match (current, backward) {
+            (Some(index), false) => (index + 1) % len,
+            (Some(index), true) => (index + len - 1) % len,
+            (None, false) => 0,
+            (None, true) => len - 1,
+        };
+        self.focus_visible = true;
+        self.focus(order[next]);
+        self.scroll_into_view(Element(order[next]), FOCUS_SCROLL_MARGIN);
+        true
+    }
+
+    fn move_focus(&mut self, to: Option<CapsuleRef>) {
+        match to {
+            Some(cref) => self.focus(cref),
+            None => {
+                self.focused_element = None;
+                #[cfg(feature = "text")]
+                self.update_caret();
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Numbers and dates written the way the locale does, for labels following
+//! a signal, see `Context::bind_formatted`.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Context, LabelRef, Signal};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Order of the day, month and year in a date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    /// 2026-10-18
+    YearMonthDay,
+    /// 10/18/2026
+    MonthDayYear,
+    /// 18/10/2026
+    DayMonthYear,
+}
+
+/// How numbers, dates and times are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    /// Between the groups of three digits of large numbers
+    pub thousands_separator: char,
+    pub date_order: DateOrder,
+    pub date_separator: char,
+    /// Times on a 12-hour clock, with AM and PM
+    pub hour12: bool,
+    /// Offset from UTC of the dates and times written, in minutes. Never
+    /// detected, times are written in UTC until it is set by hand.
+    pub utc_offset: i32,
+}
+
+impl Default for Locale {
+    /// ISO 8601 dates and 24-hour times, with a dot before the decimals
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: ',',
+            date_order: DateOrder::YearMonthDay,
+            date_separator: '-',
+            hour12: false,
+            utc_offset: 0,
+        }
+    }
+}
+
+impl Locale {
+    /// The locale of a language tag such as `fr_FR.UTF-8` or `en-GB`.
+    /// Unknown languages get the default one.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        let (language, region) = tag.split_once(['_', '-']).unwrap_or((tag, ""));
+        let base = Self::default();
+
+        let day_first = |decimal_separator, thousands_separator, date_separator| Self {
+            decimal_separator,
+            thousands_separator,
+            date_order: DateOrder::DayMonthYear,
+            date_separator,
+            ..base
+        };
+        match language {
+            "en" if region.is_empty() || region == "US" => Self {
+                date_order: DateOrder::MonthDayYear,
+                date_separator: '/',
+                hour12: true,
+                ..base
+            },
+            "en" => day_first('.', ',', '/'),
+            "fr" => day_first(',', '\u{a0}', '/'),
+            "de" | "ru" | "pl" | "fi" | "nb" | "da" => day_first(',', '.', '.'),
+            "es" | "it" | "pt" | "nl" | "tr" | "id" => day_first(',', '.', '/'),
+            "ja" | "zh" | "ko" => Self {
+                date_separator: '/',
+                ..base
+            },
+            _ => base,
+        }
+    }
+
+    /// The locale of the environment (`LC_ALL`, then `LC_TIME` and
+    /// `LANG`), the default one if unset. The time zone is not read:
+    /// `utc_offset` stays 0.
+    pub fn from_system() -> Self {
+        ["LC_ALL", "LC_TIME", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|tag| !tag.is_empty())
+            .map_or_else(Self::default, |tag| Self::from_tag(&tag))
+    }
+}
+
+/// How a value is written in a label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `decimals` digits after the separator, and the digits before it
+    /// grouped by three when `thousands`
+    Number { decimals: usize, thousands: bool },
+    /// The day of a time (seconds since the Unix epoch)
+    Date,
+    /// The hour and minutes of a time, and the seconds if `seconds`
+    Time { seconds: bool },
+    /// The day, then the hour
+    DateTime { seconds: bool },
+}
+
+impl Format {
+    pub fn format(&self, value: &impl Formattable, locale: &Locale) -> String {
+        let value = value.to_f64();
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        match *self {
+            Format::Number {
+                decimals,
+                thousands,
+            } => format_number(value, decimals, thousands, locale),
+            Format::Date => format_date(offset_seconds(value, locale), locale),
+            Format::Time { seconds } => format_time(offset_seconds(value, locale), seconds, locale),
+            Format::DateTime { seconds } => {
+                let time = offset_seconds(value, locale);
+                format!(
+                    "{} {}",
+                    format_date(time, locale),
+                    format_time(time, seconds, locale)
+                )
+            }
+        }
+    }
+}
+
+/// A value `Format` writes: numbers, and times counted in seconds since
+/// the Unix epoch
+pub trait Formattable {
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! formattable_numbers {
+    ($($ty:ty),*) => {
+        $(impl Formattable for $ty {
+            fn to_f64(&self) -> f64 {
+                *self as f64
+            }
+        })*
+    };
+}
+
+formattable_numbers!(f32, f64, i8, i16, i32, i64, u8, u16, u32, u64, usize, isize);
+
+impl Formattable for SystemTime {
+    fn to_f64(&self) -> f64 {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_secs_f64(),
+            Err(before) => -before.duration().as_secs_f64(),
+        }
+    }
+}
+
+fn format_number(value: f64, decimals: usize, thousands: bool, locale: &Locale) -> String {
+    let digits = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+
+    let mut text = String::new();
+    // No minus before a value rounded to zero
+    if value < 0.0 && digits.bytes().any(|digit| matches!(digit, b'1'..=b'9')) {
+        text.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+        if thousands && i > 0 && (integer.len() - i) % 3 == 0 {
+            text.push(locale.thousands_separator);
+        }
+        text.push(digit);
+    }
+    if !fraction.is_empty() {
+        text.push(locale.decimal_separator);
+        text.push_str(fraction);
+    }
+    text
+}
+
+/// Whole seconds since the Unix epoch, moved by the offset of the locale
+fn offset_seconds(value: f64, locale: &Locale) -> i64 {
+    value.floor() as i64 + locale.utc_offset as i64 * 60
+}
+
+fn format_date(seconds: i64, locale: &Locale) -> String {
+    let (year, month, day) = civil_date(seconds.div_euclid(SECONDS_PER_DAY));
+    let separator = locale.date_separator;
+    match locale.date_order {
+        DateOrder::YearMonthDay => format!("{year}{separator}{month:02}{separator}{day:02}"),
+        DateOrder::MonthDayYear => format!("{month:02}{separator}{day:02}{separator}{year}"),
+        DateOrder::DayMonthYear => format!("{day:02}{separator}{month:02}{separator}{year}"),
+    }
+}
+
+fn format_time(seconds: i64, with_seconds: bool, locale: &Locale) -> String {
+    let of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+    let (hour, minute, second) = (of_day / 3600, of_day / 60 % 60, of_day % 60);
+
+    let mut text = if locale.hour12 {
+        format!("{}:{minute:02}", (hour + 11) % 12 + 1)
+    } else {
+        format!("{hour:02}:{minute:02}")
+    };
+    if with_seconds {
+        text.push_str(&format!(":{second:02}"));
+    }
+    if locale.hour12 {
+        text.push_str(if hour < 12 { " AM" } else { " PM" });
+    }
+    text
+}
+
+/// Year, month and day of the `days`th day since 1970-01-01, in the
+/// proleptic Gregorian calendar
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // Counted from 0000-03-01, so leap days end the year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl Context {
+    /// Writes every formatted label the way `locale` does
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.set_signal(self.locale, locale);
+    }
+
+    /// The locale formatted labels follow, read from the environment when
+    /// the context is created
+    pub fn locale(&self) -> Locale {
+        self.get_signal(self.locale)
+    }
+
+    /// The locale as a signal, to follow its changes
+    #[inline]
+    pub fn locale_signal(&self) -> Signal<Locale> {
+        self.locale
+    }
+
+    /// Shows the value of `signal` in `label`, written with `format` in
+    /// the current locale. Unlike `bind`, the current value is shown
+    /// right away. The label follows the signal and the locale until it is
+    /// removed.
+    pub fn bind_formatted<T>(&mut self, label: LabelRef, signal: Signal<T>, format: Format)
+    where
+        T: Formattable + Clone + 'static,
+    {
+        let locale = self.locale;
+        let render = move |ctx: &mut Context, label: LabelRef| {
+            let text = format.format(&ctx.get_signal(signal), &ctx.get_signal(locale));
+            ctx.set_label_text(label, text);
+        };
+        render(self, label);
+        self.bind(signal, label, move |ctx, label, _| render(ctx, label));
+        self.bind(locale, label, move |ctx, label, _| render(ctx, label));
+    }
+}
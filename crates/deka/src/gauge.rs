@@ -0,0 +1,380 @@
+//! Dial gauges and radial progress drawn on a `Canvas`
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::time::Duration;
+
+use heka::color::Color;
+use heka::{Frame, Style, clr, layout, size};
+
+use crate::clock::AnimationClock;
+use crate::elements::{FrameElement, Paint, Scene, Shape};
+use crate::{CanvasRef, Context, Element, ElementRef};
+
+/// Time taken by value changes to settle
+const ANIMATION: Duration = Duration::from_millis(300);
+
+/// Room left around the arc
+const MARGIN: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GaugeKind {
+    /// Three quarters of a circle, open at the bottom, with a needle
+    /// pointing at the value and tick marks
+    Dial,
+    /// Full circle filled from the top up to the value (radial progress)
+    Ring,
+}
+
+impl GaugeKind {
+    /// Angle of the minimum and the sweep up to the maximum
+    fn arc(self) -> (f32, f32) {
+        match self {
+            GaugeKind::Dial => (PI * 0.75, PI * 1.5),
+            GaugeKind::Ring => (-FRAC_PI_2, TAU),
+        }
+    }
+}
+
+/// Part of the range drawn in its own color, like the red zone of a rev
+/// counter. A ring takes the color of the zone its value is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaugeZone {
+    pub from: f32,
+    pub to: f32,
+    pub color: Color,
+}
+
+impl GaugeZone {
+    pub fn new(from: f32, to: f32, color: Color) -> Self {
+        Self { from, to, color }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GaugeRef(pub(crate) heka::CapsuleRef);
+impl From<GaugeRef> for Element {
+    fn from(v: GaugeRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for GaugeRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+/// Gauge component.
+///
+/// Rebuilds the scene of its canvas whenever its value, zones or size
+/// change. The indicator uses the accent color of the theme, the track its
+/// muted color.
+pub struct Gauge {
+    pub(crate) frame: heka::Frame,
+    canvas: CanvasRef,
+    kind: GaugeKind,
+    min: f32,
+    max: f32,
+    value: f32,
+    /// Value the current animation starts from
+    from: f32,
+    /// On the animation clock
+    animation_start: Option<Duration>,
+    zones: Vec<GaugeZone>,
+    /// Major ticks of dials, evenly spread from min to max
+    ticks: u32,
+    /// Canvas size the scene was built for
+    built_size: Option<(u32, u32)>,
+    needs_rebuild: bool,
+}
+
+#[rustfmt::skip]
+impl FrameElement for Gauge {
+    fn get_frame(&self) -> heka::Frame { self.frame }
+    fn data_ref(&self) -> Option<heka::DataRef> { None }
+    fn name(&self) -> &str { "[GAUGE]" }
+
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+}
+
+impl Gauge {
+    #[inline]
+    pub fn kind(&self) -> GaugeKind {
+        self.kind
+    }
+
+    /// Target value, the displayed one may still be animating towards it
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    #[inline]
+    pub fn range(&self) -> (f32, f32) {
+        (self.min, self.max)
+    }
+
+    #[inline]
+    pub fn zones(&self) -> &[GaugeZone] {
+        &self.zones
+    }
+
+    /// Animation progress in 0..=1, eased out
+    fn progress(&self, clock: &AnimationClock) -> f32 {
+        let Some(start) = self.animation_start else {
+            return 1.0;
+        };
+        let t = clock.fraction(start, ANIMATION);
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    /// Value as currently displayed
+    fn shown_value(&self, t: f32) -> f32 {
+        self.from + (self.value - self.from) * t
+    }
+
+    /// Position of `value` along the arc, in 0..=1
+    fn fraction(&self, value: f32) -> f32 {
+        if self.max > self.min {
+            ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn build(
+        &self,
+        scene: &mut Scene,
+        (width, height): (u32, u32),
+        value: f32,
+        (accent, track): (Color, Color),
+    ) {
+        scene.clear();
+
+        let (width, height) = (width as f32, height as f32);
+        let (cx, cy) = (width / 2.0, height / 2.0);
+        let thickness = (width.min(height) * 0.08).max(2.0);
+        let r = (width.min(height) / 2.0 - MARGIN - thickness / 2.0).max(1.0);
+        let (start, sweep) = self.kind.arc();
+        let angle_of = |value: f32| start + sweep * self.fraction(value);
+
+        scene.add(Shape::Arc {
+            cx,
+            cy,
+            r,
+            start,
+            sweep,
+            paint: Paint::stroke(track, thickness),
+        });
+
+        match self.kind {
+            GaugeKind::Dial => {
+                for zone in &self.zones {
+                    let from = angle_of(zone.from.min(zone.to));
+                    scene.add(Shape::Arc {
+                        cx,
+                        cy,
+                        r,
+                        start: from,
+                        sweep: angle_of(zone.from.max(zone.to)) - from,
+                        paint: Paint::stroke(zone.color, thickness),
+                    });
+                }
+
+                // Ticks inside the arc
+                let outer = r - thickness;
+                let inner = outer - thickness;
+                if self.ticks > 0 {
+                    for i in 0..=self.ticks {
+                        let a = start + sweep * i as f32 / self.ticks as f32;
+                        let (cos, sin) = (a.cos(), a.sin());
+                        scene.add(Shape::Path {
+                            points: vec![
+                                [cx + inner * cos, cy + inner * sin],
+                                [cx + outer * cos, cy + outer * sin],
+                            ],
+                            closed: false,
+                            paint: Paint::stroke(clr!(0x00000088), 1.5),
+                        });
+                    }
+                }
+
+                // Needle, from the hub to the track
+                let a = angle_of(value);
+                let (cos, sin) = (a.cos(), a.sin());
+                let base = thickness / 2.0;
+                let tip = r - thickness / 2.0;
+                scene.add(Shape::Path {
+                    points: vec![
+                        [cx - sin * base, cy + cos * base],
+                        [cx + cos * tip, cy + sin * tip],
+                        [cx + sin * base, cy - cos * base],
+                    ],
+                    closed: true,
+                    paint: Paint::fill(accent),
+                });
+                scene.add(Shape::Circle {
+                    cx,
+                    cy,
+                    r: thickness * 0.75,
+                    paint: Paint::fill(accent),
+                });
+            }
+            GaugeKind::Ring => {
+                let color = self
+                    .zones
+                    .iter()
+                    .rev()
+                    .find(|zone| (zone.from.min(zone.to)..=zone.from.max(zone.to)).contains(&value))
+                    .map_or(accent, |zone| zone.color);
+                scene.add(Shape::Arc {
+                    cx,
+                    cy,
+                    r,
+                    start,
+                    sweep: angle_of(value) - start,
+                    paint: Paint::stroke(color, thickness),
+                });
+            }
+        }
+    }
+}
+
+impl Context {
+    /// Creates a new `Gauge` going from `min` to `max`, starting at `min`.
+    /// Its size comes from `style`.
+    pub fn new_gauge(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        kind: GaugeKind,
+        min: f32,
+        max: f32,
+        style: Style,
+    ) -> GaugeRef {
+        let parent = if let Some(pf) = parent_frame {
+            &Frame::define(pf.raw())
+        } else {
+            &self.root_frame
+        };
+
+        let gauge_frame = self.root.add_frame_child(parent, None);
+        gauge_frame.update_style(&mut self.root, |s| {
+            *s = style;
+            s.layout = layout!(flex);
+        });
+        let gauge_ref = GaugeRef(gauge_frame.get_ref());
+
+        let canvas = self.new_canvas(
+            Some(gauge_ref),
+            heka::make_style!(
+                width: size!(100%),
+                height: size!(100%),
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let gauge = Gauge {
+            frame: gauge_frame,
+            canvas,
+            kind,
+            min,
+            max,
+            value: min,
+            from: min,
+            animation_start: None,
+            zones: Vec::new(),
+            ticks: 10,
+            built_size: None,
+            needs_rebuild: true,
+        };
+        self.elements.insert(gauge_ref.raw(), Box::new(gauge));
+
+        gauge_ref
+    }
+
+    pub fn get_gauge(&self, element: GaugeRef) -> Option<&Gauge> {
+        let gauge = self.elements.get(&element.raw())?;
+        gauge.as_any().downcast_ref::<Gauge>()
+    }
+
+    /// Moves the gauge to `value`, animating from the displayed one.
+    /// Values outside of the range pin the indicator to its ends.
+    pub fn set_gauge_value(&mut self, element: GaugeRef, value: f32) {
+        let clock = self.clock;
+        self.with_gauge(element, |gauge| {
+            gauge.from = gauge.shown_value(gauge.progress(&clock));
+            gauge.value = value;
+            gauge.animation_start = Some(clock.now());
+            gauge.needs_rebuild = true;
+        });
+    }
+
+    /// Zones are drawn in order, later ones over earlier ones
+    pub fn set_gauge_zones(&mut self, element: GaugeRef, zones: Vec<GaugeZone>) {
+        self.with_gauge(element, |gauge| {
+            gauge.zones = zones;
+            gauge.needs_rebuild = true;
+        });
+    }
+
+    /// Number of intervals between the tick marks of a dial, 0 for none.
+    /// Rings have no ticks.
+    pub fn set_gauge_ticks(&mut self, element: GaugeRef, ticks: u32) {
+        self.with_gauge(element, |gauge| {
+            gauge.ticks = ticks;
+            gauge.needs_rebuild = true;
+        });
+    }
+
+    fn with_gauge(&mut self, element: GaugeRef, op: impl FnOnce(&mut Gauge)) {
+        if let Some(gauge) = self
+            .elements
+            .get_mut(&element.raw())
+            .and_then(|element| element.as_any_mut().downcast_mut::<Gauge>())
+        {
+            op(gauge);
+            self.request_redraw();
+        }
+    }
+
+    /// Rebuilds the scenes of the gauges whose value, zones or size changed
+    /// since the last frame. Runs after layout.
+    pub(crate) fn update_gauges(&mut self) {
+        let gauges: Vec<heka::CapsuleRef> = self
+            .elements
+            .iter()
+            .filter(|(_, element)| element.as_any().is::<Gauge>())
+            .map(|(cref, _)| *cref)
+            .collect();
+
+        let colors = (self.theme.accent_color, self.theme.muted_color);
+        for cref in gauges {
+            self.with_component_mut(cref, |gauge: &mut Gauge, ctx| {
+                let Some(content) = ctx.root.get_content_rect(gauge.canvas.raw()) else {
+                    return;
+                };
+                let size = (content.w, content.h);
+                if !gauge.needs_rebuild
+                    && gauge.animation_start.is_none()
+                    && gauge.built_size == Some(size)
+                {
+                    return;
+                }
+
+                let t = gauge.progress(&ctx.clock);
+                let value = gauge.shown_value(t);
+                ctx.update_canvas(gauge.canvas, |scene| {
+                    gauge.build(scene, size, value, colors)
+                });
+
+                gauge.built_size = Some(size);
+                gauge.needs_rebuild = false;
+                if t >= 1.0 {
+                    gauge.animation_start = None;
+                } else if ctx.clock.running() {
+                    ctx.request_redraw();
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,505 @@
+//! Touchpad gestures: phased scrolls with inertia, and pinches. Also
+//! dragging scroll containers and canvases around with the middle button
+//! or Space held.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use heka::CapsuleRef;
+use winit::dpi::PhysicalPosition;
+use winit::event::MouseButton;
+use winit::window::CursorIcon;
+
+use crate::elements::Canvas;
+use crate::events::{GesturePhase, PinchEvent, ScrollEvent};
+use crate::{CanvasRef, Context, ElementRef, TimerId};
+
+/// Time between two inertial scroll steps
+const MOMENTUM_STEP: Duration = Duration::from_millis(16);
+
+/// Fraction of the speed an inertial scroll keeps after one second
+const MOMENTUM_FRICTION: f64 = 0.05;
+
+/// Inertial scrolls stop below this speed, in pixels per second
+const MOMENTUM_MIN_SPEED: f64 = 30.0;
+
+const MOMENTUM_MAX_SPEED: f64 = 8000.0;
+
+/// Fingers resting this long before leaving the touchpad throw nothing
+const MOMENTUM_IDLE: Duration = Duration::from_millis(80);
+
+/// Shortest time between two scroll updates counted for their speed,
+/// touchpads sometimes send them in bursts
+const MIN_SAMPLE_INTERVAL: f64 = 0.004;
+
+/// Touchpad scroll in progress
+struct ScrollGesture {
+    /// Element listening to the scroll, `None` when it goes to scroll containers
+    target: Option<CapsuleRef>,
+    /// Pixels per second
+    velocity: (f64, f64),
+    last_update: Instant,
+}
+
+/// Element dragged around, see `Context::enable_drag_pan`
+struct DragPan {
+    target: CapsuleRef,
+    /// Pixels per second
+    velocity: (f64, f64),
+    last_update: Instant,
+}
+
+/// Inertial scroll carrying on a touchpad scroll, or a drag pan
+struct Momentum {
+    target: Option<CapsuleRef>,
+    /// `target` is dragged around rather than scrolled
+    panned: bool,
+    velocity: (f64, f64),
+    /// On the animation clock
+    last_step: Duration,
+    timer: TimerId,
+}
+
+/// Gesture state of a `Context`
+pub(crate) struct Gestures {
+    momentum_enabled: bool,
+    scroll: Option<ScrollGesture>,
+    momentum: Option<Momentum>,
+    pinch_target: Option<CapsuleRef>,
+    /// Elements that can be dragged around
+    pub(crate) pan_targets: HashSet<CapsuleRef>,
+    pan_inertia: bool,
+    pan: Option<DragPan>,
+    /// Space is held down, the left button drags too
+    pub(crate) space_held: bool,
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self {
+            // macOS throws touchpad scrolls itself, as a second scroll gesture
+            momentum_enabled: !cfg!(target_os = "macos"),
+            scroll: None,
+            momentum: None,
+            pinch_target: None,
+            pan_targets: HashSet::new(),
+            pan_inertia: false,
+            pan: None,
+            space_held: false,
+        }
+    }
+}
+
+fn speed((x, y): (f64, f64)) -> f64 {
+    (x * x + y * y).sqrt()
+}
+
+/// Smoothed speed after a move of `delta` pixels `elapsed` seconds after
+/// the last one, so the last uneven sample doesn't decide a throw alone
+fn sampled_velocity(
+    velocity: (f64, f64),
+    delta: PhysicalPosition<f64>,
+    elapsed: f64,
+) -> (f64, f64) {
+    let elapsed = elapsed.max(MIN_SAMPLE_INTERVAL);
+    let sample = (delta.x / elapsed, delta.y / elapsed);
+    (
+        velocity.0 * 0.4 + sample.0 * 0.6,
+        velocity.1 * 0.4 + sample.1 * 0.6,
+    )
+}
+
+/// Caps a throw to `MOMENTUM_MAX_SPEED`
+fn capped(velocity: (f64, f64)) -> (f64, f64) {
+    let current = speed(velocity);
+    if current > MOMENTUM_MAX_SPEED {
+        let ratio = MOMENTUM_MAX_SPEED / current;
+        (velocity.0 * ratio, velocity.1 * ratio)
+    } else {
+        velocity
+    }
+}
+
+impl Context {
+    /// Called for wheel and touchpad scrolls over the element (or a child
+    /// not listening to them). The element takes the scroll: scroll
+    /// containers around it don't move. Touchpad scrolls stay with the
+    /// element from their `Begin` to their `End`, and may be followed by
+    /// `momentum` updates once the fingers leave the touchpad.
+    pub fn on_scroll<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ScrollEvent) + 'static,
+    {
+        self.scroll_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Called for touchpad pinches over the element (or a child not
+    /// listening to them), from their `Begin` to their `End`.
+    pub fn on_pinch<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &PinchEvent) + 'static,
+    {
+        self.pinch_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Whether touchpad scrolls carry on by inertia once the fingers leave
+    /// the touchpad. Enabled by default, except on macOS where the system
+    /// sends the inertial part itself.
+    pub fn set_scroll_momentum(&mut self, enabled: bool) {
+        self.gestures.momentum_enabled = enabled;
+        if !enabled {
+            self.stop_momentum();
+        }
+    }
+
+    #[inline]
+    pub fn scroll_momentum(&self) -> bool {
+        self.gestures.momentum_enabled
+    }
+
+    /// Runs the scroll callback of an element. Returns false if it has none.
+    fn emit_scroll(
+        &mut self,
+        cref: CapsuleRef,
+        delta: PhysicalPosition<f64>,
+        phase: GesturePhase,
+        momentum: bool,
+    ) -> bool {
+        let Some(mut callback) = self.scroll_callbacks.remove(&cref) else {
+            return false;
+        };
+        let event = ScrollEvent {
+            pos: self.mouse_pos,
+            local_pos: self.local_mouse_pos(cref),
+            delta,
+            phase,
+            momentum,
+        };
+        self.guarded_on(cref, |ctx| callback(ctx, &event));
+        self.scroll_callbacks.entry(cref).or_insert(callback);
+        true
+    }
+
+    /// Gives a scroll `delta` to what is under the cursor: the topmost
+    /// element listening to scrolls, or the innermost scroll container
+    /// that can still move in that direction. Returns whether one took it.
+    fn scroll_under_cursor(&mut self, delta: PhysicalPosition<f64>, momentum: bool) -> bool {
+        for cref in self.hit_candidates() {
+            if self.emit_scroll(cref, delta, GesturePhase::Update, momentum) {
+                return true;
+            }
+            if self.scroll_container_by(cref, -delta.x as f32, -delta.y as f32, momentum) {
+                self.trace_handler(cref);
+                // The cursor may be above another element now
+                self.pointer_moved = true;
+                self.dispatch_scroll_offsets();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Gives a scroll `delta` to the element a gesture is locked on, or
+    /// to what is under the cursor
+    fn deliver_scroll(
+        &mut self,
+        target: Option<CapsuleRef>,
+        delta: PhysicalPosition<f64>,
+        momentum: bool,
+    ) -> bool {
+        match target {
+            Some(cref) => self.emit_scroll(cref, delta, GesturePhase::Update, momentum),
+            None => self.scroll_under_cursor(delta, momentum),
+        }
+    }
+
+    pub(crate) fn scroll_wheel(&mut self, delta: PhysicalPosition<f64>) {
+        let now = self.now();
+        let Some(gesture) = &mut self.gestures.scroll else {
+            // Mouse wheels stop a throw, like touching the touchpad does
+            self.stop_momentum();
+            self.scroll_under_cursor(delta, false);
+            return;
+        };
+
+        let elapsed = (now - gesture.last_update).as_secs_f64();
+        gesture.velocity = sampled_velocity(gesture.velocity, delta, elapsed);
+        gesture.last_update = now;
+
+        let target = gesture.target;
+        self.deliver_scroll(target, delta, false);
+    }
+
+    pub(crate) fn scroll_phase(&mut self, phase: GesturePhase) {
+        match phase {
+            GesturePhase::Begin => {
+                self.stop_momentum();
+                // Scroll containers are picked per update, as the one
+                // that can move depends on the direction
+                let target = self
+                    .hit_candidates()
+                    .into_iter()
+                    .find(|cref| {
+                        self.scroll_callbacks.contains_key(cref)
+                            || self
+                                .root
+                                .get_style(*cref)
+                                .is_some_and(|style| style.overflow.scrolls())
+                    })
+                    .filter(|cref| self.scroll_callbacks.contains_key(cref));
+                if let Some(cref) = target {
+                    self.emit_scroll(cref, PhysicalPosition::default(), phase, false);
+                }
+                self.gestures.scroll = Some(ScrollGesture {
+                    target,
+                    velocity: (0.0, 0.0),
+                    last_update: self.now(),
+                });
+            }
+            GesturePhase::Update => {}
+            GesturePhase::End => {
+                let Some(gesture) = self.gestures.scroll.take() else {
+                    return;
+                };
+                if let Some(cref) = gesture.target {
+                    self.emit_scroll(cref, PhysicalPosition::default(), phase, false);
+                }
+
+                let velocity = capped(gesture.velocity);
+                if self.gestures.momentum_enabled
+                    && gesture.last_update.elapsed() < MOMENTUM_IDLE
+                    && speed(velocity) > MOMENTUM_MIN_SPEED
+                {
+                    self.start_momentum(gesture.target, false, velocity);
+                }
+            }
+        }
+    }
+
+    fn start_momentum(&mut self, target: Option<CapsuleRef>, panned: bool, velocity: (f64, f64)) {
+        if let Some(cref) = target
+            && !panned
+        {
+            self.emit_scroll(cref, PhysicalPosition::default(), GesturePhase::Begin, true);
+        }
+        let timer = self.set_interval(MOMENTUM_STEP, |ctx| ctx.momentum_step());
+        self.gestures.momentum = Some(Momentum {
+            target,
+            panned,
+            velocity,
+            last_step: self.clock.now(),
+            timer,
+        });
+    }
+
+    fn momentum_step(&mut self) {
+        let Some(momentum) = &mut self.gestures.momentum else {
+            return;
+        };
+
+        // Slowed down and paused with the animations
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(momentum.last_step).as_secs_f64();
+        if elapsed == 0.0 {
+            return;
+        }
+        momentum.last_step = now;
+        let decay = MOMENTUM_FRICTION.powf(elapsed);
+        momentum.velocity = (momentum.velocity.0 * decay, momentum.velocity.1 * decay);
+
+        let (vx, vy) = momentum.velocity;
+        let (target, panned) = (momentum.target, momentum.panned);
+        if speed((vx, vy)) < MOMENTUM_MIN_SPEED {
+            self.stop_momentum();
+            return;
+        }
+
+        let delta = PhysicalPosition::new(vx * elapsed, vy * elapsed);
+        let moved = match target {
+            Some(cref) if panned => self.pan_by(cref, delta),
+            _ => self.deliver_scroll(target, delta, true),
+        };
+        if !moved {
+            // Nothing left to move
+            self.stop_momentum();
+        }
+    }
+
+    /// Ends the inertial scroll in progress, if any
+    pub(crate) fn stop_momentum(&mut self) {
+        let Some(momentum) = self.gestures.momentum.take() else {
+            return;
+        };
+        self.cancel_timer(momentum.timer);
+        if let Some(cref) = momentum.target
+            && !momentum.panned
+        {
+            self.emit_scroll(cref, PhysicalPosition::default(), GesturePhase::End, true);
+        }
+    }
+
+    pub(crate) fn pinch(&mut self, delta: f64, phase: GesturePhase) {
+        if phase == GesturePhase::Begin {
+            self.stop_momentum();
+            self.gestures.pinch_target = None;
+        }
+
+        let target = self.gestures.pinch_target.or_else(|| {
+            self.hit_candidates()
+                .into_iter()
+                .find(|cref| self.pinch_callbacks.contains_key(cref))
+        });
+        self.gestures.pinch_target = match phase {
+            GesturePhase::End => None,
+            _ => target,
+        };
+
+        let Some(cref) = target else {
+            return;
+        };
+        if let Some(mut callback) = self.pinch_callbacks.remove(&cref) {
+            let event = PinchEvent {
+                pos: self.mouse_pos,
+                local_pos: self.local_mouse_pos(cref),
+                delta,
+                phase,
+            };
+            self.guarded_on(cref, |ctx| callback(ctx, &event));
+            self.pinch_callbacks.entry(cref).or_insert(callback);
+        }
+    }
+}
+
+impl Context {
+    /// Lets a scroll container or a canvas be dragged around with the
+    /// middle button, or the left one while Space is held. The cursor turns
+    /// into a grabbing hand meanwhile.
+    pub fn enable_drag_pan(&mut self, element: impl ElementRef, enabled: bool) {
+        if enabled {
+            self.gestures.pan_targets.insert(element.raw());
+        } else {
+            self.gestures.pan_targets.remove(&element.raw());
+        }
+    }
+
+    /// Whether drag pans carry on by inertia once the button is released.
+    /// Disabled by default.
+    pub fn set_pan_inertia(&mut self, enabled: bool) {
+        self.gestures.pan_inertia = enabled;
+    }
+
+    #[inline]
+    pub fn pan_inertia(&self) -> bool {
+        self.gestures.pan_inertia
+    }
+
+    /// Whether a gesture may still move the scroll container: fingers on
+    /// the touchpad, a throw carrying on, or a drag pan of it
+    pub(crate) fn scroll_held(&self, cref: CapsuleRef) -> bool {
+        self.gestures.scroll.is_some()
+            || self.gestures.momentum.is_some()
+            || self
+                .gestures
+                .pan
+                .as_ref()
+                .is_some_and(|pan| pan.target == cref)
+    }
+
+    /// The element under the cursor that can be dragged around
+    fn pan_target(&self) -> Option<CapsuleRef> {
+        self.hit_candidates()
+            .into_iter()
+            .find(|cref| self.gestures.pan_targets.contains(cref))
+    }
+
+    /// Starts or ends a drag pan on a button press or release. Returns
+    /// whether the button went to the pan, and not to clicks.
+    pub(crate) fn drag_pan_button(&mut self, button: MouseButton, pressed: bool) -> bool {
+        if !pressed {
+            let Some(pan) = self.gestures.pan.take() else {
+                return false;
+            };
+            self.set_cursor(if self.gestures.space_held {
+                CursorIcon::Grab
+            } else {
+                CursorIcon::Default
+            });
+
+            let velocity = capped(pan.velocity);
+            if self.gestures.pan_inertia
+                && pan.last_update.elapsed() < MOMENTUM_IDLE
+                && speed(velocity) > MOMENTUM_MIN_SPEED
+            {
+                self.start_momentum(Some(pan.target), true, velocity);
+            }
+            return true;
+        }
+
+        let pans = button == MouseButton::Middle
+            || (button == MouseButton::Left && self.gestures.space_held);
+        let Some(target) = self.pan_target().filter(|_| pans) else {
+            return false;
+        };
+        self.stop_momentum();
+        self.gestures.pan = Some(DragPan {
+            target,
+            velocity: (0.0, 0.0),
+            last_update: self.now(),
+        });
+        self.set_cursor(CursorIcon::Grabbing);
+        true
+    }
+
+    /// Moves the dragged element along with the cursor. Returns whether a
+    /// drag pan is in progress.
+    pub(crate) fn drag_pan_move(&mut self, delta: PhysicalPosition<f64>) -> bool {
+        let now = self.now();
+        let Some(pan) = &mut self.gestures.pan else {
+            return false;
+        };
+        pan.velocity = sampled_velocity(pan.velocity, delta, (now - pan.last_update).as_secs_f64());
+        pan.last_update = now;
+
+        let target = pan.target;
+        self.pan_by(target, delta);
+        true
+    }
+
+    /// Shows the hand over the elements that can be dragged while Space is
+    /// held
+    pub(crate) fn drag_pan_key(&mut self, pressed: bool) {
+        if pressed == self.gestures.space_held {
+            return;
+        }
+        self.gestures.space_held = pressed;
+        if self.gestures.pan.is_none() && self.pan_target().is_some() {
+            self.set_cursor(if pressed {
+                CursorIcon::Grab
+            } else {
+                CursorIcon::Default
+            });
+        }
+    }
+
+    /// Moves the content of a canvas or a scroll container by `delta`.
+    /// Returns whether it moved.
+    fn pan_by(&mut self, cref: CapsuleRef, delta: PhysicalPosition<f64>) -> bool {
+        let is_canvas = self
+            .elements
+            .get(&cref)
+            .is_some_and(|element| element.as_any().is::<Canvas>());
+        if is_canvas {
+            self.pan_canvas(CanvasRef(cref), delta.x as f32, delta.y as f32);
+            return true;
+        }
+        let moved = self.scroll_container_by(cref, -delta.x as f32, -delta.y as f32, false);
+        if moved {
+            // The cursor may be above another element now
+            self.pointer_moved = true;
+            self.dispatch_scroll_offsets();
+        }
+        moved
+    }
+}
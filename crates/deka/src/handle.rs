@@ -0,0 +1,100 @@
+//! Access to the UI from other threads.
+//!
+//! `Context` owns the font system, the elements and their callbacks: it stays
+//! on the UI thread. A `UiHandle` is a cheap, `Send` handle that background
+//! threads use to queue work for it; queued tasks run on the UI thread with
+//! the `Context` between two frames.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::Context;
+#[cfg(feature = "text")]
+use crate::LabelRef;
+
+/// Work sent to the UI thread
+type UiTask = Box<dyn FnOnce(&mut Context) + Send>;
+
+/// Wakes the event loop up once it runs
+type Waker = Arc<Mutex<Option<EventLoopProxy<()>>>>;
+
+/// Send-able handle to a `Context`, see `Context::handle`
+#[derive(Clone)]
+pub struct UiHandle {
+    sender: Sender<UiTask>,
+    waker: Waker,
+}
+
+impl UiHandle {
+    /// Queues `task` to run on the UI thread.
+    /// Returns `false` if the context is gone.
+    pub fn run<F>(&self, task: F) -> bool
+    where
+        F: FnOnce(&mut Context) + Send + 'static,
+    {
+        if self.sender.send(Box::new(task)).is_err() {
+            return false;
+        }
+        if let Ok(waker) = self.waker.lock()
+            && let Some(proxy) = waker.as_ref()
+        {
+            let _ = proxy.send_event(());
+        }
+        true
+    }
+
+    #[cfg(feature = "text")]
+    pub fn set_label_text(&self, label: LabelRef, text: impl Into<String>) -> bool {
+        let text = text.into();
+        self.run(move |ctx| ctx.set_label_text(label, text))
+    }
+
+    /// Closes the window, see `Context::exit`
+    pub fn exit(&self) -> bool {
+        self.run(|ctx| ctx.exit())
+    }
+}
+
+/// Receiving end of the `UiHandle`s of a `Context`
+pub(crate) struct UiTasks {
+    sender: Sender<UiTask>,
+    receiver: Receiver<UiTask>,
+    waker: Waker,
+}
+
+impl Default for UiTasks {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            waker: Arc::default(),
+        }
+    }
+}
+
+impl Context {
+    /// A handle for background threads to update the UI through
+    pub fn handle(&self) -> UiHandle {
+        UiHandle {
+            sender: self.tasks.sender.clone(),
+            waker: self.tasks.waker.clone(),
+        }
+    }
+
+    /// Lets handles wake the event loop up when they queue a task
+    pub(crate) fn set_waker(&mut self, proxy: EventLoopProxy<()>) {
+        if let Ok(mut waker) = self.tasks.waker.lock() {
+            *waker = Some(proxy);
+        }
+    }
+
+    /// Runs the tasks queued by handles so far
+    pub fn run_pending_tasks(&mut self) {
+        while let Ok(task) = self.tasks.receiver.try_recv() {
+            self.dispatching(|ctx| ctx.guarded(task));
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Inspector overlay: outlines every element on top of the UI, optionally
+//! labelled with its size, to see how the tree is laid out.
+
+#[cfg(feature = "text")]
+use std::collections::HashMap;
+
+#[cfg(feature = "text")]
+use cosmic_text::Buffer;
+use heka::color::Color;
+use heka::geometry::Rect;
+
+#[cfg(feature = "text")]
+use crate::TextStyle;
+#[cfg(feature = "text")]
+use crate::elements::{Button, Label, Link, TextInput, fill_buffer};
+use crate::elements::{Checkbox, Panel};
+use crate::{Context, cmd};
+
+/// Font size of the size labels
+#[cfg(feature = "text")]
+const SIZE_LABEL_FONT_SIZE: f32 = 11.0;
+
+/// Room left around the text of a size label
+#[cfg(feature = "text")]
+const SIZE_LABEL_PADDING: u32 = 2;
+
+/// Outline colors of the inspector, one per kind of element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlinePalette {
+    pub panel: Color,
+    pub label: Color,
+    /// Text inputs and checkboxes
+    pub input: Color,
+    /// Buttons and links
+    pub button: Color,
+    /// Canvases, forms and custom elements
+    pub other: Color,
+}
+
+impl OutlinePalette {
+    pub const DEFAULT: OutlinePalette = OutlinePalette {
+        panel: Color::Hex(0xE53935FF),
+        label: Color::Hex(0x1E88E5FF),
+        input: Color::Hex(0x43A047FF),
+        button: Color::Hex(0x8E24AAFF),
+        other: Color::Hex(0xFB8C00FF),
+    };
+
+    /// Okabe-Ito colors, told apart with any common color vision deficiency
+    pub const OKABE_ITO: OutlinePalette = OutlinePalette {
+        panel: Color::Hex(0xE69F00FF),
+        label: Color::Hex(0x0072B2FF),
+        input: Color::Hex(0x009E73FF),
+        button: Color::Hex(0xCC79A7FF),
+        other: Color::Hex(0xD55E00FF),
+    };
+
+    /// Paul Tol's bright scheme, also safe for color-blind people
+    pub const TOL_BRIGHT: OutlinePalette = OutlinePalette {
+        panel: Color::Hex(0xEE6677FF),
+        label: Color::Hex(0x4477AAFF),
+        input: Color::Hex(0x228833FF),
+        button: Color::Hex(0xAA3377FF),
+        other: Color::Hex(0xCCBB44FF),
+    };
+
+    fn colors(&self) -> [Color; 5] {
+        [self.panel, self.label, self.input, self.button, self.other]
+    }
+}
+
+impl Default for OutlinePalette {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How the inspector picks the outline color of an element
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineColoring {
+    /// Panels, labels, inputs... each get their color of the palette
+    #[default]
+    ByKind,
+    /// The palette colors cycle with the depth in the tree, to tell
+    /// nested boxes apart
+    ByDepth,
+}
+
+/// Inspector overlay settings, see `Context::set_inspector`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inspector {
+    pub palette: OutlinePalette,
+    pub coloring: OutlineColoring,
+    pub stroke_width: u32,
+    /// Labels each box with its size, when the label fits in it.
+    /// Needs the `text` feature.
+    pub show_sizes: bool,
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self {
+            palette: OutlinePalette::DEFAULT,
+            coloring: OutlineColoring::ByKind,
+            stroke_width: 1,
+            show_sizes: false,
+        }
+    }
+}
+
+/// Shaped size of an element
+#[cfg(feature = "text")]
+struct SizeLabel {
+    size: (u32, u32),
+    buffer_ref: heka::DataRef,
+    /// Size of the shaped text
+    text_size: (u32, u32),
+}
+
+/// Inspector state of a `Context`
+#[derive(Default)]
+pub(crate) struct InspectorState {
+    config: Option<Inspector>,
+    #[cfg(feature = "text")]
+    labels: HashMap<heka::CapsuleRef, SizeLabel>,
+}
+
+impl Context {
+    /// Shows (or hides, with `None`) outlines around every element
+    pub fn set_inspector(&mut self, inspector: Option<Inspector>) {
+        if self.inspector.config != inspector {
+            self.inspector.config = inspector;
+            #[cfg(feature = "text")]
+            self.update_inspector();
+            self.redraw_requested = true;
+        }
+    }
+
+    #[inline]
+    pub fn inspector(&self) -> Option<Inspector> {
+        self.inspector.config
+    }
+
+    /// Shapes the size labels of the elements whose size changed.
+    /// Runs after layout.
+    #[cfg(feature = "text")]
+    pub(crate) fn update_inspector(&mut self) {
+        let show_sizes = self
+            .inspector
+            .config
+            .is_some_and(|inspector| inspector.show_sizes);
+
+        let stale: Vec<heka::CapsuleRef> = self
+            .inspector
+            .labels
+            .keys()
+            .filter(|cref| !show_sizes || !self.elements.contains_key(cref))
+            .copied()
+            .collect();
+        for cref in stale {
+            if let Some(label) = self.inspector.labels.remove(&cref) {
+                self.root.remove_binding(label.buffer_ref);
+            }
+        }
+        if !show_sizes {
+            return;
+        }
+
+        let text_style = self.size_label_style();
+        let sizes: Vec<(heka::CapsuleRef, (u32, u32))> = self
+            .elements
+            .keys()
+            .filter_map(|cref| {
+                let rect = self.root.get_rect(*cref)?;
+                Some((*cref, (rect.w, rect.h)))
+            })
+            .collect();
+
+        for (cref, size) in sizes {
+            if self
+                .inspector
+                .labels
+                .get(&cref)
+                .is_some_and(|label| label.size == size)
+            {
+                continue;
+            }
+
+            let text = format!("{}×{}", size.0, size.1);
+            let buffer_ref = match self.inspector.labels.get(&cref) {
+                Some(label) => label.buffer_ref,
+                None => self.root.set_binding(Buffer::new(
+                    &mut self.font_system,
+                    text_style.as_cosmic_metrics(),
+                )),
+            };
+            let Some(buffer) = self.root.get_binding_mut::<Buffer>(buffer_ref) else {
+                continue;
+            };
+            buffer.set_size(&mut self.font_system, None, None);
+            fill_buffer(buffer, &mut self.font_system, &text, &text_style);
+            buffer.shape_until_scroll(&mut self.font_system, true);
+
+            let width = buffer
+                .layout_runs()
+                .map(|run| run.line_w)
+                .fold(0.0, f32::max)
+                .ceil() as u32;
+            let height = buffer
+                .layout_runs()
+                .last()
+                .map(|run| run.line_top + run.line_height)
+                .unwrap_or(0.0)
+                .ceil() as u32;
+
+            self.inspector.labels.insert(
+                cref,
+                SizeLabel {
+                    size,
+                    buffer_ref,
+                    text_size: (width, height),
+                },
+            );
+        }
+    }
+
+    #[cfg(feature = "text")]
+    fn size_label_style(&self) -> TextStyle {
+        TextStyle {
+            font_size: SIZE_LABEL_FONT_SIZE,
+            color: Color::white,
+            ..self.theme.text_style()
+        }
+    }
+
+    /// Outline color of an element
+    fn outline_color(&self, inspector: &Inspector, cref: heka::CapsuleRef) -> Color {
+        match inspector.coloring {
+            OutlineColoring::ByKind => {
+                let Some(element) = self.elements.get(&cref) else {
+                    return inspector.palette.other;
+                };
+                let element = element.as_any();
+                if element.is::<Panel>() {
+                    return inspector.palette.panel;
+                }
+                if element.is::<Checkbox>() {
+                    return inspector.palette.input;
+                }
+                #[cfg(feature = "text")]
+                if element.is::<Label>() {
+                    return inspector.palette.label;
+                } else if element.is::<TextInput>() {
+                    return inspector.palette.input;
+                } else if element.is::<Button>() || element.is::<Link>() {
+                    return inspector.palette.button;
+                }
+                inspector.palette.other
+            }
+            OutlineColoring::ByDepth => {
+                let mut depth = 0;
+                let mut current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+                while let Some(parent) = current {
+                    depth += 1;
+                    current = self.root.get_capsule(parent).and_then(|c| c.parent_ref);
+                }
+                let colors = inspector.palette.colors();
+                colors[depth % colors.len()]
+            }
+        }
+    }
+
+    /// Overlay commands of an element, with their priority
+    pub(crate) fn inspector_commands(
+        &self,
+        cref: heka::CapsuleRef,
+        rect: Rect,
+    ) -> Vec<(u8, cmd::DrawCommand)> {
+        let Some(inspector) = self.inspector.config else {
+            return Vec::new();
+        };
+        let color = self.outline_color(&inspector, cref);
+        #[cfg_attr(not(feature = "text"), allow(unused_mut))]
+        let mut commands = vec![(
+            0,
+            cmd::DrawCommand::Rect {
+                rect,
+                fill_color: Color::transparent,
+                stroke_color: color,
+                z_index: u32::MAX,
+                border_radius: 0,
+                stroke_width: inspector.stroke_width,
+                shadow_color: Color::transparent,
+                shadow_blur: 0.0,
+            },
+        )];
+
+        #[cfg(feature = "text")]
+        self.push_size_label(&mut commands, cref, rect, color);
+        commands
+    }
+
+    /// Size label of an element, in the top-left corner of its box
+    #[cfg(feature = "text")]
+    fn push_size_label(
+        &self,
+        commands: &mut Vec<(u8, cmd::DrawCommand)>,
+        cref: heka::CapsuleRef,
+        rect: Rect,
+        color: Color,
+    ) {
+        let Some(label) = self.inspector.labels.get(&cref) else {
+            return;
+        };
+        let (width, height) = label.text_size;
+        let padding = 2 * SIZE_LABEL_PADDING;
+        if width + padding > rect.w || height + padding > rect.h {
+            // Small boxes stay readable without their label
+            return;
+        }
+
+        // In the top-left corner, on the outline color
+        commands.push((
+            0,
+            cmd::DrawCommand::Rect {
+                rect: Rect::new(rect.x, rect.y, width + padding, height + padding),
+                fill_color: color,
+                stroke_color: Color::transparent,
+                z_index: u32::MAX,
+                border_radius: 0,
+                stroke_width: 0,
+                shadow_color: Color::transparent,
+                shadow_blur: 0.0,
+            },
+        ));
+        commands.push((
+            1,
+            cmd::DrawCommand::Text {
+                rect: Rect::new(
+                    rect.x + SIZE_LABEL_PADDING as i32,
+                    rect.y + SIZE_LABEL_PADDING as i32,
+                    width,
+                    height,
+                ),
+                buffer_ref: label.buffer_ref,
+                style: self.size_label_style(),
+                z_index: u32::MAX,
+            },
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! Key repeat for platforms that don't send repeated key presses themselves.
+//!
+//! Until a native repeat is seen, holding a key schedules repeats with the
+//! timers. The first native repeat turns this off for the rest of the run.
+
+use std::time::Duration;
+
+use winit::keyboard::{Key, NamedKey};
+
+use crate::events::KeyEvent;
+use crate::{Context, TimerId};
+
+/// Timing of the repeats sent while a key is held, see `Context::set_key_repeat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeat {
+    /// Time the key is held before it starts repeating
+    pub delay: Duration,
+    /// Time between two repeats
+    pub interval: Duration,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(500),
+            interval: Duration::from_millis(33),
+        }
+    }
+}
+
+/// Key repeat state of a `Context`
+#[derive(Default)]
+pub(crate) struct KeyRepeatState {
+    /// `None` turns the fallback off
+    pub(crate) config: Option<KeyRepeat>,
+    /// The platform sends repeats, none are made up
+    native: bool,
+    /// Key being repeated, and the timer sending its repeats
+    held: Option<(Key, TimerId)>,
+}
+
+impl KeyRepeatState {
+    pub(crate) fn new() -> Self {
+        Self {
+            config: Some(KeyRepeat::default()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Modifiers are held rather than typed, they never repeat
+fn repeats(key: &Key) -> bool {
+    !matches!(
+        key,
+        Key::Named(
+            NamedKey::Shift
+                | NamedKey::Control
+                | NamedKey::Alt
+                | NamedKey::Super
+                | NamedKey::CapsLock
+                | NamedKey::NumLock
+                | NamedKey::Fn
+        ) | Key::Dead(_)
+            | Key::Unidentified(_)
+    )
+}
+
+impl Context {
+    /// Sets the timing of the key repeats made up on platforms that don't
+    /// send them. `None` turns them off. Platform repeats, when there are,
+    /// follow the system settings instead.
+    pub fn set_key_repeat(&mut self, repeat: Option<KeyRepeat>) {
+        self.key_repeat.config = repeat;
+        if repeat.is_none() {
+            self.stop_key_repeat();
+        }
+    }
+
+    #[inline]
+    pub fn key_repeat(&self) -> Option<KeyRepeat> {
+        self.key_repeat.config
+    }
+
+    /// Starts or stops repeating a key, before `event` is dispatched
+    pub(crate) fn track_key_repeat(&mut self, event: &KeyEvent) {
+        if event.repeat {
+            // Real repeats from now on
+            self.key_repeat.native = true;
+            self.stop_key_repeat();
+            return;
+        }
+
+        let held = self.key_repeat.held.as_ref().map(|(key, _)| key);
+        if event.pressed || held == Some(&event.logical_key) {
+            // Releasing the held key, or pressing another one, ends the repeats
+            self.stop_key_repeat();
+        }
+
+        let Some(config) = self.key_repeat.config else {
+            return;
+        };
+        if !event.pressed || self.key_repeat.native || !repeats(&event.logical_key) {
+            return;
+        }
+
+        let repeated = KeyEvent {
+            repeat: true,
+            ..event.clone()
+        };
+        let timer = self.set_timeout(config.delay, move |ctx| {
+            let timer = ctx.set_interval(config.interval, move |ctx| {
+                ctx.key_event(repeated.clone());
+            });
+            if let Some((_, id)) = &mut ctx.key_repeat.held {
+                *id = timer;
+            }
+        });
+        self.key_repeat.held = Some((event.logical_key.clone(), timer));
+    }
+
+    /// Stops the repeats of the held key, if any
+    pub(crate) fn stop_key_repeat(&mut self) {
+        if let Some((_, timer)) = self.key_repeat.held.take() {
+            self.cancel_timer(timer);
+        }
+    }
+}
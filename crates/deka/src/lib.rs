@@ -1,55 +1,271 @@
 use std::collections::HashMap;
-
+use std::panic::{self, AssertUnwindSafe};
+
+pub use accessibility::AccessibilityPrefs;
+pub use app::{App, AppBuilder};
+pub use bulk::ElementSpec;
+pub use drag_drop::DragPreview;
+pub use draw_list::DrawList;
+#[cfg(feature = "text")]
+pub use draw_list::ShapedText;
 pub use edl_macro::eka;
+pub use event_trace::{HitStatus, TracedElement, TracedEvent};
+#[cfg(feature = "text")]
+pub use format::{DateOrder, Format, Formattable, Locale};
+pub use handle::UiHandle;
 pub use heka;
 use heka::Frame;
 use heka::Style;
-use heka::align;
 use heka::clr;
-use heka::justify;
-use heka::margin;
-use heka::pad;
+use heka::color::Color;
+#[cfg(feature = "text")]
+use heka::{align, justify, margin, pad};
+#[cfg(feature = "debug")]
+pub use inspector::{Inspector, OutlineColoring, OutlinePalette};
+pub use key_repeat::KeyRepeat;
 use log::warn;
-pub use text_style::AsCosmicColor;
-pub use text_style::TextStyle;
+#[cfg(not(feature = "text"))]
+#[doc(hidden)]
+pub use no_text::TextFeature;
+pub use overscroll::ScrollSnap;
+pub use popup::PopupPlacement;
+pub use signal::Signal;
+#[cfg(feature = "text")]
+pub use text_defaults::TextDefaults;
+#[cfg(feature = "text")]
+pub use text_style::{AsCosmicColor, TextDecoration, TextHeight, TextStyle, WhiteSpace, Wrap};
+pub use theme::ElementDefaults;
+pub use theme::Theme;
+pub use timers::TimerId;
 use winit::dpi::PhysicalPosition;
 use winit::event::MouseButton;
 
-use crate::elements::{Button, Checkbox, FrameElement, Label, Panel, TextInput};
-
+#[cfg(feature = "text")]
+use crate::elements::{
+    Avatar, AvatarSize, AvatarStatus, Breadcrumb, Button, Form, Label, Link, Shape, TextInput,
+    Toolbar,
+};
+use crate::elements::{
+    Canvas, CanvasTransform, Checkbox, FrameElement, Image, ImageError, ImageSource, Panel, Scene,
+    ScrollView,
+};
+
+#[cfg(feature = "text")]
 use cosmic_text::{FontSystem, SwashCache};
 pub mod events;
 use events::*;
 use heka::{layout, size, style};
 
+mod accessibility;
 mod al;
+mod app;
+#[cfg(feature = "text")]
+mod buffer_pool;
+mod bulk;
+mod carousel;
+#[cfg(feature = "charts")]
+pub mod charts;
+mod clock;
 mod cmd;
+pub mod commands;
+mod decorations;
+mod disabled;
+mod drag_drop;
+mod draw_list;
 pub mod elements;
+#[cfg(feature = "text")]
+mod error_boundary;
+mod event_trace;
+mod focus;
+#[cfg(feature = "text")]
+mod format;
+pub mod gauge;
+mod gestures;
+mod handle;
+#[cfg(feature = "debug")]
+mod inspector;
+mod key_repeat;
+mod nav;
+#[cfg(not(feature = "text"))]
+mod no_text;
+mod overscroll;
+mod png;
+mod popup;
+mod render_thread;
 pub mod renderer;
+mod scrollbar;
+pub mod session;
+#[cfg(feature = "text")]
+mod shaping;
+mod signal;
+mod snapshot;
+#[cfg(feature = "text")]
+mod text_defaults;
+#[cfg(feature = "text")]
 mod text_style;
+mod theme;
+mod timers;
+mod tokens;
+
+/// Event callbacks registered per element (or per element and key)
+type Callbacks<E, K = heka::CapsuleRef> = HashMap<K, Box<dyn FnMut(&mut Context, &E)>>;
+
+/// Returns whether the window may close
+type CloseRequestedHook = Box<dyn FnMut(&mut Context) -> bool>;
+
+/// Receives the message of a panicking callback
+type CallbackPanicHook = Box<dyn FnMut(&mut Context, &str)>;
+
+/// Room kept around an element scrolled into view by keyboard navigation
+const FOCUS_SCROLL_MARGIN: u32 = 8;
+
+/// Scroll distance of one wheel notch, for devices reporting lines
+pub(crate) const SCROLL_LINE_HEIGHT: f64 = 40.0;
+
+/// Mutation postponed until the current event is dispatched
+type DeferredOp = Box<dyn FnOnce(&mut Context)>;
 
 /// Deka UI Context
 pub struct Context {
     root: heka::Root,
     root_frame: heka::Frame,
     elements: HashMap<heka::CapsuleRef, Box<dyn FrameElement>>,
-    click_callbacks: HashMap<heka::CapsuleRef, Box<dyn FnMut(&mut Context, &ClickEvent)>>,
-    hover_callbacks: HashMap<heka::CapsuleRef, Box<dyn FnMut(&mut Context, &HoverEvent)>>,
+    click_callbacks: Callbacks<ClickEvent>,
+    mouse_button_callbacks: Callbacks<ClickEvent, (heka::CapsuleRef, MouseButton)>,
+    hover_callbacks: Callbacks<HoverEvent>,
+    pointer_move_callbacks: Callbacks<PointerMoveEvent>,
+    mouse_down_callbacks: Callbacks<ClickEvent>,
+    mouse_up_callbacks: Callbacks<ClickEvent>,
+    drag_callbacks: Callbacks<DragEvent>,
+    drop_callbacks: Callbacks<DropEvent>,
+    /// Payload type each drop target takes, see `on_drop`
+    drop_types: HashMap<heka::CapsuleRef, std::any::TypeId>,
+    /// Elements that can be dragged onto drop targets, see `set_draggable`
+    drag_sources: HashMap<heka::CapsuleRef, drag_drop::DragSource>,
+    /// Press on a draggable element, and the drag it started
+    drag: Option<drag_drop::DragSession>,
+    scroll_callbacks: Callbacks<ScrollEvent>,
+    scroll_offset_callbacks: Callbacks<ScrollOffsetEvent>,
+    page_change_callbacks: Callbacks<PageChangeEvent>,
+    /// Press on a carousel, and the swipe it started
+    swipe: Option<carousel::Swipe>,
+    pinch_callbacks: Callbacks<PinchEvent>,
 
     pub(crate) attr: WindowAttr,
+    /// Maximized windows keep square corners
+    maximized: bool,
+    pub(crate) frame_stats: FrameStats,
+    draw_stats: std::cell::Cell<DrawStats>,
 
+    #[cfg(feature = "text")]
     pub(crate) font_system: FontSystem,
+    #[cfg(feature = "text")]
+    shaping: Option<shaping::ShapingPool>,
+    /// Shaped buffers of the labels repeating a short text
+    #[cfg(feature = "text")]
+    buffer_pool: buffer_pool::BufferPool,
+    #[cfg(feature = "text")]
     pub(crate) swash_cache: SwashCache,
 
     pub(crate) mouse_pos: PhysicalPosition<f64>,
     pub(crate) mouse_pressed: bool,
     pub(crate) hovered_element: Option<heka::CapsuleRef>,
+    /// Elements ignored by hit testing
+    non_interactive: std::collections::HashSet<heka::CapsuleRef>,
+    /// Elements disabled along with their children, see `set_enabled`
+    disabled: std::collections::HashSet<heka::CapsuleRef>,
+    /// Element receiving every pointer move until the button is released
+    pub(crate) pointer_capture: Option<heka::CapsuleRef>,
+    /// Cursor position and button of the press in progress
+    press: Option<(PhysicalPosition<f64>, MouseButton)>,
+    /// Scrollbar thumb following the cursor
+    thumb_drag: Option<scrollbar::ThumbDrag>,
+    /// Scroll views waiting for their scroll to stop to spring back or snap
+    settles: HashMap<heka::CapsuleRef, overscroll::Settle>,
+    /// Cursor position of the last dispatched pointer move
+    pub(crate) last_pointer_pos: PhysicalPosition<f64>,
+    pub(crate) pointer_moved: bool,
     pub(crate) focused_element: Option<heka::CapsuleRef>,
-
-    pub(crate) keyboard_callbacks:
-        HashMap<heka::CapsuleRef, Box<dyn FnMut(&mut Context, &KeyEvent)>>,
+    /// Values elements follow, see `create_signal`
+    signals: signal::Signals,
+    /// Locale formatted labels follow, see `bind_formatted`
+    #[cfg(feature = "text")]
+    locale: Signal<Locale>,
+    /// Elements keeping the focus inside, see `trap_focus`
+    focus_scopes: Vec<focus::FocusScope>,
+    /// Open popups, the last opened last, see `open_popup`
+    popups: Vec<popup::Popup>,
+    /// The focus was moved with the keyboard, the focused element shows a ring
+    focus_visible: bool,
+    /// Text input showing its caret, and the timer making it blink
+    #[cfg(feature = "text")]
+    caret_owner: Option<(heka::CapsuleRef, Option<TimerId>)>,
+    /// Arrow keys move the focus between clickable elements
+    pub(crate) spatial_nav: bool,
+    /// Shift is held down, arrow keys select text
+    pub(crate) shift_held: bool,
+
+    pub(crate) keyboard_callbacks: Callbacks<KeyEvent>,
+    #[cfg(feature = "text")]
+    text_change_callbacks: Callbacks<TextChangeEvent>,
 
     pub(crate) commands: Vec<WindowCommand>,
+
+    sound_hook: Option<Box<dyn FnMut(InteractionSound)>>,
+    pub(crate) theme: Theme,
+    /// Per element type overrides of the built-in look
+    defaults: HashMap<std::any::TypeId, ElementDefaults>,
+    /// Text properties passed on to the labels under an element, see
+    /// `set_text_defaults`
+    #[cfg(feature = "text")]
+    text_defaults: HashMap<heka::CapsuleRef, TextDefaults>,
+    /// Device pixels per layout unit, glyphs are rasterized at this scale
+    pub(crate) scale_factor: f32,
+    /// Window pixels per layout unit, see `set_ui_scale`
+    pub(crate) ui_scale: f32,
+    /// Size of the window in pixels, the layout gets it divided by `ui_scale`
+    window_size: (u32, u32),
+    /// Something changed on screen without touching the layout
+    pub(crate) redraw_requested: bool,
+
+    pub(crate) history: commands::History,
+
+    pub(crate) recorder: Option<session::Recorder>,
+    pub(crate) playback: Option<session::Playback>,
+
+    close_requested_hook: Option<CloseRequestedHook>,
+    callback_panic_hook: Option<CallbackPanicHook>,
+    /// Element whose callback runs, see `guarded_on`
+    guarded_element: Option<heka::CapsuleRef>,
+    /// Drawing of elements over their background, see `set_painter`
+    painters: HashMap<heka::CapsuleRef, draw_list::Painter>,
+    /// Dispatch tracing, see `trace_events`
+    trace: Option<Box<event_trace::EventTrace>>,
+    /// Lines of the event log panels, see `new_event_log`
+    #[cfg(feature = "text")]
+    event_logs: HashMap<heka::CapsuleRef, Vec<LabelRef>>,
+    /// Called when an error boundary catches a panic, see `on_error`
+    #[cfg(feature = "text")]
+    error_callbacks: Callbacks<ErrorEvent>,
+
+    /// Nesting depth of event dispatches in progress
+    dispatch_depth: usize,
+    deferred: Vec<DeferredOp>,
+
+    pub(crate) tasks: handle::UiTasks,
+    pub(crate) timers: timers::Timers,
+    /// Time the animations sample
+    pub(crate) clock: clock::AnimationClock,
+    accessibility: AccessibilityPrefs,
+    /// Pointer targets enlarged to `min_target_size`, see
+    /// `Root::set_enlargement`
+    enlarged_targets: std::collections::HashSet<heka::CapsuleRef>,
+    key_repeat: key_repeat::KeyRepeatState,
+    gestures: gestures::Gestures,
+    #[cfg(feature = "debug")]
+    inspector: inspector::InspectorState,
+    #[cfg(feature = "text")]
+    decoration_texts: HashMap<heka::decoration::DecorationId, decorations::DecorationText>,
 }
 
 pub trait ElementRef: Copy + Into<Element> {
@@ -72,13 +288,16 @@ impl Element {
     }
 }
 
+#[cfg(feature = "text")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct LabelRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
 impl From<LabelRef> for Element {
     fn from(v: LabelRef) -> Self {
         Element(v.0)
     }
 }
+#[cfg(feature = "text")]
 impl ElementRef for LabelRef {
     fn raw(&self) -> heka::CapsuleRef {
         self.0
@@ -98,19 +317,125 @@ impl ElementRef for PanelRef {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScrollViewRef(pub(crate) heka::CapsuleRef);
+impl From<ScrollViewRef> for Element {
+    fn from(v: ScrollViewRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for ScrollViewRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CarouselRef(pub(crate) heka::CapsuleRef);
+impl From<CarouselRef> for Element {
+    fn from(v: CarouselRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for CarouselRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorBoundaryRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<ErrorBoundaryRef> for Element {
+    fn from(v: ErrorBoundaryRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for ErrorBoundaryRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PopupRef(pub(crate) heka::CapsuleRef);
+impl From<PopupRef> for Element {
+    fn from(v: PopupRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for PopupRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[cfg(feature = "text")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ButtonRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
 impl From<ButtonRef> for Element {
     fn from(v: ButtonRef) -> Self {
         Element(v.0)
     }
 }
+#[cfg(feature = "text")]
 impl ElementRef for ButtonRef {
     fn raw(&self) -> heka::CapsuleRef {
         self.0
     }
 }
 
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AvatarRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<AvatarRef> for Element {
+    fn from(v: AvatarRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for AvatarRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ToolbarRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<ToolbarRef> for Element {
+    fn from(v: ToolbarRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for ToolbarRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BreadcrumbRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<BreadcrumbRef> for Element {
+    fn from(v: BreadcrumbRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for BreadcrumbRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CheckboxRef(pub(crate) heka::CapsuleRef);
 impl From<CheckboxRef> for Element {
@@ -124,25 +449,121 @@ impl ElementRef for CheckboxRef {
     }
 }
 
+#[cfg(feature = "text")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TextInputRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
 impl From<TextInputRef> for Element {
     fn from(v: TextInputRef) -> Self {
         Element(v.0)
     }
 }
+#[cfg(feature = "text")]
 impl ElementRef for TextInputRef {
     fn raw(&self) -> heka::CapsuleRef {
         self.0
     }
 }
 
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<FormRef> for Element {
+    fn from(v: FormRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for FormRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[cfg(feature = "text")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinkRef(pub(crate) heka::CapsuleRef);
+#[cfg(feature = "text")]
+impl From<LinkRef> for Element {
+    fn from(v: LinkRef) -> Self {
+        Element(v.0)
+    }
+}
+#[cfg(feature = "text")]
+impl ElementRef for LinkRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CanvasRef(pub(crate) heka::CapsuleRef);
+impl From<CanvasRef> for Element {
+    fn from(v: CanvasRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for CanvasRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageRef(pub(crate) heka::CapsuleRef);
+impl From<ImageRef> for Element {
+    fn from(v: ImageRef) -> Self {
+        Element(v.0)
+    }
+}
+impl ElementRef for ImageRef {
+    fn raw(&self) -> heka::CapsuleRef {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WindowAttr {
     pub resizable: bool,
     pub title: String,
     pub size: (u32, u32),
     pub app_id: String,
+    /// Color the window is cleared with before drawing
+    pub clear_color: Color,
+    /// Record and present frames on a thread of their own, see
+    /// `AppBuilder::render_thread`
+    pub render_thread: bool,
+    /// Rounds the corners of the window, see `Context::set_corner_radius`
+    pub corner_radius: u32,
+    /// Blurs what is behind the transparent parts of the window, see
+    /// `Context::set_blur`
+    pub blur: bool,
+}
+
+/// Presentation counters of the window, see `Context::frame_stats`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Time from asking the swapchain for an image to handing the finished
+    /// frame back to it, for the last frame presented
+    pub last_present_latency: Option<std::time::Duration>,
+    pub frames_presented: u64,
+    /// Frames thrown away because the swapchain went out of date
+    pub dropped_frames: u64,
+    /// Times the swapchain was rebuilt (resizes, out of date or suboptimal swapchains)
+    pub swapchain_recreations: u64,
+    /// The last acquired image did not match the surface exactly
+    pub suboptimal: bool,
+    pub suboptimal_frames: u64,
+}
+
+/// Draw commands of the last `Context::render`, see `Context::draw_stats`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DrawStats {
+    pub emitted: usize,
+    /// Commands left out because they could not change any pixel
+    /// (transparent panels, empty frames...)
+    pub skipped: usize,
 }
 
 impl Default for WindowAttr {
@@ -152,6 +573,10 @@ impl Default for WindowAttr {
             title: String::from("heka, deka, heka, eve"),
             size: (800, 600),
             app_id: String::from("org.deka.app"),
+            clear_color: Color::transparent,
+            render_thread: false,
+            corner_radius: 0,
+            blur: false,
         }
     }
 }
@@ -173,26 +598,112 @@ impl Context {
         let mut elements: HashMap<heka::CapsuleRef, Box<dyn FrameElement>> = HashMap::new();
         elements.insert(root_frame.get_ref(), Box::new(root_panel));
 
+        #[cfg(feature = "text")]
         let mut ft_sys = FontSystem::new();
+        #[cfg(feature = "text")]
         ft_sys.db_mut().load_system_fonts();
 
-        Self {
+        #[cfg_attr(not(feature = "text"), allow(unused_mut))]
+        let mut signals = signal::Signals::default();
+        #[cfg(feature = "text")]
+        let locale = signals.create(Locale::from_system());
+
+        let mut ctx = Self {
             root,
             root_frame,
             elements,
             click_callbacks: HashMap::new(),
+            mouse_button_callbacks: HashMap::new(),
             hover_callbacks: HashMap::new(),
+            pointer_move_callbacks: HashMap::new(),
+            mouse_down_callbacks: HashMap::new(),
+            mouse_up_callbacks: HashMap::new(),
+            drag_callbacks: HashMap::new(),
+            drop_callbacks: HashMap::new(),
+            drop_types: HashMap::new(),
+            drag_sources: HashMap::new(),
+            drag: None,
+            scroll_callbacks: HashMap::new(),
+            scroll_offset_callbacks: HashMap::new(),
+            page_change_callbacks: HashMap::new(),
+            swipe: None,
+            pinch_callbacks: HashMap::new(),
+            #[cfg(feature = "text")]
             font_system: ft_sys,
+            #[cfg(feature = "text")]
+            shaping: None,
+            #[cfg(feature = "text")]
+            buffer_pool: buffer_pool::BufferPool::default(),
+            #[cfg(feature = "text")]
             swash_cache: SwashCache::new(),
 
             attr,
+            maximized: false,
+            frame_stats: FrameStats::default(),
+            draw_stats: Default::default(),
             mouse_pos: PhysicalPosition::default(),
             mouse_pressed: false,
             hovered_element: None,
+            non_interactive: Default::default(),
+            disabled: Default::default(),
+            pointer_capture: None,
+            press: None,
+            thumb_drag: None,
+            settles: HashMap::new(),
+            last_pointer_pos: PhysicalPosition::default(),
+            pointer_moved: false,
             focused_element: None,
+            signals,
+            #[cfg(feature = "text")]
+            locale,
+            focus_scopes: Vec::new(),
+            popups: Vec::new(),
+            focus_visible: false,
+            #[cfg(feature = "text")]
+            caret_owner: None,
+            spatial_nav: false,
+            shift_held: false,
             keyboard_callbacks: HashMap::new(),
+            #[cfg(feature = "text")]
+            text_change_callbacks: HashMap::new(),
             commands: Vec::new(),
-        }
+            sound_hook: None,
+            defaults: HashMap::new(),
+            #[cfg(feature = "text")]
+            text_defaults: HashMap::new(),
+            theme: Theme::default(),
+            scale_factor: 1.0,
+            ui_scale: 1.0,
+            window_size: (width, height),
+            redraw_requested: false,
+            history: commands::History::default(),
+            recorder: None,
+            playback: None,
+            close_requested_hook: None,
+            callback_panic_hook: None,
+            guarded_element: None,
+            painters: HashMap::new(),
+            trace: None,
+            #[cfg(feature = "text")]
+            event_logs: HashMap::new(),
+            #[cfg(feature = "text")]
+            error_callbacks: HashMap::new(),
+            dispatch_depth: 0,
+            deferred: Vec::new(),
+            tasks: handle::UiTasks::default(),
+            timers: timers::Timers::default(),
+            clock: clock::AnimationClock::new(),
+            accessibility: AccessibilityPrefs::default(),
+            enlarged_targets: std::collections::HashSet::new(),
+            key_repeat: key_repeat::KeyRepeatState::new(),
+            gestures: gestures::Gestures::default(),
+            #[cfg(feature = "debug")]
+            inspector: inspector::InspectorState::default(),
+            #[cfg(feature = "text")]
+            decoration_texts: HashMap::new(),
+        };
+        ctx.apply_window_shape();
+        ctx
     }
 }
 
@@ -203,11 +714,374 @@ impl Context {
         self.push_command(WindowCommand::SetTitle(title));
     }
 
+    /// Paints the window backdrop, below every element
+    pub fn set_clear_color(&mut self, color: Color) {
+        if self.attr.clear_color != color {
+            self.attr.clear_color = color;
+            self.apply_window_shape();
+            self.redraw_requested = true;
+        }
+    }
+
+    /// Rounds the corners of the (undecorated) window by `radius` pixels,
+    /// 0 for square ones. The backdrop, `clear_color`, is then drawn as a
+    /// rounded rectangle and the corners are left transparent for the
+    /// compositor. Elements are not cut at the corners. Maximized windows
+    /// keep square corners.
+    pub fn set_corner_radius(&mut self, radius: u32) {
+        if self.attr.corner_radius != radius {
+            self.attr.corner_radius = radius;
+            self.apply_window_shape();
+        }
+    }
+
+    #[inline]
+    pub fn corner_radius(&self) -> u32 {
+        self.attr.corner_radius
+    }
+
+    /// Asks the compositor to blur what is behind the window, showing
+    /// through a translucent `clear_color` (vibrancy). Only some platforms
+    /// support it (KDE on Wayland, macOS), it is ignored elsewhere.
+    pub fn set_blur(&mut self, blur: bool) {
+        if self.attr.blur != blur {
+            self.attr.blur = blur;
+            self.push_command(WindowCommand::SetBlur(blur));
+        }
+    }
+
+    #[inline]
+    pub fn blur(&self) -> bool {
+        self.attr.blur
+    }
+
+    pub(crate) fn set_maximized(&mut self, maximized: bool) {
+        if self.maximized != maximized {
+            self.maximized = maximized;
+            self.apply_window_shape();
+        }
+    }
+
+    /// The backdrop is drawn by the root frame rather than cleared
+    pub(crate) fn rounds_corners(&self) -> bool {
+        self.attr.corner_radius > 0 && !self.maximized
+    }
+
+    fn apply_window_shape(&mut self) {
+        let (radius, background) = if self.rounds_corners() {
+            (self.attr.corner_radius, self.attr.clear_color)
+        } else {
+            (0, Color::transparent)
+        };
+        self.root_frame.update_style(&mut self.root, |style| {
+            style.border.radius = radius;
+            style.background_color = background;
+        });
+    }
+
+    #[inline]
+    pub fn clear_color(&self) -> Color {
+        self.attr.clear_color
+    }
+
+    /// What presenting frames has been like so far, to diagnose stutter
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// How many draw commands the last frame emitted, and how many it left
+    /// out, to see how heavy the tree is to draw
+    #[inline]
+    pub fn draw_stats(&self) -> DrawStats {
+        self.draw_stats.get()
+    }
+
+    /// Work done by the layout since the context was created or
+    /// `reset_layout_profile`, see `heka::stats::LayoutProfile`
+    #[cfg(feature = "profiling")]
+    pub fn layout_profile(&self) -> heka::stats::LayoutProfile {
+        self.root.profile()
+    }
+
+    #[cfg(feature = "profiling")]
+    pub fn reset_layout_profile(&mut self) {
+        self.root.reset_profile();
+    }
+
+    /// Read-only copy of the laid out element tree, for tooling reading it
+    /// on another thread. See `heka::Root::snapshot`.
+    pub fn layout_snapshot(&self) -> heka::snapshot::LayoutSnapshot {
+        self.root.snapshot()
+    }
+
+    #[inline]
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Sets the number of device pixels per layout unit (1.25, 1.5, 2...).
+    /// Text is rasterized at device pixels so fractional scales stay sharp.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        if scale_factor > 0.0 && scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            // Layout is unchanged, glyphs only need to be drawn again
+            self.root.invalidate_content(self.root_frame.get_ref());
+        }
+    }
+
+    #[inline]
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Zooms the whole UI in (above 1) or out, whatever the monitor: sizes,
+    /// text and hit testing grow together, and the layout gets the window
+    /// size divided by `scale`. Positions of pointer events, and the ones
+    /// the context hands out, stay in layout units.
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        if !scale.is_finite() || scale <= 0.0 || scale == self.ui_scale {
+            return;
+        }
+        // The pointer stays over the same window pixel
+        let ratio = (self.ui_scale / scale) as f64;
+        self.mouse_pos = PhysicalPosition::new(self.mouse_pos.x * ratio, self.mouse_pos.y * ratio);
+        self.last_pointer_pos = PhysicalPosition::new(
+            self.last_pointer_pos.x * ratio,
+            self.last_pointer_pos.y * ratio,
+        );
+
+        self.ui_scale = scale;
+        let (width, height) = self.window_size;
+        self.resize(width, height);
+        // Glyphs are rasterized at the new scale
+        self.root.invalidate_content(self.root_frame.get_ref());
+        self.request_redraw();
+    }
+
+    /// A window position in layout units
+    fn unscaled(&self, pos: PhysicalPosition<f64>) -> PhysicalPosition<f64> {
+        let scale = self.ui_scale as f64;
+        PhysicalPosition::new(pos.x / scale, pos.y / scale)
+    }
+
+    #[inline]
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Sets the theme used by components created from now on.
+    /// Its baseline grid applies to the whole layout right away.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.root.set_baseline_grid(theme.baseline_grid);
+        self.theme = theme;
+    }
+
+    /// Replaces the built-in look of the `T` elements created from now on
+    /// (`Button`, `Checkbox`, `TextInput`, `Link`).
+    /// Labels only use `text_style`, checkboxes use the style background
+    /// as their unchecked color.
+    #[cfg(feature = "text")]
+    pub fn set_default_style<T: FrameElement>(
+        &mut self,
+        style: Style,
+        text_style: Option<TextStyle>,
+    ) {
+        self.defaults.insert(
+            std::any::TypeId::of::<T>(),
+            ElementDefaults { style, text_style },
+        );
+    }
+
+    /// Replaces the built-in look of the `T` elements created from now on
+    /// (`Checkbox`). Checkboxes use the style background as their
+    /// unchecked color.
+    #[cfg(not(feature = "text"))]
+    pub fn set_default_style<T: FrameElement>(&mut self, style: Style) {
+        self.defaults
+            .insert(std::any::TypeId::of::<T>(), ElementDefaults { style });
+    }
+
+    /// Goes back to the built-in look for the `T` elements created from now on
+    pub fn reset_default_style<T: FrameElement>(&mut self) {
+        self.defaults.remove(&std::any::TypeId::of::<T>());
+    }
+
+    pub fn default_style<T: FrameElement>(&self) -> Option<&ElementDefaults> {
+        self.defaults.get(&std::any::TypeId::of::<T>())
+    }
+
+    #[cfg(feature = "text")]
+    fn default_text_style<T: FrameElement>(&self) -> Option<TextStyle> {
+        self.default_style::<T>()?.text_style.clone()
+    }
+
+    /// Makes a font available to text styles, on top of the system fonts
+    #[cfg(feature = "text")]
+    pub fn load_font_data(&mut self, data: Vec<u8>) {
+        self.font_system.db_mut().load_font_data(data);
+        self.buffer_pool.forget();
+        self.restart_shaping();
+    }
+
+    /// Loads a font file, on top of the system fonts
+    #[cfg(feature = "text")]
+    pub fn load_font_file(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.font_system.db_mut().load_font_file(path)?;
+        self.buffer_pool.forget();
+        self.restart_shaping();
+        Ok(())
+    }
+
+    /// Stops the event loop once the current events are handled.
+    /// Unlike closing the window, this is not subject to `on_close_requested`.
+    pub fn exit(&mut self) {
+        self.push_command(WindowCommand::Quit);
+    }
+
+    /// Called when the user asks to close the window.
+    /// Returning `false` keeps the window open (e.g. to confirm unsaved changes first).
+    pub fn on_close_requested<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut Context) -> bool + 'static,
+    {
+        self.close_requested_hook = Some(Box::new(hook));
+    }
+
+    /// Whether the window may close, as decided by the `on_close_requested` hook
+    pub(crate) fn close_requested(&mut self) -> bool {
+        let Some(mut hook) = self.close_requested_hook.take() else {
+            return true;
+        };
+        // A panicking hook does not trap the user in the window
+        let allowed = self
+            .dispatching(|ctx| ctx.guarded(|ctx| hook(ctx)))
+            .unwrap_or(true);
+        // The hook may have replaced itself
+        if self.close_requested_hook.is_none() {
+            self.close_requested_hook = Some(hook);
+        }
+        allowed
+    }
+
+    /// Called when an event callback panics, with the panic message.
+    /// The panic is caught and the application keeps running; without a hook
+    /// it is only logged.
+    pub fn on_callback_panic<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut Context, &str) + 'static,
+    {
+        self.callback_panic_hook = Some(Box::new(hook));
+    }
+
+    /// Runs an event dispatch. Mutations deferred by callbacks are applied
+    /// once the outermost dispatch is over.
+    pub(crate) fn dispatching<R>(&mut self, op: impl FnOnce(&mut Context) -> R) -> R {
+        self.dispatch_depth += 1;
+        let result = op(self);
+        self.dispatch_depth -= 1;
+
+        if self.dispatch_depth == 0 {
+            while !self.deferred.is_empty() {
+                for deferred in std::mem::take(&mut self.deferred) {
+                    // A panic in one leaves the others to run
+                    self.guarded(|ctx| deferred(ctx));
+                }
+            }
+        }
+        result
+    }
+
+    /// Runs `op` once the event being dispatched is fully handled, or right
+    /// away outside of a dispatch. Callbacks use it for mutations that would
+    /// disturb the dispatch, such as removing the element they belong to.
+    pub fn defer<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut Context) + 'static,
+    {
+        if self.dispatch_depth > 0 {
+            self.deferred.push(Box::new(op));
+        } else {
+            op(self);
+        }
+    }
+
+    /// Removes an element along with its children and their callbacks.
+    /// Removals requested by a callback happen after the current event.
+    pub fn remove_element(&mut self, element: impl ElementRef) {
+        let cref = element.raw();
+        if cref == self.root_frame.get_ref() {
+            warn!("The root element cannot be removed");
+            return;
+        }
+        self.defer(move |ctx| ctx.remove_element_tree(cref));
+    }
+
+    /// Runs user code, catching a panic so it does not unwind through the
+    /// context while an element or callback is out of its map.
+    /// Returns `None` if `op` panicked.
+    pub(crate) fn guarded<R>(&mut self, op: impl FnOnce(&mut Context) -> R) -> Option<R> {
+        let payload = match panic::catch_unwind(AssertUnwindSafe(|| op(self))) {
+            Ok(result) => return Some(result),
+            Err(payload) => payload,
+        };
+
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic payload");
+
+        match self.callback_panic_hook.take() {
+            Some(mut hook) => {
+                if panic::catch_unwind(AssertUnwindSafe(|| hook(self, message))).is_err() {
+                    log::error!("Callback panic hook panicked, removing it");
+                } else if self.callback_panic_hook.is_none() {
+                    self.callback_panic_hook = Some(hook);
+                }
+            }
+            None => log::error!("Callback panicked: {message}"),
+        }
+        #[cfg(feature = "text")]
+        if let Some(cref) = self.guarded_element {
+            self.catch_in_boundary(cref, message);
+        }
+        None
+    }
+
+    /// Runs a callback of an element like `guarded`. A panic replaces the
+    /// error boundary around the element (if any) with its fallback.
+    pub(crate) fn guarded_on<R>(
+        &mut self,
+        cref: heka::CapsuleRef,
+        op: impl FnOnce(&mut Context) -> R,
+    ) -> Option<R> {
+        self.trace_handler(cref);
+        let outer = self.guarded_element.replace(cref);
+        let result = self.guarded(op);
+        self.guarded_element = outer;
+        result
+    }
+
+    /// Changes the mouse cursor of the window
+    pub fn set_cursor(&mut self, icon: winit::window::CursorIcon) {
+        self.push_command(WindowCommand::SetCursor(icon));
+    }
+
     pub fn push_command(&mut self, cmd: WindowCommand) {
         self.commands.push(cmd);
     }
 
     pub fn process_event(&mut self, event: SystemEvent) {
+        self.record_event(&event);
+        self.dispatching(|ctx| {
+            ctx.trace_start(&event);
+            ctx.handle_event(event);
+            ctx.trace_end();
+        });
+    }
+
+    fn handle_event(&mut self, event: SystemEvent) {
         match event {
             SystemEvent::Click {
                 pos: _,
@@ -218,19 +1092,52 @@ impl Context {
                 self.click(button, pressed, double_click);
             }
             SystemEvent::CursorMoved(pos) => {
+                let pos = self.unscaled(pos);
+                let delta =
+                    PhysicalPosition::new(pos.x - self.mouse_pos.x, pos.y - self.mouse_pos.y);
                 self.mouse_pos = pos;
+                let taken = if self.drag_pan_move(delta) {
+                    Some("drag pan")
+                } else if self.scrollbar_drag() {
+                    Some("scrollbar")
+                } else if self.carousel_swipe() {
+                    Some("carousel")
+                } else {
+                    None
+                };
+                if let Some(part) = taken {
+                    self.trace_taken(part);
+                    // Pointer moves resume from here once the pan ends
+                    self.last_pointer_pos = pos;
+                    return;
+                }
+                self.drag_move();
+                self.pointer_moved = true;
                 self.update_hover();
             }
             SystemEvent::Keyboard {
                 logical_key,
                 text,
                 pressed,
+                repeat,
             } => {
-                self.key_event(KeyEvent {
+                let event = KeyEvent {
                     logical_key,
                     text,
                     pressed,
-                });
+                    repeat,
+                };
+                self.track_key_repeat(&event);
+                self.key_event(event);
+            }
+            SystemEvent::Scroll(delta) => {
+                self.scroll_wheel(self.unscaled(delta));
+            }
+            SystemEvent::ScrollPhase(phase) => {
+                self.scroll_phase(phase);
+            }
+            SystemEvent::Pinch { delta, phase } => {
+                self.pinch(delta, phase);
             }
             SystemEvent::Resize(w, h) => {
                 self.resize(w, h);
@@ -241,25 +1148,51 @@ impl Context {
         }
     }
 
+    #[cfg(feature = "text")]
     pub fn new_label<S: ToString>(
         &mut self,
         text: S,
         parent_frame: Option<impl ElementRef>,
         text_style: Option<TextStyle>,
     ) -> LabelRef {
-        let parent_frame = if let Some(pf) = parent_frame {
-            &Frame::define(pf.raw())
-        } else {
-            &self.root_frame
-        };
+        let base_style = self
+            .default_text_style::<Label>()
+            .unwrap_or_else(|| self.theme.text_style());
+        self.add_label(text.to_string(), parent_frame, text_style, base_style)
+    }
 
-        let label = Label::new(
+    /// A label in `text_style`, or inheriting its text properties over
+    /// `base_style` without one, see `set_text_defaults`
+    #[cfg(feature = "text")]
+    fn add_label(
+        &mut self,
+        text: String,
+        parent_frame: Option<impl ElementRef>,
+        text_style: Option<TextStyle>,
+        base_style: TextStyle,
+    ) -> LabelRef {
+        let parent_frame = if let Some(pf) = parent_frame {
+            Frame::define(pf.raw())
+        } else {
+            self.root_frame
+        };
+
+        let (text_style, base_style) = match text_style {
+            Some(style) => (style, None),
+            None => (
+                self.inherit_text_style(parent_frame.get_ref(), &base_style),
+                Some(base_style),
+            ),
+        };
+        let mut label = Label::new(
             &mut self.root,
-            Some(parent_frame),
-            text.to_string(),
-            text_style.unwrap_or(TextStyle::default()),
+            Some(&parent_frame),
+            text,
+            text_style,
             &mut self.font_system,
+            &mut self.buffer_pool,
         );
+        label.base_style = base_style;
 
         let label_ref = label.frame.get_ref();
 
@@ -285,6 +1218,74 @@ impl Context {
         PanelRef(new_frame.get_ref())
     }
 
+    /// Creates a `ScrollView` sized by `style`. It scrolls with scrollbars
+    /// showing when the content does not fit, unless `style` makes it
+    /// scroll without any (`Overflow::Scroll`). Children go in
+    /// `scroll_view_content`.
+    pub fn new_scroll_view(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        mut style: Style,
+    ) -> ScrollViewRef {
+        if !style.overflow.scrolls() {
+            style.overflow = heka::position::Overflow::Auto;
+        }
+        let view = self.new_panel(parent_frame, style).0;
+        let content = self.new_panel(
+            Some(Element(view)),
+            heka::make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                layout: layout!(flex),
+                flow: heka::flow!(column),
+                background_color: clr!(transparent),
+            ),
+        );
+
+        let frame = Frame::define(view);
+        self.elements.insert(
+            view,
+            Box::new(ScrollView {
+                frame,
+                content,
+                overscroll: false,
+                snap: ScrollSnap::None,
+                stretch: (0.0, 0.0),
+            }),
+        );
+        ScrollViewRef(view)
+    }
+
+    pub fn get_scroll_view(&self, element: ScrollViewRef) -> Option<&ScrollView> {
+        let view = self.elements.get(&element.raw())?;
+        view.as_any().downcast_ref::<ScrollView>()
+    }
+
+    /// The panel of a `ScrollView` its children go in
+    pub fn scroll_view_content(&self, element: ScrollViewRef) -> Option<PanelRef> {
+        self.get_scroll_view(element).map(ScrollView::content)
+    }
+
+    /// Scrolls a `ScrollView` (or any scroll container) to `offset`, in
+    /// pixels from the top-left of its content, clamped to it. Returns
+    /// whether it moved.
+    pub fn scroll_to(&mut self, container: impl ElementRef, offset: (f32, f32)) -> bool {
+        let moved = self
+            .root
+            .set_scroll_offset(container.raw(), offset.0, offset.1);
+        if moved {
+            // The cursor may be above another element now
+            self.pointer_moved = true;
+            self.dispatch_scroll_offsets();
+        }
+        moved
+    }
+
+    /// Scroll position of a `ScrollView` (or any scroll container)
+    pub fn scroll_offset(&self, container: impl ElementRef) -> (f32, f32) {
+        self.root.scroll_offset(container.raw())
+    }
+
     pub fn new_checkbox(
         &mut self,
         parent_frame: Option<impl ElementRef>,
@@ -296,19 +1297,59 @@ impl Context {
             &self.root_frame
         };
 
-        let checkbox = Checkbox::new(&mut self.root, Some(parent), initial_checked);
+        let style = self
+            .default_style::<Checkbox>()
+            .map(|defaults| defaults.style);
+        let checkbox = Checkbox::new(
+            &mut self.root,
+            Some(parent),
+            initial_checked,
+            &self.theme,
+            style,
+        );
         let checkbox_ref = checkbox.frame.get_ref();
 
         self.elements.insert(checkbox_ref, Box::new(checkbox));
         CheckboxRef(checkbox_ref)
     }
 
+    /// Creates a named layer laid out against the whole window.
+    /// Elements added to it are stacked according to `order`,
+    /// above (or below) everything in lower (higher) layers.
+    /// The main UI lives at order 0.
+    pub fn create_layer(&mut self, name: impl Into<String>, order: i32) -> PanelRef {
+        let frame = self.root.create_layer(name, order);
+        self.elements
+            .insert(frame.get_ref(), Box::new(Panel { frame }));
+        PanelRef(frame.get_ref())
+    }
+
+    /// Finds a layer created with `create_layer`
+    pub fn layer(&self, name: &str) -> Option<PanelRef> {
+        self.root
+            .get_layer(name)
+            .map(|frame| PanelRef(frame.get_ref()))
+    }
+
+    /// Checks or unchecks a checkbox, without an undo step
+    pub fn set_checked(&mut self, element: CheckboxRef, checked: bool) {
+        self.with_component_mut::<Checkbox>(element.0, |checkbox, ctx| {
+            if checkbox.checked != checked {
+                checkbox.toggle(&mut ctx.root);
+            }
+        });
+    }
+
     pub fn toggle_checkbox(&mut self, element: CheckboxRef) {
         self.with_component_mut::<Checkbox>(element.0, |checkbox, ctx| {
             checkbox.toggle(&mut ctx.root);
+            ctx.play_sound(InteractionSound::Toggle);
         });
+        self.history
+            .push(Box::new(commands::Toggle { checkbox: element }));
     }
 
+    #[cfg(feature = "text")]
     pub fn new_text_input(
         &mut self,
         parent_frame: Option<impl ElementRef>,
@@ -335,12 +1376,427 @@ impl Context {
         TextInputRef(text_input_ref)
     }
 
+    /// Color of the caret of a text input
+    #[cfg(feature = "text")]
+    pub fn set_caret_color(&mut self, input: TextInputRef, color: Color) {
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| {
+            input.caret_color = color;
+            ctx.request_redraw();
+        });
+    }
+
+    /// Color highlighting the selected text of a text input
+    #[cfg(feature = "text")]
+    pub fn set_selection_color(&mut self, input: TextInputRef, color: Color) {
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| {
+            input.selection_color = color;
+            ctx.request_redraw();
+        });
+    }
+
+    /// Time the caret of a text input stays on, then off. `None` keeps it on.
+    #[cfg(feature = "text")]
+    pub fn set_caret_blink(&mut self, input: TextInputRef, blink: Option<std::time::Duration>) {
+        self.with_component_mut::<TextInput>(input.0, |input, _| input.caret_blink = blink);
+        if self.caret_owner.is_some_and(|(owner, _)| owner == input.0) {
+            // Restarts the blink timer
+            self.drop_caret();
+            self.update_caret();
+        }
+    }
+
+    /// Selects the byte range `selection` of the text of an input, with the
+    /// cursor at its end. An empty range only moves the cursor.
+    #[cfg(feature = "text")]
+    pub fn select_text(&mut self, input: TextInputRef, selection: std::ops::Range<usize>) {
+        self.set_text_selection(input, (selection.start, selection.end));
+    }
+
+    /// Sets the `(anchor, cursor)` selection of an input
+    #[cfg(feature = "text")]
+    pub(crate) fn set_text_selection(&mut self, input: TextInputRef, selection: (usize, usize)) {
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| {
+            let text = input.text(ctx).to_string();
+            input.select(&text, selection.0, selection.1);
+            ctx.request_redraw();
+        });
+    }
+
+    /// Replaces the text of an input, keeping its selection where it still
+    /// fits in the new text
+    #[cfg(feature = "text")]
+    pub fn set_input_text<S: ToString>(&mut self, input: TextInputRef, new_text: S) {
+        if self.get_text_input(input).is_none() {
+            return;
+        }
+        let new_text = new_text.to_string();
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| input.set_text(ctx, &new_text));
+        let selection = self
+            .get_text_input(input)
+            .map_or((0, 0), |input| (input.anchor, input.cursor()));
+        self.set_text_selection(input, selection);
+    }
+
+    /// The text of an input, the actual one for masked inputs
+    #[cfg(feature = "text")]
+    pub fn input_text(&self, input: TextInputRef) -> Option<&str> {
+        Some(self.get_text_input(input)?.text(self))
+    }
+
+    /// Shows `mask` (`'•'`...) in place of each character of the text of
+    /// an input, for passwords, or the text again with `None`
+    #[cfg(feature = "text")]
+    pub fn set_input_mask(&mut self, input: TextInputRef, mask: Option<char>) {
+        let mut reveal_button = None;
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| {
+            let text = input.text(ctx).to_string();
+            input.mask = mask;
+            input.revealed = false;
+            input.value = if mask.is_some() { text } else { String::new() };
+            ctx.set_label_text(input.label, input.shown_text(ctx));
+            if mask.is_none() {
+                reveal_button = input.reveal_button.take();
+            }
+        });
+        if let Some(button) = reveal_button {
+            self.remove_element(button);
+        }
+    }
+
+    /// Shows the text of a masked input as is, or masks it again
+    #[cfg(feature = "text")]
+    pub fn reveal_input_text(&mut self, input: TextInputRef, revealed: bool) {
+        let mut reveal_button = None;
+        self.with_component_mut::<TextInput>(input.0, |input, ctx| {
+            if input.mask.is_none() {
+                return;
+            }
+            input.revealed = revealed;
+            ctx.set_label_text(input.label, input.shown_text(ctx));
+            reveal_button = input.reveal_button;
+        });
+        if let Some(button) = reveal_button {
+            self.set_button_text(button, if revealed { "Hide" } else { "Show" });
+        }
+    }
+
+    /// Adds a button at the end of a masked input that reveals its text,
+    /// or removes it
+    #[cfg(feature = "text")]
+    pub fn set_reveal_button(&mut self, input: TextInputRef, shown: bool) {
+        let Some(current) = self
+            .get_text_input(input)
+            .filter(|text_input| text_input.mask.is_some())
+            .map(|text_input| text_input.reveal_button)
+        else {
+            return;
+        };
+
+        match (current, shown) {
+            (None, true) => {
+                let text = if self.get_text_input(input).is_some_and(TextInput::revealed) {
+                    "Hide"
+                } else {
+                    "Show"
+                };
+                let button = self.new_button(
+                    text,
+                    Some(input),
+                    move |ctx, _| {
+                        let revealed = ctx.get_text_input(input).is_some_and(TextInput::revealed);
+                        ctx.reveal_input_text(input, !revealed);
+                    },
+                    None,
+                );
+                // Over the end of the input, across its height
+                Frame::define(button.raw()).update_style(&mut self.root, |style| {
+                    style.position = heka::position::Position::Absolute(heka::position::Insets {
+                        top: Some(0.into()),
+                        right: Some(0.into()),
+                        bottom: Some(0.into()),
+                        left: None,
+                    });
+                    style.height = heka::sizing::SizeSpec::Auto;
+                    style.padding = heka::sizing::Padding::lr_tb(4, 0);
+                    style.margin = heka::sizing::Margin::all(0);
+                    style.border.size = 0;
+                    style.background_color = Color::transparent;
+                });
+                self.with_component_mut::<TextInput>(input.0, |input, _| {
+                    input.reveal_button = Some(button);
+                });
+            }
+            (Some(button), false) => {
+                self.with_component_mut::<TextInput>(input.0, |input, _| {
+                    input.reveal_button = None;
+                });
+                self.remove_element(button);
+            }
+            _ => {}
+        }
+    }
+
+    /// Lets the selection of a masked input be copied, see
+    /// `copy_input_selection`. Not allowed by default.
+    #[cfg(feature = "text")]
+    pub fn allow_masked_copy(&mut self, input: TextInputRef, allowed: bool) {
+        self.with_component_mut::<TextInput>(input.0, |input, _| input.masked_copy = allowed);
+    }
+
+    /// The selected text of an input, for the application to put on the
+    /// clipboard. `None` when nothing is selected, or for a masked input
+    /// that doesn't allow it.
+    #[cfg(feature = "text")]
+    pub fn copy_input_selection(&self, input: TextInputRef) -> Option<String> {
+        let text_input = self.get_text_input(input)?;
+        if text_input.mask.is_some() && !text_input.masked_copy {
+            return None;
+        }
+        let selection = text_input.selection();
+        if selection.is_empty() {
+            return None;
+        }
+        text_input
+            .text(self)
+            .get(selection)
+            .map(ToString::to_string)
+    }
+
+    #[cfg(feature = "text")]
+    pub fn get_text_input(&self, element: TextInputRef) -> Option<&TextInput> {
+        let input = self.elements.get(&element.raw())?;
+        input.as_any().downcast_ref::<TextInput>()
+    }
+
+    /// Shows the caret of the focused text input, blinking on a timer, and
+    /// hides the one of the input that lost the focus
+    #[cfg(feature = "text")]
+    fn update_caret(&mut self) {
+        let focused = self.focused_element.filter(|cref| {
+            self.elements
+                .get(cref)
+                .is_some_and(|element| element.as_any().is::<TextInput>())
+        });
+        if self.caret_owner.map(|(owner, _)| owner) == focused {
+            return;
+        }
+        self.drop_caret();
+
+        let Some(cref) = focused else {
+            return;
+        };
+        let mut blink = None;
+        self.with_component_mut::<TextInput>(cref, |input, ctx| {
+            input.set_focused(ctx, true);
+            blink = input.caret_blink;
+        });
+        let timer = blink.map(|blink| {
+            self.set_interval(blink, move |ctx| {
+                if ctx.elements.contains_key(&cref) {
+                    ctx.with_component_mut::<TextInput>(cref, |input, ctx| input.blink(ctx));
+                } else {
+                    // Removed while focused
+                    ctx.update_caret();
+                }
+            })
+        });
+        self.caret_owner = Some((cref, timer));
+    }
+
+    /// Hides the caret shown, if any, and stops its blinking
+    #[cfg(feature = "text")]
+    fn drop_caret(&mut self) {
+        let Some((owner, timer)) = self.caret_owner.take() else {
+            return;
+        };
+        if let Some(timer) = timer {
+            self.cancel_timer(timer);
+        }
+        if self.elements.contains_key(&owner) {
+            self.with_component_mut::<TextInput>(owner, |input, ctx| input.set_focused(ctx, false));
+        }
+    }
+
+    /// Creates a round avatar showing the initials of `name`
+    #[cfg(feature = "text")]
+    pub fn new_avatar(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        name: impl Into<String>,
+        size: AvatarSize,
+    ) -> AvatarRef {
+        let avatar = Avatar::new(self, parent_frame, name.into(), size);
+        let avatar_ref = avatar.frame.get_ref();
+
+        self.elements.insert(avatar_ref, Box::new(avatar));
+        AvatarRef(avatar_ref)
+    }
+
+    /// Shows `status` in a dot at the bottom right of an avatar, `None`
+    /// hides it
+    #[cfg(feature = "text")]
+    pub fn set_avatar_status(&mut self, avatar: AvatarRef, status: Option<AvatarStatus>) {
+        self.with_component_mut::<Avatar>(avatar.0, |avatar, ctx| {
+            avatar.set_status(ctx, status);
+            ctx.request_redraw();
+        });
+    }
+
+    #[cfg(feature = "text")]
+    pub fn get_avatar(&self, element: AvatarRef) -> Option<&Avatar> {
+        let avatar = self.elements.get(&element.raw())?;
+        avatar.as_any().downcast_ref::<Avatar>()
+    }
+
+    #[cfg(feature = "text")]
+    pub fn new_form(&mut self, parent_frame: Option<impl ElementRef>) -> FormRef {
+        let form = Form::new(self, parent_frame);
+        let form_ref = form.frame.get_ref();
+
+        self.elements.insert(form_ref, Box::new(form));
+        FormRef(form_ref)
+    }
+
+    /// Adds a row to a form, with `label` in the label column and `field`
+    /// (moved into the form) next to it. Returns the created label.
+    #[cfg(feature = "text")]
+    pub fn form_add_row<S: ToString>(
+        &mut self,
+        form: FormRef,
+        label: S,
+        field: impl ElementRef,
+    ) -> Option<LabelRef> {
+        let mut label_ref = None;
+        self.with_component_mut::<Form>(form.0, |form, ctx| {
+            label_ref = Some(form.add_row(ctx, label.to_string(), field.raw()));
+        });
+        label_ref
+    }
+
+    /// Shows a validation message under a form field. `None` clears it.
+    #[cfg(feature = "text")]
+    pub fn set_form_error<S: ToString>(
+        &mut self,
+        form: FormRef,
+        field: impl ElementRef,
+        message: Option<S>,
+    ) {
+        let message = message.map(|m| m.to_string());
+        if message.is_some() {
+            self.play_sound(InteractionSound::Error);
+        }
+        self.with_component_mut::<Form>(form.0, |form, ctx| {
+            form.set_error(ctx, field.raw(), message);
+        });
+    }
+
+    /// Creates a row of buttons, the ones that do not fit in its width
+    /// going to a "…" menu. Its width comes from `style`.
+    #[cfg(feature = "text")]
+    pub fn new_toolbar(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> ToolbarRef {
+        let toolbar = Toolbar::new(self, parent_frame, style);
+        let toolbar_ref = toolbar.row.frame.get_ref();
+
+        self.elements.insert(toolbar_ref, Box::new(toolbar));
+        ToolbarRef(toolbar_ref)
+    }
+
+    /// Adds a button at the end of a toolbar
+    #[cfg(feature = "text")]
+    pub fn toolbar_add_item<S: ToString, F>(
+        &mut self,
+        toolbar: ToolbarRef,
+        text: S,
+        on_click: F,
+    ) -> Option<ButtonRef>
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        let mut button = None;
+        self.with_component_mut::<Toolbar>(toolbar.0, |toolbar, ctx| {
+            button = Some(toolbar.add_item(ctx, text.to_string(), on_click));
+        });
+        button
+    }
+
+    #[cfg(feature = "text")]
+    pub fn get_toolbar(&self, element: ToolbarRef) -> Option<&Toolbar> {
+        let toolbar = self.elements.get(&element.raw())?;
+        toolbar.as_any().downcast_ref::<Toolbar>()
+    }
+
+    /// Creates a path of buttons keeping its first and last items in view,
+    /// the ones in between going to a "…" menu when it does not fit in its
+    /// width. Its width comes from `style`.
+    #[cfg(feature = "text")]
+    pub fn new_breadcrumb(
+        &mut self,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> BreadcrumbRef {
+        let breadcrumb = Breadcrumb::new(self, parent_frame, style);
+        let breadcrumb_ref = breadcrumb.row.frame.get_ref();
+
+        self.elements.insert(breadcrumb_ref, Box::new(breadcrumb));
+        BreadcrumbRef(breadcrumb_ref)
+    }
+
+    /// Adds a button at the end of a breadcrumb
+    #[cfg(feature = "text")]
+    pub fn breadcrumb_push<S: ToString, F>(
+        &mut self,
+        breadcrumb: BreadcrumbRef,
+        text: S,
+        on_click: F,
+    ) -> Option<ButtonRef>
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        let mut button = None;
+        self.with_component_mut::<Breadcrumb>(breadcrumb.0, |breadcrumb, ctx| {
+            button = Some(breadcrumb.push(ctx, text.to_string(), on_click));
+        });
+        button
+    }
+
+    /// Keeps the first `len` items of a breadcrumb, e.g. when going back up
+    /// the path
+    #[cfg(feature = "text")]
+    pub fn breadcrumb_truncate(&mut self, breadcrumb: BreadcrumbRef, len: usize) {
+        self.with_component_mut::<Breadcrumb>(breadcrumb.0, |breadcrumb, ctx| {
+            breadcrumb.truncate(ctx, len);
+        });
+    }
+
+    #[cfg(feature = "text")]
+    pub fn get_breadcrumb(&self, element: BreadcrumbRef) -> Option<&Breadcrumb> {
+        let breadcrumb = self.elements.get(&element.raw())?;
+        breadcrumb.as_any().downcast_ref::<Breadcrumb>()
+    }
+
+    /// Long texts are shaped on the shaping threads when there are,
+    /// see `Context::set_shaping_threads`
+    #[cfg(feature = "text")]
     pub fn set_label_text<S: ToString>(&mut self, element: LabelRef, new_text: S) {
+        let new_text = new_text.to_string();
+        if self.shape_label_async(element, &new_text) {
+            return;
+        }
         self.with_component_mut::<Label>(element.0, |label, ctx| {
-            label.set_text(&mut ctx.root, &mut ctx.font_system, new_text.to_string());
+            label.set_text(
+                &mut ctx.root,
+                &mut ctx.font_system,
+                &mut ctx.buffer_pool,
+                new_text,
+            );
         });
     }
 
+    #[cfg(feature = "text")]
     pub fn get_label_text(&self, element: LabelRef) -> &str {
         if let Some(el) = self.elements.get(&element.0) {
             if let Some(label) = el.as_any().downcast_ref::<Label>() {
@@ -350,12 +1806,60 @@ impl Context {
         ""
     }
 
+    #[cfg(feature = "text")]
+    pub fn get_label_style(&self, element: LabelRef) -> Option<&TextStyle> {
+        let label = self.elements.get(&element.0)?;
+        label
+            .as_any()
+            .downcast_ref::<Label>()
+            .map(|label| &label.text_style)
+    }
+
+    /// Number of shaped buffers shared by labels showing the same short
+    /// text in the same style
+    #[cfg(feature = "text")]
+    pub fn shared_label_buffers(&self) -> usize {
+        self.buffer_pool.len()
+    }
+
+    #[cfg(feature = "text")]
     pub fn set_label_style(&mut self, element: LabelRef, new_style: TextStyle) {
         self.with_component_mut::<Label>(element.0, |label, ctx| {
-            label.set_style(&mut ctx.root, &mut ctx.font_system, new_style);
+            label.base_style = None;
+            label.set_style(
+                &mut ctx.root,
+                &mut ctx.font_system,
+                &mut ctx.buffer_pool,
+                new_style,
+            );
         });
     }
 
+    /// The label showing the text of a button
+    #[cfg(feature = "text")]
+    fn button_label(&self, button: ButtonRef) -> Option<LabelRef> {
+        let button = self
+            .elements
+            .get(&button.0)?
+            .as_any()
+            .downcast_ref::<Button>()?;
+        Some(LabelRef(button.child_label.raw()))
+    }
+
+    #[cfg(feature = "text")]
+    pub fn set_button_text<S: ToString>(&mut self, button: ButtonRef, new_text: S) {
+        if let Some(label) = self.button_label(button) {
+            self.set_label_text(label, new_text);
+        }
+    }
+
+    #[cfg(feature = "text")]
+    pub fn set_button_style(&mut self, button: ButtonRef, new_style: TextStyle) {
+        if let Some(label) = self.button_label(button) {
+            self.set_label_style(label, new_style);
+        }
+    }
+
     /// Helper to safely downcast and modify a component.
     /// Reduces boilerplate in set_* methods.
     fn with_component_mut<T: FrameElement + 'static>(
@@ -365,7 +1869,12 @@ impl Context {
     ) {
         if let Some(mut frame_element) = self.elements.remove(&capsule_ref) {
             if let Some(component) = frame_element.as_any_mut().downcast_mut::<T>() {
-                op(component, self);
+                // The element must be back into the map even if `op` panics
+                let result = panic::catch_unwind(AssertUnwindSafe(|| op(component, self)));
+                if let Err(payload) = result {
+                    self.elements.insert(capsule_ref, frame_element);
+                    panic::resume_unwind(payload);
+                }
             } else {
                 warn!(
                     "Element type mismatch: Expected {}",
@@ -380,6 +1889,7 @@ impl Context {
     }
 
     /// Creates a new `Button` component with text.
+    #[cfg(feature = "text")]
     pub fn new_button<S: ToString, F>(
         &mut self,
         text: S,
@@ -399,27 +1909,37 @@ impl Context {
         let button_frame = self.root.add_frame_child(parent, None);
         let button_ref = button_frame.get_ref();
 
-        style!(button_frame, &mut self.root, {
-            width: size!(fit),
-            height: size!(fit),
-            padding: pad!(6, 2),
-            margin: margin!(0, 4),
-            border: heka::sizing::Border {
-                size: 2,
-                radius: 5,
-                color: clr!(0x8f8f9dFF),
-            },
-            justify_content: justify!(center),
-            align_items: align!(center),
-            background_color: clr!(0xe9e9edFF),
-            layout: layout!(flex),
-        });
+        if let Some(style) = self
+            .default_style::<Button>()
+            .map(|defaults| defaults.style)
+        {
+            button_frame.update_style(&mut self.root, |s| *s = style);
+        } else {
+            style!(button_frame, &mut self.root, {
+                width: size!(fit),
+                height: size!(fit),
+                padding: pad!(6, 2),
+                margin: margin!(0, 4),
+                border: heka::sizing::Border {
+                    size: 2,
+                    radius: 5,
+                    color: self.theme.border_color,
+                },
+                justify_content: justify!(center),
+                align_items: align!(center),
+                background_color: self.theme.surface_color,
+                layout: layout!(flex),
+            });
+        }
 
-        let label_style = label_style.unwrap_or(TextStyle::default());
-        let label_element = self.new_label(
-            text,
+        let base_style = self
+            .default_text_style::<Button>()
+            .unwrap_or_else(|| self.theme.text_style());
+        let label_element = self.add_label(
+            text.to_string(),
             Some(Element(button_frame.get_ref())),
-            Some(label_style),
+            label_style,
+            base_style,
         );
 
         let button_component = Button {
@@ -435,6 +1955,113 @@ impl Context {
 }
 
 impl Context {
+    /// Creates a new `Link` component. Activating it (click, or Enter when
+    /// focused) opens `url`, unless replaced with `on_link_activate`.
+    #[cfg(feature = "text")]
+    pub fn new_link<S: ToString, U: Into<String>>(
+        &mut self,
+        text: S,
+        url: U,
+        parent_frame: Option<impl ElementRef>,
+        text_style: Option<TextStyle>,
+    ) -> LinkRef {
+        let parent = if let Some(pf) = parent_frame {
+            &Frame::define(pf.raw())
+        } else {
+            &self.root_frame
+        };
+
+        let link_frame = self.root.add_frame_child(parent, None);
+        let link_ref = link_frame.get_ref();
+
+        if let Some(style) = self.default_style::<Link>().map(|defaults| defaults.style) {
+            link_frame.update_style(&mut self.root, |s| *s = style);
+        } else {
+            style!(link_frame, &mut self.root, {
+                width: size!(fit),
+                height: size!(fit),
+                background_color: clr!(transparent),
+                layout: layout!(flex),
+            });
+        }
+
+        let base_style = self
+            .default_text_style::<Link>()
+            .unwrap_or_else(|| TextStyle {
+                color: self.theme.accent_color,
+                ..self.theme.text_style()
+            });
+        let label = self.add_label(
+            text.to_string(),
+            Some(Element(link_ref)),
+            text_style,
+            base_style,
+        );
+
+        let link = Link {
+            frame: link_frame,
+            url: url.into(),
+            child_label: label,
+        };
+        self.elements.insert(link_ref, Box::new(link));
+
+        self.on_click(LinkRef(link_ref), move |ctx, _| ctx.open_link_url(link_ref));
+
+        // Underlined with a pointer cursor while hovered
+        self.on_hover(LinkRef(link_ref), move |ctx, event| {
+            ctx.set_cursor(if event.hovered {
+                winit::window::CursorIcon::Pointer
+            } else {
+                winit::window::CursorIcon::Default
+            });
+
+            let hovered = event.hovered;
+            ctx.update_label_style(label, |style| {
+                if hovered {
+                    style.decoration |= TextDecoration::UNDERLINE;
+                } else {
+                    style.decoration = TextDecoration::NONE;
+                }
+            });
+        });
+
+        LinkRef(link_ref)
+    }
+
+    #[inline]
+    #[cfg(feature = "text")]
+    pub fn get_link_url(&self, element: LinkRef) -> Option<&str> {
+        let link = self.elements.get(&element.raw())?;
+        link.as_any().downcast_ref::<Link>().map(Link::url)
+    }
+
+    /// Replaces what activating the link does (opening its URL by default)
+    #[cfg(feature = "text")]
+    pub fn on_link_activate<F>(&mut self, element: LinkRef, mut callback: F)
+    where
+        F: FnMut(&mut Context, &str) + 'static,
+    {
+        self.on_click(element, move |ctx, _| {
+            let url = ctx.get_link_url(element).unwrap_or_default().to_owned();
+            callback(ctx, &url);
+        });
+    }
+
+    /// Default link activation
+    #[cfg(feature = "text")]
+    fn open_link_url(&mut self, link: heka::CapsuleRef) {
+        let Some(url) = self.get_link_url(LinkRef(link)) else {
+            return;
+        };
+
+        #[cfg(feature = "open-url")]
+        if let Err(e) = elements::open_url(url) {
+            warn!("Unable to open {url}: {e}");
+        }
+        #[cfg(not(feature = "open-url"))]
+        warn!("Unable to open {url}: the `open-url` feature is disabled");
+    }
+
     pub fn on_hover<F>(&mut self, element: impl ElementRef, callback: F)
     where
         F: FnMut(&mut Context, &HoverEvent) + 'static,
@@ -450,73 +2077,389 @@ impl Context {
         self.click_callbacks
             .insert(element.raw(), Box::new(callback));
     }
-}
 
-impl Context {
-    pub fn run(self) -> Result<(), impl std::error::Error> {
-        use winit::event_loop::EventLoop;
-        let _ = env_logger::try_init();
-
-        let event_loop = EventLoop::new().unwrap();
-        let mut application = al::Application::new(&event_loop, self);
-
-        event_loop.run_app(&mut application)
+    /// Called after the user edited the text of an input.
+    /// See `Context::debounce` to react once typing pauses.
+    #[cfg(feature = "text")]
+    pub fn on_text_change<F>(&mut self, element: TextInputRef, callback: F)
+    where
+        F: FnMut(&mut Context, &TextChangeEvent) + 'static,
+    {
+        self.text_change_callbacks
+            .insert(element.0, Box::new(callback));
     }
 
-    #[inline]
-    pub fn is_dirty(&self) -> bool {
-        self.root.is_dirty()
+    #[cfg(feature = "text")]
+    pub(crate) fn emit_text_change(&mut self, input: TextInputRef, text: String) {
+        if let Some(mut callback) = self.text_change_callbacks.remove(&input.0) {
+            let event = TextChangeEvent { input, text };
+            self.guarded_on(input.0, |ctx| callback(ctx, &event));
+            self.text_change_callbacks
+                .entry(input.0)
+                .or_insert(callback);
+        }
     }
 
-    /// Compute inner layout
-    pub fn compute_layout(&mut self) {
-        self.root.compute();
+    /// Called whenever a component wants audible feedback (button click,
+    /// checkbox toggle, form error...). Play the sound of your choice in it.
+    pub fn on_interaction_sound<F>(&mut self, hook: F)
+    where
+        F: FnMut(InteractionSound) + 'static,
+    {
+        self.sound_hook = Some(Box::new(hook));
     }
 
-    /// Resizes the root window.
-    pub(crate) fn resize(&mut self, new_width: u32, new_height: u32) {
-        self.root.resize(new_width, new_height);
+    /// Mutes (or unmutes) interaction sounds without removing the hook.
+    /// Shorthand for changing `Theme::sounds`.
+    pub fn set_interaction_sounds(&mut self, enabled: bool) {
+        self.theme.sounds = enabled;
     }
-}
 
-impl Context {
-    pub(crate) fn click(&mut self, mouse_button: MouseButton, pressed: bool, double_click: bool) {
-        if pressed {
-            self.mouse_pressed = true;
+    pub(crate) fn play_sound(&mut self, sound: InteractionSound) {
+        if !self.theme.sounds {
+            return;
+        }
+        if let Some(hook) = self.sound_hook.as_mut() {
+            hook(sound);
+        }
+    }
+
+    /// Whether an element is a button, which clicks sound
+    #[cfg(feature = "text")]
+    fn is_button(&self, cref: heka::CapsuleRef) -> bool {
+        self.elements
+            .get(&cref)
+            .is_some_and(|element| element.as_any().is::<Button>())
+    }
+
+    /// Buttons come with the `text` feature
+    #[cfg(not(feature = "text"))]
+    fn is_button(&self, _cref: heka::CapsuleRef) -> bool {
+        false
+    }
+
+    /// Called when `button` is released over the element.
+    /// Takes precedence over `on_click` for that button.
+    pub fn on_mouse_button<F>(&mut self, element: impl ElementRef, button: MouseButton, callback: F)
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.mouse_button_callbacks
+            .insert((element.raw(), button), Box::new(callback));
+    }
+
+    /// Shorthand for `on_mouse_button` with the right button
+    pub fn on_right_click<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.on_mouse_button(element, MouseButton::Right, callback);
+    }
+
+    /// Called for every cursor move while the pointer is over the element,
+    /// or captured by it (pressed on it and not released yet).
+    /// Moves are coalesced, so the callback runs at most once per frame.
+    pub fn on_pointer_move<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &PointerMoveEvent) + 'static,
+    {
+        self.pointer_move_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Called when a button is pressed over the element. The element then
+    /// captures the pointer: it gets the drags and the release even once
+    /// the cursor left it.
+    pub fn on_mouse_down<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.mouse_down_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Called for every cursor move while a button pressed on the element
+    /// is held, wherever the cursor is. Coalesced like pointer moves.
+    pub fn on_drag<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &DragEvent) + 'static,
+    {
+        self.drag_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Called when a button pressed on the element is released, wherever
+    /// the cursor is. Before the click, if released over the element.
+    pub fn on_mouse_up<F>(&mut self, element: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        self.mouse_up_callbacks
+            .insert(element.raw(), Box::new(callback));
+    }
+
+    /// Called every time a scroll container moves: scrolled, or brought
+    /// back by a layout after its content shrank. Meant for effects
+    /// following the scroll position (a header shrinking, a parallax
+    /// background) that update other elements right away, on the scroll
+    /// itself rather than on every frame.
+    pub fn on_scroll_offset<F>(&mut self, container: impl ElementRef, callback: F)
+    where
+        F: FnMut(&mut Context, &ScrollOffsetEvent) + 'static,
+    {
+        self.scroll_offset_callbacks
+            .insert(container.raw(), Box::new(callback));
+    }
+
+    /// Sends their new position to the scroll containers that moved
+    pub(crate) fn dispatch_scroll_offsets(&mut self) {
+        let scrolled = self.root.take_scrolled();
+        self.settle_scrolled(&scrolled);
+        self.follow_carousel_pages(&scrolled);
+        if self.scroll_offset_callbacks.is_empty() {
+            return;
+        }
+        self.dispatching(|ctx| {
+            for cref in scrolled {
+                if let Some(mut callback) = ctx.scroll_offset_callbacks.remove(&cref) {
+                    let event = ScrollOffsetEvent {
+                        offset: ctx.root.scroll_offset(cref),
+                        max_offset: ctx.root.max_scroll_offset(cref),
+                    };
+                    ctx.guarded_on(cref, |ctx| callback(ctx, &event));
+                    ctx.scroll_offset_callbacks.entry(cref).or_insert(callback);
+                }
+            }
+        });
+    }
+
+    /// Whether a press on the element makes it capture the pointer
+    fn captures_pointer(&self, cref: &heka::CapsuleRef) -> bool {
+        self.pointer_move_callbacks.contains_key(cref)
+            || self.mouse_down_callbacks.contains_key(cref)
+            || self.drag_callbacks.contains_key(cref)
+            || self.mouse_up_callbacks.contains_key(cref)
+    }
+}
+
+impl Context {
+    pub fn run(self) -> Result<(), impl std::error::Error> {
+        self.run_until_exit().map(|_| ())
+    }
+
+    /// Runs the event loop until the window closes or `exit` is called,
+    /// then hands the context back with its final state.
+    pub fn run_until_exit(mut self) -> Result<Context, winit::error::EventLoopError> {
+        use winit::event_loop::EventLoop;
+        let _ = env_logger::try_init();
+
+        let event_loop = EventLoop::new()?;
+        self.set_waker(event_loop.create_proxy());
+        let mut application = al::Application::new(&event_loop, self);
+
+        event_loop.run_app(&mut application)?;
+        Ok(application.into_context())
+    }
+
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.redraw_requested || self.root.is_dirty()
+    }
+
+    /// Asks for a new frame even though the layout did not change
+    #[inline]
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    /// Compute inner layout
+    pub fn compute_layout(&mut self) {
+        self.redraw_requested = false;
+        #[cfg(feature = "text")]
+        {
+            self.root.compute_with(&mut self.font_system);
+
+            // Form label columns follow the measured label widths,
+            // toolbars and breadcrumbs collapse what does not fit and
+            // labels wrapping a shared text get a buffer of their own
+            let forms_changed = self.align_forms();
+            let rows_changed = self.fit_overflow_rows();
+            let unshared = self.unshare_label_buffers();
+            let targets_changed = self.fit_hit_targets();
+            let popups_moved = self.place_popups();
+            let carousels_fitted = self.fit_carousels();
+            if forms_changed
+                || rows_changed
+                || unshared
+                || targets_changed
+                || popups_moved
+                || carousels_fitted
+            {
+                self.root.compute_with(&mut self.font_system);
+            }
+            // Carousels keep their page in view once its new width is laid out
+            if self.align_carousels() {
+                self.root.compute_with(&mut self.font_system);
+            }
+        }
+        #[cfg(not(feature = "text"))]
+        {
+            self.root.compute();
+            let targets_changed = self.fit_hit_targets();
+            let carousels_fitted = self.fit_carousels();
+            if self.place_popups() || targets_changed || carousels_fitted {
+                self.root.compute();
+            }
+            if self.align_carousels() {
+                self.root.compute();
+            }
+        }
+
+        #[cfg(feature = "charts")]
+        self.update_charts();
+        self.update_gauges();
+        self.dispatch_scroll_offsets();
+
+        #[cfg(all(feature = "debug", feature = "text"))]
+        self.update_inspector();
+    }
+
+    /// Gives the labels the layout has wrap a shared text a buffer of their
+    /// own. Returns whether any label got one.
+    #[cfg(feature = "text")]
+    fn unshare_label_buffers(&mut self) -> bool {
+        let mut changed = false;
+        for element in self.elements.values_mut() {
+            if let Some(label) = element.as_any_mut().downcast_mut::<Label>() {
+                changed |= label.unshare(&mut self.root, &mut self.buffer_pool);
+            }
+        }
+        changed
+    }
+
+    /// Keeps form label columns in sync with their labels.
+    /// Returns whether any column changed.
+    #[cfg(feature = "text")]
+    fn align_forms(&mut self) -> bool {
+        let mut changed = false;
+        for element in self.elements.values_mut() {
+            if let Some(form) = element.as_any_mut().downcast_mut::<Form>() {
+                changed |= form.align(&mut self.root);
+            }
+        }
+        changed
+    }
+
+    /// Resizes the root window, `new_width` and `new_height` in pixels
+    pub(crate) fn resize(&mut self, new_width: u32, new_height: u32) {
+        self.window_size = (new_width, new_height);
+        self.root.resize(
+            (new_width as f32 / self.ui_scale).round() as u32,
+            (new_height as f32 / self.ui_scale).round() as u32,
+        );
+    }
+}
+
+impl Context {
+    pub(crate) fn click(&mut self, mouse_button: MouseButton, pressed: bool, double_click: bool) {
+        if pressed {
+            self.dismiss_popups();
+        }
+        if self.drag_pan_button(mouse_button, pressed) {
+            self.trace_taken("drag pan");
+            return;
+        }
+        let on_scrollbar = match pressed {
+            true => mouse_button == MouseButton::Left && self.scrollbar_press(),
+            false => self.scrollbar_release(),
+        };
+        if on_scrollbar {
+            self.trace_taken("scrollbar");
+            return;
+        }
+        if pressed {
+            // Moves before the press are no drag
+            self.dispatch_pointer_move();
+            self.mouse_pressed = true;
+            self.press = Some((self.mouse_pos, mouse_button));
+            self.pointer_capture = self
+                .hit_candidates()
+                .into_iter()
+                .find(|cref| self.captures_pointer(cref));
+
+            if let Some(cref) = self.pointer_capture
+                && let Some(mut callback) = self.mouse_down_callbacks.remove(&cref)
+            {
+                let event = ClickEvent {
+                    pos: self.mouse_pos,
+                    local_pos: self.local_mouse_pos(cref),
+                    button: mouse_button,
+                    double_click,
+                };
+                self.guarded_on(cref, |ctx| callback(ctx, &event));
+                self.mouse_down_callbacks.entry(cref).or_insert(callback);
+            }
+            self.drag_press(mouse_button);
+            self.carousel_press(mouse_button);
             return;
         }
 
         if self.mouse_pressed && !pressed {
+            // The last moves are dragged before the release
+            self.dispatch_pointer_move();
             self.mouse_pressed = false;
-            let hits = self.root.hit_test(
-                self.mouse_pos.x.ceil() as i32,
-                self.mouse_pos.y.ceil() as i32,
-            );
-
-            if hits.is_empty() {
+            self.press = None;
+
+            if let Some(cref) = self.pointer_capture.take()
+                && let Some(mut callback) = self.mouse_up_callbacks.remove(&cref)
+            {
+                let event = ClickEvent {
+                    pos: self.mouse_pos,
+                    local_pos: self.local_mouse_pos(cref),
+                    button: mouse_button,
+                    double_click,
+                };
+                self.guarded_on(cref, |ctx| callback(ctx, &event));
+                self.mouse_up_callbacks.entry(cref).or_insert(callback);
+            }
+            if self.drag_release() {
+                self.trace_taken("drag and drop");
+                return;
+            }
+            if self.carousel_release() {
+                self.trace_taken("carousel");
                 return;
             }
 
-            let mut hit_candidates: Vec<(heka::CapsuleRef, u32)> = hits
-                .into_iter()
-                .filter_map(|cref| {
-                    let style = self.root.get_style(cref)?;
-                    Some((cref, style.z_index))
-                })
-                .collect();
+            for cref in self.hit_candidates() {
+                let event = ClickEvent {
+                    pos: self.mouse_pos,
+                    local_pos: self.local_mouse_pos(cref),
+                    button: mouse_button,
+                    double_click,
+                };
+
+                if self.is_button(cref)
+                    && (self.click_callbacks.contains_key(&cref)
+                        || self
+                            .mouse_button_callbacks
+                            .contains_key(&(cref, mouse_button)))
+                {
+                    self.play_sound(InteractionSound::Click);
+                }
 
-            hit_candidates.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+                // A callback for this very button wins over the generic one
+                let key = (cref, mouse_button);
+                if let Some(mut callback) = self.mouse_button_callbacks.remove(&key) {
+                    self.guarded_on(cref, |ctx| callback(ctx, &event));
+                    self.mouse_button_callbacks.entry(key).or_insert(callback);
 
-            let event = ClickEvent {
-                pos: self.mouse_pos,
-                button: mouse_button,
-                double_click,
-            };
+                    return;
+                }
 
-            for (cref, _) in hit_candidates {
                 if let Some(mut callback) = self.click_callbacks.remove(&cref) {
-                    callback(self, &event);
-                    self.click_callbacks.insert(cref, callback);
+                    self.guarded_on(cref, |ctx| callback(ctx, &event));
+                    self.click_callbacks.entry(cref).or_insert(callback);
 
                     return;
                 }
@@ -524,42 +2467,52 @@ impl Context {
         }
     }
 
-    pub(crate) fn update_hover(&mut self) {
-        let hits = self.root.hit_test(
-            self.mouse_pos.x.ceil() as i32,
-            self.mouse_pos.y.ceil() as i32,
-        );
+    /// Elements under the cursor, topmost first
+    fn hit_candidates(&self) -> Vec<heka::CapsuleRef> {
+        self.hit_candidates_at(self.mouse_pos)
+    }
 
-        let mut hit_candidates: Vec<(heka::CapsuleRef, u32)> = hits
+    /// Interactive elements at a window position, topmost first.
+    /// Parts cut by scroll containers are not hit.
+    fn hit_candidates_at(&self, pos: PhysicalPosition<f64>) -> Vec<heka::CapsuleRef> {
+        self.root
+            .hit_test(pos.x.ceil() as i32, pos.y.ceil() as i32)
             .into_iter()
-            .filter_map(|cref| {
-                let style = self.root.get_style(cref)?;
-                Some((cref, style.z_index))
-            })
-            .collect();
-
-        hit_candidates.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+            .filter(|cref| !self.non_interactive.contains(cref) && !self.is_disabled(*cref))
+            .collect()
+    }
 
+    pub(crate) fn update_hover(&mut self) {
         // Find the topmost candidate that has a hover callback
-        let best_cref = hit_candidates
-            .iter()
-            .find(|(cref, _)| self.hover_callbacks.contains_key(cref))
-            .map(|(cref, _)| *cref);
+        let best_cref = self
+            .hit_candidates()
+            .into_iter()
+            .find(|cref| self.hover_callbacks.contains_key(cref));
 
         if best_cref != self.hovered_element {
             // Leave previous
             if let Some(prev_cref) = self.hovered_element {
                 if let Some(mut callback) = self.hover_callbacks.remove(&prev_cref) {
-                    callback(self, &HoverEvent { hovered: false });
-                    self.hover_callbacks.insert(prev_cref, callback);
+                    let event = HoverEvent {
+                        hovered: false,
+                        pos: self.mouse_pos,
+                        local_pos: self.local_mouse_pos(prev_cref),
+                    };
+                    self.guarded_on(prev_cref, |ctx| callback(ctx, &event));
+                    self.hover_callbacks.entry(prev_cref).or_insert(callback);
                 }
             }
 
             // Enter new
             if let Some(new_cref) = best_cref {
                 if let Some(mut callback) = self.hover_callbacks.remove(&new_cref) {
-                    callback(self, &HoverEvent { hovered: true });
-                    self.hover_callbacks.insert(new_cref, callback);
+                    let event = HoverEvent {
+                        hovered: true,
+                        pos: self.mouse_pos,
+                        local_pos: self.local_mouse_pos(new_cref),
+                    };
+                    self.guarded_on(new_cref, |ctx| callback(ctx, &event));
+                    self.hover_callbacks.entry(new_cref).or_insert(callback);
                 }
             }
 
@@ -567,58 +2520,887 @@ impl Context {
         }
     }
 
+    /// Sends the pending cursor move, if any, to the captured element
+    /// or to the topmost element listening for pointer moves.
+    pub(crate) fn dispatch_pointer_move(&mut self) {
+        if !std::mem::take(&mut self.pointer_moved) {
+            return;
+        }
+
+        let delta = PhysicalPosition::new(
+            self.mouse_pos.x - self.last_pointer_pos.x,
+            self.mouse_pos.y - self.last_pointer_pos.y,
+        );
+        self.last_pointer_pos = self.mouse_pos;
+
+        let (target, captured) = match self.pointer_capture {
+            Some(cref) => (Some(cref), true),
+            None => (
+                self.hit_candidates()
+                    .into_iter()
+                    .find(|cref| self.pointer_move_callbacks.contains_key(cref)),
+                false,
+            ),
+        };
+
+        let Some(cref) = target else {
+            return;
+        };
+        self.dispatching(|ctx| {
+            if let Some(mut callback) = ctx.pointer_move_callbacks.remove(&cref) {
+                let event = PointerMoveEvent {
+                    pos: ctx.mouse_pos,
+                    local_pos: ctx.local_mouse_pos(cref),
+                    delta,
+                    captured,
+                };
+                ctx.guarded_on(cref, |ctx| callback(ctx, &event));
+                ctx.pointer_move_callbacks.entry(cref).or_insert(callback);
+            }
+
+            let moved = delta.x != 0.0 || delta.y != 0.0;
+            if captured
+                && moved
+                && let Some((start, button)) = ctx.press
+                && let Some(mut callback) = ctx.drag_callbacks.remove(&cref)
+            {
+                let event = DragEvent {
+                    start,
+                    current: ctx.mouse_pos,
+                    delta,
+                    button,
+                };
+                ctx.guarded_on(cref, |ctx| callback(ctx, &event));
+                ctx.drag_callbacks.entry(cref).or_insert(callback);
+            }
+        });
+    }
+
+    /// Removes an element along with all its children and their callbacks
+    pub(crate) fn remove_element_tree(&mut self, element: heka::CapsuleRef) {
+        let mut stack = vec![element];
+        while let Some(cref) = stack.pop() {
+            if let Some(capsule) = self.root.get_capsule(cref) {
+                stack.extend_from_slice(capsule.children());
+            }
+
+            #[cfg(feature = "text")]
+            if let Some(element) = self.elements.remove(&cref) {
+                if let Some(canvas) = element.as_any().downcast_ref::<Canvas>() {
+                    for buffer_ref in canvas.text_buffers.values() {
+                        self.root.remove_binding(*buffer_ref);
+                    }
+                }
+                // heka frees the data of the frame, not a buffer other
+                // labels still show
+                if let Some(label) = element.as_any().downcast_ref::<Label>()
+                    && label.shared.is_some()
+                {
+                    self.root.rebind_data(cref, None);
+                    self.buffer_pool.release(&mut self.root, label.buffer_ref);
+                }
+            }
+            #[cfg(not(feature = "text"))]
+            self.elements.remove(&cref);
+            self.click_callbacks.remove(&cref);
+            self.hover_callbacks.remove(&cref);
+            self.pointer_move_callbacks.remove(&cref);
+            self.mouse_down_callbacks.remove(&cref);
+            self.mouse_up_callbacks.remove(&cref);
+            self.drag_callbacks.remove(&cref);
+            self.forget_drag_element(cref);
+            self.scroll_callbacks.remove(&cref);
+            self.scroll_offset_callbacks.remove(&cref);
+            self.pinch_callbacks.remove(&cref);
+            self.gestures.pan_targets.remove(&cref);
+            self.forget_settle(cref);
+            self.forget_carousel(cref);
+            #[cfg(feature = "text")]
+            self.error_callbacks.remove(&cref);
+            #[cfg(feature = "text")]
+            self.text_defaults.remove(&cref);
+            #[cfg(feature = "text")]
+            self.event_logs.remove(&cref);
+            self.painters.remove(&cref);
+            self.keyboard_callbacks.remove(&cref);
+            #[cfg(feature = "text")]
+            self.text_change_callbacks.remove(&cref);
+            self.non_interactive.remove(&cref);
+            self.disabled.remove(&cref);
+            #[cfg(feature = "text")]
+            self.drop_decoration_texts(cref);
+            self.mouse_button_callbacks.retain(|(c, _), _| *c != cref);
+            self.release_focus(Element(cref));
+
+            for slot in [
+                &mut self.hovered_element,
+                &mut self.focused_element,
+                &mut self.pointer_capture,
+            ] {
+                if *slot == Some(cref) {
+                    *slot = None;
+                }
+            }
+        }
+
+        self.root.remove_frame(element);
+    }
+
+    /// Cursor position relative to the content box of an element
+    fn local_mouse_pos(&self, cref: heka::CapsuleRef) -> PhysicalPosition<f64> {
+        self.local_pos(cref, self.mouse_pos)
+    }
+
+    /// A window position relative to the content box of an element
+    /// (to the scrolled content, for scroll containers)
+    fn local_pos(
+        &self,
+        cref: heka::CapsuleRef,
+        pos: PhysicalPosition<f64>,
+    ) -> PhysicalPosition<f64> {
+        let (x, y) = self
+            .root
+            .to_local(cref, (pos.x as f32, pos.y as f32))
+            .unwrap_or((pos.x as f32, pos.y as f32));
+        PhysicalPosition::new(x as f64, y as f64)
+    }
+
+    /// Converts a window position to one relative to the content box of an
+    /// element, see `heka::Root::to_local`
+    pub fn to_local(
+        &self,
+        element: impl ElementRef,
+        pos: PhysicalPosition<f64>,
+    ) -> Option<PhysicalPosition<f64>> {
+        let (x, y) = self
+            .root
+            .to_local(element.raw(), (pos.x as f32, pos.y as f32))?;
+        Some(PhysicalPosition::new(x as f64, y as f64))
+    }
+
+    /// Converts a position relative to the content box of an element back
+    /// to a window position
+    pub fn to_global(
+        &self,
+        element: impl ElementRef,
+        pos: PhysicalPosition<f64>,
+    ) -> Option<PhysicalPosition<f64>> {
+        let (x, y) = self
+            .root
+            .to_global(element.raw(), (pos.x as f32, pos.y as f32))?;
+        Some(PhysicalPosition::new(x as f64, y as f64))
+    }
+
+    /// Finds back an element from `CapsuleRef::to_raw`, for tooling living
+    /// outside the process. `None` once the element was removed.
+    pub fn element_from_raw(&self, raw: u64) -> Option<Element> {
+        let cref = heka::CapsuleRef::from_raw(raw, &self.root)?;
+        self.elements.contains_key(&cref).then_some(Element(cref))
+    }
+
+    /// The topmost element at a window position, following layers,
+    /// z-indices and scroll clipping. Non-interactive elements are skipped.
+    pub fn element_at(&self, x: f64, y: f64) -> Option<(Element, PointInElement)> {
+        let pos = PhysicalPosition::new(x, y);
+        let cref = self.hit_candidates_at(pos).into_iter().next()?;
+        Some((
+            Element(cref),
+            PointInElement {
+                pos,
+                local_pos: self.local_pos(cref, pos),
+            },
+        ))
+    }
+
+    /// Non-interactive elements let the pointer through to what is below
+    /// them: they get no clicks, hovers or pointer moves.
+    /// Their children are not affected, unlike with `Style::pointer_events`.
+    pub fn set_interactive(&mut self, element: impl ElementRef, interactive: bool) {
+        if interactive {
+            self.non_interactive.remove(&element.raw());
+        } else {
+            self.non_interactive.insert(element.raw());
+        }
+    }
+
+    #[inline]
+    pub fn is_interactive(&self, element: impl ElementRef) -> bool {
+        !self.non_interactive.contains(&element.raw())
+    }
+
+    /// Replaces the style of an element's frame. Built-in elements may
+    /// change parts of it afterwards (a checkbox its background...).
+    pub fn set_style(&mut self, element: impl ElementRef, style: Style) {
+        Frame::define(element.raw()).update_style(&mut self.root, |current| *current = style);
+    }
+
+    /// Lets `provider` measure the content of an element sized with `Fit`,
+    /// in place of its children: custom widgets drawing on a canvas size
+    /// themselves this way, as labels and images do. The `context` it
+    /// gets is the `cosmic_text::FontSystem` with the `text` feature, `()`
+    /// without. Call `invalidate_content` when what it measures changes.
+    pub fn set_content_provider(
+        &mut self,
+        element: impl ElementRef,
+        provider: impl heka::content::ContentProvider,
+    ) {
+        self.root.set_content_provider(element.raw(), provider);
+        self.request_redraw();
+    }
+
+    /// Measures the element from its children again.
+    /// Returns false if it had no content provider.
+    pub fn remove_content_provider(&mut self, element: impl ElementRef) -> bool {
+        let removed = self.root.remove_content_provider(element.raw());
+        if removed {
+            self.request_redraw();
+        }
+        removed
+    }
+
+    /// Measures the content of an element again on the next layout
+    pub fn invalidate_content(&mut self, element: impl ElementRef) {
+        self.root.invalidate_content(element.raw());
+        self.request_redraw();
+    }
+
+    /// Lets the pointer through an element and all its children when
+    /// `PointerEvents::None`, see `Style::pointer_events`
+    pub fn set_pointer_events(
+        &mut self,
+        element: impl ElementRef,
+        pointer_events: heka::position::PointerEvents,
+    ) {
+        Frame::define(element.raw()).update_style(&mut self.root, |style| {
+            style.pointer_events = pointer_events;
+        });
+    }
+
     pub(crate) fn key_event(&mut self, event: KeyEvent) {
+        use winit::keyboard::{Key, NamedKey};
+
+        if event.logical_key == Key::Named(NamedKey::Shift) {
+            self.shift_held = event.pressed;
+        }
+        if event.logical_key == Key::Named(NamedKey::Space) {
+            self.drag_pan_key(event.pressed);
+        }
+        if event.pressed && event.logical_key == Key::Named(NamedKey::Escape) {
+            if self.cancel_drag() {
+                self.trace_taken("drag and drop");
+                return;
+            }
+            if self.close_last_popup() {
+                self.trace_taken("popups");
+                return;
+            }
+        }
+
+        if self.spatial_nav && event.pressed && self.navigate(&event.logical_key, event.repeat) {
+            self.trace_taken("spatial navigation");
+            return;
+        }
+
+        // Tab goes through the focusable elements, Shift+Tab back
+        if event.pressed
+            && event.logical_key == Key::Named(NamedKey::Tab)
+            && self.tab_focus(self.shift_held)
+        {
+            self.trace_taken("tab navigation");
+            return;
+        }
+
+        // Focused links follow on Enter, with or without spatial navigation
+        #[cfg(feature = "text")]
+        if event.pressed
+            && !event.repeat
+            && event.logical_key == Key::Named(NamedKey::Enter)
+            && let Some(focused) = self.focused_element
+            && self
+                .elements
+                .get(&focused)
+                .is_some_and(|element| element.as_any().is::<Link>())
+        {
+            self.activate(focused);
+            return;
+        }
+
         if let Some(focused) = self.focused_element {
             if let Some(mut callback) = self.keyboard_callbacks.remove(&focused) {
-                callback(self, &event);
-                self.keyboard_callbacks.insert(focused, callback);
+                self.guarded_on(focused, |ctx| callback(ctx, &event));
+                self.keyboard_callbacks.entry(focused).or_insert(callback);
             }
         }
     }
 
+    /// Scrolls the scroll containers holding `element` just enough for it
+    /// to be visible, with `margin` pixels around it when there is room.
+    pub fn scroll_into_view(&mut self, element: impl ElementRef, margin: u32) {
+        let cref = element.raw();
+        let Some(mut rect) = self.root.get_rect(cref) else {
+            return;
+        };
+        let margin = margin as i32;
+
+        // Distance to move the content so `start..end` fits in `view_start..view_end`
+        let shift = |start: i32, end: i32, view_start: i32, view_end: i32| {
+            if start - margin < view_start {
+                start - margin - view_start
+            } else if end + margin > view_end {
+                // Never past the start of the element
+                (end + margin - view_end).min(start - margin - view_start)
+            } else {
+                0
+            }
+        };
+
+        let mut current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        while let Some(parent) = current {
+            current = self.root.get_capsule(parent).and_then(|c| c.parent_ref);
+
+            let scrolls = self
+                .root
+                .get_style(parent)
+                .is_some_and(|style| style.overflow.scrolls());
+            let Some(view) = self.root.get_content_rect(parent).filter(|_| scrolls) else {
+                continue;
+            };
+
+            let dx = shift(
+                rect.x,
+                rect.x + rect.w as i32,
+                view.x,
+                view.x + view.w as i32,
+            );
+            let dy = shift(
+                rect.y,
+                rect.y + rect.h as i32,
+                view.y,
+                view.y + view.h as i32,
+            );
+            let (old_x, old_y) = self.root.scroll_offset(parent);
+            self.root
+                .set_scroll_offset(parent, old_x + dx as f32, old_y + dy as f32);
+
+            // Outer containers see the element where this one moved it
+            let (new_x, new_y) = self.root.scroll_offset(parent);
+            rect.x -= (new_x - old_x).round() as i32;
+            rect.y -= (new_y - old_y).round() as i32;
+        }
+        self.dispatch_scroll_offsets();
+    }
+
     pub fn set_focus(&mut self, element: impl ElementRef) {
-        self.focused_element = Some(element.raw());
+        self.focus_visible = false;
+        self.focus(element.raw());
+    }
+
+    /// Element receiving the keys
+    #[inline]
+    pub fn focused(&self) -> Option<Element> {
+        self.focused_element.map(Element)
+    }
+
+    /// Focuses `cref`, unless a focus scope keeps it out
+    fn focus(&mut self, cref: heka::CapsuleRef) {
+        if !self.in_focus_scope(cref) {
+            return;
+        }
+        self.focused_element = Some(cref);
+        #[cfg(feature = "text")]
+        self.update_caret();
+    }
+
+    /// Enables arrow-key navigation between focusable elements (the ones
+    /// listening to clicks or keys), based on their on-screen position.
+    /// Enter and Space then activate the focused element, unless it
+    /// handles keys itself.
+    pub fn enable_spatial_nav(&mut self, enabled: bool) {
+        self.spatial_nav = enabled;
+    }
+
+    /// Focusable elements with their on-screen rectangles
+    fn focusables(&self) -> Vec<(heka::CapsuleRef, heka::geometry::Rect)> {
+        let mut crefs: Vec<heka::CapsuleRef> = self
+            .click_callbacks
+            .keys()
+            .chain(self.keyboard_callbacks.keys())
+            .chain(self.mouse_button_callbacks.keys().map(|(cref, _)| cref))
+            .copied()
+            .collect();
+        crefs.sort();
+        crefs.dedup();
+
+        crefs
+            .into_iter()
+            .filter(|cref| self.in_focus_scope(*cref) && !self.is_disabled(*cref))
+            .filter_map(|cref| Some((cref, self.root.get_rect(cref)?)))
+            .filter(|(_, rect)| !rect.is_empty())
+            .collect()
+    }
+
+    /// Handles a spatial navigation key. Returns whether it was consumed.
+    /// Held arrow keys keep moving the focus, held Enter or Space only
+    /// activate the focused element once.
+    fn navigate(&mut self, key: &winit::keyboard::Key, repeat: bool) -> bool {
+        use winit::keyboard::{Key, NamedKey};
+
+        if let Some(direction) = nav::NavDirection::from_key(key) {
+            // Left and right move the cursor of a focused text input
+            #[cfg(feature = "text")]
+            if matches!(
+                direction,
+                nav::NavDirection::Left | nav::NavDirection::Right
+            ) && self
+                .focused_element
+                .and_then(|cref| self.elements.get(&cref))
+                .is_some_and(|element| element.as_any().is::<TextInput>())
+            {
+                return false;
+            }
+
+            let candidates = self.focusables();
+            let current = self
+                .focused_element
+                .and_then(|cref| candidates.iter().find(|(c, _)| *c == cref).copied());
+
+            let next = match current {
+                Some((cref, rect)) => nav::nearest(
+                    rect,
+                    direction,
+                    candidates.into_iter().filter(|(c, _)| *c != cref),
+                ),
+                // Nothing focused yet: start from the top-left element
+                None => candidates
+                    .into_iter()
+                    .min_by_key(|(cref, rect)| (rect.y, rect.x, *cref))
+                    .map(|(cref, _)| cref),
+            };
+
+            if let Some(next) = next {
+                self.focus_visible = true;
+                self.focus(next);
+                self.scroll_into_view(Element(next), FOCUS_SCROLL_MARGIN);
+            }
+            return true;
+        }
+
+        let activate = matches!(key, Key::Named(NamedKey::Enter | NamedKey::Space));
+        match self.focused_element {
+            Some(focused) if activate && !self.keyboard_callbacks.contains_key(&focused) => {
+                if !repeat {
+                    self.activate(focused);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the click callback of an element as if it was clicked in its center
+    fn activate(&mut self, cref: heka::CapsuleRef) {
+        let Some(rect) = self.root.get_rect(cref) else {
+            return;
+        };
+        let (cx, cy) = rect.center();
+        let pos = PhysicalPosition::new(cx as f64, cy as f64);
+        let content = self.root.get_content_rect(cref).unwrap_or(rect);
+
+        let event = ClickEvent {
+            pos,
+            local_pos: PhysicalPosition::new(pos.x - content.x as f64, pos.y - content.y as f64),
+            button: MouseButton::Left,
+            double_click: false,
+        };
+
+        if self.is_button(cref) {
+            self.play_sound(InteractionSound::Click);
+        }
+
+        let key = (cref, MouseButton::Left);
+        if let Some(mut callback) = self.mouse_button_callbacks.remove(&key) {
+            self.guarded_on(cref, |ctx| callback(ctx, &event));
+            self.mouse_button_callbacks.entry(key).or_insert(callback);
+        } else if let Some(mut callback) = self.click_callbacks.remove(&cref) {
+            self.guarded_on(cref, |ctx| callback(ctx, &event));
+            self.click_callbacks.entry(cref).or_insert(callback);
+        }
+    }
+}
+
+impl Context {
+    /// Creates a new `Canvas` component, drawing a retained scene of shapes.
+    pub fn new_canvas(&mut self, parent_frame: Option<impl ElementRef>, style: Style) -> CanvasRef {
+        let parent = if let Some(pf) = parent_frame {
+            &Frame::define(pf.raw())
+        } else {
+            &self.root_frame
+        };
+
+        let canvas_frame = self.root.add_frame_child(parent, None);
+        canvas_frame.update_style(&mut self.root, |s| {
+            *s = style;
+        });
+
+        self.elements
+            .insert(canvas_frame.get_ref(), Box::new(Canvas::new(canvas_frame)));
+        CanvasRef(canvas_frame.get_ref())
+    }
+
+    /// Creates an image from a PNG file, its bytes or decoded pixels.
+    /// It is as large as its pixels unless `style` sizes it, given one side
+    /// only the other keeps the aspect ratio.
+    pub fn new_image(
+        &mut self,
+        source: impl Into<ImageSource>,
+        parent_frame: Option<impl ElementRef>,
+        style: Style,
+    ) -> Result<ImageRef, ImageError> {
+        let data = source.into().load()?;
+        let parent = if let Some(pf) = parent_frame {
+            &Frame::define(pf.raw())
+        } else {
+            &self.root_frame
+        };
+
+        let image = Image::new(&mut self.root, parent, data, style);
+        let image_ref = image.frame.get_ref();
+        self.elements.insert(image_ref, Box::new(image));
+        Ok(ImageRef(image_ref))
+    }
+
+    pub fn get_image(&self, element: ImageRef) -> Option<&Image> {
+        let image = self.elements.get(&element.raw())?;
+        image.as_any().downcast_ref::<Image>()
+    }
+
+    /// Multiplies the pixels of an image, white (the default) shows them
+    /// as they are
+    pub fn set_image_tint(&mut self, element: ImageRef, tint: Color) {
+        self.with_component_mut::<Image>(element.raw(), |image, ctx| {
+            image.tint = tint;
+            ctx.request_redraw();
+        });
+    }
+
+    pub fn get_canvas(&self, element: CanvasRef) -> Option<&Canvas> {
+        let canvas = self.elements.get(&element.raw())?;
+        canvas.as_any().downcast_ref::<Canvas>()
+    }
+
+    /// Mutates the scene of a canvas. Only the changed area is repainted.
+    pub fn update_canvas<R>(
+        &mut self,
+        element: CanvasRef,
+        op: impl FnOnce(&mut Scene) -> R,
+    ) -> Option<R> {
+        let mut result = None;
+        self.with_component_mut(element.raw(), |canvas: &mut Canvas, ctx| {
+            result = Some(op(&mut canvas.scene));
+            ctx.sync_canvas(canvas, false);
+        });
+        result
+    }
+
+    pub fn set_canvas_transform(&mut self, element: CanvasRef, transform: CanvasTransform) {
+        self.with_component_mut(element.raw(), |canvas: &mut Canvas, ctx| {
+            if canvas.transform == transform {
+                return;
+            }
+            let rezoomed = canvas.transform.zoom != transform.zoom;
+            canvas.transform = transform;
+
+            if let Some(content) = ctx.root.get_content_rect(element.raw()) {
+                ctx.root.add_damage(content);
+            }
+            ctx.sync_canvas(canvas, rezoomed);
+        });
+    }
+
+    /// Moves the scene by (dx, dy) pixels
+    pub fn pan_canvas(&mut self, element: CanvasRef, dx: f32, dy: f32) {
+        let Some(mut transform) = self.get_canvas(element).map(Canvas::transform) else {
+            return;
+        };
+        transform.pan[0] += dx;
+        transform.pan[1] += dy;
+        self.set_canvas_transform(element, transform);
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the `anchor` point
+    /// (relative to the canvas content box) in place.
+    pub fn zoom_canvas(&mut self, element: CanvasRef, factor: f32, anchor: [f32; 2]) {
+        let Some(mut transform) = self.get_canvas(element).map(Canvas::transform) else {
+            return;
+        };
+        if factor <= 0.0 {
+            return;
+        }
+        transform.zoom *= factor;
+        transform.pan = [
+            anchor[0] - (anchor[0] - transform.pan[0]) * factor,
+            anchor[1] - (anchor[1] - transform.pan[1]) * factor,
+        ];
+        self.set_canvas_transform(element, transform);
+    }
+
+    /// Called when a shape of the canvas is clicked, with the topmost shape
+    /// under the cursor. Replaces any `on_click` of the canvas.
+    pub fn on_shape_click<F>(&mut self, element: CanvasRef, mut callback: F)
+    where
+        F: FnMut(&mut Context, &ShapeEvent) + 'static,
+    {
+        self.on_click(element, move |ctx, click| {
+            let Some(canvas) = ctx.get_canvas(element) else {
+                return;
+            };
+            let local = [click.local_pos.x as f32, click.local_pos.y as f32];
+            let Some(shape) = canvas.pick(local) else {
+                return;
+            };
+            let event = ShapeEvent {
+                shape,
+                scene_pos: canvas.transform.to_scene(local),
+                click: *click,
+            };
+            callback(ctx, &event);
+        });
+    }
+
+    /// Brings text buffers in line with the scene and repaints what changed.
+    /// `reshape_all` reshapes every text, after a zoom change.
+    #[cfg_attr(not(feature = "text"), allow(unused_variables))]
+    fn sync_canvas(&mut self, canvas: &mut Canvas, reshape_all: bool) {
+        #[cfg(feature = "text")]
+        self.sync_canvas_texts(canvas, reshape_all);
+
+        if let Some(area) = canvas.scene.damage.take()
+            && let Some(content) = self.root.get_content_rect(canvas.frame.get_ref())
+            && let Some(damage) = canvas.window_rect(content, area).intersection(&content)
+        {
+            self.root.add_damage(damage);
+        }
+        self.request_redraw();
+    }
+
+    /// Shapes the texts of a canvas that changed (all of them with
+    /// `reshape_all`) and frees the ones removed
+    #[cfg(feature = "text")]
+    fn sync_canvas_texts(&mut self, canvas: &mut Canvas, reshape_all: bool) {
+        use cosmic_text::{Attrs, Buffer, Shaping};
+
+        for id in std::mem::take(&mut canvas.scene.removed) {
+            if let Some(buffer_ref) = canvas.text_buffers.remove(&id) {
+                self.root.remove_binding(buffer_ref);
+            }
+        }
+
+        let mut changed = std::mem::take(&mut canvas.scene.changed);
+        if reshape_all {
+            changed.extend(canvas.text_buffers.keys());
+            changed.sort();
+            changed.dedup();
+        }
+
+        let zoom = canvas.transform.zoom;
+        for id in changed {
+            let Some(Shape::Text { text, style, .. }) = canvas.scene.get(id) else {
+                // No longer a text
+                if let Some(buffer_ref) = canvas.text_buffers.remove(&id) {
+                    self.root.remove_binding(buffer_ref);
+                }
+                continue;
+            };
+
+            let zoomed = TextStyle {
+                font_size: style.font_size * zoom,
+                line_height: match style.line_height {
+                    TextHeight::Fixed(h) => TextHeight::Fixed(h * zoom),
+                    TextHeight::Auto => TextHeight::Auto,
+                },
+                ..style.clone()
+            };
+
+            let buffer_ref = *canvas.text_buffers.entry(id).or_insert_with(|| {
+                let buffer = Buffer::new(&mut self.font_system, zoomed.as_cosmic_metrics());
+                self.root.set_binding(buffer)
+            });
+            let Some(buffer) = self.root.get_binding_mut::<Buffer>(buffer_ref) else {
+                continue;
+            };
+
+            buffer.set_metrics(&mut self.font_system, zoomed.as_cosmic_metrics());
+            buffer.set_size(&mut self.font_system, None, None);
+            buffer.set_text(
+                &mut self.font_system,
+                text,
+                &Attrs {
+                    family: zoomed.font_family.as_family(),
+                    ..zoomed.as_cosmic_attrs()
+                },
+                Shaping::Advanced,
+                Some(zoomed.align),
+            );
+            buffer.shape_until_scroll(&mut self.font_system, true);
+
+            let width = buffer
+                .layout_runs()
+                .map(|run| run.line_w)
+                .fold(0.0, f32::max);
+            let height = buffer
+                .layout_runs()
+                .last()
+                .map(|run| run.line_top + run.line_height)
+                .unwrap_or(0.0);
+
+            canvas
+                .scene
+                .text_sizes
+                .insert(id, [width / zoom, height / zoom]);
+            if let Some(bounds) = canvas.scene.bounds(id) {
+                canvas.scene.add_damage(bounds);
+            }
+        }
     }
 }
 
 impl Context {
     pub fn render(&self) -> Vec<cmd::DrawCommand> {
-        // Tuple: (Z-Index, Priority, CapsuleRef, Command)
-        // Priority: 0 for Rects, 1 for Text. Ensures Text is always ON TOP of Rects for same Z.
+        // Elements out of the window are not drawn
+        let (width, height) = self.root.size();
+        let visible = self
+            .root
+            .frames_in(heka::geometry::Rect::new(0, 0, width, height));
+
+        let (commands, skipped) = self.draw_commands(&visible);
+        self.draw_stats.set(DrawStats {
+            emitted: commands.len(),
+            skipped,
+        });
+        commands
+    }
+
+    /// Commands drawing `frames`, in drawing order, and how many were left
+    /// out for being invisible
+    pub(crate) fn draw_commands(
+        &self,
+        frames: &[heka::CapsuleRef],
+    ) -> (Vec<cmd::DrawCommand>, usize) {
+        // Tuple: ((Layer order, Z-Index), Priority, CapsuleRef, Command)
+        // Layer order: Layers stack above each other whatever their z-indices are.
+        // Priority: 0 for Rects, 1 for Text, 2 for scrollbars. Ensures Text is always ON TOP of Rects for same Z.
         // CapsuleRef: Used as a stable tie-breaker to prevent HashMap-induced flickering.
 
-        let mut commands = Vec::with_capacity(self.elements.len());
+        let mut commands = Vec::with_capacity(frames.len());
+        let mut clips = HashMap::new();
+        let mut disabled = std::collections::HashSet::new();
 
-        for (capsule_ref, element) in &self.elements {
+        for capsule_ref in frames {
+            let Some(element) = self.elements.get(capsule_ref) else {
+                continue;
+            };
             // Get the computed layout and style
-            if let (Some(space), Some(style)) = (
-                self.root.get_space(*capsule_ref),
+            if let (Some(rect), Some(style)) = (
+                self.root.get_rect(*capsule_ref),
                 self.root.get_style(*capsule_ref),
             ) {
+                // Elements inside scroll containers (or other clipping parents)
+                // are cut to the visible area
+                if let Some(clip) = self.root.clip_rect(*capsule_ref) {
+                    if !clip.intersects(&rect) {
+                        continue;
+                    }
+                    clips.insert(*capsule_ref, clip);
+                }
+                if self.is_disabled(*capsule_ref) {
+                    disabled.insert(*capsule_ref);
+                }
+
+                let stacking = (self.root.layer_order(*capsule_ref), style.z_index);
+                let shadow = style.resolved_shadow();
                 commands.push((
-                    style.z_index,
+                    stacking,
                     0,
                     *capsule_ref,
                     cmd::DrawCommand::Rect {
-                        space,
+                        rect,
                         fill_color: style.background_color,
-                        stroke_color: style.border.color,
+                        // Faint borders stand out in high contrast
+                        stroke_color: if self.accessibility.high_contrast && style.border.size > 0 {
+                            self.contrast_color()
+                        } else {
+                            style.border.color
+                        },
                         z_index: style.z_index,
                         border_radius: style.border.radius,
                         stroke_width: style.border.size,
-                        shadow_color: style.shadow.color,
-                        shadow_blur: style.shadow.blur,
+                        shadow_color: shadow.color,
+                        shadow_blur: shadow.blur,
                     },
                 ));
 
+                for (priority, command) in self.decoration_commands(*capsule_ref, style.z_index) {
+                    commands.push((stacking, priority, *capsule_ref, command));
+                }
+
+                // Above the children sharing the layer and z-index
+                let scrollbars = self.root.scrollbars(*capsule_ref);
+                for scrollbar in [scrollbars.vertical, scrollbars.horizontal]
+                    .into_iter()
+                    .flatten()
+                {
+                    commands.push((
+                        stacking,
+                        2,
+                        *capsule_ref,
+                        cmd::DrawCommand::Rect {
+                            rect: scrollbar.thumb,
+                            fill_color: self.theme.scrollbar_color,
+                            stroke_color: Color::transparent,
+                            z_index: style.z_index,
+                            border_radius: scrollbar.thumb.w.min(scrollbar.thumb.h) / 2,
+                            stroke_width: 0,
+                            shadow_color: Color::transparent,
+                            shadow_blur: 0.0,
+                        },
+                    ));
+                }
+
+                if let Some(canvas) = element.as_any().downcast_ref::<Canvas>() {
+                    let content = self.root.get_content_rect(*capsule_ref).unwrap_or(rect);
+                    for command in canvas.draw_commands(content, style.z_index) {
+                        commands.push((stacking, 1, *capsule_ref, command));
+                    }
+                }
+
+                for command in self.painter_commands(*capsule_ref, style.z_index) {
+                    commands.push((stacking, 1, *capsule_ref, command));
+                }
+
+                if let Some(image) = element.as_any().downcast_ref::<Image>() {
+                    commands.push((
+                        stacking,
+                        1,
+                        *capsule_ref,
+                        cmd::DrawCommand::Image {
+                            rect: self.root.get_content_rect(*capsule_ref).unwrap_or(rect),
+                            texture_ref: image.data_ref,
+                            uv: [0.0, 0.0, 1.0, 1.0],
+                            tint: image.tint,
+                            z_index: style.z_index,
+                        },
+                    ));
+                }
+
+                #[cfg(feature = "text")]
                 if let Some(label) = element.as_any().downcast_ref::<Label>() {
                     if let Some(data_ref) = element.data_ref() {
                         commands.push((
-                            style.z_index,
+                            stacking,
                             1,
                             *capsule_ref,
                             cmd::DrawCommand::Text {
-                                space,
+                                rect,
                                 buffer_ref: data_ref,
                                 style: label.text_style.clone(),
                                 z_index: style.z_index,
@@ -626,12 +3408,107 @@ impl Context {
                         ));
                     }
                 }
+
+                // Selection and caret over the rect of the input, around its text
+                #[cfg(feature = "text")]
+                if let Some(input) = element.as_any().downcast_ref::<TextInput>()
+                    && let Some(text) = self.root.get_rect(input.label.raw())
+                    && let Some(buffer) = self
+                        .elements
+                        .get(&input.label.raw())
+                        .and_then(|label| label.data_ref())
+                        .and_then(|data_ref| self.root.get_binding::<cosmic_text::Buffer>(data_ref))
+                {
+                    for (priority, command) in input.draw_commands(buffer, text, style.z_index) {
+                        commands.push((stacking, priority, *capsule_ref, command));
+                    }
+                }
+
+                // Around the focused element, over its neighbours
+                let ring = self.focus_ring_width();
+                if ring > 0 && self.focused_element == Some(*capsule_ref) {
+                    let color = if self.accessibility.high_contrast {
+                        self.contrast_color()
+                    } else {
+                        self.theme.accent_color
+                    };
+                    commands.push((
+                        stacking,
+                        2,
+                        *capsule_ref,
+                        cmd::DrawCommand::Rect {
+                            rect: rect.inflate(ring as i32, ring as i32),
+                            fill_color: Color::transparent,
+                            stroke_color: color,
+                            z_index: style.z_index,
+                            border_radius: style.border.radius + ring,
+                            stroke_width: ring,
+                            shadow_color: Color::transparent,
+                            shadow_blur: 0.0,
+                        },
+                    ));
+                }
+
+                // Tinted and outlined while something can be dropped on it
+                if self.drop_target() == Some(*capsule_ref) {
+                    let accent = self.theme.accent_color;
+                    commands.push((
+                        stacking,
+                        2,
+                        *capsule_ref,
+                        cmd::DrawCommand::Rect {
+                            rect,
+                            fill_color: Color::new(accent.r, accent.g, accent.b, 48),
+                            stroke_color: accent,
+                            z_index: style.z_index,
+                            border_radius: style.border.radius,
+                            stroke_width: 2,
+                            shadow_color: Color::transparent,
+                            shadow_blur: 0.0,
+                        },
+                    ));
+                }
+
+                // Above everything else
+                #[cfg(feature = "debug")]
+                for (priority, command) in self.inspector_commands(*capsule_ref, rect) {
+                    commands.push(((i32::MAX, u32::MAX), priority, *capsule_ref, command));
+                }
+            }
+        }
+
+        let before = commands.len();
+        // Text colors follow their tokens
+        #[cfg(feature = "text")]
+        for (_, _, _, command) in &mut commands {
+            if let Some(style) = command.text_style_mut()
+                && let Some(token) = style.color_token
+            {
+                style.color = self.token_color(token);
             }
         }
+        commands.retain(|(_, _, _, command)| command.is_visible());
+        let skipped = before - commands.len();
 
-        // Z-Index (Logic) -> Priority (Text > Rect) -> CapsuleRef (Stability)
+        // Layer -> Z-Index (Logic) -> Priority (Text > Rect) -> CapsuleRef (Stability)
         commands.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
-        commands.into_iter().map(|(_, _, _, cmd)| cmd).collect()
+        let commands = commands.into_iter().map(|(_, _, capsule_ref, command)| {
+            let command = if disabled.contains(&capsule_ref) {
+                cmd::DrawCommand::Disabled {
+                    command: Box::new(command),
+                }
+            } else {
+                command
+            };
+            match clips.get(&capsule_ref) {
+                Some(clip) => cmd::DrawCommand::Clipped {
+                    clip: *clip,
+                    command: Box::new(command),
+                },
+                None => command,
+            }
+        });
+        (commands.collect(), skipped)
     }
 }
 
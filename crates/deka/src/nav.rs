@@ -0,0 +1,55 @@
+//! Spatial navigation: moving the focus with the arrow keys.
+
+use heka::{CapsuleRef, geometry::Rect};
+use winit::keyboard::{Key, NamedKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl NavDirection {
+    pub(crate) fn from_key(key: &Key) -> Option<Self> {
+        match key {
+            Key::Named(NamedKey::ArrowUp) => Some(NavDirection::Up),
+            Key::Named(NamedKey::ArrowDown) => Some(NavDirection::Down),
+            Key::Named(NamedKey::ArrowLeft) => Some(NavDirection::Left),
+            Key::Named(NamedKey::ArrowRight) => Some(NavDirection::Right),
+            _ => None,
+        }
+    }
+}
+
+/// Picks the candidate closest to `from` in `direction`.
+///
+/// Only candidates whose center lies past the center of `from` count.
+/// Distance across the direction weighs more than along it, so staying
+/// on the same row (or column) is preferred over a closer diagonal jump.
+pub(crate) fn nearest(
+    from: Rect,
+    direction: NavDirection,
+    candidates: impl IntoIterator<Item = (CapsuleRef, Rect)>,
+) -> Option<CapsuleRef> {
+    const CROSS_WEIGHT: i64 = 2;
+    let (fx, fy) = from.center();
+
+    candidates
+        .into_iter()
+        .filter_map(|(cref, rect)| {
+            let (cx, cy) = rect.center();
+            let (dx, dy) = ((cx - fx) as i64, (cy - fy) as i64);
+            let (along, across) = match direction {
+                NavDirection::Up => (-dy, dx),
+                NavDirection::Down => (dy, dx),
+                NavDirection::Left => (-dx, dy),
+                NavDirection::Right => (dx, dy),
+            };
+
+            (along > 0).then_some((along + across.abs() * CROSS_WEIGHT, cref))
+        })
+        .min()
+        .map(|(_, cref)| cref)
+}
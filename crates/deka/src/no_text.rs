@@ -0,0 +1,67 @@
+//! Stand-ins for the text elements when the `text` feature is disabled.
+//!
+//! They can't be called: their bound is never met, so the compiler points
+//! at the feature instead of reporting a missing method.
+
+use crate::events::ClickEvent;
+use crate::{Context, Element, ElementRef};
+
+mod sealed {
+    /// Implemented by no type, so neither is `TextFeature`
+    pub trait Sealed {}
+}
+
+/// Never implemented, and sealed so it can't be: see the module documentation
+#[diagnostic::on_unimplemented(
+    message = "text elements need the `text` feature of deka",
+    label = "labels, buttons, links, text inputs and forms are disabled",
+    note = "enable it in Cargo.toml: `deka = {{ version = \"...\", features = [\"text\"] }}`"
+)]
+pub trait TextFeature: sealed::Sealed {}
+
+#[doc(hidden)]
+impl Context {
+    pub fn new_label<S: TextFeature>(
+        &mut self,
+        _text: S,
+        _parent_frame: Option<impl ElementRef>,
+        _text_style: Option<()>,
+    ) -> Element {
+        unreachable!()
+    }
+
+    pub fn new_button<S: TextFeature, F>(
+        &mut self,
+        _text: S,
+        _parent_frame: Option<impl ElementRef>,
+        _on_click: F,
+        _label_style: Option<()>,
+    ) -> Element
+    where
+        F: FnMut(&mut Context, &ClickEvent) + 'static,
+    {
+        unreachable!()
+    }
+
+    pub fn new_link<S: TextFeature, U>(
+        &mut self,
+        _text: S,
+        _url: U,
+        _parent_frame: Option<impl ElementRef>,
+        _text_style: Option<()>,
+    ) -> Element {
+        unreachable!()
+    }
+
+    pub fn new_text_input<S: TextFeature>(
+        &mut self,
+        _parent_frame: Option<impl ElementRef>,
+        _initial_text: S,
+    ) -> Element {
+        unreachable!()
+    }
+
+    pub fn new_form<P: ElementRef + TextFeature>(&mut self, _parent_frame: Option<P>) -> Element {
+        unreachable!()
+    }
+}
@@ -0,0 +1,381 @@
+//! How scroll views come to rest: elastic ones can be pulled past the ends
+//! of their content and spring back, snapping ones bring the child nearest
+//! to their scroll position into place once the scroll stops.
+
+use std::time::Duration;
+
+use heka::CapsuleRef;
+
+use crate::elements::ScrollView;
+use crate::{Context, ElementRef, ScrollViewRef, TimerId};
+
+/// Time the scroll has to stand still before a scroll view settles
+const SETTLE_DELAY: Duration = Duration::from_millis(120);
+
+/// Time between two settling steps
+const SETTLE_STEP: Duration = Duration::from_millis(16);
+
+const SETTLE_DURATION: Duration = Duration::from_millis(300);
+
+/// How hard the content resists being pulled past its ends, see
+/// `rubber_band`
+const ELASTICITY: f32 = 0.55;
+
+/// Fraction of the viewport a throw carries the content past its ends
+/// before it springs back
+const MOMENTUM_BOUNCE: f32 = 0.125;
+
+/// Where a snapping `ScrollView` brings its children once the scroll stops
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollSnap {
+    /// The scroll stays where it stopped
+    #[default]
+    None,
+    /// The nearest child starts at the start of the viewport
+    Start,
+    /// The nearest child is centered in the viewport
+    Center,
+    /// The nearest child ends at the end of the viewport
+    End,
+}
+
+/// A scroll view waiting for the scroll to stop, then settling
+pub(crate) struct Settle {
+    timer: TimerId,
+    /// Last time the scroll moved, on the animation clock
+    last_move: Duration,
+    animation: Option<SettleAnimation>,
+}
+
+/// Scroll positions (overscroll included) a settling scroll view goes
+/// between
+struct SettleAnimation {
+    /// On the animation clock
+    start: Duration,
+    from: (f32, f32),
+    to: (f32, f32),
+}
+
+/// Distance the content is drawn past an end when pulled `stretch` pixels
+/// past it, in a viewport of `len`: it follows less and less, never
+/// reaching `len`
+fn rubber_band(stretch: f32, len: f32) -> f32 {
+    if len <= 0.0 {
+        return 0.0;
+    }
+    let pulled = stretch.abs();
+    (pulled * ELASTICITY * len / (pulled * ELASTICITY + len)).copysign(stretch)
+}
+
+/// Pull giving a drawn overscroll, the inverse of `rubber_band`
+fn stretch_for(overscroll: f32, len: f32) -> f32 {
+    let drawn = overscroll.abs().min(len * 0.99);
+    if drawn == 0.0 {
+        return 0.0;
+    }
+    (drawn * len / (ELASTICITY * (len - drawn))).copysign(overscroll)
+}
+
+/// Splits a position along an axis into a scroll position within `max` and
+/// the pull past the end it went by
+fn split(at: f32, max: f32) -> (f32, f32) {
+    let offset = at.clamp(0.0, max);
+    (offset, at - offset)
+}
+
+impl Context {
+    /// Lets a `ScrollView` be pulled past the ends of its content, which
+    /// resists more the further it goes and springs back once the scroll
+    /// stops. Only along the axes its content overflows. Disabled by
+    /// default.
+    pub fn set_overscroll(&mut self, view: ScrollViewRef, enabled: bool) {
+        let cref = view.raw();
+        let Some(scroll_view) = self.scroll_view_mut(cref) else {
+            return;
+        };
+        scroll_view.overscroll = enabled;
+        if !enabled {
+            scroll_view.stretch = (0.0, 0.0);
+            self.root.set_overscroll(cref, 0.0, 0.0);
+        }
+    }
+
+    /// Makes a `ScrollView` bring the child of its content nearest to
+    /// where a scroll stopped into place, with an animation. Scrolls stop
+    /// when the wheel, the touchpad, a throw or a dragged thumb are done
+    /// moving it, `scroll_to` included.
+    pub fn set_scroll_snap(&mut self, view: ScrollViewRef, snap: ScrollSnap) {
+        let cref = view.raw();
+        let Some(scroll_view) = self.scroll_view_mut(cref) else {
+            return;
+        };
+        scroll_view.snap = snap;
+        self.wait_to_settle(cref);
+    }
+
+    fn scroll_view_mut(&mut self, cref: CapsuleRef) -> Option<&mut ScrollView> {
+        let view = self.elements.get_mut(&cref)?;
+        view.as_any_mut().downcast_mut::<ScrollView>()
+    }
+
+    /// Scrolls a scroll container by a distance. Elastic scroll views take
+    /// what goes past the ends of their content as overscroll, a throw
+    /// (`momentum`) stops once it carried them far enough.
+    /// Returns whether the content moved.
+    pub(crate) fn scroll_container_by(
+        &mut self,
+        cref: CapsuleRef,
+        dx: f32,
+        dy: f32,
+        momentum: bool,
+    ) -> bool {
+        let elastic = self
+            .get_scroll_view(ScrollViewRef(cref))
+            .filter(|view| view.overscroll)
+            .map(|view| view.stretch);
+        let Some(stretch) = elastic else {
+            return self.root.scroll_by(cref, dx, dy);
+        };
+
+        let (offset_x, offset_y) = self.root.scroll_offset(cref);
+        let (max_x, max_y) = self.root.max_scroll_offset(cref);
+        let (len_x, len_y) = self
+            .root
+            .get_content_rect(cref)
+            .map_or((0.0, 0.0), |view| (view.w as f32, view.h as f32));
+
+        // Overflowing axes move past the ends, the others stay clamped
+        let axis = |offset: f32, stretch: f32, delta: f32, max: f32| {
+            if max > 0.0 {
+                split(offset + stretch + delta, max)
+            } else {
+                (offset + delta, 0.0)
+            }
+        };
+        let (offset_x, stretch_x) = axis(offset_x, stretch.0, dx, max_x);
+        let (offset_y, stretch_y) = axis(offset_y, stretch.1, dy, max_y);
+        let overscroll = (rubber_band(stretch_x, len_x), rubber_band(stretch_y, len_y));
+
+        let scrolled = self.root.set_scroll_offset(cref, offset_x, offset_y);
+        let pulled = self.root.set_overscroll(cref, overscroll.0, overscroll.1);
+        if let Some(view) = self.scroll_view_mut(cref) {
+            view.stretch = (stretch_x, stretch_y);
+        }
+        if pulled {
+            self.wait_to_settle(cref);
+            let bounced = overscroll.0.abs() >= len_x * MOMENTUM_BOUNCE
+                || overscroll.1.abs() >= len_y * MOMENTUM_BOUNCE;
+            if momentum && bounced {
+                self.stop_momentum();
+            }
+        }
+        scrolled || pulled
+    }
+
+    /// Waits for the scroll to stop to settle the scroll containers that
+    /// moved, when they overscroll or snap
+    pub(crate) fn settle_scrolled(&mut self, scrolled: &[CapsuleRef]) {
+        for cref in scrolled {
+            // Settling moves them too
+            let settling = self
+                .settles
+                .get(cref)
+                .is_some_and(|settle| settle.animation.is_some());
+            if !settling {
+                self.wait_to_settle(*cref);
+            }
+        }
+    }
+
+    /// Settles a scroll view once its scroll stands still, from now on
+    fn wait_to_settle(&mut self, cref: CapsuleRef) {
+        let Some(view) = self.get_scroll_view(ScrollViewRef(cref)) else {
+            return;
+        };
+        if !view.overscroll && view.snap == ScrollSnap::None {
+            return;
+        }
+
+        let now = self.clock.now();
+        if let Some(settle) = self.settles.get_mut(&cref) {
+            settle.last_move = now;
+            settle.animation = None;
+            return;
+        }
+        let timer = self.set_interval(SETTLE_STEP, move |ctx| ctx.settle_step(cref));
+        self.settles.insert(
+            cref,
+            Settle {
+                timer,
+                last_move: now,
+                animation: None,
+            },
+        );
+    }
+
+    fn settle_step(&mut self, cref: CapsuleRef) {
+        let Some(snap) = self
+            .get_scroll_view(ScrollViewRef(cref))
+            .map(|view| view.snap)
+        else {
+            self.forget_settle(cref);
+            return;
+        };
+        // Still in the hands of a touchpad, a throw or a thumb
+        if self.scroll_held(cref) || self.thumb_drag.is_some() || self.swiping(cref) {
+            self.wait_to_settle(cref);
+            return;
+        }
+
+        let now = self.clock.now();
+        let Some(settle) = self.settles.get(&cref) else {
+            return;
+        };
+        let Some(animation) = &settle.animation else {
+            if now.saturating_sub(settle.last_move) < SETTLE_DELAY {
+                return;
+            }
+            let from = self.scrolled_to(cref);
+            let to = self.snap_target(cref, snap, from);
+            if from == to {
+                self.forget_settle(cref);
+            } else if let Some(settle) = self.settles.get_mut(&cref) {
+                settle.animation = Some(SettleAnimation {
+                    start: now,
+                    from,
+                    to,
+                });
+            }
+            return;
+        };
+
+        let t = self.clock.fraction(animation.start, SETTLE_DURATION);
+        let eased = 1.0 - (1.0 - t).powi(3);
+        let (from, to) = (animation.from, animation.to);
+        let at = (
+            from.0 + (to.0 - from.0) * eased,
+            from.1 + (to.1 - from.1) * eased,
+        );
+        self.place_scroll(cref, at);
+        if t >= 1.0 {
+            self.forget_settle(cref);
+        }
+    }
+
+    /// Animates a scroll view to a scroll position, the way it settles
+    pub(crate) fn animate_scroll_to(&mut self, cref: CapsuleRef, to: (f32, f32)) {
+        let from = self.scrolled_to(cref);
+        if from == to {
+            self.forget_settle(cref);
+            return;
+        }
+        let now = self.clock.now();
+        let timer = match self.settles.remove(&cref) {
+            Some(settle) => settle.timer,
+            None => self.set_interval(SETTLE_STEP, move |ctx| ctx.settle_step(cref)),
+        };
+        self.settles.insert(
+            cref,
+            Settle {
+                timer,
+                last_move: now,
+                animation: Some(SettleAnimation {
+                    start: now,
+                    from,
+                    to,
+                }),
+            },
+        );
+    }
+
+    /// Scroll position of a scroll container, overscroll included
+    pub(crate) fn scrolled_to(&self, cref: CapsuleRef) -> (f32, f32) {
+        let (offset_x, offset_y) = self.root.scroll_offset(cref);
+        let (over_x, over_y) = self.root.overscroll(cref);
+        (offset_x + over_x, offset_y + over_y)
+    }
+
+    /// Moves a scroll view to a scroll position, past its ends as
+    /// overscroll
+    fn place_scroll(&mut self, cref: CapsuleRef, (x, y): (f32, f32)) {
+        let (max_x, max_y) = self.root.max_scroll_offset(cref);
+        let (offset_x, over_x) = split(x, max_x);
+        let (offset_y, over_y) = split(y, max_y);
+        let (len_x, len_y) = self
+            .root
+            .get_content_rect(cref)
+            .map_or((0.0, 0.0), |view| (view.w as f32, view.h as f32));
+
+        let scrolled = self.root.set_scroll_offset(cref, offset_x, offset_y);
+        let pulled = self.root.set_overscroll(cref, over_x, over_y);
+        if let Some(view) = self.scroll_view_mut(cref) {
+            view.stretch = (stretch_for(over_x, len_x), stretch_for(over_y, len_y));
+        }
+        if scrolled || pulled {
+            // The cursor may be above another element now
+            self.pointer_moved = true;
+            self.dispatch_scroll_offsets();
+        }
+    }
+
+    /// Scroll position a scroll view settles at from `from`: within its
+    /// content, and on the snap position of a child nearest to `from` when
+    /// it snaps
+    fn snap_target(&self, cref: CapsuleRef, snap: ScrollSnap, from: (f32, f32)) -> (f32, f32) {
+        let (max_x, max_y) = self.root.max_scroll_offset(cref);
+        let mut target = (from.0.clamp(0.0, max_x), from.1.clamp(0.0, max_y));
+        if snap == ScrollSnap::None {
+            return target;
+        }
+        let (Some(view), Some(content)) = (
+            self.root.get_content_rect(cref),
+            self.get_scroll_view(ScrollViewRef(cref))
+                .and_then(|view| self.root.get_capsule(view.content.raw())),
+        ) else {
+            return target;
+        };
+
+        // Where a child of `len` at `start` in the content snaps to
+        let snap_at = |start: f32, len: f32, view_len: f32, max: f32| {
+            let at = match snap {
+                ScrollSnap::Start | ScrollSnap::None => start,
+                ScrollSnap::Center => start + (len - view_len) / 2.0,
+                ScrollSnap::End => start + len - view_len,
+            };
+            at.clamp(0.0, max)
+        };
+        let nearest = |best: f32, at: f32, from: f32| {
+            if (at - from).abs() < (best - from).abs() {
+                at
+            } else {
+                best
+            }
+        };
+
+        let mut found = false;
+        let mut best = (f32::INFINITY, f32::INFINITY);
+        for child in content.children() {
+            let Some(rect) = self.root.get_rect(*child) else {
+                continue;
+            };
+            // Laid out at the current position, overscroll included
+            let start_x = (rect.x - view.x) as f32 + from.0;
+            let start_y = (rect.y - view.y) as f32 + from.1;
+            let at_x = snap_at(start_x, rect.w as f32, view.w as f32, max_x);
+            let at_y = snap_at(start_y, rect.h as f32, view.h as f32, max_y);
+            best = (nearest(best.0, at_x, from.0), nearest(best.1, at_y, from.1));
+            found = true;
+        }
+        if found {
+            target = best;
+        }
+        target
+    }
+
+    /// Stops settling a scroll view
+    pub(crate) fn forget_settle(&mut self, cref: CapsuleRef) {
+        if let Some(settle) = self.settles.remove(&cref) {
+            self.cancel_timer(settle.timer);
+        }
+    }
+}
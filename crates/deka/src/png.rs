@@ -0,0 +1,505 @@
+//! PNG decoding into RGBA pixels, for `ImageSource::Path` and
+//! `ImageSource::Bytes`.
+//!
+//! Every standard color type and bit depth is read, interlaced or not, with
+//! `tRNS` transparency. Ancillary chunks (gamma, color profiles, text) are
+//! skipped and checksums are not verified.
+
+use crate::elements::ImageError;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Images with more pixels than this are refused rather than allocated
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// Origin and step of the 7 passes of an Adam7 interlaced image
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+fn error(message: &str) -> ImageError {
+    ImageError::Decode(message.to_string())
+}
+
+/// Decodes a PNG file into its size and straight alpha RGBA rows
+pub(crate) fn decode(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), ImageError> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(error("not a PNG file"));
+    }
+
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut rest = &bytes[SIGNATURE.len()..];
+    loop {
+        if rest.len() < 12 {
+            return Err(error("truncated chunk"));
+        }
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let kind = &rest[4..8];
+        let Some(data) = rest.get(8..8 + length) else {
+            return Err(error("truncated chunk"));
+        };
+        rest = rest.get(12 + length..).unwrap_or(&[]);
+
+        match kind {
+            b"IHDR" => header = Some(Header::read(data)?),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            // Critical chunks are the ones starting with an uppercase letter
+            _ if kind[0].is_ascii_uppercase() => return Err(error("unknown critical chunk")),
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or_else(|| error("missing IHDR chunk"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(error("missing PLTE chunk"));
+    }
+    let raw = inflate_zlib(&compressed)?;
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let mut rgba = vec![0; width * height * 4];
+    let passes: &[(usize, usize, usize, usize)] = if header.interlaced {
+        &ADAM7
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in passes {
+        let pass_width = (width + dx - 1 - x0) / dx;
+        let pass_height = (height + dy - 1 - y0) / dy;
+        if pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+
+        let stride = (pass_width * header.bits_per_pixel()).div_ceil(8);
+        let size = (stride + 1) * pass_height;
+        let Some(scanlines) = raw.get(offset..offset + size) else {
+            return Err(error("truncated image data"));
+        };
+        offset += size;
+
+        let rows = unfilter(scanlines, stride, header.bits_per_pixel().div_ceil(8))?;
+        for (row_index, row) in rows.chunks(stride).enumerate() {
+            let y = y0 + row_index * dy;
+            for column in 0..pass_width {
+                let x = x0 + column * dx;
+                let pixel = header.pixel(row, column, palette, transparency);
+                rgba[(y * width + x) * 4..][..4].copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    Ok((header.width, header.height, rgba))
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn read(data: &[u8]) -> Result<Self, ImageError> {
+        if data.len() < 13 {
+            return Err(error("truncated IHDR chunk"));
+        }
+        let header = Self {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            bit_depth: data[8],
+            color_type: data[9],
+            interlaced: data[12] == 1,
+        };
+
+        let depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(error("unknown color type")),
+        };
+        if !depths.contains(&header.bit_depth) {
+            return Err(error("invalid bit depth for the color type"));
+        }
+        if data[10] != 0 || data[11] != 0 || data[12] > 1 {
+            return Err(error("unknown compression, filter or interlace method"));
+        }
+        if header.width == 0 || header.height == 0 {
+            return Err(error("empty image"));
+        }
+        if header.width as u64 * header.height as u64 > MAX_PIXELS {
+            return Err(error("image too large"));
+        }
+        Ok(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    /// Sample `index` of an unfiltered row, at the bit depth of the image
+    fn sample(&self, row: &[u8], index: usize) -> u16 {
+        match self.bit_depth {
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            8 => row[index] as u16,
+            depth => {
+                let bit = index * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                (row[bit / 8] >> shift) as u16 & ((1 << depth) - 1)
+            }
+        }
+    }
+
+    /// A sample brought to 8 bits
+    fn scale(&self, sample: u16) -> u8 {
+        match self.bit_depth {
+            16 => (sample >> 8) as u8,
+            8 => sample as u8,
+            depth => (sample * 255 / ((1 << depth) - 1)) as u8,
+        }
+    }
+
+    fn pixel(&self, row: &[u8], column: usize, palette: &[u8], transparency: &[u8]) -> [u8; 4] {
+        let channels = self.channels();
+        let samples: Vec<u16> = (0..channels)
+            .map(|channel| self.sample(row, column * channels + channel))
+            .collect();
+        // Color key of gray and truecolor images, at the bit depth of the image
+        let key = |channel: usize| {
+            transparency
+                .get(channel * 2..channel * 2 + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        };
+
+        match self.color_type {
+            0 => {
+                let gray = self.scale(samples[0]);
+                let alpha = if key(0) == Some(samples[0]) { 0 } else { 255 };
+                [gray, gray, gray, alpha]
+            }
+            2 => {
+                let keyed = (0..3).all(|channel| key(channel) == Some(samples[channel]));
+                let [r, g, b] = [0, 1, 2].map(|channel| self.scale(samples[channel]));
+                [r, g, b, if keyed { 0 } else { 255 }]
+            }
+            3 => {
+                let index = samples[0] as usize;
+                let color = palette.get(index * 3..index * 3 + 3).unwrap_or(&[0, 0, 0]);
+                let alpha = transparency.get(index).copied().unwrap_or(255);
+                [color[0], color[1], color[2], alpha]
+            }
+            4 => {
+                let gray = self.scale(samples[0]);
+                [gray, gray, gray, self.scale(samples[1])]
+            }
+            _ => [0, 1, 2, 3].map(|channel| self.scale(samples[channel])),
+        }
+    }
+}
+
+/// Reverts the filter starting each scanline. `bpp` is the number of bytes
+/// per complete pixel, at least 1.
+fn unfilter(scanlines: &[u8], stride: usize, bpp: usize) -> Result<Vec<u8>, ImageError> {
+    let rows = scanlines.len() / (stride + 1);
+    let mut out = vec![0u8; rows * stride];
+
+    for row in 0..rows {
+        let filter = scanlines[row * (stride + 1)];
+        let line = &scanlines[row * (stride + 1) + 1..][..stride];
+        let (done, current) = out.split_at_mut(row * stride);
+        let previous = done
+            .get(done.len().saturating_sub(stride)..)
+            .filter(|_| row > 0);
+        let current = &mut current[..stride];
+
+        for i in 0..stride {
+            let left = if i >= bpp { current[i - bpp] } else { 0 };
+            let up = previous.map_or(0, |previous| previous[i]);
+            let up_left = match previous {
+                Some(previous) if i >= bpp => previous[i - bpp],
+                _ => 0,
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((left as u16 + up as u16) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(error("unknown filter type")),
+            };
+            current[i] = line[i].wrapping_add(predicted);
+        }
+    }
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Base length and extra bits of the length codes 257 to 285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base distance and extra bits of the distance codes
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order the code lengths of the code length alphabet come in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads a deflate stream least significant bit first
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32, ImageError> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| error("truncated deflate stream"))?;
+            self.position += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u64 << n) - 1) as u32;
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Drops the bits left of the current byte
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+/// Canonical Huffman code, as symbol counts per code length and symbols
+/// sorted by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, ImageError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(error("invalid Huffman code"))
+    }
+}
+
+/// Decompresses a zlib stream, without checking its checksum
+fn inflate_zlib(data: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let [cmf, flg, ..] = *data else {
+        return Err(error("truncated zlib stream"));
+    };
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) || flg & 0x20 != 0 {
+        return Err(error("invalid zlib header"));
+    }
+
+    let mut bits = Bits {
+        data: &data[2..],
+        position: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let header = bits
+                    .data
+                    .get(bits.position..bits.position + 4)
+                    .ok_or_else(|| error("truncated stored block"))?;
+                let length = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if length != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err(error("corrupt stored block"));
+                }
+                bits.position += 4;
+                let stored = bits
+                    .data
+                    .get(bits.position..bits.position + length)
+                    .ok_or_else(|| error("truncated stored block"))?;
+                out.extend_from_slice(stored);
+                bits.position += length;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                inflate_block(
+                    &mut bits,
+                    &mut out,
+                    &Huffman::new(&lengths),
+                    &Huffman::new(&[5; 30]),
+                )?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &mut out, &literals, &distances)?;
+            }
+            _ => return Err(error("invalid deflate block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn read_dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman), ImageError> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_length_count = bits.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = bits.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (length, repeat) = match code_lengths.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths
+                    .last()
+                    .ok_or_else(|| error("repeated code length without a previous one"))?;
+                (previous, 3 + bits.bits(2)?)
+            }
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(error("too many code lengths"));
+    }
+
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn inflate_block(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), ImageError> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let code = symbol - 257;
+                if code >= LENGTH_BASE.len() {
+                    return Err(error("invalid length code"));
+                }
+                let length =
+                    LENGTH_BASE[code] as usize + bits.bits(LENGTH_EXTRA[code] as u32)? as usize;
+
+                let code = distances.decode(bits)? as usize;
+                if code >= DISTANCE_BASE.len() {
+                    return Err(error("invalid distance code"));
+                }
+                let distance =
+                    DISTANCE_BASE[code] as usize + bits.bits(DISTANCE_EXTRA[code] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(error("distance before the start of the stream"));
+                }
+
+                // The copy may overlap what it writes
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
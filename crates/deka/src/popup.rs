@@ -0,0 +1,249 @@
+//! Popups: the frames of tooltips, dropdowns and context menus. They open
+//! next to an element, over everything else whatever its z-index, and close
+//! on Escape or on a press outside of them.
+
+use heka::CapsuleRef;
+use heka::geometry::Rect;
+use heka::position::{Offset, Position};
+use heka::{flow, layout, make_style, size};
+
+use crate::{Context, ElementRef, PanelRef, PopupRef};
+
+/// Layer holding the popups, above the main UI and the toolbar menus
+const POPUP_LAYER: &str = "deka-popups";
+const POPUP_LAYER_ORDER: i32 = 200;
+
+/// Side of its anchor a popup opens on. It goes to the opposite side when
+/// it does not fit in the window there.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PopupPlacement {
+    /// Under the anchor, their left edges aligned
+    #[default]
+    Below,
+    /// Over the anchor, their left edges aligned
+    Above,
+    /// On the right of the anchor, their top edges aligned
+    Right,
+    /// On the left of the anchor, their top edges aligned
+    Left,
+}
+
+impl PopupPlacement {
+    fn opposite(self) -> Self {
+        match self {
+            PopupPlacement::Below => PopupPlacement::Above,
+            PopupPlacement::Above => PopupPlacement::Below,
+            PopupPlacement::Right => PopupPlacement::Left,
+            PopupPlacement::Left => PopupPlacement::Right,
+        }
+    }
+
+    /// Top-left corner of a popup of `size` on this side of `anchor`
+    fn origin(self, anchor: Rect, (width, height): (i32, i32)) -> (i32, i32) {
+        match self {
+            PopupPlacement::Below => (anchor.x, anchor.y + anchor.h as i32),
+            PopupPlacement::Above => (anchor.x, anchor.y - height),
+            PopupPlacement::Right => (anchor.x + anchor.w as i32, anchor.y),
+            PopupPlacement::Left => (anchor.x - width, anchor.y),
+        }
+    }
+}
+
+/// An open popup, see `Context::open_popup`
+pub(crate) struct Popup {
+    frame: CapsuleRef,
+    anchor: CapsuleRef,
+    placement: PopupPlacement,
+    /// Top-left corner it was placed at
+    origin: (i32, i32),
+}
+
+/// Where a popup of `size` goes: on the `placement` side of `anchor`, or
+/// on the opposite one when only that one fits in the window, then moved
+/// along that side to stay inside the window
+fn place(
+    placement: PopupPlacement,
+    anchor: Rect,
+    size: (i32, i32),
+    window: (i32, i32),
+) -> (i32, i32) {
+    let vertical = matches!(placement, PopupPlacement::Below | PopupPlacement::Above);
+    let fits = |(x, y): (i32, i32)| {
+        if vertical {
+            y >= 0 && y + size.1 <= window.1
+        } else {
+            x >= 0 && x + size.0 <= window.0
+        }
+    };
+
+    let mut origin = placement.origin(anchor, size);
+    let flipped = placement.opposite().origin(anchor, size);
+    if !fits(origin) && fits(flipped) {
+        origin = flipped;
+    }
+
+    let clamp = |at: i32, len: i32, max: i32| at.min(max - len).max(0);
+    if vertical {
+        origin.0 = clamp(origin.0, size.0, window.0);
+    } else {
+        origin.1 = clamp(origin.1, size.1, window.1);
+    }
+    origin
+}
+
+impl Context {
+    /// Opens a popup on `anchor`: a panel over everything else, under the
+    /// anchor until `set_popup_placement` says otherwise. `build` fills
+    /// it. It follows the anchor as the layout changes, and closes on
+    /// Escape (the last opened first), on a press outside of it and of its
+    /// anchor, and when the anchor is removed.
+    ///
+    /// Popups do not take the focus, `trap_focus` keeps it in a menu.
+    pub fn open_popup(
+        &mut self,
+        anchor: impl ElementRef,
+        build: impl FnOnce(&mut Context, PanelRef),
+    ) -> PopupRef {
+        let anchor = anchor.raw();
+        let layer = self
+            .layer(POPUP_LAYER)
+            .unwrap_or_else(|| self.create_layer(POPUP_LAYER, POPUP_LAYER_ORDER));
+
+        let border = heka::sizing::Border {
+            size: 1,
+            radius: 5,
+            color: self.theme.border_color,
+        };
+        let panel = self.new_panel(
+            Some(layer),
+            make_style!(
+                width: size!(fit),
+                height: size!(fit),
+                position: Position::Fixed {
+                    x: Offset::Pixel(0),
+                    y: Offset::Pixel(0),
+                },
+                layout: layout!(flex),
+                flow: flow!(column),
+                padding: heka::pad!(2),
+                gap: 2,
+                border: border,
+                background_color: self.theme.surface_color,
+            ),
+        );
+        self.popups.push(Popup {
+            frame: panel.raw(),
+            anchor,
+            placement: PopupPlacement::default(),
+            origin: (0, 0),
+        });
+        self.guarded(|ctx| build(ctx, panel));
+        // Placed from the current layout, then again once laid out
+        self.place_popups();
+        self.request_redraw();
+        PopupRef(panel.raw())
+    }
+
+    /// Opens the popup on another side of its anchor
+    pub fn set_popup_placement(&mut self, popup: PopupRef, placement: PopupPlacement) {
+        if let Some(open) = self.popups.iter_mut().find(|open| open.frame == popup.0) {
+            open.placement = placement;
+            self.place_popups();
+            self.request_redraw();
+        }
+    }
+
+    /// Closes a popup and removes its elements.
+    /// Returns false if it was not open.
+    pub fn close_popup(&mut self, popup: PopupRef) -> bool {
+        let Some(index) = self.popups.iter().position(|open| open.frame == popup.0) else {
+            return false;
+        };
+        self.popups.remove(index);
+        self.remove_element(popup);
+        self.request_redraw();
+        true
+    }
+
+    #[inline]
+    pub fn is_popup_open(&self, popup: PopupRef) -> bool {
+        self.popups.iter().any(|open| open.frame == popup.0)
+    }
+
+    /// Closes the last opened popup on Escape.
+    /// Returns whether there was one.
+    pub(crate) fn close_last_popup(&mut self) -> bool {
+        match self.popups.last() {
+            Some(open) => self.close_popup(PopupRef(open.frame)),
+            None => false,
+        }
+    }
+
+    /// Closes the popups a press at the cursor is outside of, from the last
+    /// opened. A press in a popup or on its anchor keeps it and the popups
+    /// opened before it, so that nested menus stay open and anchors
+    /// toggling their popup get the press.
+    pub(crate) fn dismiss_popups(&mut self) {
+        let hit = self.root.hit_test(
+            self.mouse_pos.x.ceil() as i32,
+            self.mouse_pos.y.ceil() as i32,
+        );
+        while let Some(open) = self.popups.last() {
+            let inside = hit.iter().any(|cref| {
+                self.is_within(*cref, open.frame) || self.is_within(*cref, open.anchor)
+            });
+            if inside {
+                break;
+            }
+            self.close_popup(PopupRef(open.frame));
+        }
+    }
+
+    /// Whether `cref` is `ancestor` or one of its children
+    fn is_within(&self, cref: CapsuleRef, ancestor: CapsuleRef) -> bool {
+        let mut current = Some(cref);
+        while let Some(cref) = current {
+            if cref == ancestor {
+                return true;
+            }
+            current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        }
+        false
+    }
+
+    /// Moves the popups next to their anchors, and closes the ones whose
+    /// anchor is gone. Returns whether any of them moved.
+    pub(crate) fn place_popups(&mut self) -> bool {
+        let (width, height) = self.root.size();
+        let window = (width as i32, height as i32);
+
+        let mut moved = false;
+        let mut orphans = Vec::new();
+        for open in &mut self.popups {
+            let Some(anchor) = self.root.get_rect(open.anchor) else {
+                orphans.push(PopupRef(open.frame));
+                continue;
+            };
+            let size = self
+                .root
+                .get_rect(open.frame)
+                .map_or((0, 0), |rect| (rect.w as i32, rect.h as i32));
+            let origin = place(open.placement, anchor, size, window);
+            if origin == open.origin {
+                continue;
+            }
+            open.origin = origin;
+            heka::Frame::define(open.frame).update_style(&mut self.root, |style| {
+                style.position = Position::Fixed {
+                    x: Offset::Pixel(origin.0),
+                    y: Offset::Pixel(origin.1),
+                };
+            });
+            moved = true;
+        }
+        for popup in orphans {
+            self.close_popup(popup);
+        }
+        moved
+    }
+}
@@ -0,0 +1,115 @@
+//! Render thread, see `AppBuilder::render_thread`.
+//!
+//! The event loop lays frames out and turns them into `SceneSnapshot`s, the
+//! render thread records and presents them. They share a single slot: the
+//! event loop only builds a scene once the render thread took the previous
+//! one, so it is never more than one frame ahead, and the render thread
+//! wakes the event loop up after each frame.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use winit::event_loop::EventLoopProxy;
+
+use crate::FrameStats;
+use crate::al::Renderer;
+use crate::renderer::gui::SceneSnapshot;
+
+#[derive(Default)]
+struct State {
+    /// Scene waiting for the render thread
+    pending: Option<SceneSnapshot>,
+    /// Counters of the render thread as of its last frame
+    stats: FrameStats,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+pub(crate) struct RenderThread {
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    /// Moves `renderer` to a new thread. `waker` is signaled after every
+    /// frame it presents.
+    pub(crate) fn spawn(mut renderer: Renderer, waker: EventLoopProxy<()>) -> Self {
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+        let handle = std::thread::Builder::new()
+            .name("deka-render".into())
+            .spawn(move || {
+                let shared = thread_shared;
+                loop {
+                    let scene = {
+                        let mut state = shared.state.lock().unwrap();
+                        loop {
+                            if state.closed {
+                                return;
+                            }
+                            if let Some(scene) = state.pending.take() {
+                                break scene;
+                            }
+                            state = shared.changed.wait(state).unwrap();
+                        }
+                    };
+
+                    renderer.draw(scene);
+
+                    shared.state.lock().unwrap().stats = renderer.stats;
+                    let _ = waker.send_event(());
+                }
+            })
+            .expect("failed to spawn the render thread");
+
+        Self {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// The render thread took the last scene, a new one can be built.
+    /// Panics of the render thread resume here.
+    pub(crate) fn is_ready(&mut self) -> bool {
+        if self.handle.as_ref().is_some_and(JoinHandle::is_finished)
+            && let Some(Err(panic)) = self.handle.take().map(JoinHandle::join)
+        {
+            std::panic::resume_unwind(panic);
+        }
+        self.shared.state.lock().unwrap().pending.is_none()
+    }
+
+    /// Hands a scene over. One still waiting is replaced, its glyphs are
+    /// copied with the new one.
+    pub(crate) fn submit(&self, mut scene: SceneSnapshot) {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(mut previous) = state.pending.take() {
+            previous.uploads.append(&mut scene.uploads);
+            scene.uploads = previous.uploads;
+        }
+        state.pending = Some(scene);
+        self.shared.changed.notify_one();
+    }
+
+    pub(crate) fn stats(&self) -> FrameStats {
+        self.shared.state.lock().unwrap().stats
+    }
+}
+
+impl Drop for RenderThread {
+    /// Lets the frame being recorded finish, pending scenes are dropped
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.closed = true;
+        }
+        self.shared.changed.notify_one();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
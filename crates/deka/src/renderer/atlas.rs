@@ -1,5 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
 
+#[cfg(feature = "text")]
 use cosmic_text::CacheKey;
 use vulkano::{
     format::Format,
@@ -7,7 +9,16 @@ use vulkano::{
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
 };
 
+/// Size of an atlas page, in pixels
+const PAGE_SIZE: u32 = 1024;
+
+/// Images wider or taller than this (in pixels) are not drawn, every
+/// device supports textures of this size
+const MAX_IMAGE_SIZE: u32 = 4096;
+
 pub struct TextureUpdate {
+    /// Index of the page to write into
+    pub page: usize,
     pub x: u32,
     pub y: u32,
     pub width: u32,
@@ -15,27 +26,28 @@ pub struct TextureUpdate {
     pub data: Vec<u8>,
 }
 
-pub struct Atlas {
+/// Texture of the atlas, filled row by row
+#[cfg_attr(not(feature = "text"), allow(dead_code))]
+pub struct AtlasPage {
     pub texture: Arc<Image>,
-    pub width: u32,
-    pub height: u32,
     cursor_x: u32,
     cursor_y: u32,
     row_height: u32,
-    // key -> (u, v, width, height) in normalized coords? No, pixel coords for now.
-    pub cache: HashMap<CacheKey, (u32, u32, u32, u32)>,
 }
 
-impl Atlas {
-    pub fn new(memory_allocator: Arc<StandardMemoryAllocator>) -> Self {
-        let width = 1024;
-        let height = 1024;
-
+impl AtlasPage {
+    /// A page of `format`: single channel for glyphs, RGBA for images
+    fn new(
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        width: u32,
+        height: u32,
+        format: Format,
+    ) -> Self {
         let texture = Image::new(
             memory_allocator,
             ImageCreateInfo {
                 image_type: ImageType::Dim2d,
-                format: Format::R8_UNORM, // Single channel for glyphs
+                format,
                 extent: [width, height, 1],
                 usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
                 ..Default::default()
@@ -49,34 +61,23 @@ impl Atlas {
 
         Self {
             texture,
-            width,
-            height,
             cursor_x: 0,
             cursor_y: 0,
             row_height: 0,
-            cache: HashMap::new(),
         }
     }
 
-    /// Returns (x, y, is_new_allocation).
-    pub fn allocate(&mut self, key: CacheKey, width: u32, height: u32) -> Option<(u32, u32, bool)> {
-        if let Some(&(x, y, _, _)) = self.cache.get(&key) {
-            return Some((x, y, false));
-        }
-
-        // 1px padding
-        let padding = 1;
-        let w = width + padding;
-        let h = height + padding;
-
-        if self.cursor_x + w > self.width {
+    /// Finds room for a `w` by `h` slot, returns its position
+    #[cfg(feature = "text")]
+    fn place(&mut self, w: u32, h: u32, width: u32, height: u32) -> Option<(u32, u32)> {
+        if self.cursor_x + w > width {
             self.cursor_x = 0;
             self.cursor_y += self.row_height;
             self.row_height = 0;
         }
 
-        if self.cursor_y + h > self.height {
-            // Atlas full
+        if self.cursor_y + h > height {
+            // Page full
             return None;
         }
 
@@ -87,8 +88,177 @@ impl Atlas {
         if h > self.row_height {
             self.row_height = h;
         }
+        Some((x, y))
+    }
+}
+
+/// Glyph and image textures. Glyphs share pages, once one is full they go
+/// to a new one. Every image has a page of its own.
+/// Without the `text` feature, it only holds images and the empty page the
+/// shaders sample.
+#[cfg_attr(not(feature = "text"), allow(dead_code))]
+pub struct Atlas {
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    pub pages: Vec<AtlasPage>,
+    /// Page new glyphs are placed in
+    glyph_page: usize,
+    /// Pages of the images drawn so far, by the address of their pixels
+    images: HashMap<usize, (Weak<[u8]>, usize)>,
+    /// Pages of dropped images, given to the next ones
+    free_pages: Vec<usize>,
+    /// Size of every page
+    pub width: u32,
+    pub height: u32,
+    // key -> (page, u, v, width, height) in pixel coords.
+    // Keys come from `LayoutGlyph::physical` and hold the font size already
+    // multiplied by the scale factor: glyphs of different scales never share a slot.
+    #[cfg(feature = "text")]
+    pub cache: HashMap<CacheKey, (usize, u32, u32, u32, u32)>,
+}
+
+impl Atlas {
+    pub fn new(memory_allocator: Arc<StandardMemoryAllocator>) -> Self {
+        let width = PAGE_SIZE;
+        let height = PAGE_SIZE;
+
+        Self {
+            pages: vec![AtlasPage::new(
+                memory_allocator.clone(),
+                width,
+                height,
+                Format::R8_UNORM,
+            )],
+            memory_allocator,
+            glyph_page: 0,
+            images: HashMap::new(),
+            free_pages: Vec::new(),
+            width,
+            height,
+            #[cfg(feature = "text")]
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns (page, x, y, is_new_allocation). Only glyphs bigger than a
+    /// page get `None`.
+    #[cfg(feature = "text")]
+    pub fn allocate(
+        &mut self,
+        key: CacheKey,
+        width: u32,
+        height: u32,
+    ) -> Option<(usize, u32, u32, bool)> {
+        if let Some(&(page, x, y, _, _)) = self.cache.get(&key) {
+            return Some((page, x, y, false));
+        }
+
+        // 1px padding
+        let padding = 1;
+        let w = width + padding;
+        let h = height + padding;
+
+        if w > self.width || h > self.height {
+            return None;
+        }
+
+        // Earlier pages are full, apart from gaps at the end of their rows
+        let current = self.glyph_page;
+        let (page, (x, y)) = match self.pages[current].place(w, h, self.width, self.height) {
+            Some(position) => (current, position),
+            None => {
+                log::debug!(
+                    "Atlas page {current} full, adding page {}",
+                    self.pages.len()
+                );
+                let mut page = AtlasPage::new(
+                    self.memory_allocator.clone(),
+                    self.width,
+                    self.height,
+                    Format::R8_UNORM,
+                );
+                let position = page.place(w, h, self.width, self.height)?;
+                self.pages.push(page);
+                self.glyph_page = self.pages.len() - 1;
+                (self.glyph_page, position)
+            }
+        };
+
+        self.cache.insert(key, (page, x, y, width, height));
+        Some((page, x, y, true))
+    }
+
+    /// Page of the image made of `pixels`, copied with `uploads` the first
+    /// time it is drawn. `None` for images larger than `MAX_IMAGE_SIZE`.
+    pub fn image_page(
+        &mut self,
+        pixels: &Arc<[u8]>,
+        width: u32,
+        height: u32,
+        uploads: &mut Vec<TextureUpdate>,
+    ) -> Option<usize> {
+        let key = Arc::as_ptr(pixels).cast::<u8>() as usize;
+        match self.images.get(&key) {
+            Some((image, page)) if image.upgrade().is_some_and(|i| Arc::ptr_eq(&i, pixels)) => {
+                return Some(*page);
+            }
+            // Pixels of a dropped image were at the same address
+            Some(&(_, page)) => self.free_page(page),
+            None => {}
+        }
+        if width > MAX_IMAGE_SIZE || height > MAX_IMAGE_SIZE {
+            log::warn!("Images are drawn up to {MAX_IMAGE_SIZE}px, this one is {width}x{height}");
+            return None;
+        }
+
+        let texture = AtlasPage::new(
+            self.memory_allocator.clone(),
+            width,
+            height,
+            Format::R8G8B8A8_UNORM,
+        );
+        let page = match self.free_pages.pop() {
+            Some(page) => {
+                self.pages[page] = texture;
+                page
+            }
+            None => {
+                self.pages.push(texture);
+                self.pages.len() - 1
+            }
+        };
+        self.images.insert(key, (Arc::downgrade(pixels), page));
+        uploads.push(TextureUpdate {
+            page,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            data: pixels.to_vec(),
+        });
+        Some(page)
+    }
+
+    /// Frees the textures of the images whose pixels were dropped
+    pub fn release_images(&mut self) {
+        let dropped: Vec<usize> = self
+            .images
+            .iter()
+            .filter(|(_, (image, _))| image.strong_count() == 0)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in dropped {
+            if let Some((_, page)) = self.images.remove(&key) {
+                self.free_page(page);
+            }
+        }
+    }
 
-        self.cache.insert(key, (x, y, width, height));
-        Some((x, y, true))
+    /// Swaps the texture of an image page for a 1x1 one until another
+    /// image takes the page
+    fn free_page(&mut self, page: usize) {
+        self.images.retain(|_, (_, image_page)| *image_page != page);
+        self.pages[page] =
+            AtlasPage::new(self.memory_allocator.clone(), 1, 1, Format::R8G8B8A8_UNORM);
+        self.free_pages.push(page);
     }
 }
@@ -1,5 +1,6 @@
-use crate::renderer::atlas::Atlas;
+use crate::renderer::atlas::{Atlas, TextureUpdate};
 use crate::{Context, cmd::DrawCommand};
+use heka::color::Color;
 use log::debug;
 use std::sync::Arc;
 use vulkano::{
@@ -8,7 +9,7 @@ use vulkano::{
         AutoCommandBufferBuilder, BufferImageCopy, CopyBufferToImageInfo, PrimaryAutoCommandBuffer,
     },
     descriptor_set::DescriptorSet,
-    image::{ImageAspects, ImageSubresourceLayers},
+    image::{Image, ImageAspects, ImageSubresourceLayers},
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
     pipeline::PipelineLayout,
 };
@@ -35,28 +36,148 @@ pub mod utils {
         pub blur: f32,
         #[format(R32_UINT)]
         pub obj_type: u32,
+        /// Area the vertex's primitive is drawn in, as `[left, top, right, bottom]`
+        #[format(R32G32B32A32_SFLOAT)]
+        pub clip: [f32; 4],
+        /// Atlas page text and image vertices sample, draws are split where
+        /// it changes.
+        /// Not read by the shaders.
+        #[format(R32_UINT)]
+        pub page: u32,
+    }
+
+    /// Indices drawn with one atlas page bound
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DrawBatch {
+        pub page: usize,
+        pub first_index: u32,
+        pub index_count: u32,
+    }
+
+    /// Splits `indices` into runs sampling a single atlas page. Only text
+    /// and images sample the atlas, other primitives join whichever run
+    /// they are in.
+    pub fn batch_by_page(vertices: &[TVertex], indices: &[u32]) -> Vec<DrawBatch> {
+        // Page of each run, `None` while nothing in it samples the atlas
+        let mut runs: Vec<(Option<usize>, u32, u32)> = Vec::new();
+        for (i, triangle) in indices.chunks(3).enumerate() {
+            let page = triangle
+                .iter()
+                .map(|&index| &vertices[index as usize])
+                .find(|vertex| matches!(vertex.obj_type & TYPE_MASK, 1 | 3))
+                .map(|vertex| vertex.page as usize);
+            let count = triangle.len() as u32;
+            match runs.last_mut() {
+                Some((run_page, _, run_count))
+                    if page.is_none() || run_page.is_none() || *run_page == page =>
+                {
+                    *run_page = run_page.or(page);
+                    *run_count += count;
+                }
+                _ => runs.push((page, i as u32 * 3, count)),
+            }
+        }
+        runs.into_iter()
+            .map(|(page, first_index, index_count)| DrawBatch {
+                page: page.unwrap_or(0),
+                first_index,
+                index_count,
+            })
+            .collect()
+    }
+
+    /// Clip of primitives drawn everywhere
+    pub const NO_CLIP: [f32; 4] = [f32::MIN, f32::MIN, f32::MAX, f32::MAX];
+
+    /// Bits of `TVertex::obj_type` giving the type of the primitive
+    pub const TYPE_MASK: u32 = 0xFF;
+    /// Flag of `TVertex::obj_type` over the type: the primitive is part of a
+    /// disabled element, the shader fades and greys it out
+    pub const DISABLED: u32 = 1 << 8;
+}
+
+/// A frame ready to be recorded: its geometry and the glyphs it adds to
+/// the atlas. Built where the `Context` lives, then handed to whichever
+/// thread records the command buffers.
+pub struct SceneSnapshot {
+    pub vertices: Vec<utils::TVertex>,
+    pub indices: Vec<u32>,
+    /// Glyphs and images to copy into the atlas before drawing
+    pub uploads: Vec<TextureUpdate>,
+    /// Atlas pages, `TextureUpdate::page` and `TVertex::page` index into it
+    pub pages: Vec<Arc<Image>>,
+    /// Window size the frame was laid out for
+    pub size: [u32; 2],
+    /// Window pixels per layout unit, see `Context::set_ui_scale`
+    pub scale: f32,
+    /// Premultiplied clear color
+    pub clear_color: [f32; 4],
+}
+
+impl SceneSnapshot {
+    pub fn build(
+        draw_commands: &[DrawCommand],
+        ctx: &mut Context,
+        atlas: &mut Atlas,
+        size: [u32; 2],
+    ) -> Self {
+        let mut vertices: Vec<utils::TVertex> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut uploads = Vec::new();
+        atlas.release_images();
+
+        for cmd in draw_commands {
+            let (cmd_vertices, cmd_indices) = cmd.to_geometry(ctx, atlas, &mut uploads);
+            let offset = vertices.len() as u32;
+
+            vertices.extend(cmd_vertices);
+            indices.extend(cmd_indices.iter().map(|i| i + offset));
+        }
+
+        // The window composites premultiplied colors. With rounded corners,
+        // the root frame draws the backdrop.
+        let clear_color = if ctx.rounds_corners() {
+            Color::transparent
+        } else {
+            ctx.attr.clear_color
+        };
+        let [r, g, b, a]: [f32; 4] = clear_color.into();
+
+        Self {
+            vertices,
+            indices,
+            uploads,
+            pages: atlas
+                .pages
+                .iter()
+                .map(|page| page.texture.clone())
+                .collect(),
+            size,
+            scale: ctx.ui_scale,
+            clear_color: [r * a, g * a, b * a, a],
+        }
     }
 }
 
 pub struct GuiRenderer {
     pub memory_allocator: Arc<StandardMemoryAllocator>,
-    pub atlas: Atlas,
     // Change: Store Option so we can easily replace the whole buffer
     pub vertex_buffers: Vec<Option<Subbuffer<[utils::TVertex]>>>,
     pub vertex_counts: Vec<u32>,
     pub index_buffers: Vec<Option<Subbuffer<[u32]>>>,
     pub index_counts: Vec<u32>,
+    pub batches: Vec<Vec<utils::DrawBatch>>,
 }
 
 impl GuiRenderer {
     pub fn new(memory_allocator: Arc<StandardMemoryAllocator>) -> Self {
         Self {
-            atlas: Atlas::new(memory_allocator.clone()),
             memory_allocator,
             vertex_buffers: Vec::new(),
             vertex_counts: Vec::new(),
             index_buffers: Vec::new(),
             index_counts: Vec::new(),
+            batches: Vec::new(),
         }
     }
 
@@ -65,6 +186,7 @@ impl GuiRenderer {
         self.vertex_counts.clear();
         self.index_buffers.clear();
         self.index_counts.clear();
+        self.batches.clear();
 
         // Fill with None initially
         for _ in 0..num_buffers {
@@ -72,36 +194,36 @@ impl GuiRenderer {
             self.vertex_counts.push(0);
             self.index_buffers.push(None);
             self.index_counts.push(0);
+            self.batches.push(Vec::new());
         }
     }
 
-    pub fn upload_draw_commands(
+    /// Uploads the geometry of a scene for `image_index` and records the
+    /// copies of its glyphs into the atlas pages
+    pub fn upload_scene(
         &mut self,
         image_index: usize,
-        draw_commands: &[DrawCommand],
-        ctx: &mut Context,
+        all_vertices: Vec<utils::TVertex>,
+        all_indices: Vec<u32>,
+        uploads: Vec<TextureUpdate>,
+        pages: &[Arc<Image>],
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
     ) {
-        let mut all_vertices: Vec<utils::TVertex> = Vec::new();
-        let mut all_indices: Vec<u32> = Vec::new();
-        let mut uploads = Vec::new();
-
-        for cmd in draw_commands {
-            let (vertices, indices) = cmd.to_geometry(ctx, &mut self.atlas, &mut uploads);
-            let offset = all_vertices.len() as u32;
-
-            all_vertices.extend(vertices);
-            all_indices.extend(indices.iter().map(|i| i + offset));
-        }
-
         let mut all_data = Vec::new();
-        let mut regions = Vec::new();
+        // Copies into each atlas page
+        let mut regions: Vec<Vec<BufferImageCopy>> = Vec::new();
         let mut current_offset = 0;
 
         for upload in uploads {
             if upload.data.is_empty() {
                 continue;
             }
+            // Copies of dropped scenes may target the page of an image
+            // freed since
+            let [page_width, page_height, _] = pages[upload.page].extent();
+            if upload.x + upload.width > page_width || upload.y + upload.height > page_height {
+                continue;
+            }
 
             // Align to 4 bytes
             let padding = (4 - (current_offset % 4)) % 4;
@@ -110,7 +232,10 @@ impl GuiRenderer {
                 current_offset += 1;
             }
 
-            regions.push(BufferImageCopy {
+            if regions.len() <= upload.page {
+                regions.resize_with(upload.page + 1, Vec::new);
+            }
+            regions[upload.page].push(BufferImageCopy {
                 buffer_offset: current_offset,
                 image_offset: [upload.x, upload.y, 0],
                 image_extent: [upload.width, upload.height, 1],
@@ -142,15 +267,20 @@ impl GuiRenderer {
             )
             .expect("Failed to create staging buffer");
 
-            builder
-                .copy_buffer_to_image(CopyBufferToImageInfo {
-                    regions: regions.into_iter().collect(),
-                    ..CopyBufferToImageInfo::buffer_image(
-                        staging_buffer,
-                        self.atlas.texture.clone(),
-                    )
-                })
-                .expect("Failed to copy buffer to image");
+            for (page, regions) in regions.into_iter().enumerate() {
+                if regions.is_empty() {
+                    continue;
+                }
+                builder
+                    .copy_buffer_to_image(CopyBufferToImageInfo {
+                        regions: regions.into_iter().collect(),
+                        ..CopyBufferToImageInfo::buffer_image(
+                            staging_buffer.clone(),
+                            pages[page].clone(),
+                        )
+                    })
+                    .expect("Failed to copy buffer to image");
+            }
         }
 
         let vertex_count = all_vertices.len();
@@ -158,6 +288,7 @@ impl GuiRenderer {
 
         self.vertex_counts[image_index] = vertex_count as u32;
         self.index_counts[image_index] = index_count as u32;
+        self.batches[image_index] = utils::batch_by_page(&all_vertices, &all_indices);
 
         if vertex_count == 0 || index_count == 0 {
             return;
@@ -210,7 +341,7 @@ impl GuiRenderer {
         image_index: usize,
         builder: &mut AutoCommandBufferBuilder<PrimaryAutoCommandBuffer>,
         pipeline_layout: &Arc<PipelineLayout>,
-        descriptor_set: &Arc<DescriptorSet>,
+        descriptor_sets: &[Arc<DescriptorSet>],
     ) {
         let index_count = self.index_counts[image_index];
         if index_count == 0 {
@@ -221,19 +352,24 @@ impl GuiRenderer {
             &self.vertex_buffers[image_index],
             &self.index_buffers[image_index],
         ) {
-            builder
-                .bind_descriptor_sets(
-                    vulkano::pipeline::PipelineBindPoint::Graphics,
-                    pipeline_layout.clone(),
-                    0,
-                    descriptor_set.clone(),
-                )
-                .unwrap();
-
             builder.bind_vertex_buffers(0, vb.clone()).unwrap();
             builder.bind_index_buffer(ib.clone()).unwrap();
-            unsafe {
-                builder.draw_indexed(index_count, 1, 0, 0, 0).unwrap();
+
+            // One descriptor set per atlas page
+            for batch in &self.batches[image_index] {
+                builder
+                    .bind_descriptor_sets(
+                        vulkano::pipeline::PipelineBindPoint::Graphics,
+                        pipeline_layout.clone(),
+                        0,
+                        descriptor_sets[batch.page].clone(),
+                    )
+                    .unwrap();
+                unsafe {
+                    builder
+                        .draw_indexed(batch.index_count, 1, batch.first_index, 0, 0)
+                        .unwrap();
+                }
             }
         }
     }
@@ -9,9 +9,19 @@ layout(location = 3) in float v_radius;
 layout(location = 4) in float v_stroke_width;
 layout(location = 5) in float v_blur;
 layout(location = 6) in flat uint v_type;
+layout(location = 7) in flat vec4 v_clip;
+layout(location = 8) in vec2 v_pos;
 
 layout(location = 0) out vec4 f_color;
 
+// Flag over the type of the primitive: it is part of a disabled element
+const uint DISABLED = 256u;
+const uint TYPE_MASK = 255u;
+// How much disabled elements are greyed out, and the opacity they keep.
+// Snapshots read them from this file: keep them plain decimals.
+const float DISABLED_DESATURATION = 0.8;
+const float DISABLED_OPACITY = 0.5;
+
 // Standard SDF for a rounded box
 // p: position relative to center
 // b: half-extents (width/2, height/2)
@@ -22,10 +32,24 @@ float sdRoundedBox(vec2 p, vec2 b, float r) {
 }
 
 void main() {
-    // v_type == 1: Text (Texture Sample)
-    // v_type == 0: Rect (SDF)
+    // Outside of the scroll container (or clipping parent) showing it
+    if (v_pos.x < v_clip.x || v_pos.y < v_clip.y || v_pos.x > v_clip.z || v_pos.y > v_clip.w) {
+        discard;
+    }
 
-    if (v_type == 1) {
+    // kind == 3: Image (RGBA texture sample)
+    // kind == 2: Mesh (Flat color)
+    // kind == 1: Text (Texture Sample)
+    // kind == 0: Rect (SDF)
+    uint kind = v_type & TYPE_MASK;
+
+    if (kind == 3) {
+        // Straight alpha texels, tinted by the vertex color
+        vec4 texel = texture(tex, v_uv) * v_color;
+        f_color = vec4(texel.rgb * texel.a, texel.a);
+    } else if (kind == 2) {
+        f_color = vec4(v_color.rgb * v_color.a, v_color.a);
+    } else if (kind == 1) {
         // Sample alpha from texture (assuming single channel format like R8)
         float alpha = texture(tex, v_uv).r;
         f_color = vec4(v_color.rgb * alpha, v_color.a * alpha);
@@ -83,4 +107,11 @@ void main() {
         float final_alpha = v_color.a * alpha;
         f_color = vec4(v_color.rgb * final_alpha, final_alpha);
     }
+
+    if ((v_type & DISABLED) != 0u) {
+        // Premultiplied, the whole color fades
+        float luma = dot(f_color.rgb, vec3(0.299, 0.587, 0.114));
+        f_color = vec4(mix(f_color.rgb, vec3(luma), DISABLED_DESATURATION), f_color.a)
+            * DISABLED_OPACITY;
+    }
 }
@@ -8,6 +8,7 @@ layout(location = 4) in float radius;
 layout(location = 5) in float stroke_width;
 layout(location = 6) in float blur;
 layout(location = 7) in uint obj_type;
+layout(location = 8) in vec4 clip;
 
 layout(location = 0) out vec4 v_color;
 layout(location = 1) out vec2 v_uv;
@@ -16,6 +17,8 @@ layout(location = 3) out float v_radius;
 layout(location = 4) out float v_stroke_width;
 layout(location = 5) out float v_blur;
 layout(location = 6) out flat uint v_type;
+layout(location = 7) out flat vec4 v_clip;
+layout(location = 8) out vec2 v_pos;
 
 layout(push_constant) uniform PushConstants {
     vec2 screen_size;
@@ -33,4 +36,6 @@ void main() {
     v_stroke_width = stroke_width;
     v_blur = blur;
     v_type = obj_type;
+    v_clip = clip;
+    v_pos = position;
 }
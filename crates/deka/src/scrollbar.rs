@@ -0,0 +1,126 @@
+//! The scrollbars `Overflow::Auto` containers show follow the pointer:
+//! their thumbs can be dragged, and a press on a track scrolls a page
+//! towards the cursor.
+
+use heka::CapsuleRef;
+use heka::scrollbar::Scrollbar;
+
+use crate::Context;
+
+/// A thumb dragged along its track
+pub(crate) struct ThumbDrag {
+    container: CapsuleRef,
+    vertical: bool,
+    /// Distance from the start of the thumb to where it was grabbed
+    grab: f64,
+}
+
+/// Position along the axis of a scrollbar, and the start and length of its
+/// track and thumb
+fn along(scrollbar: &Scrollbar, vertical: bool, x: f64, y: f64) -> (f64, f64, f64, f64, f64) {
+    let (track, thumb) = (scrollbar.track, scrollbar.thumb);
+    if vertical {
+        (
+            y,
+            track.y as f64,
+            track.h as f64,
+            thumb.y as f64,
+            thumb.h as f64,
+        )
+    } else {
+        (
+            x,
+            track.x as f64,
+            track.w as f64,
+            thumb.x as f64,
+            thumb.w as f64,
+        )
+    }
+}
+
+impl Context {
+    /// Grabs the thumb under the cursor, or scrolls a page on a press on a
+    /// track. Returns whether the press went to a scrollbar.
+    pub(crate) fn scrollbar_press(&mut self) -> bool {
+        let pos = self.mouse_pos;
+        let (x, y) = (pos.x.ceil() as i32, pos.y.ceil() as i32);
+
+        for cref in self.hit_candidates() {
+            let scrollbars = self.root.scrollbars(cref);
+            for (scrollbar, vertical) in
+                [(scrollbars.vertical, true), (scrollbars.horizontal, false)]
+            {
+                let Some(scrollbar) = scrollbar.filter(|scrollbar| scrollbar.track.contains(x, y))
+                else {
+                    continue;
+                };
+                let (at, _, _, thumb_start, _) = along(&scrollbar, vertical, pos.x, pos.y);
+                if scrollbar.thumb.contains(x, y) {
+                    self.thumb_drag = Some(ThumbDrag {
+                        container: cref,
+                        vertical,
+                        grab: at - thumb_start,
+                    });
+                    return true;
+                }
+
+                let view = self
+                    .root
+                    .get_content_rect(cref)
+                    .map_or(0.0, |view| (if vertical { view.h } else { view.w }) as f32);
+                let page = if at < thumb_start { -view } else { view };
+                let (dx, dy) = if vertical { (0.0, page) } else { (page, 0.0) };
+                if self.root.scroll_by(cref, dx, dy) {
+                    // The cursor may be above another element now
+                    self.pointer_moved = true;
+                    self.dispatch_scroll_offsets();
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Lets go of the dragged thumb. Returns whether there was one.
+    pub(crate) fn scrollbar_release(&mut self) -> bool {
+        self.thumb_drag.take().is_some()
+    }
+
+    /// Moves the dragged thumb under the cursor, and the content with it.
+    /// Returns whether a thumb is dragged.
+    pub(crate) fn scrollbar_drag(&mut self) -> bool {
+        let Some(drag) = &self.thumb_drag else {
+            return false;
+        };
+        let (cref, vertical, grab) = (drag.container, drag.vertical, drag.grab);
+
+        let scrollbars = self.root.scrollbars(cref);
+        let Some(scrollbar) = (if vertical {
+            scrollbars.vertical
+        } else {
+            scrollbars.horizontal
+        }) else {
+            return true;
+        };
+        let (at, track_start, track_len, _, thumb_len) =
+            along(&scrollbar, vertical, self.mouse_pos.x, self.mouse_pos.y);
+        let free = track_len - thumb_len;
+        let ratio = if free > 0.0 {
+            ((at - grab - track_start) / free).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        };
+
+        let (x, y) = self.root.scroll_offset(cref);
+        let (max_x, max_y) = self.root.max_scroll_offset(cref);
+        let moved = if vertical {
+            self.root.set_scroll_offset(cref, x, ratio * max_y)
+        } else {
+            self.root.set_scroll_offset(cref, ratio * max_x, y)
+        };
+        if moved {
+            self.dispatch_scroll_offsets();
+        }
+        true
+    }
+}
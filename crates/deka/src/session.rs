@@ -0,0 +1,369 @@
+//! Session recording and input replay.
+//!
+//! Sessions are stored one event per line: the time since the recording
+//! started (in microseconds), the event kind and its fields.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use winit::dpi::PhysicalPosition;
+use winit::event::MouseButton;
+use winit::keyboard::{Key, NamedKey, SmolStr};
+
+use crate::Context;
+use crate::events::{GesturePhase, SystemEvent};
+
+/// Named keys a session can hold
+const NAMED_KEYS: &[NamedKey] = &[
+    NamedKey::Enter,
+    NamedKey::Tab,
+    NamedKey::Space,
+    NamedKey::Backspace,
+    NamedKey::Delete,
+    NamedKey::Insert,
+    NamedKey::Escape,
+    NamedKey::ArrowUp,
+    NamedKey::ArrowDown,
+    NamedKey::ArrowLeft,
+    NamedKey::ArrowRight,
+    NamedKey::Home,
+    NamedKey::End,
+    NamedKey::PageUp,
+    NamedKey::PageDown,
+    NamedKey::Shift,
+    NamedKey::Control,
+    NamedKey::Alt,
+    NamedKey::Super,
+    NamedKey::CapsLock,
+    NamedKey::F1,
+    NamedKey::F2,
+    NamedKey::F3,
+    NamedKey::F4,
+    NamedKey::F5,
+    NamedKey::F6,
+    NamedKey::F7,
+    NamedKey::F8,
+    NamedKey::F9,
+    NamedKey::F10,
+    NamedKey::F11,
+    NamedKey::F12,
+];
+
+/// Writes the events of a running session to a file
+pub(crate) struct Recorder {
+    start: Instant,
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub(crate) fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            start: Instant::now(),
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: &SystemEvent) -> io::Result<()> {
+        let Some(line) = encode(event) else {
+            return Ok(());
+        };
+        let micros = self.start.elapsed().as_micros();
+        writeln!(self.writer, "{micros} {line}")
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Recorded events, in order
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    events: Vec<(Duration, SystemEvent)>,
+}
+
+impl Session {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut events = Vec::new();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = decode(&line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid session event on line {}: {line}", number + 1),
+                )
+            })?;
+            events.push(event);
+        }
+
+        Ok(Self { events })
+    }
+
+    /// Events with their time since the start of the recording
+    pub fn events(&self) -> &[(Duration, SystemEvent)] {
+        &self.events
+    }
+
+    /// Length of the recording
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|(at, _)| *at).unwrap_or_default()
+    }
+}
+
+/// Session being replayed live by the event loop
+pub(crate) struct Playback {
+    session: Session,
+    start: Instant,
+    next: usize,
+}
+
+impl Context {
+    /// Starts writing every incoming `SystemEvent` to `path`,
+    /// replacing any recording in progress.
+    pub fn record_session(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.stop_recording()?;
+        self.recorder = Some(Recorder::create(path)?);
+        Ok(())
+    }
+
+    /// Stops the recording in progress, if any, and flushes it.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs all the events of a session at once, laying the UI out before
+    /// each one as a frame would. Useful to reproduce interactions in tests.
+    ///
+    /// The session keeps its timing: `now` moves to the time of each event,
+    /// and the timers due in between run at their time.
+    pub fn replay(&mut self, session: &Session) {
+        let start = Instant::now();
+        self.clock.set_replay_time(Some(start));
+        for (at, event) in session.events() {
+            self.advance_replay_time(start + *at);
+            self.replay_event(event.clone());
+        }
+        self.compute_layout();
+        self.clock.set_replay_time(None);
+    }
+
+    /// Moves the replay time to `to`, running the timers due on the way
+    fn advance_replay_time(&mut self, to: Instant) {
+        while let Some(due) = self.timers.next_due().filter(|due| *due <= to) {
+            self.clock.set_replay_time(Some(due.max(self.now())));
+            self.run_due_timers();
+        }
+        self.clock.set_replay_time(Some(to));
+    }
+
+    /// Replays a session in the running event loop, respecting its timing
+    pub fn start_replay(&mut self, session: Session) {
+        self.playback = Some(Playback {
+            session,
+            start: Instant::now(),
+            next: 0,
+        });
+    }
+
+    #[inline]
+    pub fn is_replaying(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Processes the replayed events that are due.
+    /// Returns when the next one is, `None` once the replay is over.
+    pub(crate) fn pump_replay(&mut self) -> Option<Instant> {
+        let mut playback = self.playback.take()?;
+        let elapsed = playback.start.elapsed();
+
+        while let Some((at, event)) = playback.session.events.get(playback.next) {
+            if *at > elapsed {
+                let next = playback.start + *at;
+                self.playback = Some(playback);
+                return Some(next);
+            }
+            let event = event.clone();
+            playback.next += 1;
+            self.replay_event(event);
+        }
+        None
+    }
+
+    fn replay_event(&mut self, event: SystemEvent) {
+        // Hit testing relies on an up-to-date layout
+        if self.is_dirty() {
+            self.compute_layout();
+        }
+        self.process_event(event);
+        self.dispatch_pointer_move();
+    }
+
+    pub(crate) fn record_event(&mut self, event: &SystemEvent) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if let Err(e) = recorder.record(event) {
+            log::warn!("Session recording stopped: {e}");
+            self.recorder = None;
+        }
+    }
+}
+
+fn encode(event: &SystemEvent) -> Option<String> {
+    Some(match event {
+        SystemEvent::Click {
+            pos,
+            button,
+            pressed,
+            double_click,
+        } => format!(
+            "click {} {} {} {} {}",
+            pos.x,
+            pos.y,
+            encode_button(*button),
+            *pressed as u8,
+            *double_click as u8
+        ),
+        SystemEvent::CursorMoved(pos) => format!("move {} {}", pos.x, pos.y),
+        SystemEvent::Keyboard {
+            logical_key,
+            text,
+            pressed,
+            repeat,
+        } => {
+            let key = match logical_key {
+                Key::Character(c) => format!("char:{}", hex(c)),
+                Key::Named(named) if NAMED_KEYS.contains(named) => format!("named:{named:?}"),
+                _ => return None,
+            };
+            let text = text.as_ref().map(|t| hex(t)).unwrap_or_else(|| "-".into());
+            format!("key {} {key} {text} {}", *pressed as u8, *repeat as u8)
+        }
+        SystemEvent::Scroll(delta) => format!("scroll {} {}", delta.x, delta.y),
+        SystemEvent::ScrollPhase(phase) => format!("scrollphase {}", encode_phase(*phase)),
+        SystemEvent::Pinch { delta, phase } => format!("pinch {delta} {}", encode_phase(*phase)),
+        SystemEvent::Resize(w, h) => format!("resize {w} {h}"),
+        SystemEvent::RequestRedraw => "redraw".into(),
+    })
+}
+
+fn decode(line: &str) -> Option<(Duration, SystemEvent)> {
+    let mut fields = line.split_whitespace();
+    let at = Duration::from_micros(fields.next()?.parse().ok()?);
+    let mut next = || fields.next();
+
+    let event = match next()? {
+        "click" => SystemEvent::Click {
+            pos: PhysicalPosition::new(next()?.parse().ok()?, next()?.parse().ok()?),
+            button: decode_button(next()?)?,
+            pressed: next()? == "1",
+            double_click: next()? == "1",
+        },
+        "move" => SystemEvent::CursorMoved(PhysicalPosition::new(
+            next()?.parse().ok()?,
+            next()?.parse().ok()?,
+        )),
+        "key" => {
+            let pressed = next()? == "1";
+            let logical_key = match next()?.split_once(':')? {
+                ("char", c) => Key::Character(SmolStr::new(unhex(c)?)),
+                ("named", name) => Key::Named(
+                    *NAMED_KEYS
+                        .iter()
+                        .find(|named| format!("{named:?}") == name)?,
+                ),
+                _ => return None,
+            };
+            let text = match next()? {
+                "-" => None,
+                t => Some(SmolStr::new(unhex(t)?)),
+            };
+            // Sessions recorded before repeats were tracked don't have the field
+            let repeat = next() == Some("1");
+            SystemEvent::Keyboard {
+                logical_key,
+                text,
+                pressed,
+                repeat,
+            }
+        }
+        "scroll" => SystemEvent::Scroll(PhysicalPosition::new(
+            next()?.parse().ok()?,
+            next()?.parse().ok()?,
+        )),
+        "scrollphase" => SystemEvent::ScrollPhase(decode_phase(next()?)?),
+        "pinch" => SystemEvent::Pinch {
+            delta: next()?.parse().ok()?,
+            phase: decode_phase(next()?)?,
+        },
+        "resize" => SystemEvent::Resize(next()?.parse().ok()?, next()?.parse().ok()?),
+        "redraw" => SystemEvent::RequestRedraw,
+        _ => return None,
+    };
+    Some((at, event))
+}
+
+fn encode_phase(phase: GesturePhase) -> &'static str {
+    match phase {
+        GesturePhase::Begin => "begin",
+        GesturePhase::Update => "update",
+        GesturePhase::End => "end",
+    }
+}
+
+fn decode_phase(s: &str) -> Option<GesturePhase> {
+    match s {
+        "begin" => Some(GesturePhase::Begin),
+        "update" => Some(GesturePhase::Update),
+        "end" => Some(GesturePhase::End),
+        _ => None,
+    }
+}
+
+fn encode_button(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => "left".into(),
+        MouseButton::Right => "right".into(),
+        MouseButton::Middle => "middle".into(),
+        MouseButton::Back => "back".into(),
+        MouseButton::Forward => "forward".into(),
+        MouseButton::Other(n) => format!("other:{n}"),
+    }
+}
+
+fn decode_button(s: &str) -> Option<MouseButton> {
+    Some(match s {
+        "left" => MouseButton::Left,
+        "right" => MouseButton::Right,
+        "middle" => MouseButton::Middle,
+        "back" => MouseButton::Back,
+        "forward" => MouseButton::Forward,
+        other => MouseButton::Other(other.strip_prefix("other:")?.parse().ok()?),
+    })
+}
+
+/// Text is hex encoded to keep one event per line, whatever it contains
+fn hex(s: &str) -> String {
+    s.bytes().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<String> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,172 @@
+//! Shaping long label texts on worker threads, see
+//! `Context::set_shaping_threads`.
+//!
+//! Workers own a `FontSystem` cloned from the one of the `Context`: fonts
+//! keep the same ids, so their buffers can be drawn by the UI thread. The
+//! shaped buffers come back as `UiHandle` tasks.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use cosmic_text::{Buffer, FontSystem};
+
+use crate::elements::{Label, fill_buffer};
+use crate::{Context, LabelRef, TextStyle, UiHandle};
+
+/// Texts shorter than this (in bytes) are shaped right away, handing them
+/// over would cost more than shaping them
+pub(crate) const ASYNC_SHAPING_MIN_LEN: usize = 1024;
+
+struct Job {
+    id: u64,
+    label: LabelRef,
+    text: String,
+    style: TextStyle,
+}
+
+/// Worker threads of a `Context`
+pub(crate) struct ShapingPool {
+    threads: usize,
+    /// Workers stop once their queue is closed
+    jobs: Sender<Job>,
+    next_id: u64,
+}
+
+impl ShapingPool {
+    fn new(threads: usize, font_system: &FontSystem, handle: UiHandle) -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for index in 0..threads {
+            let receiver = receiver.clone();
+            let handle = handle.clone();
+            let locale = font_system.locale().to_string();
+            let db = font_system.db().clone();
+            let spawned = thread::Builder::new()
+                .name(format!("deka-shaping-{index}"))
+                .spawn(move || {
+                    let font_system = FontSystem::new_with_locale_and_db(locale, db);
+                    work(font_system, &receiver, &handle);
+                });
+            if let Err(e) = spawned {
+                log::warn!("Unable to start a shaping thread: {e}");
+            }
+        }
+
+        Self {
+            threads,
+            jobs,
+            next_id: 0,
+        }
+    }
+}
+
+fn work(mut font_system: FontSystem, receiver: &Mutex<Receiver<Job>>, handle: &UiHandle) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => match receiver.recv() {
+                Ok(job) => job,
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+
+        let mut buffer = Buffer::new(&mut font_system, job.style.as_cosmic_metrics());
+        fill_buffer(&mut buffer, &mut font_system, &job.text, &job.style);
+
+        let (label, id) = (job.label, job.id);
+        if !handle.run(move |ctx| ctx.apply_shaped_text(label, id, buffer)) {
+            return;
+        }
+    }
+}
+
+impl Context {
+    /// Shapes the label texts of `ASYNC_SHAPING_MIN_LEN` bytes or more on
+    /// `threads` worker threads, so long paragraphs don't hold the frame.
+    /// Such labels keep showing their previous text until the new one is
+    /// shaped, usually the next frame; `get_label_text` already returns it.
+    /// `0` (the default) shapes everything on the UI thread.
+    pub fn set_shaping_threads(&mut self, threads: usize) {
+        let pending = self.shaping.as_ref().map_or(0, |pool| pool.next_id);
+        // Dropping the pool closes its queue, its workers finish the texts
+        // they were given and stop
+        self.shaping = (threads > 0).then(|| {
+            let mut pool = ShapingPool::new(threads, &self.font_system, self.handle());
+            // Ids stay unique, results of the previous pool may still come
+            pool.next_id = pending;
+            pool
+        });
+    }
+
+    #[inline]
+    pub fn shaping_threads(&self) -> usize {
+        self.shaping.as_ref().map_or(0, |pool| pool.threads)
+    }
+
+    /// Whether a label waits for its text to be shaped
+    pub fn is_label_shaping(&self, element: LabelRef) -> bool {
+        self.elements
+            .get(&element.0)
+            .and_then(|element| element.as_any().downcast_ref::<Label>())
+            .is_some_and(|label| label.pending_shape.is_some())
+    }
+
+    /// Workers see the fonts loaded when they started, they are started
+    /// again with the new ones
+    pub(crate) fn restart_shaping(&mut self) {
+        let threads = self.shaping_threads();
+        if threads > 0 {
+            self.set_shaping_threads(threads);
+        }
+    }
+
+    /// Hands the text of a label to the workers. Returns false when it is
+    /// to be shaped right away.
+    pub(crate) fn shape_label_async(&mut self, element: LabelRef, text: &str) -> bool {
+        if text.len() < ASYNC_SHAPING_MIN_LEN {
+            return false;
+        }
+        let Some(pool) = self.shaping.as_mut() else {
+            return false;
+        };
+        let Some(label) = self
+            .elements
+            .get_mut(&element.0)
+            .and_then(|element| element.as_any_mut().downcast_mut::<Label>())
+        else {
+            return false;
+        };
+        if label.text == text {
+            return true;
+        }
+
+        let id = pool.next_id;
+        let job = Job {
+            id,
+            label: element,
+            text: text.to_string(),
+            style: label.text_style.clone(),
+        };
+        if pool.jobs.send(job).is_err() {
+            return false;
+        }
+        pool.next_id += 1;
+        label.text = text.to_string();
+        label.pending_shape = Some(id);
+        true
+    }
+
+    /// Shows the text a worker shaped, unless the label changed since
+    fn apply_shaped_text(&mut self, element: LabelRef, id: u64, buffer: Buffer) {
+        if !self.elements.contains_key(&element.0) {
+            return;
+        }
+        self.with_component_mut::<Label>(element.0, |label, ctx| {
+            if label.pending_shape == Some(id) {
+                label.set_buffer(&mut ctx.root, &mut ctx.buffer_pool, buffer);
+            }
+        });
+    }
+}
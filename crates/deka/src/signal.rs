@@ -0,0 +1,180 @@
+//! Signals: values held by the context that elements follow. Setting one
+//! updates every element bound to it, see `Context::bind`. The `eka!`
+//! macro binds its fields given as `signal(...)`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use heka::CapsuleRef;
+
+use crate::{Context, ElementRef};
+
+/// A value of type `T` held by a `Context`, see `Context::create_signal`
+#[derive(Debug)]
+pub struct Signal<T> {
+    id: usize,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Signal<T> {
+    /// Gives `map` its argument type, for `eka!` to call it with values of
+    /// the signal
+    #[doc(hidden)]
+    pub fn mapper<U, F: FnMut(&T) -> U>(self, map: F) -> F {
+        map
+    }
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Signal<T> {}
+
+impl<T> PartialEq for Signal<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Signal<T> {}
+
+type Apply = Box<dyn FnMut(&mut Context, &dyn Any)>;
+
+struct Observer {
+    /// Element the observer updates, it is dropped along with it
+    owner: Option<CapsuleRef>,
+    apply: Apply,
+}
+
+struct Slot {
+    value: Box<dyn Any>,
+    observers: Vec<Observer>,
+}
+
+#[derive(Default)]
+pub(crate) struct Signals {
+    next_id: usize,
+    slots: HashMap<usize, Slot>,
+}
+
+impl Signals {
+    pub(crate) fn create<T: Clone + 'static>(&mut self, value: T) -> Signal<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots.insert(
+            id,
+            Slot {
+                value: Box::new(value),
+                observers: Vec::new(),
+            },
+        );
+        Signal {
+            id,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl Context {
+    pub fn create_signal<T: Clone + 'static>(&mut self, value: T) -> Signal<T> {
+        self.signals.create(value)
+    }
+
+    /// A copy of the value of `signal`
+    pub fn get_signal<T: Clone + 'static>(&self, signal: Signal<T>) -> T {
+        self.signals
+            .slots
+            .get(&signal.id)
+            .and_then(|slot| slot.value.downcast_ref::<T>())
+            .cloned()
+            .expect("signals live as long as their context")
+    }
+
+    /// Changes the value of `signal` and updates what follows it. An
+    /// observer setting the signal it observes does not run again, the
+    /// observers after it get the new value.
+    pub fn set_signal<T: Clone + 'static>(&mut self, signal: Signal<T>, value: T) {
+        let Some(slot) = self.signals.slots.get_mut(&signal.id) else {
+            return;
+        };
+        slot.value = Box::new(value);
+        let mut observers = std::mem::take(&mut slot.observers);
+
+        observers.retain(|observer| {
+            observer
+                .owner
+                .is_none_or(|owner| self.elements.contains_key(&owner))
+        });
+        for observer in &mut observers {
+            // Read again, an observer before may have set the signal
+            let value = self.get_signal(signal);
+            // A panic must not lose the observers taken out of the slot
+            self.guarded(|ctx| (observer.apply)(ctx, &value));
+        }
+
+        // Observers added meanwhile come after
+        if let Some(slot) = self.signals.slots.get_mut(&signal.id) {
+            observers.append(&mut slot.observers);
+            slot.observers = observers;
+        }
+    }
+
+    /// Sets `signal` to `update` applied to its value
+    pub fn update_signal<T: Clone + 'static>(
+        &mut self,
+        signal: Signal<T>,
+        update: impl FnOnce(&mut T),
+    ) {
+        let mut value = self.get_signal(signal);
+        update(&mut value);
+        self.set_signal(signal, value);
+    }
+
+    /// Runs `observer` with every new value of `signal`
+    pub fn observe<T: 'static, F>(&mut self, signal: Signal<T>, mut observer: F)
+    where
+        F: FnMut(&mut Context, &T) + 'static,
+    {
+        self.add_observer(
+            signal,
+            None,
+            Box::new(move |ctx, value| {
+                if let Some(value) = value.downcast_ref::<T>() {
+                    observer(ctx, value);
+                }
+            }),
+        );
+    }
+
+    /// Updates `element` with `apply` on every new value of `signal`, until
+    /// the element is removed. The current value is not applied: the
+    /// element is expected to be created from it.
+    pub fn bind<T: 'static, E: ElementRef + 'static, F>(
+        &mut self,
+        signal: Signal<T>,
+        element: E,
+        mut apply: F,
+    ) where
+        F: FnMut(&mut Context, E, &T) + 'static,
+    {
+        self.add_observer(
+            signal,
+            Some(element.raw()),
+            Box::new(move |ctx, value| {
+                if let Some(value) = value.downcast_ref::<T>() {
+                    apply(ctx, element, value);
+                }
+            }),
+        );
+    }
+
+    fn add_observer<T>(&mut self, signal: Signal<T>, owner: Option<CapsuleRef>, apply: Apply) {
+        if let Some(slot) = self.signals.slots.get_mut(&signal.id) {
+            slot.observers.push(Observer { owner, apply });
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! Snapshots: an element and its children drawn into an image, see
+//! `Context::snapshot_element`. They are drawn on the CPU the way
+//! `rect.frag.glsl` draws them on screen, so no window nor GPU is needed.
+
+use heka::color::Color;
+use heka::geometry::Rect;
+
+use crate::cmd::DrawCommand;
+use crate::elements::ImageData;
+use crate::renderer::gui::utils::NO_CLIP;
+use crate::{Context, ElementRef};
+
+/// The shader snapshots are drawn like
+const RECT_FRAG: &str = include_str!("renderer/shaders/rect.frag.glsl");
+
+/// How much disabled elements are greyed out, and the opacity they keep,
+/// taken from the shader
+const DISABLED_DESATURATION: f32 = shader_float("DISABLED_DESATURATION");
+const DISABLED_OPACITY: f32 = shader_float("DISABLED_OPACITY");
+
+/// Value of `const float <name> = <decimal>;` in `RECT_FRAG`, failing the
+/// build if the shader has no such constant
+const fn shader_float(name: &str) -> f32 {
+    const DECLARATION: &[u8] = b"const float ";
+    let source = RECT_FRAG.as_bytes();
+    let name = name.as_bytes();
+
+    let mut at = 0;
+    while at < source.len() {
+        if bytes_at(source, at, DECLARATION) && bytes_at(source, at + DECLARATION.len(), name) {
+            at += DECLARATION.len() + name.len();
+            let mut digits: u64 = 0;
+            let mut divisor: u64 = 1;
+            let mut fraction = false;
+            while source[at] != b';' {
+                match source[at] {
+                    b'0'..=b'9' => {
+                        digits = digits * 10 + (source[at] - b'0') as u64;
+                        if fraction {
+                            divisor *= 10;
+                        }
+                    }
+                    b'.' => fraction = true,
+                    b' ' | b'=' => {}
+                    _ => panic!("shader constant is not a plain decimal"),
+                }
+                at += 1;
+            }
+            return digits as f32 / divisor as f32;
+        }
+        at += 1;
+    }
+    panic!("no such float constant in rect.frag.glsl")
+}
+
+/// Whether `bytes` holds `pattern` at `at`
+const fn bytes_at(bytes: &[u8], at: usize, pattern: &[u8]) -> bool {
+    if at + pattern.len() > bytes.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < pattern.len() {
+        if bytes[at + i] != pattern[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// How the primitives of a command are drawn
+#[derive(Clone, Copy)]
+struct Paint {
+    /// Area they are drawn in, as `[left, top, right, bottom]`
+    clip: [f32; 4],
+    /// They are part of a disabled element
+    disabled: bool,
+}
+
+/// Premultiplied pixels covering a part of the layout
+struct Pixmap {
+    width: u32,
+    height: u32,
+    /// Layout position of the top-left corner
+    origin: (f32, f32),
+    /// Pixels per layout unit
+    scale: f32,
+    pixels: Vec<[f32; 4]>,
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Distance from `p` to a box of half-extents `half` centered on the origin,
+/// its corners rounded by `radius`. Negative inside.
+fn rounded_box(p: (f32, f32), half: (f32, f32), radius: f32) -> f32 {
+    let q = (p.0.abs() - half.0 + radius, p.1.abs() - half.1 + radius);
+    q.0.max(q.1).min(0.0) + q.0.max(0.0).hypot(q.1.max(0.0)) - radius
+}
+
+/// Premultiplied `color`, with `coverage` of it
+fn premultiplied(color: [f32; 4], coverage: f32) -> [f32; 4] {
+    let alpha = color[3] * coverage;
+    [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha]
+}
+
+impl Pixmap {
+    fn new(rect: Rect, scale: f32) -> Self {
+        let width = (rect.w as f32 * scale).round().max(1.0) as u32;
+        let height = (rect.h as f32 * scale).round().max(1.0) as u32;
+        Self {
+            width,
+            height,
+            origin: (rect.x as f32, rect.y as f32),
+            scale,
+            pixels: vec![[0.0; 4]; width as usize * height as usize],
+        }
+    }
+
+    /// Blends the premultiplied color `shade` gives at the layout position
+    /// of the center of every pixel in `area` (`[left, top, right, bottom]`)
+    /// and in the clip over that pixel
+    fn shade(
+        &mut self,
+        area: [f32; 4],
+        paint: Paint,
+        shade: impl Fn(f32, f32) -> Option<[f32; 4]>,
+    ) {
+        let [left, top, right, bottom] = [
+            area[0].max(paint.clip[0]),
+            area[1].max(paint.clip[1]),
+            area[2].min(paint.clip[2]),
+            area[3].min(paint.clip[3]),
+        ];
+        let (scale, origin) = (self.scale, self.origin);
+        // Pixels from the one `from` is in to the one `to` is in
+        let span = |from: f32, to: f32, origin: f32, count: u32| {
+            let first = ((from - origin) * scale).floor().clamp(0.0, count as f32);
+            let last = ((to - origin) * scale).ceil().clamp(0.0, count as f32);
+            first as u32..last as u32
+        };
+
+        for py in span(top, bottom, origin.1, self.height) {
+            let y = origin.1 + (py as f32 + 0.5) / scale;
+            if y < top || y >= bottom {
+                continue;
+            }
+            for px in span(left, right, origin.0, self.width) {
+                let x = origin.0 + (px as f32 + 0.5) / scale;
+                if x < left || x >= right {
+                    continue;
+                }
+                if let Some(color) = shade(x, y) {
+                    self.blend(px, py, color, paint.disabled);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, px: u32, py: u32, mut color: [f32; 4], disabled: bool) {
+        if disabled {
+            let luma = color[0] * 0.299 + color[1] * 0.587 + color[2] * 0.114;
+            for channel in &mut color[..3] {
+                *channel += (luma - *channel) * DISABLED_DESATURATION;
+            }
+            for channel in &mut color {
+                *channel *= DISABLED_OPACITY;
+            }
+        }
+        let pixel = &mut self.pixels[(py * self.width + px) as usize];
+        let kept = 1.0 - color[3];
+        for (dst, src) in pixel.iter_mut().zip(color) {
+            *dst = src + *dst * kept;
+        }
+    }
+
+    /// A quad of `DrawCommand::rect_vertices`: `rect` (`[x, y, w, h]`)
+    /// filled, stroked when `stroke_width` > 0, or its shadow when
+    /// `blur` > 0
+    fn rounded_rect(
+        &mut self,
+        [x, y, w, h]: [f32; 4],
+        color: Color,
+        radius: f32,
+        stroke_width: f32,
+        blur: f32,
+        paint: Paint,
+    ) {
+        let color: [f32; 4] = color.into();
+        let center = (x + w / 2.0, y + h / 2.0);
+        let half = (w / 2.0, h / 2.0);
+        let spread = blur.max(0.0);
+        let area = [x - spread, y - spread, x + w + spread, y + h + spread];
+
+        self.shade(area, paint, |px, py| {
+            let dist = rounded_box((px - center.0, py - center.1), half, radius);
+            let alpha = if blur > 0.0 {
+                1.0 - smoothstep(-blur, blur, dist)
+            } else if stroke_width > 0.0 {
+                (1.0 - smoothstep(-0.5, 0.5, dist))
+                    * smoothstep(-stroke_width - 0.5, -stroke_width + 0.5, dist)
+            } else {
+                1.0 - smoothstep(-0.5, 0.5, dist)
+            };
+            (alpha > 0.0).then(|| premultiplied(color, alpha))
+        });
+    }
+
+    /// A flat-colored triangle, pixels whose center is inside it
+    fn triangle(&mut self, [a, b, c]: [[f32; 2]; 3], color: [f32; 4], paint: Paint) {
+        let edge = |p: [f32; 2], q: [f32; 2], x: f32, y: f32| {
+            (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0])
+        };
+        let winding = edge(a, b, c[0], c[1]);
+        if winding == 0.0 {
+            return;
+        }
+        let area = [
+            a[0].min(b[0]).min(c[0]),
+            a[1].min(b[1]).min(c[1]),
+            a[0].max(b[0]).max(c[0]),
+            a[1].max(b[1]).max(c[1]),
+        ];
+        let color = premultiplied(color, 1.0);
+
+        self.shade(area, paint, |x, y| {
+            let sides = [edge(a, b, x, y), edge(b, c, x, y), edge(c, a, x, y)];
+            sides
+                .iter()
+                .all(|side| side * winding >= 0.0)
+                .then_some(color)
+        });
+    }
+
+    fn draw(&mut self, ctx: &mut Context, command: &DrawCommand, paint: Paint) {
+        match command {
+            DrawCommand::Clipped { clip, command } => {
+                let [left, top, right, bottom] = paint.clip;
+                let clip = [
+                    left.max(clip.x as f32),
+                    top.max(clip.y as f32),
+                    right.min(clip.x as f32 + clip.w as f32),
+                    bottom.min(clip.y as f32 + clip.h as f32),
+                ];
+                self.draw(ctx, command, Paint { clip, ..paint });
+            }
+            DrawCommand::Disabled { command } => {
+                self.draw(
+                    ctx,
+                    command,
+                    Paint {
+                        disabled: true,
+                        ..paint
+                    },
+                );
+            }
+            DrawCommand::Rect {
+                rect,
+                fill_color,
+                stroke_color,
+                z_index: _,
+                border_radius,
+                stroke_width,
+                shadow_color,
+                shadow_blur,
+            } => {
+                let bounds = [rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32];
+                let radius = *border_radius as f32;
+                if shadow_color.a > 0 && *shadow_blur > 0.0 {
+                    self.rounded_rect(bounds, *shadow_color, radius, 0.0, *shadow_blur, paint);
+                }
+                if fill_color.a > 0 {
+                    self.rounded_rect(bounds, *fill_color, radius, 0.0, 0.0, paint);
+                }
+                if stroke_color.a > 0 && *stroke_width > 0 {
+                    let stroke_width = *stroke_width as f32;
+                    self.rounded_rect(bounds, *stroke_color, radius, stroke_width, 0.0, paint);
+                }
+            }
+            DrawCommand::Mesh {
+                points,
+                indices,
+                color,
+                z_index: _,
+            } => {
+                let color: [f32; 4] = (*color).into();
+                for triangle in indices.chunks_exact(3) {
+                    let corner = |index: u32| points.get(index as usize).copied();
+                    if let (Some(a), Some(b), Some(c)) = (
+                        corner(triangle[0]),
+                        corner(triangle[1]),
+                        corner(triangle[2]),
+                    ) {
+                        self.triangle([a, b, c], color, paint);
+                    }
+                }
+            }
+            DrawCommand::Image {
+                rect,
+                texture_ref,
+                uv: [u0, v0, u1, v1],
+                tint,
+                z_index: _,
+            } => {
+                let Some(image) = ctx.get_buffer::<ImageData>(*texture_ref).cloned() else {
+                    return;
+                };
+                let tint: [f32; 4] = (*tint).into();
+                let [x, y, w, h] = [rect.x as f32, rect.y as f32, rect.w as f32, rect.h as f32];
+                let (width, height) = (image.width(), image.height());
+
+                // Nearest texel, as the atlas is sampled
+                self.shade([x, y, x + w, y + h], paint, |px, py| {
+                    let u = u0 + (px - x) / w * (u1 - u0);
+                    let v = v0 + (py - y) / h * (v1 - v0);
+                    let tx = ((u * width as f32) as u32).min(width - 1);
+                    let ty = ((v * height as f32) as u32).min(height - 1);
+                    let at = (ty * width + tx) as usize * 4;
+                    let texel = &image.pixels()[at..at + 4];
+                    let color: [f32; 4] =
+                        std::array::from_fn(|i| texel[i] as f32 / 255.0 * tint[i]);
+                    Some(premultiplied(color, 1.0))
+                });
+            }
+            #[cfg(feature = "text")]
+            DrawCommand::Text {
+                buffer_ref,
+                rect,
+                style,
+                z_index: _,
+            } => {
+                let Some(buffer) = ctx.get_buffer::<cosmic_text::Buffer>(*buffer_ref).cloned()
+                else {
+                    return;
+                };
+                let [r, g, b, a]: [f32; 4] = style.color.into();
+                let scale = self.scale;
+
+                for run in buffer.layout_runs() {
+                    for glyph in run.glyphs.iter() {
+                        let phys = glyph.physical(
+                            (rect.x as f32 * scale, (rect.y as f32 + run.line_y) * scale),
+                            scale,
+                        );
+                        let Some(image) = ctx
+                            .swash_cache
+                            .get_image(&mut ctx.font_system, phys.cache_key)
+                            .clone()
+                        else {
+                            continue;
+                        };
+
+                        let placement = image.placement;
+                        let x = (phys.x + placement.left) as f32 / scale;
+                        let y = (phys.y - placement.top) as f32 / scale;
+                        let w = placement.width as f32 / scale;
+                        let h = placement.height as f32 / scale;
+                        // Coverage is the last byte of a pixel
+                        let stride = match image.content {
+                            cosmic_text::SwashContent::Mask => 1,
+                            _ => 4,
+                        };
+
+                        self.shade([x, y, x + w, y + h], paint, |px, py| {
+                            let gx = (((px - x) * scale) as u32).min(placement.width - 1);
+                            let gy = (((py - y) * scale) as u32).min(placement.height - 1);
+                            let at = (gy * placement.width + gx) as usize * stride + stride - 1;
+                            let alpha = *image.data.get(at)? as f32 / 255.0;
+                            // As the shader does, the color is not faded by its own alpha
+                            (alpha > 0.0).then_some([r * alpha, g * alpha, b * alpha, a * alpha])
+                        });
+                    }
+
+                    if style.decoration.is_none() || run.glyphs.is_empty() {
+                        continue;
+                    }
+                    let start = run.glyphs.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+                    let end = run
+                        .glyphs
+                        .iter()
+                        .map(|g| g.x + g.w)
+                        .fold(f32::MIN, f32::max);
+                    let baseline = rect.y as f32 + run.line_y;
+                    for (top, thickness) in
+                        DrawCommand::decoration_lines(ctx, &run, style.decoration, baseline, scale)
+                    {
+                        self.rounded_rect(
+                            [rect.x as f32 + start, top, end - start, thickness],
+                            style.color,
+                            0.0,
+                            0.0,
+                            0.0,
+                            paint,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Straight alpha RGBA rows
+    fn into_image(self) -> Option<ImageData> {
+        let pixels = self
+            .pixels
+            .iter()
+            .flat_map(|&[r, g, b, a]| {
+                let byte = |channel: f32| (channel * 255.0).round().clamp(0.0, 255.0) as u8;
+                let straight = |channel: f32| if a > 0.0 { byte(channel / a) } else { 0 };
+                [straight(r), straight(g), straight(b), byte(a)]
+            })
+            .collect();
+        ImageData::from_rgba(self.width, self.height, pixels)
+    }
+}
+
+impl Context {
+    /// Draws an element and its children into an image of its laid-out
+    /// size, in window pixels, off the screen: for drag previews, copies
+    /// as images or checks of a single component. What is behind the
+    /// element is not drawn, its pixels stay transparent. As on screen,
+    /// the parts scroll containers around it cut are left out.
+    ///
+    /// `None` until the element is laid out, or if it is empty.
+    pub fn snapshot_element(&mut self, element: impl ElementRef) -> Option<ImageData> {
+        let cref = element.raw();
+        let rect = self.root.get_rect(cref).filter(|rect| !rect.is_empty())?;
+
+        let mut frames = Vec::new();
+        let mut stack = vec![cref];
+        while let Some(cref) = stack.pop() {
+            if let Some(capsule) = self.root.get_capsule(cref) {
+                stack.extend_from_slice(capsule.children());
+            }
+            frames.push(cref);
+        }
+        let (commands, _) = self.draw_commands(&frames);
+
+        let mut pixmap = Pixmap::new(rect, self.scale_factor * self.ui_scale);
+        let paint = Paint {
+            clip: NO_CLIP,
+            disabled: false,
+        };
+        for command in &commands {
+            pixmap.draw(self, command, paint);
+        }
+        pixmap.into_image()
+    }
+}
@@ -0,0 +1,152 @@
+//! Text properties cascading down the element tree: the color, family and
+//! size set on an element go to the labels under it that were not given a
+//! style of their own, unless an element closer to them sets them too.
+
+use cosmic_text::FamilyOwned;
+use heka::CapsuleRef;
+use heka::color::Color;
+
+use crate::elements::Label;
+use crate::{Context, ElementRef, LabelRef, TextStyle};
+
+/// Text properties an element passes on to the labels under it, see
+/// `Context::set_text_defaults`. Those left unset come from further up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextDefaults {
+    pub color: Option<Color>,
+    pub font_family: Option<FamilyOwned>,
+    pub font_size: Option<f32>,
+}
+
+impl TextDefaults {
+    /// Takes the properties unset here from `other`
+    fn or(&mut self, other: &TextDefaults) {
+        self.color = self.color.or(other.color);
+        if self.font_family.is_none() {
+            self.font_family = other.font_family.clone();
+        }
+        self.font_size = self.font_size.or(other.font_size);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.color.is_some() && self.font_family.is_some() && self.font_size.is_some()
+    }
+
+    /// `style` with the properties set here
+    pub fn apply(&self, mut style: TextStyle) -> TextStyle {
+        if let Some(color) = self.color {
+            style.color = color;
+        }
+        if let Some(family) = &self.font_family {
+            style.font_family = family.clone();
+        }
+        if let Some(size) = self.font_size {
+            style.font_size = size;
+        }
+        style
+    }
+}
+
+impl Context {
+    /// Passes the text properties of `defaults` on to the labels under
+    /// `element` (the labels of its buttons and links included), and to
+    /// `element` itself if it is a label. Labels given a style of their
+    /// own, at creation or with `set_label_style`, keep it.
+    pub fn set_text_defaults(&mut self, element: impl ElementRef, defaults: TextDefaults) {
+        let cref = element.raw();
+        if self.text_defaults.get(&cref) == Some(&defaults) {
+            return;
+        }
+        self.text_defaults.insert(cref, defaults);
+        self.restyle_inheriting(cref);
+    }
+
+    /// Stops `element` from passing text properties on, the labels under
+    /// it take them from further up again
+    pub fn clear_text_defaults(&mut self, element: impl ElementRef) {
+        let cref = element.raw();
+        if self.text_defaults.remove(&cref).is_some() {
+            self.restyle_inheriting(cref);
+        }
+    }
+
+    pub fn text_defaults(&self, element: impl ElementRef) -> Option<&TextDefaults> {
+        self.text_defaults.get(&element.raw())
+    }
+
+    /// Whether `label` takes its text properties from the elements above it
+    pub fn label_inherits_style(&self, label: LabelRef) -> bool {
+        self.elements
+            .get(&label.raw())
+            .and_then(|element| element.as_any().downcast_ref::<Label>())
+            .is_some_and(|label| label.base_style.is_some())
+    }
+
+    /// The text properties reaching `cref`, nearest first
+    fn inherited_text(&self, cref: CapsuleRef) -> TextDefaults {
+        let mut inherited = TextDefaults::default();
+        let mut current = Some(cref);
+        while let Some(cref) = current
+            && !inherited.is_complete()
+        {
+            if let Some(defaults) = self.text_defaults.get(&cref) {
+                inherited.or(defaults);
+            }
+            current = self.root.get_capsule(cref).and_then(|c| c.parent_ref);
+        }
+        inherited
+    }
+
+    /// `base` with the text properties reaching a label at `cref`
+    pub(crate) fn inherit_text_style(&self, cref: CapsuleRef, base: &TextStyle) -> TextStyle {
+        if self.text_defaults.is_empty() {
+            return base.clone();
+        }
+        self.inherited_text(cref).apply(base.clone())
+    }
+
+    /// Resolves again the style of the inheriting labels under `cref`
+    fn restyle_inheriting(&mut self, cref: CapsuleRef) {
+        let mut stack = vec![cref];
+        while let Some(cref) = stack.pop() {
+            if let Some(capsule) = self.root.get_capsule(cref) {
+                stack.extend_from_slice(capsule.children());
+            }
+            let Some(base) = self
+                .elements
+                .get(&cref)
+                .and_then(|element| element.as_any().downcast_ref::<Label>())
+                .and_then(|label| label.base_style.clone())
+            else {
+                continue;
+            };
+            let style = self.inherit_text_style(cref, &base);
+            self.with_component_mut::<Label>(cref, |label, ctx| {
+                label.set_style(
+                    &mut ctx.root,
+                    &mut ctx.font_system,
+                    &mut ctx.buffer_pool,
+                    style,
+                );
+            });
+        }
+    }
+
+    /// Changes the style of `label` without giving it one of its own: an
+    /// inheriting label keeps inheriting
+    pub(crate) fn update_label_style(&mut self, label: LabelRef, update: impl Fn(&mut TextStyle)) {
+        self.with_component_mut::<Label>(label.raw(), |label, ctx| {
+            if let Some(base) = &mut label.base_style {
+                update(base);
+            }
+            let mut style = label.text_style.clone();
+            update(&mut style);
+            label.set_style(
+                &mut ctx.root,
+                &mut ctx.font_system,
+                &mut ctx.buffer_pool,
+                style,
+            );
+        });
+    }
+}
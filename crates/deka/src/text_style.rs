@@ -1,4 +1,7 @@
+use std::borrow::Cow;
+
 use heka::color;
+use heka::tokens::Token;
 
 use cosmic_text::{Align, Attrs, FamilyOwned, Metrics, Style as FontStyle, Weight};
 
@@ -17,15 +20,80 @@ impl TextHeight {
     }
 }
 
+/// Lines drawn along the text, combined with `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextDecoration(u8);
+
+impl TextDecoration {
+    pub const NONE: Self = Self(0);
+    pub const UNDERLINE: Self = Self(1);
+    pub const STRIKETHROUGH: Self = Self(1 << 1);
+    pub const OVERLINE: Self = Self(1 << 2);
+
+    #[inline]
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline]
+    pub fn is_none(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for TextDecoration {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for TextDecoration {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Where lines may break when the text is wider than its frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Wrap {
+    /// Lines only break at line breaks of the text
+    None,
+    /// Between words, inside a word only if it does not fit on a line alone
+    #[default]
+    Word,
+    /// Between any two glyphs
+    Glyph,
+}
+
+/// How the spaces and line breaks of the text are rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WhiteSpace {
+    /// Sequences of spaces and line breaks collapse into a single space
+    Normal,
+    /// Kept as written
+    #[default]
+    Pre,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TextStyle {
     pub font_family: FamilyOwned,
     pub color: color::Color,
+    /// Design token the color is taken from when the text is drawn, instead
+    /// of `color`, see `Context::set_token`. Unknown tokens are transparent.
+    pub color_token: Option<Token>,
     pub font_size: f32,
     pub line_height: TextHeight,
     pub weight: Weight,
     pub style: FontStyle,
     pub align: Align,
+    pub decoration: TextDecoration,
+    pub wrap: Wrap,
+    pub white_space: WhiteSpace,
+    /// Lines shown at most, the last one ends with an ellipsis if the text is cut
+    pub max_lines: Option<u32>,
 }
 
 impl Default for TextStyle {
@@ -33,11 +101,16 @@ impl Default for TextStyle {
         Self {
             font_family: FamilyOwned::SansSerif,
             color: color::Color::black,
+            color_token: None,
             font_size: 14.0,
             line_height: TextHeight::Auto,
             weight: Weight::NORMAL,
             style: FontStyle::Normal,
             align: Align::Left,
+            decoration: TextDecoration::NONE,
+            wrap: Wrap::Word,
+            white_space: WhiteSpace::Pre,
+            max_lines: None,
         }
     }
 }
@@ -65,4 +138,20 @@ impl TextStyle {
     pub fn as_cosmic_metrics(&self) -> Metrics {
         Metrics::new(self.font_size, self.line_height.measure(self.font_size))
     }
+
+    pub fn as_cosmic_wrap(&self) -> cosmic_text::Wrap {
+        match self.wrap {
+            Wrap::None => cosmic_text::Wrap::None,
+            Wrap::Word => cosmic_text::Wrap::WordOrGlyph,
+            Wrap::Glyph => cosmic_text::Wrap::Glyph,
+        }
+    }
+
+    /// `text` as displayed, once the `white_space` rule is applied
+    pub fn display_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self.white_space {
+            WhiteSpace::Pre => Cow::Borrowed(text),
+            WhiteSpace::Normal => Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" ")),
+        }
+    }
 }
@@ -0,0 +1,105 @@
+#[cfg(feature = "text")]
+use std::time::Duration;
+
+#[cfg(feature = "text")]
+use cosmic_text::FamilyOwned;
+use heka::Style;
+use heka::color::Color;
+
+#[cfg(feature = "text")]
+use crate::TextStyle;
+
+/// Look and feel shared by every component of a `Context`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Default font family of labels
+    #[cfg(feature = "text")]
+    pub font_family: FamilyOwned,
+    /// Default font size of labels
+    #[cfg(feature = "text")]
+    pub font_size: f32,
+    #[cfg(feature = "text")]
+    pub text_color: Color,
+    /// Highlights active states (checked checkbox...)
+    pub accent_color: Color,
+    /// Inactive states (unchecked checkbox...)
+    pub muted_color: Color,
+    /// Background of buttons
+    pub surface_color: Color,
+    /// Background of text inputs
+    pub input_color: Color,
+    /// Borders of buttons and inputs
+    pub border_color: Color,
+    /// Validation messages
+    pub error_color: Color,
+    /// Thumbs of the scrollbars of `Overflow::Auto` panels
+    pub scrollbar_color: Color,
+    /// Text cursor of focused text inputs
+    #[cfg(feature = "text")]
+    pub caret_color: Color,
+    /// Highlight behind selected text
+    #[cfg(feature = "text")]
+    pub selection_color: Color,
+    /// Time the caret stays on, then off. `None` keeps it on.
+    #[cfg(feature = "text")]
+    pub caret_blink: Option<Duration>,
+    /// Diameters of small, medium and large avatars
+    pub avatar_sizes: [u32; 3],
+    /// Rounds the heights of text and the gaps of columns up to a multiple
+    /// of this unit, for a regular vertical rhythm (see
+    /// `heka::Root::set_baseline_grid`)
+    pub baseline_grid: Option<u32>,
+    /// Whether interaction sounds are emitted (see `Context::on_interaction_sound`)
+    pub sounds: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "text")]
+            font_family: FamilyOwned::SansSerif,
+            #[cfg(feature = "text")]
+            font_size: 14.0,
+            #[cfg(feature = "text")]
+            text_color: Color::black,
+            accent_color: Color::new(100, 100, 255, 255),
+            muted_color: Color::new(200, 200, 200, 255),
+            surface_color: Color::Hex(0xE9E9EDFF),
+            input_color: Color::white,
+            border_color: Color::Hex(0x8F8F9DFF),
+            error_color: Color::Hex(0xD70022FF),
+            scrollbar_color: Color::Hex(0x8F8F9DB0),
+            #[cfg(feature = "text")]
+            caret_color: Color::black,
+            #[cfg(feature = "text")]
+            selection_color: Color::new(100, 100, 255, 90),
+            #[cfg(feature = "text")]
+            caret_blink: Some(Duration::from_millis(530)),
+            avatar_sizes: [24, 40, 64],
+            baseline_grid: None,
+            sounds: true,
+        }
+    }
+}
+
+#[cfg(feature = "text")]
+impl Theme {
+    /// Text style used when a label is created without one
+    pub fn text_style(&self) -> TextStyle {
+        TextStyle {
+            font_family: self.font_family.clone(),
+            font_size: self.font_size,
+            color: self.text_color,
+            ..Default::default()
+        }
+    }
+}
+
+/// Baseline look of a kind of element, see `Context::set_default_style`
+#[derive(Debug, Clone)]
+pub struct ElementDefaults {
+    pub style: Style,
+    /// Style of the element's text, if it has any
+    #[cfg(feature = "text")]
+    pub text_style: Option<TextStyle>,
+}
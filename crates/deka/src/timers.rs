@@ -0,0 +1,198 @@
+//! Timers run on the UI thread by the event loop, and the debounce / throttle
+//! helpers built on them.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::Context;
+
+type TimerCallback = Box<dyn FnMut(&mut Context)>;
+
+/// Identifies a timer, see `Context::set_timeout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+struct Timer {
+    id: TimerId,
+    due: Instant,
+    /// Interval timers fire again `period` after each run
+    period: Option<Duration>,
+    callback: TimerCallback,
+}
+
+/// Pending timers of a `Context`
+#[derive(Default)]
+pub(crate) struct Timers {
+    next_id: u64,
+    pending: Vec<Timer>,
+    /// Timer whose callback is running, and whether it cancelled itself
+    running: Option<(TimerId, bool)>,
+}
+
+impl Timers {
+    fn add(&mut self, due: Instant, period: Option<Duration>, callback: TimerCallback) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.pending.push(Timer {
+            id,
+            due,
+            period,
+            callback,
+        });
+        id
+    }
+
+    /// Takes the earliest timer that is due
+    fn pop_due(&mut self, now: Instant) -> Option<Timer> {
+        let index = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, timer)| timer.due <= now)
+            .min_by_key(|(_, timer)| timer.due)
+            .map(|(index, _)| index)?;
+        Some(self.pending.swap_remove(index))
+    }
+
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        self.pending.iter().map(|timer| timer.due).min()
+    }
+}
+
+impl Context {
+    /// Runs `callback` once, `delay` from now
+    pub fn set_timeout<F>(&mut self, delay: Duration, callback: F) -> TimerId
+    where
+        F: FnOnce(&mut Context) + 'static,
+    {
+        let mut callback = Some(callback);
+        let callback = move |ctx: &mut Context| {
+            if let Some(callback) = callback.take() {
+                callback(ctx);
+            }
+        };
+        let due = self.now() + delay;
+        self.timers.add(due, None, Box::new(callback))
+    }
+
+    /// Runs `callback` every `period` until the timer is cancelled
+    pub fn set_interval<F>(&mut self, period: Duration, callback: F) -> TimerId
+    where
+        F: FnMut(&mut Context) + 'static,
+    {
+        let due = self.now() + period;
+        self.timers.add(due, Some(period), Box::new(callback))
+    }
+
+    /// Stops a timer. Returns whether it was still pending.
+    pub fn cancel_timer(&mut self, id: TimerId) -> bool {
+        if let Some((running, cancelled)) = &mut self.timers.running
+            && *running == id
+        {
+            let was_pending = !*cancelled;
+            *cancelled = true;
+            return was_pending;
+        }
+
+        let before = self.timers.pending.len();
+        self.timers.pending.retain(|timer| timer.id != id);
+        self.timers.pending.len() != before
+    }
+
+    /// Runs the callbacks of the timers that are due.
+    /// Returns when the next timer is, `None` if there is none left.
+    pub fn run_due_timers(&mut self) -> Option<Instant> {
+        let now = self.now();
+
+        while let Some(mut timer) = self.timers.pop_due(now) {
+            self.timers.running = Some((timer.id, false));
+            self.dispatching(|ctx| ctx.guarded(|ctx| (timer.callback)(ctx)));
+            let cancelled = matches!(self.timers.running.take(), Some((_, true)));
+
+            if let Some(period) = timer.period
+                && !cancelled
+            {
+                // Late intervals skip the runs they missed rather than catching up
+                timer.due = (timer.due + period).max(now);
+                self.timers.pending.push(timer);
+            }
+        }
+        self.timers.next_due()
+    }
+
+    /// Wraps `callback` so it only runs once no call happened for `delay`,
+    /// with the last event it received. Suited to search-as-you-type and
+    /// other handlers that are too costly to run on every keystroke.
+    pub fn debounce<E, F>(delay: Duration, callback: F) -> impl FnMut(&mut Context, &E) + 'static
+    where
+        E: Clone + 'static,
+        F: FnMut(&mut Context, &E) + 'static,
+    {
+        let callback = Rc::new(RefCell::new(callback));
+        let mut pending: Option<TimerId> = None;
+
+        move |ctx, event| {
+            if let Some(id) = pending.take() {
+                ctx.cancel_timer(id);
+            }
+            let callback = callback.clone();
+            let event = event.clone();
+            pending = Some(ctx.set_timeout(delay, move |ctx| {
+                (callback.borrow_mut())(ctx, &event);
+            }));
+        }
+    }
+
+    /// Wraps `callback` so it runs at most once per `interval`. The first
+    /// call runs right away; calls made in between are collapsed into one,
+    /// run at the end of the interval with the last event received.
+    pub fn throttle<E, F>(interval: Duration, callback: F) -> impl FnMut(&mut Context, &E) + 'static
+    where
+        E: Clone + 'static,
+        F: FnMut(&mut Context, &E) + 'static,
+    {
+        struct State<E> {
+            last_run: Option<Instant>,
+            /// Event of the trailing run, once one is scheduled
+            trailing: Option<E>,
+        }
+
+        let callback = Rc::new(RefCell::new(callback));
+        let state = Rc::new(RefCell::new(State {
+            last_run: None,
+            trailing: None,
+        }));
+
+        move |ctx, event| {
+            let now = ctx.now();
+            let mut current = state.borrow_mut();
+
+            let ready_at = current.last_run.map(|last| last + interval);
+            match ready_at {
+                Some(ready_at) if ready_at > now => {
+                    let scheduled = current.trailing.replace(event.clone()).is_some();
+                    if scheduled {
+                        return;
+                    }
+                    let (callback, state) = (callback.clone(), state.clone());
+                    ctx.set_timeout(ready_at - now, move |ctx| {
+                        let event = {
+                            let mut current = state.borrow_mut();
+                            current.last_run = Some(ctx.now());
+                            current.trailing.take()
+                        };
+                        if let Some(event) = event {
+                            (callback.borrow_mut())(ctx, &event);
+                        }
+                    });
+                }
+                _ => {
+                    current.last_run = Some(now);
+                    drop(current);
+                    (callback.borrow_mut())(ctx, event);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Design tokens of the context: named sizes and colors the styles of the
+//! frames and the text styles refer to, see `Context::set_token`.
+
+#[cfg(feature = "text")]
+use heka::color::Color;
+#[cfg(feature = "text")]
+use heka::geometry::Rect;
+#[cfg(feature = "text")]
+use heka::tokens::Token;
+use heka::tokens::TokenValue;
+
+use crate::Context;
+#[cfg(feature = "text")]
+use crate::elements::Label;
+
+impl Context {
+    /// Sets a design token the styles (see `Style::tokens`) and the text
+    /// styles (see `TextStyle::color_token`) can refer to. What uses it is
+    /// laid out and drawn again.
+    pub fn set_token(&mut self, name: impl Into<String>, value: impl Into<TokenValue>) {
+        let name = name.into();
+        let value = value.into();
+        if self.root.token(&name) == Some(value) {
+            return;
+        }
+        self.root.set_token(name.clone(), value);
+        self.redraw_token_text(&name);
+    }
+
+    #[inline]
+    pub fn token(&self, name: &str) -> Option<TokenValue> {
+        self.root.token(name)
+    }
+
+    /// Returns whether the token existed. What uses it falls back to the
+    /// defaults of `Root::remove_token`, texts are transparent.
+    pub fn remove_token(&mut self, name: &str) -> bool {
+        let removed = self.root.remove_token(name);
+        if removed {
+            self.redraw_token_text(name);
+        }
+        removed
+    }
+
+    /// Color a text takes from `token` when drawn
+    #[cfg(feature = "text")]
+    pub(crate) fn token_color(&self, token: Token) -> Color {
+        match self.root.token(token.name()) {
+            Some(TokenValue::Color(color)) => color,
+            _ => Color::transparent,
+        }
+    }
+
+    /// Draws again the labels colored by the token `name`
+    fn redraw_token_text(&mut self, name: &str) {
+        #[cfg(feature = "text")]
+        {
+            let damaged: Vec<Rect> = self
+                .elements
+                .iter()
+                .filter_map(|(cref, element)| {
+                    let label = element.as_any().downcast_ref::<Label>()?;
+                    let token = label.text_style.color_token?;
+                    (token.name() == name).then(|| self.root.get_rect(*cref))?
+                })
+                .collect();
+            for rect in damaged {
+                self.root.add_damage(rect);
+            }
+        }
+        #[cfg(not(feature = "text"))]
+        let _ = name;
+        self.request_redraw();
+    }
+}
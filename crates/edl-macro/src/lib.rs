@@ -1,13 +1,20 @@
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{
-    Expr, Ident, Result, Token, braced, bracketed,
+    DeriveInput, Expr, Ident, Path, Result, Token, braced, bracketed,
     parse::{Parse, ParseStream},
     parse_macro_input,
 };
 
+mod style_fields;
+
 struct EkaInput {
     ctx: Ident,
+    /// Element the tree is added to, the window root when `None`
+    parent: Option<Expr>,
+    /// Struct built from all the bound elements and returned by the macro
+    /// instead of the root element
+    returns: Option<Path>,
     root_element: ElementDef,
 }
 
@@ -18,31 +25,66 @@ struct ElementDef {
 
 enum ElementType {
     Label {
-        text: Expr,
-        style: Option<Expr>,
+        text: Value,
+        style: Option<Value>,
         common: CommonAttrs,
     },
     Button {
-        text: Expr,
+        text: Value,
         on_click: Expr,
-        style: Option<Expr>,
+        style: Option<Value>,
         common: CommonAttrs,
     },
     Panel {
-        style: Option<Expr>,
+        style: Option<Value>,
         children: Vec<ElementDef>,
         common: CommonAttrs,
     },
     Checkbox {
-        checked: Expr,
+        checked: Value,
         common: CommonAttrs,
     },
     TextInput {
-        text: Expr,
+        text: Value,
         common: CommonAttrs,
     },
 }
 
+/// Value of a field that can follow a signal
+enum Value {
+    Plain(Expr),
+    /// `signal(signal)` or `signal(signal, map)`: the field is set to the
+    /// value of the signal (passed through `map`) whenever it changes
+    Signal {
+        signal: Expr,
+        map: Option<Expr>,
+    },
+}
+
+impl Parse for Value {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let expr: Expr = input.parse()?;
+        let Expr::Call(call) = &expr else {
+            return Ok(Value::Plain(expr));
+        };
+        let Expr::Path(func) = &*call.func else {
+            return Ok(Value::Plain(expr));
+        };
+        if !func.path.is_ident("signal") {
+            return Ok(Value::Plain(expr));
+        }
+
+        let mut args = call.args.iter().cloned();
+        match (args.next(), args.next(), args.next()) {
+            (Some(signal), map, None) => Ok(Value::Signal { signal, map }),
+            _ => Err(syn::Error::new_spanned(
+                &call.args,
+                "Expected `signal(signal)` or `signal(signal, map)`",
+            )),
+        }
+    }
+}
+
 #[derive(Default)]
 struct CommonAttrs {
     on_click: Option<Expr>,
@@ -53,11 +95,89 @@ impl Parse for EkaInput {
     fn parse(input: ParseStream) -> Result<Self> {
         let ctx: Ident = input.parse()?;
         input.parse::<Token![,]>()?;
+
+        // `parent = expr,` and `returns = Type,` are told apart from a root
+        // element bound to the same name by the comma following them
+        let mut parent = None;
+        let mut returns = None;
+        loop {
+            let fork = input.fork();
+            let Ok(name) = fork.parse::<Ident>() else {
+                break;
+            };
+            if (name != "parent" && name != "returns") || fork.parse::<Token![=]>().is_err() {
+                break;
+            }
+            if fork.parse::<Expr>().is_err() || !fork.peek(Token![,]) {
+                break;
+            }
+
+            input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            if name == "parent" {
+                parent = Some(input.parse::<Expr>()?);
+            } else {
+                returns = Some(input.parse::<Path>()?);
+            }
+            input.parse::<Token![,]>()?;
+        }
+
         let root_element = input.parse()?;
-        Ok(EkaInput { ctx, root_element })
+        Ok(EkaInput {
+            ctx,
+            parent,
+            returns,
+            root_element,
+        })
     }
 }
 
+/// Fields each element type accepts
+const ELEMENT_FIELDS: &[(&str, &[&str])] = &[
+    ("Label", &["text", "style", "on_click", "on_hover"]),
+    ("Button", &["text", "on_click", "on_hover", "style"]),
+    ("Panel", &["style", "on_click", "on_hover", "children"]),
+    ("Checkbox", &["checked", "on_click", "on_hover"]),
+    ("TextInput", &["text", "on_click", "on_hover"]),
+];
+
+/// `a`, `b`, `c` for error messages
+fn list<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn unknown_field(field: &Ident, element: &str) -> syn::Error {
+    let allowed = ELEMENT_FIELDS
+        .iter()
+        .find(|(name, _)| *name == element)
+        .map(|(_, fields)| list(fields.iter().copied()))
+        .unwrap_or_default();
+    syn::Error::new(
+        field.span(),
+        format!("Unknown field `{field}` for {element}, expected one of {allowed}"),
+    )
+}
+
+fn missing_field(element: &Ident, field: &str) -> syn::Error {
+    syn::Error::new(element.span(), format!("Missing `{field}` for {element}"))
+}
+
+/// Parses `field:`, rejecting fields given twice in an element
+fn parse_field(content: ParseStream, seen: &mut Vec<Ident>) -> Result<Ident> {
+    let field: Ident = content.parse()?;
+    content.parse::<Token![:]>()?;
+    if let Some(first) = seen.iter().find(|name| **name == field) {
+        let mut error = syn::Error::new(field.span(), format!("Field `{field}` given twice"));
+        error.combine(syn::Error::new(first.span(), "first given here"));
+        return Err(error);
+    }
+    seen.push(field.clone());
+    Ok(field)
+}
+
 impl Parse for ElementDef {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut binding = None;
@@ -70,6 +190,8 @@ impl Parse for ElementDef {
         let content;
         braced!(content in input);
 
+        // Fields already given, to reject the ones given twice
+        let mut seen = Vec::new();
         let element_type = match name.to_string().as_str() {
             "Label" => {
                 let mut text = None;
@@ -77,14 +199,13 @@ impl Parse for ElementDef {
                 let mut common = CommonAttrs::default();
 
                 while !content.is_empty() {
-                    let field: Ident = content.parse()?;
-                    content.parse::<Token![:]>()?;
+                    let field = parse_field(&content, &mut seen)?;
                     match field.to_string().as_str() {
-                        "text" => text = Some(content.parse::<Expr>()?),
-                        "style" => style = Some(content.parse::<Expr>()?),
+                        "text" => text = Some(content.parse::<Value>()?),
+                        "style" => style = Some(content.parse::<Value>()?),
                         "on_click" => common.on_click = Some(content.parse::<Expr>()?),
                         "on_hover" => common.on_hover = Some(content.parse::<Expr>()?),
-                        _ => return Err(content.error("Unknown field for Label")),
+                        _ => return Err(unknown_field(&field, "Label")),
                     }
                     if !content.is_empty() {
                         content.parse::<Token![,]>()?;
@@ -92,7 +213,7 @@ impl Parse for ElementDef {
                 }
 
                 ElementType::Label {
-                    text: text.ok_or_else(|| content.error("Missing 'text' for Label"))?,
+                    text: text.ok_or_else(|| missing_field(&name, "text"))?,
                     style,
                     common,
                 }
@@ -104,14 +225,13 @@ impl Parse for ElementDef {
                 let mut common = CommonAttrs::default();
 
                 while !content.is_empty() {
-                    let field: Ident = content.parse()?;
-                    content.parse::<Token![:]>()?;
+                    let field = parse_field(&content, &mut seen)?;
                     match field.to_string().as_str() {
-                        "text" => text = Some(content.parse::<Expr>()?),
+                        "text" => text = Some(content.parse::<Value>()?),
                         "on_click" => on_click = Some(content.parse::<Expr>()?),
                         "on_hover" => common.on_hover = Some(content.parse::<Expr>()?),
-                        "style" => style = Some(content.parse::<Expr>()?),
-                        _ => return Err(content.error("Unknown field for Button")),
+                        "style" => style = Some(content.parse::<Value>()?),
+                        _ => return Err(unknown_field(&field, "Button")),
                     }
                     if !content.is_empty() {
                         content.parse::<Token![,]>()?;
@@ -119,9 +239,8 @@ impl Parse for ElementDef {
                 }
 
                 ElementType::Button {
-                    text: text.ok_or_else(|| content.error("Missing 'text' for Button"))?,
-                    on_click: on_click
-                        .ok_or_else(|| content.error("Missing 'on_click' for Button"))?,
+                    text: text.ok_or_else(|| missing_field(&name, "text"))?,
+                    on_click: on_click.ok_or_else(|| missing_field(&name, "on_click"))?,
                     style,
                     common,
                 }
@@ -132,10 +251,9 @@ impl Parse for ElementDef {
                 let mut common = CommonAttrs::default();
 
                 while !content.is_empty() {
-                    let field: Ident = content.parse()?;
-                    content.parse::<Token![:]>()?;
+                    let field = parse_field(&content, &mut seen)?;
                     match field.to_string().as_str() {
-                        "style" => style = Some(content.parse::<Expr>()?),
+                        "style" => style = Some(content.parse::<Value>()?),
                         "on_click" => common.on_click = Some(content.parse::<Expr>()?),
                         "on_hover" => common.on_hover = Some(content.parse::<Expr>()?),
                         "children" => {
@@ -148,7 +266,7 @@ impl Parse for ElementDef {
                                 }
                             }
                         }
-                        _ => return Err(content.error("Unknown field for Panel")),
+                        _ => return Err(unknown_field(&field, "Panel")),
                     }
                     if !content.is_empty() {
                         content.parse::<Token![,]>()?;
@@ -166,13 +284,12 @@ impl Parse for ElementDef {
                 let mut common = CommonAttrs::default();
 
                 while !content.is_empty() {
-                    let field: Ident = content.parse()?;
-                    content.parse::<Token![:]>()?;
+                    let field = parse_field(&content, &mut seen)?;
                     match field.to_string().as_str() {
-                        "checked" => checked = Some(content.parse::<Expr>()?),
+                        "checked" => checked = Some(content.parse::<Value>()?),
                         "on_click" => common.on_click = Some(content.parse::<Expr>()?),
                         "on_hover" => common.on_hover = Some(content.parse::<Expr>()?),
-                        _ => return Err(content.error("Unknown field for Checkbox")),
+                        _ => return Err(unknown_field(&field, "Checkbox")),
                     }
                     if !content.is_empty() {
                         content.parse::<Token![,]>()?;
@@ -180,8 +297,7 @@ impl Parse for ElementDef {
                 }
 
                 ElementType::Checkbox {
-                    checked: checked
-                        .ok_or_else(|| content.error("Missing 'checked' for Checkbox"))?,
+                    checked: checked.ok_or_else(|| missing_field(&name, "checked"))?,
                     common,
                 }
             }
@@ -190,13 +306,12 @@ impl Parse for ElementDef {
                 let mut common = CommonAttrs::default();
 
                 while !content.is_empty() {
-                    let field: Ident = content.parse()?;
-                    content.parse::<Token![:]>()?;
+                    let field = parse_field(&content, &mut seen)?;
                     match field.to_string().as_str() {
-                        "text" => text = Some(content.parse::<Expr>()?),
+                        "text" => text = Some(content.parse::<Value>()?),
                         "on_click" => common.on_click = Some(content.parse::<Expr>()?),
                         "on_hover" => common.on_hover = Some(content.parse::<Expr>()?),
-                        _ => return Err(content.error("Unknown field for TextInput")),
+                        _ => return Err(unknown_field(&field, "TextInput")),
                     }
                     if !content.is_empty() {
                         content.parse::<Token![,]>()?;
@@ -204,11 +319,19 @@ impl Parse for ElementDef {
                 }
 
                 ElementType::TextInput {
-                    text: text.ok_or_else(|| content.error("Missing 'text' for TextInput"))?,
+                    text: text.ok_or_else(|| missing_field(&name, "text"))?,
                     common,
                 }
             }
-            _ => return Err(syn::Error::new(name.span(), "Unknown element type")),
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "Unknown element type `{name}`, expected one of {}",
+                        list(ELEMENT_FIELDS.iter().map(|(element, _)| *element))
+                    ),
+                ));
+            }
         };
 
         Ok(ElementDef {
@@ -223,22 +346,154 @@ pub fn eka(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as EkaInput);
     let ctx = &input.ctx;
 
-    let code = generate_element(&input.root_element, ctx, quote!(None::<deka::Element>));
+    let parent = match &input.parent {
+        Some(parent) => quote!(Some(#parent)),
+        None => quote!(None::<deka::Element>),
+    };
+
+    let mut bindings = Vec::new();
+    collect_bindings(&input.root_element, &mut bindings);
+    for (index, binding) in bindings.iter().enumerate() {
+        if let Some(first) = bindings[..index].iter().find(|first| *first == binding) {
+            let mut error = syn::Error::new(
+                binding.span(),
+                format!("Element bound to `{binding}` more than once"),
+            );
+            error.combine(syn::Error::new(first.span(), "first bound here"));
+            return error.to_compile_error().into();
+        }
+    }
+
+    let Some(returns) = &input.returns else {
+        let code = generate_element(&input.root_element, ctx, parent, false);
+        return quote! {
+            {
+                #code
+            }
+        }
+        .into();
+    };
+
+    // The elements are kept in temporaries declared up front, so they
+    // outlive the panel blocks they are created in without the names of
+    // the bindings shadowing anything before the elements exist
+    let temporaries: Vec<Ident> = bindings.iter().map(hoisted_ident).collect();
+    let code = generate_element(&input.root_element, ctx, parent, true);
 
     quote! {
         {
-            #code
+            #( let #temporaries; )*
+            #code;
+            #returns { #( #bindings: #temporaries ),* }
         }
     }
     .into()
 }
 
+/// Generates the field checks of the heka style macros (`make_style!`,
+/// `style!`) from the fields of the struct
+#[proc_macro_derive(StyleFields)]
+pub fn style_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    style_fields::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Error at a name the style macros do not know, suggesting the closest
+/// of the fields following it
+#[doc(hidden)]
+#[proc_macro]
+pub fn unknown_style_field(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as style_fields::UnknownField);
+    style_fields::unknown(input).into_compile_error().into()
+}
+
+/// Macro-private temporary holding the element bound to `binding`
+fn hoisted_ident(binding: &Ident) -> Ident {
+    quote::format_ident!("__eka_{}", binding, span = proc_macro2::Span::mixed_site())
+}
+
+fn collect_bindings(def: &ElementDef, bindings: &mut Vec<Ident>) {
+    if let Some(binding) = &def.binding {
+        bindings.push(binding.clone());
+    }
+    if let ElementType::Panel { children, .. } = &def.element_type {
+        for child in children {
+            collect_bindings(child, bindings);
+        }
+    }
+}
+
+/// Code of a field that may follow a signal
+struct FieldCode {
+    /// Statements to run before the element is created
+    prelude: proc_macro2::TokenStream,
+    /// Value the element is created with
+    value: proc_macro2::TokenStream,
+    /// Statement binding the created element to the signal
+    bind: Option<proc_macro2::TokenStream>,
+}
+
+/// `setter` is the context method updating `element` with a new value
+fn field_code(value: &Value, field: &str, ctx: &Ident, element: &Ident, setter: &str) -> FieldCode {
+    let (signal, map) = match value {
+        Value::Plain(expr) => {
+            return FieldCode {
+                prelude: quote!(),
+                value: quote!(#expr),
+                bind: None,
+            };
+        }
+        Value::Signal { signal, map } => (signal, map),
+    };
+
+    let signal_ident = quote::format_ident!("__{}_signal", field);
+    let map_ident = quote::format_ident!("__{}_map", field);
+    let value_ident = quote::format_ident!("__{}_value", field);
+    let setter = quote::format_ident!("{}", setter);
+    let map = match map {
+        Some(map) => quote!(#map),
+        None => quote!(|value: &_| ::core::clone::Clone::clone(value)),
+    };
+
+    FieldCode {
+        prelude: quote! {
+            let #signal_ident = #signal;
+            #[allow(unused_mut)]
+            let mut #map_ident = #signal_ident.mapper(#map);
+            let #value_ident = #map_ident(&#ctx.get_signal(#signal_ident));
+        },
+        value: quote!(#value_ident),
+        bind: Some(quote! {
+            #ctx.bind(#signal_ident, #element, move |ctx, element, value| {
+                ctx.#setter(element, #map_ident(value));
+            });
+        }),
+    }
+}
+
 fn generate_element(
     def: &ElementDef,
     ctx: &Ident,
     parent: proc_macro2::TokenStream,
+    hoisted: bool,
 ) -> proc_macro2::TokenStream {
     let binding = &def.binding;
+    let element_ident = if let Some(ident) = binding {
+        ident.clone()
+    } else {
+        quote::format_ident!("_el")
+    };
+
+    // Fields following a signal
+    let mut fields = Vec::new();
+    let mut field = |value: &Value, name: &str, setter: &str| {
+        let code = field_code(value, name, ctx, &element_ident, setter);
+        let value = code.value.clone();
+        fields.push(code);
+        value
+    };
 
     let (creation_code, common) = match &def.element_type {
         ElementType::Label {
@@ -246,8 +501,12 @@ fn generate_element(
             style,
             common,
         } => {
+            let text = field(text, "text", "set_label_text");
             let style = match style {
-                Some(s) => quote!(Some(#s)),
+                Some(s) => {
+                    let s = field(s, "style", "set_label_style");
+                    quote!(Some(#s))
+                }
                 None => quote!(None),
             };
             (
@@ -263,8 +522,12 @@ fn generate_element(
             style,
             common,
         } => {
+            let text = field(text, "text", "set_button_text");
             let style = match style {
-                Some(s) => quote!(Some(#s)),
+                Some(s) => {
+                    let s = field(s, "style", "set_button_style");
+                    quote!(Some(#s))
+                }
                 None => quote!(None),
             };
             (
@@ -280,7 +543,7 @@ fn generate_element(
             common,
         } => {
             let style = match style {
-                Some(s) => quote!(#s),
+                Some(s) => field(s, "style", "set_style"),
                 None => quote!(deka::heka::Style::default()),
             };
 
@@ -288,7 +551,7 @@ fn generate_element(
 
             let children_code: Vec<_> = children
                 .iter()
-                .map(|child| generate_element(child, ctx, quote!(Some(#panel_ref))))
+                .map(|child| generate_element(child, ctx, quote!(Some(#panel_ref)), hoisted))
                 .collect();
 
             (
@@ -302,25 +565,27 @@ fn generate_element(
                 common,
             )
         }
-        ElementType::Checkbox { checked, common } => (
-            quote! {
-                #ctx.new_checkbox(#parent, #checked)
-            },
-            common,
-        ),
-        ElementType::TextInput { text, common } => (
-            quote! {
-                #ctx.new_text_input(#parent, #text.to_string())
-            },
-            common,
-        ),
-    };
-
-    let element_ident = if let Some(ident) = binding {
-        ident.clone()
-    } else {
-        quote::format_ident!("_el")
+        ElementType::Checkbox { checked, common } => {
+            let checked = field(checked, "checked", "set_checked");
+            (
+                quote! {
+                    #ctx.new_checkbox(#parent, #checked)
+                },
+                common,
+            )
+        }
+        ElementType::TextInput { text, common } => {
+            let text = field(text, "text", "set_input_text");
+            (
+                quote! {
+                    #ctx.new_text_input(#parent, #text.to_string())
+                },
+                common,
+            )
+        }
     };
+    let preludes = fields.iter().map(|field| &field.prelude);
+    let binds = fields.iter().filter_map(|field| field.bind.as_ref());
 
     let mut common_code = Vec::new();
     if let Some(on_click) = &common.on_click {
@@ -331,16 +596,29 @@ fn generate_element(
     }
 
     if let Some(ident) = binding {
+        let create = if hoisted {
+            let temporary = hoisted_ident(ident);
+            quote! {
+                #temporary = #creation_code;
+                let #ident = #temporary;
+            }
+        } else {
+            quote!(let #ident = #creation_code;)
+        };
         quote! {
-            let #ident = #creation_code;
+            #( #preludes )*
+            #create
             #( #common_code )*
+            #( #binds )*
             #ident
         }
     } else {
         quote! {
             {
+                #( #preludes )*
                 let #element_ident = #creation_code;
                 #( #common_code )*
+                #( #binds )*
                 #element_ident
             }
         }
@@ -0,0 +1,119 @@
+//! Field checks of the heka style macros, generated from the `Style` struct
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Ident, Result, Token,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+};
+
+use crate::list;
+
+/// Edits a misspelled field name may be away from the field
+const MAX_TYPO_DISTANCE: usize = 2;
+
+/// `__style_field!`: one arm per field assigning it (or the fields of its
+/// block, after `@block`), the other names are errors. Fields of the nested
+/// structs are left to the compiler.
+pub(crate) fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "StyleFields only derives for structs",
+        ));
+    };
+    let fields: Vec<&Ident> = data
+        .fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .collect();
+
+    // The field comes twice: matched by name, and captured to keep its span
+    Ok(quote! {
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! __style_field {
+            #(
+                ($style:ident; #fields $field:ident = $value:expr) => {
+                    $style.$field = $value;
+                };
+                (@block $style:ident; #fields $field:ident { $($inner:tt)* }) => {
+                    $crate::__style_fields!($style . $field; $($inner)*);
+                };
+            )*
+            ($style:ident $(. $path:ident)+; $name:ident $field:ident = $value:expr) => {
+                $style $(. $path)+ . $field = $value;
+            };
+            ($style:ident; $name:ident $field:ident = $value:expr) => {
+                $crate::macros::unknown_style_field!($field; #( #fields ),*);
+            };
+            (@block $style:ident; $name:ident $field:ident { $($inner:tt)* }) => {
+                $crate::macros::unknown_style_field!($field; #( #fields ),*);
+            };
+        }
+    })
+}
+
+/// `field; allowed, fields` of `unknown_style_field!`
+pub(crate) struct UnknownField {
+    field: Ident,
+    allowed: Punctuated<Ident, Token![,]>,
+}
+
+impl Parse for UnknownField {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let field = input.parse()?;
+        input.parse::<Token![;]>()?;
+        Ok(UnknownField {
+            field,
+            allowed: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+pub(crate) fn unknown(input: UnknownField) -> syn::Error {
+    let name = input.field.to_string();
+    let allowed: Vec<String> = input.allowed.iter().map(Ident::to_string).collect();
+    let message = match closest(&name, &allowed) {
+        Some(field) => format!("`{name}` is not a style field, did you mean `{field}`?"),
+        None => format!(
+            "`{name}` is not a style field, expected one of {}",
+            list(allowed.iter().map(String::as_str))
+        ),
+    };
+    syn::Error::new(input.field.span(), message)
+}
+
+/// Field `name` is most likely meant for: one it abbreviates (like `bg_color`
+/// for `background_color`) or a few typos away from, the nearest first
+fn closest<'a>(name: &str, fields: &'a [String]) -> Option<&'a str> {
+    fields
+        .iter()
+        .map(|field| (distance(name, field), field))
+        .filter(|(distance, field)| *distance <= MAX_TYPO_DISTANCE || abbreviates(name, field))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, field)| field.as_str())
+}
+
+/// Whether the letters of `name` all appear in `field`, in order
+fn abbreviates(name: &str, field: &str) -> bool {
+    let mut letters = field.chars();
+    name.chars().all(|c| letters.any(|letter| letter == c))
+}
+
+/// Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
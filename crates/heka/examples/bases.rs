@@ -1,5 +1,3 @@
-use std::time::Instant;
-
 use heka::{Frame, Root, clr, pad, size, style};
 
 fn main() {
@@ -46,15 +44,11 @@ fn main() {
         flex_grow: 2.0,
     });
 
-    let now = Instant::now();
-    root.compute();
-    let elapsed = now.elapsed();
-    eprintln!("operation took {elapsed:?}");
+    let stats = root.compute();
+    eprintln!("operation took {:?} ({stats:?})", stats.duration);
 
-    let now_fast = Instant::now();
-    root.compute(); // <-- This one will be ~nanoseconds
-    let elapsed_fast = now_fast.elapsed();
-    eprintln!("'Do nothing' compute took {elapsed_fast:?}");
+    let stats_fast = root.compute(); // <-- This one will be ~nanoseconds
+    eprintln!("'Do nothing' compute took {:?}", stats_fast.duration);
 
     #[cfg(feature = "debug")]
     root.debug_layout_tree();
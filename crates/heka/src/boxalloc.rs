@@ -53,6 +53,14 @@ impl Allocator {
         }
     }
 
+    /// Untyped access, left to the caller to downcast
+    pub fn get_any_mut(&mut self, id: usize) -> Option<&mut dyn Any> {
+        match self.slots.get_mut(id) {
+            Some(Some(boxed_data)) => Some(boxed_data.as_mut()),
+            _ => None,
+        }
+    }
+
     pub fn get_mut<T: 'static + Any>(&mut self, id: usize) -> Option<&mut T> {
         if let Some(Some(boxed_data)) = self.slots.get_mut(id) {
             // runtime type-check.
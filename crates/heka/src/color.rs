@@ -28,44 +28,19 @@ impl Color {
 
 impl Color {
     /// Red color
-    pub const red: Color = Color {
-        r: 255,
-        g: 0,
-        b: 0,
-        a: 255,
-    };
+    pub const red: Color = Color::new(255, 0, 0, 255);
 
     /// White color
-    pub const white: Color = Color {
-        r: 255,
-        g: 255,
-        b: 255,
-        a: 255,
-    };
+    pub const white: Color = Color::new(255, 255, 255, 255);
 
     /// Black color
-    pub const black: Color = Color {
-        r: 0,
-        g: 0,
-        b: 0,
-        a: 255,
-    };
+    pub const black: Color = Color::new(0, 0, 0, 255);
 
     /// Transparent color
-    pub const transparent: Color = Color {
-        r: 0,
-        g: 0,
-        b: 0,
-        a: 0,
-    };
+    pub const transparent: Color = Color::new(0, 0, 0, 0);
 
     /// DodgerBlue - a nice color
-    pub const dodger_blue: Color = Color {
-        r: 30,
-        g: 144,
-        b: 255,
-        a: 255,
-    };
+    pub const dodger_blue: Color = Color::new(30, 144, 255, 255);
 
     /// RISDBlue - I like this one a lot
     pub const risd_blue: Color = Color::Hex(0x4455eeFF);
@@ -79,7 +54,7 @@ impl Color {
         let b = ((hex >> (8 * 1)) & 0xFF) as u8;
         let a = ((hex >> (8 * 0)) & 0xFF) as u8;
 
-        Color { r, g, b, a }
+        Color::new(r, g, b, a)
     }
 
     /// Set an alpha value for the color
@@ -87,6 +62,18 @@ impl Color {
         self.a = value;
         self
     }
+
+    /// Linear interpolation between two colors, `t` being clamped to 0..1
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
 }
 
 impl Color {
@@ -121,12 +108,12 @@ impl Color {
             b = hue_to_rgb(p, q, h_norm - 1.0 / 3.0);
         }
 
-        Self {
-            r: (r * 255.0).round() as u8,
-            g: (g * 255.0).round() as u8,
-            b: (b * 255.0).round() as u8,
-            a: 255, // Default opaque
-        }
+        Self::new(
+            (r * 255.0).round() as u8,
+            (g * 255.0).round() as u8,
+            (b * 255.0).round() as u8,
+            255, // Default opaque
+        )
     }
 
     /// Same as from_hsl, but with an alpha channel (0.0 - 1.0)
@@ -190,3 +177,42 @@ impl Default for Shadow {
         }
     }
 }
+
+/// (blur, alpha) of every elevation level, from flat to floating.
+const ELEVATION_PRESETS: [(f32, u8); 6] = [
+    (0.0, 0x00),
+    (2.0, 0x33),
+    (4.0, 0x38),
+    (8.0, 0x3d),
+    (12.0, 0x42),
+    (16.0, 0x47),
+];
+
+impl Shadow {
+    /// Highest elevation level with its own preset.
+    /// Higher levels use this one.
+    pub const MAX_ELEVATION: u8 = ELEVATION_PRESETS.len() as u8 - 1;
+
+    /// Shadow preset for an elevation level. `0` is flat (no shadow).
+    pub fn elevation(level: u8) -> Self {
+        let (blur, alpha) = ELEVATION_PRESETS[level.min(Self::MAX_ELEVATION) as usize];
+        Self {
+            blur,
+            color: Color::black.with_alpha(alpha),
+        }
+    }
+
+    /// Linear interpolation between two shadows, `t` being clamped to 0..1
+    pub fn lerp(self, other: Shadow, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self {
+            blur: self.blur + (other.blur - self.blur) * t,
+            color: self.color.lerp(other.color, t),
+        }
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.blur > 0.0 && self.color.a > 0
+    }
+}
@@ -0,0 +1,68 @@
+use std::any::Any;
+use std::collections::HashMap;
+
+use crate::CapsuleRef;
+
+/// Limits the content of a frame has to fit in. `None` means unconstrained.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Constraints {
+    pub max_width: Option<u32>,
+    pub max_height: Option<u32>,
+    /// `max_width` is the exact width of the frame rather than the room
+    /// its parent has left (the frame is not sized to fit its content)
+    pub fixed_width: bool,
+}
+
+/// Declares the content of a frame, so heka can query its intrinsic size
+/// while measuring instead of relying on `Style::intrinsic_width/height`.
+pub trait ContentProvider: 'static {
+    /// Returns the content size (without padding and border).
+    ///
+    /// Content wider than the width its frame ends up with is measured
+    /// again with that width as `max_width`, so text can wrap to it.
+    ///
+    /// * `data` - The data bound to the frame, if any
+    /// * `context` - The value given to `Root::compute_with` (`()` for `Root::compute`)
+    fn measure(
+        &mut self,
+        constraints: Constraints,
+        data: Option<&mut dyn Any>,
+        context: &mut dyn Any,
+    ) -> (u32, u32);
+
+    /// Distance from the top of the content to its first baseline, as of
+    /// the last `measure`. Rows aligning their children on their baselines
+    /// (`AlignItems::Baseline`) line it up with the ones of its siblings.
+    fn baseline(&self) -> Option<f32> {
+        None
+    }
+}
+
+/// Content providers registered per frame
+#[derive(Default)]
+pub(crate) struct Providers {
+    map: HashMap<CapsuleRef, Box<dyn ContentProvider>>,
+}
+
+impl Providers {
+    #[inline]
+    pub(crate) fn insert(&mut self, frame_ref: CapsuleRef, provider: Box<dyn ContentProvider>) {
+        self.map.insert(frame_ref, provider);
+    }
+
+    #[inline]
+    pub(crate) fn remove(&mut self, frame_ref: &CapsuleRef) -> Option<Box<dyn ContentProvider>> {
+        self.map.remove(frame_ref)
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, frame_ref: &CapsuleRef) -> bool {
+        self.map.contains_key(frame_ref)
+    }
+}
+
+impl std::fmt::Debug for Providers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.map.keys()).finish()
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{DataRef, color::Color, geometry::Rect, sizing::Border};
+
+/// Side of its frame a decoration sits on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationPlacement {
+    /// Left of the frame, vertically centered (bullets...)
+    #[default]
+    Before,
+    /// Right of the frame, vertically centered (required-field marks...)
+    After,
+}
+
+/// A small box drawn next to a frame. Decorations follow their frame around
+/// but take no room in the layout and are never hit by `Root::hit_test`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decoration {
+    pub placement: DecorationPlacement,
+    pub width: u32,
+    pub height: u32,
+    /// Space between the decoration and the border box of its frame
+    pub gap: u32,
+    /// Moves the decoration from its placement (a badge overlapping the
+    /// corner of its frame...)
+    pub offset: (i32, i32),
+    pub background_color: Color,
+    pub border: Border,
+    /// Data drawn in the decoration, like the data of a frame
+    pub data_ref: Option<DataRef>,
+}
+
+impl Default for Decoration {
+    fn default() -> Self {
+        Self {
+            placement: DecorationPlacement::default(),
+            width: 0,
+            height: 0,
+            gap: 0,
+            offset: (0, 0),
+            background_color: Color::transparent,
+            border: Border::default(),
+            data_ref: None,
+        }
+    }
+}
+
+impl Decoration {
+    /// Where the decoration lands next to a frame laid out at `frame`
+    pub fn place(&self, frame: Rect) -> Rect {
+        let x = match self.placement {
+            DecorationPlacement::Before => frame
+                .left()
+                .saturating_sub_unsigned(self.gap)
+                .saturating_sub_unsigned(self.width),
+            DecorationPlacement::After => frame.right().saturating_add_unsigned(self.gap),
+        };
+        let y = frame.top() + (frame.h as i32 - self.height as i32) / 2;
+
+        Rect::new(
+            x.saturating_add(self.offset.0),
+            y.saturating_add(self.offset.1),
+            self.width,
+            self.height,
+        )
+    }
+}
+
+/// Identifies a decoration, see `Root::add_decoration`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DecorationId(pub(crate) u64);
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::CapsuleRef;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Layout pass a diagnostic comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutPass {
+    /// Pass 1, sizes wanted by the frames
+    Measure,
+    /// Pass 2, final positions and sizes
+    Arrange,
+}
+
+/// Why a frame was left out of a pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The frame was removed (or its handle is from an older generation)
+    DeadHandle,
+    MissingStyle,
+    MissingSpace,
+}
+
+/// Something `Root::compute` had to work around, recorded in strict mode
+/// (see `Root::set_strict`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutDiagnostic {
+    /// A frame was arranged without a size from the measure pass,
+    /// the size was taken as 0
+    MissingMeasurement { frame: CapsuleRef, axis: Axis },
+    /// A frame was left out of a pass, it keeps its previous box
+    SkippedCapsule {
+        frame: CapsuleRef,
+        pass: LayoutPass,
+        reason: SkipReason,
+    },
+    /// A `Fit` (or `Auto`) frame had nothing to size it along an axis: no
+    /// children, content provider or intrinsic size. It is 0 wide (or high)
+    /// besides its padding and border.
+    UnresolvedFit { frame: CapsuleRef, axis: Axis },
+}
+
+impl fmt::Display for LayoutDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingMeasurement { frame, axis } => {
+                write!(f, "{frame:?} was arranged without a measured {axis:?} size")
+            }
+            Self::SkippedCapsule {
+                frame,
+                pass,
+                reason,
+            } => write!(f, "{frame:?} was skipped during {pass:?} ({reason:?})"),
+            Self::UnresolvedFit { frame, axis } => write!(
+                f,
+                "{frame:?} fits its content but has nothing to size it along the {axis:?} axis"
+            ),
+        }
+    }
+}
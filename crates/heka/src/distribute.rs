@@ -0,0 +1,58 @@
+//! Integer space distribution used by the deterministic layout mode.
+
+/// Number of fractional bits used when converting `f32` factors
+/// (flex_grow, flex_shrink...) to fixed-point weights.
+const FRACTION_BITS: u32 = 16;
+
+/// Converts a float factor into a 16.16 fixed-point weight.
+/// Negative and non-finite factors are treated as 0.
+#[inline]
+pub(crate) fn to_fixed(value: f32) -> u64 {
+    if !value.is_finite() || value <= 0.0 {
+        return 0;
+    }
+    (value as f64 * (1u64 << FRACTION_BITS) as f64).round() as u64
+}
+
+/// Splits `amount` pixels between slots proportionally to `weights`
+/// using the largest-remainder method.
+///
+/// Every slot first receives the floor of its exact share, then the leftover
+/// pixels are handed one by one to the slots with the largest remainders.
+/// Ties are broken by slot order, so the result only depends on the inputs.
+/// The returned shares always sum to `amount` (unless all weights are 0,
+/// in which case every share is 0).
+pub(crate) fn largest_remainder(amount: u32, weights: &[u64]) -> Vec<u32> {
+    let total: u128 = weights.iter().map(|w| *w as u128).sum();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+
+    let mut shares = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    let mut given: u128 = 0;
+
+    for (i, weight) in weights.iter().enumerate() {
+        let exact = amount as u128 * *weight as u128;
+        let share = exact / total;
+        given += share;
+        shares.push(share as u32);
+        remainders.push((exact % total, i));
+    }
+
+    // Biggest remainder first, lowest index first on ties
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let leftover = (amount as u128 - given) as usize;
+    for (_, i) in remainders.into_iter().take(leftover) {
+        shares[i] += 1;
+    }
+
+    shares
+}
+
+/// Splits `amount` pixels evenly between `slots` slots.
+#[inline]
+pub(crate) fn evenly(amount: u32, slots: usize) -> Vec<u32> {
+    largest_remainder(amount, &vec![1; slots])
+}
@@ -0,0 +1,173 @@
+use crate::Space;
+
+/// A resolved, axis aligned rectangle in pixels.
+///
+/// Unlike [`Space`], every value is known. The rectangle covers
+/// `x..x + w` horizontally and `y..y + h` vertically.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, w: u32, h: u32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Build a rectangle from two corners, in any order
+    pub fn from_corners(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        let (left, right) = (x0.min(x1), x0.max(x1));
+        let (top, bottom) = (y0.min(y1), y0.max(y1));
+        Self {
+            x: left,
+            y: top,
+            w: right.abs_diff(left),
+            h: bottom.abs_diff(top),
+        }
+    }
+
+    #[inline]
+    pub fn left(&self) -> i32 {
+        self.x
+    }
+
+    #[inline]
+    pub fn top(&self) -> i32 {
+        self.y
+    }
+
+    #[inline]
+    pub fn right(&self) -> i32 {
+        self.x.saturating_add_unsigned(self.w)
+    }
+
+    #[inline]
+    pub fn bottom(&self) -> i32 {
+        self.y.saturating_add_unsigned(self.h)
+    }
+
+    /// Center point, rounded towards the top left
+    #[inline]
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.x.saturating_add_unsigned(self.w / 2),
+            self.y.saturating_add_unsigned(self.h / 2),
+        )
+    }
+
+    #[inline]
+    pub fn area(&self) -> u64 {
+        self.w as u64 * self.h as u64
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+
+    /// Whether the point lies inside the rectangle.
+    /// The right and bottom edges are exclusive.
+    #[inline]
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.left() && x < self.right() && y >= self.top() && y < self.bottom()
+    }
+
+    /// Whether `other` lies entirely inside this rectangle
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.left() >= self.left()
+            && other.right() <= self.right()
+            && other.top() >= self.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The overlapping area of both rectangles, if any
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        if right > left && bottom > top {
+            Some(Rect::from_corners(left, top, right, bottom))
+        } else {
+            None
+        }
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    /// Empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        Rect::from_corners(
+            self.left().min(other.left()),
+            self.top().min(other.top()),
+            self.right().max(other.right()),
+            self.bottom().max(other.bottom()),
+        )
+    }
+
+    /// Grow the rectangle by `dx` on the left and right, and `dy` on the top
+    /// and bottom. Negative values shrink it, never past an empty rectangle.
+    pub fn inflate(&self, dx: i32, dy: i32) -> Rect {
+        let w = (self.w as i64 + dx as i64 * 2).max(0) as u32;
+        let h = (self.h as i64 + dy as i64 * 2).max(0) as u32;
+        let x = if w == 0 {
+            self.center().0
+        } else {
+            self.x.saturating_sub(dx)
+        };
+        let y = if h == 0 {
+            self.center().1
+        } else {
+            self.y.saturating_sub(dy)
+        };
+
+        Rect { x, y, w, h }
+    }
+
+    /// Move the rectangle by the given offset
+    #[inline]
+    pub fn translate(&self, dx: i32, dy: i32) -> Rect {
+        Rect {
+            x: self.x.saturating_add(dx),
+            y: self.y.saturating_add(dy),
+            ..*self
+        }
+    }
+}
+
+impl From<Space> for Rect {
+    /// Unknown sizes are resolved to 0
+    fn from(space: Space) -> Self {
+        Self {
+            x: space.x,
+            y: space.y,
+            w: space.width.unwrap_or(0),
+            h: space.height.unwrap_or(0),
+        }
+    }
+}
+
+impl From<Rect> for Space {
+    fn from(rect: Rect) -> Self {
+        Self {
+            x: rect.x,
+            y: rect.y,
+            width: Some(rect.w),
+            height: Some(rect.h),
+        }
+    }
+}
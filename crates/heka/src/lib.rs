@@ -1,19 +1,49 @@
 #![doc = include_str!("../README.md")]
 
-use std::collections::{HashSet, VecDeque};
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use edl_macro::StyleFields;
 
 use crate::{
     boxalloc::Allocator,
     color::{Color, Shadow},
-    position::{AlignItems, Direction, JustifyContent, LayoutStrategy, Position},
-    sizing::{Border, Margin, Padding, SizeSpec},
+    content::{Constraints, ContentProvider, Providers},
+    decoration::{Decoration, DecorationId},
+    diagnostics::{Axis, LayoutDiagnostic, LayoutPass, SkipReason},
+    geometry::Rect,
+    position::{
+        AlignItems, Direction, JustifyContent, LayoutMode, LayoutStrategy, Offset, Overflow,
+        PixelSnapping, PointerEvents, Position,
+    },
+    scrollbar::{Gutters, Scrollbar, Scrollbars},
+    sizing::{Border, Enlargement, Margin, Padding, SizeSpec},
+    spatial::SpatialIndex,
+    stats::LayoutStats,
+    tokens::{StyleTokens, TokenValue, Tokens},
 };
 
 mod boxalloc;
 pub mod color;
+pub mod content;
+pub mod decoration;
+pub mod diagnostics;
+mod distribute;
+pub mod geometry;
 pub mod macros;
 pub mod position;
+pub mod scrollbar;
 pub mod sizing;
+pub mod snapshot;
+mod spatial;
+pub mod stats;
+pub mod tokens;
+
+/// Layout rounds `Root::compute` runs at most by default
+pub const DEFAULT_MAX_LAYOUT_ROUNDS: usize = 3;
+
+/// Width of the scrollbars of `Overflow::Auto` frames by default
+pub const DEFAULT_SCROLLBAR_WIDTH: u32 = 8;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Space {
@@ -42,6 +72,32 @@ impl Space {
         self.height = Some(height);
         self
     }
+
+    /// Resolved rectangle of this space, unknown sizes being 0
+    #[inline]
+    pub fn rect(&self) -> Rect {
+        Rect::from(*self)
+    }
+}
+
+/// Subpixel layout result of a node, before pixel snapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LayoutBox {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Space a parent gave to a frame in pass 2, to lay the frame out again on
+/// its own (see `Root::is_layout_boundary`)
+#[derive(Debug, Clone, Copy)]
+struct LayoutArgs {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    depth: usize,
 }
 
 /// A reference to an internal data element
@@ -67,6 +123,25 @@ impl Ord for CapsuleRef {
     }
 }
 
+impl CapsuleRef {
+    /// Packs the reference in a `u64` (generation in the high 32 bits, id in
+    /// the low ones) that can be handed to other processes
+    pub fn to_raw(self) -> u64 {
+        debug_assert!(self.id <= u32::MAX as usize, "capsule id overflows 32 bits");
+        ((self.generation as u64) << 32) | (self.id as u64 & u32::MAX as u64)
+    }
+
+    /// Unpacks a value from `to_raw`. Gives `None` when it does not refer to
+    /// a live frame of `root` (removed frame, slot reused since...).
+    pub fn from_raw(raw: u64, root: &Root) -> Option<Self> {
+        let capsule_ref = Self {
+            id: (raw & u32::MAX as u64) as usize,
+            generation: (raw >> 32) as u32,
+        };
+        root.get_capsule(capsule_ref).map(|_| capsule_ref)
+    }
+}
+
 impl std::fmt::Debug for CapsuleRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}@{}", self.id, self.generation)
@@ -88,6 +163,13 @@ pub struct Capsule {
     children: Vec<CapsuleRef>,
 }
 
+impl Capsule {
+    #[inline]
+    pub fn children(&self) -> &[CapsuleRef] {
+        &self.children
+    }
+}
+
 /// Describe a frame box element
 #[derive(Debug, Clone, Copy)]
 pub struct Frame {
@@ -104,27 +186,17 @@ impl<'a> Frame {
     {
         if let Some(style_mut) = self.get_style_mut(root) {
             applier(style_mut);
+            // The look may change without the layout changing
+            if let Some(rect) = root.get_rect(self.capsule_ref) {
+                root.add_damage(rect);
+            }
             self.set_dirty(root);
         }
     }
 
     fn get_style_mut(&self, root: &'a mut Root) -> Option<&'a mut Style> {
-        let style_ref = if let Some(capsule) = root.get_capsule_mut(self.capsule_ref) {
-            // We get the `usize`, and the borrow of `root` ends here.
-            Some(capsule.style_ref)
-        } else {
-            // The handle was invalid
-            return None;
-        };
-
-        if let Some(style_ref) = style_ref {
-            root.styles
-                .get_mut(style_ref)
-                .and_then(|style_option| style_option.as_mut())
-        } else {
-            // This case is already handled, but we're explicit.
-            None
-        }
+        // `None` if the handle is stale
+        root.get_style_mut(self.capsule_ref)
     }
 }
 
@@ -138,12 +210,15 @@ impl Frame {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, StyleFields)]
 pub struct Style {
     /// Informative style only. Depending on the Frame
     /// type, this information may be taken into consideration for
     /// use. Like a Box like Frame
     pub background_color: Color,
+    /// Design tokens the colors and spacings are taken from, see
+    /// `Root::set_token`
+    pub tokens: StyleTokens,
     /// Width taken by a Frame
     pub width: SizeSpec,
     /// Height taken by a Frame
@@ -161,6 +236,10 @@ pub struct Style {
     /// Shadow definition
     pub shadow: Shadow,
 
+    /// Shorthand for a shadow preset (see `Shadow::elevation`).
+    /// Only used when no visible `shadow` is set.
+    pub elevation: u8,
+
     /// Defines how much a flex item will grow.
     /// Default is 0.0 (don't grow).
     pub flex_grow: f32,
@@ -179,17 +258,23 @@ pub struct Style {
     /// Position relative to the parent element
     pub position: Position,
 
+    /// Whether children past the content box are drawn, cut or scrollable
+    pub overflow: Overflow,
+
+    /// Whether the frame and its children can be hit by the pointer
+    pub pointer_events: PointerEvents,
+
     /// The distribution of children along the **main axis**.
     pub justify_content: JustifyContent,
     /// The alignment of children along the **cross axis**.
     pub align_items: AlignItems,
 
     /// The intrinsic content width, as measured by a component.
-    /// This is used by `SizeSpec::Fit`.
+    /// This is used by `SizeSpec::Fit`, unless a `ContentProvider` is set.
     pub intrinsic_width: Option<u32>,
 
     /// The intrinsic content height, as measured by a component.
-    /// This is used by `SizeSpec::Fit`.
+    /// This is used by `SizeSpec::Fit`, unless a `ContentProvider` is set.
     pub intrinsic_height: Option<u32>,
 
     /// Draw order change. Higher the later
@@ -202,15 +287,19 @@ impl Default for Style {
     fn default() -> Self {
         Self {
             background_color: Color::default(),
+            tokens: StyleTokens::default(),
             width: SizeSpec::default(),
             height: SizeSpec::default(),
             padding: Padding::default(),
             margin: Margin::default(),
             border: Border::default(),
             shadow: Shadow::default(),
+            elevation: 0,
             layout: LayoutStrategy::default(),
             flow: Direction::default(),
             position: Position::default(),
+            overflow: Overflow::default(),
+            pointer_events: PointerEvents::default(),
 
             justify_content: JustifyContent::default(),
             align_items: AlignItems::default(),
@@ -227,15 +316,105 @@ impl Default for Style {
     }
 }
 
+impl Style {
+    /// Whether the parent alone sets the size of the frame on both axes
+    /// (`Pixel`, `Percent`, `Fill`...), whatever its content
+    pub fn sized_by_parent(&self) -> bool {
+        let from_parent = |spec: SizeSpec| {
+            matches!(
+                spec,
+                SizeSpec::Pixel(_)
+                    | SizeSpec::Fill
+                    | SizeSpec::Percent(_)
+                    | SizeSpec::PercentOfRemaining(_)
+            )
+        };
+        from_parent(self.width) && from_parent(self.height)
+    }
+
+    /// The shadow to draw: `shadow` when visible, the `elevation` preset otherwise
+    pub fn resolved_shadow(&self) -> Shadow {
+        if self.shadow.is_visible() {
+            self.shadow
+        } else {
+            Shadow::elevation(self.elevation)
+        }
+    }
+}
+
+/// A named top-level tree with an explicit stacking order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    /// Layers with a higher order are stacked above the others
+    pub order: i32,
+}
+
 #[derive(Debug)]
 pub struct Root {
     pub capsules: Vec<CapsuleSlot>,
     capsule_free_list: VecDeque<usize>,
     pub spaces: Vec<Option<Space>>,
+    /// Subpixel boxes backing `spaces`, indexed the same way
+    layout_boxes: Vec<Option<LayoutBox>>,
     styles: Vec<Option<Style>>,
 
     dirties: HashSet<CapsuleRef>,
+    /// Dirty frames laid out on their own, their parent being left as is
+    layout_roots: HashSet<CapsuleRef>,
+    /// Last pass 2 space of the frames that can be layout roots
+    layout_args: HashMap<CapsuleRef, LayoutArgs>,
     allocator: Allocator,
+    layout_mode: LayoutMode,
+    pixel_snapping: PixelSnapping,
+    /// Area that changed since the last `take_damage`
+    damage: Option<Rect>,
+    /// Top-level frames acting as layers
+    layers: HashMap<CapsuleRef, Layer>,
+    last_stats: LayoutStats,
+    #[cfg(feature = "profiling")]
+    profile: stats::LayoutProfile,
+    content_providers: Providers,
+    /// Width each frame was last measured within, see `compute_pass_1_measure`
+    measured_within: HashMap<CapsuleRef, Option<u32>>,
+    /// Scroll position of `Overflow::Scroll` (and `Auto`) frames
+    scroll_offsets: HashMap<CapsuleRef, (f32, f32)>,
+    /// Distance scroll containers are pulled past the ends of their content
+    overscrolls: HashMap<CapsuleRef, (f32, f32)>,
+    /// Scroll containers that moved since the last `take_scrolled`
+    scrolled: Vec<CapsuleRef>,
+    /// Size taken by the children of `Overflow::Scroll` (and `Auto`) frames
+    scroll_extents: HashMap<CapsuleRef, (f32, f32)>,
+    /// Scrollbars shown by `Overflow::Auto` frames
+    gutters: HashMap<CapsuleRef, Gutters>,
+    scrollbar_width: u32,
+    /// Rhythm unit of the heights of measured content, see `set_baseline_grid`
+    baseline_grid: Option<u32>,
+    tokens: Tokens,
+    /// Laid out frames by area, for `hit_test` and `frames_in`
+    spatial: SpatialIndex,
+    /// Max width handed to each content provider and the content width it
+    /// answered, see `frames_to_remeasure`
+    provider_widths: HashMap<CapsuleRef, (Option<u32>, u32)>,
+    /// Width and height of the content of frames with a content provider or
+    /// intrinsic sizes, for `SizeSpec::IntrinsicRatio`
+    content_ratios: HashMap<CapsuleRef, (u32, u32)>,
+    /// Enlargements laid out on top of the styles, by style slot (slots are
+    /// never reused), see `set_enlargement`
+    enlargements: HashMap<usize, Enlargement>,
+    /// First baseline of the content of frames, from the top of their
+    /// content box, see `set_baseline`
+    baselines: HashMap<CapsuleRef, f32>,
+    /// Content width forced on providers by a later layout round
+    width_overrides: HashMap<CapsuleRef, u32>,
+    max_layout_rounds: usize,
+    /// Frames that had not settled when the last `compute` stopped
+    unstable_frames: Vec<CapsuleRef>,
+    decorations: HashMap<CapsuleRef, Vec<(DecorationId, Decoration)>>,
+    next_decoration_id: u64,
+    /// Diagnostics are recorded, see `set_strict`
+    strict: bool,
+    diagnostics: Vec<LayoutDiagnostic>,
 }
 
 impl Root {
@@ -243,15 +422,251 @@ impl Root {
         Self {
             // NOTE: space[0] is the root space and should always be accessible
             spaces: vec![Some(Space::zero().with_width(width).with_height(height))],
+            layout_boxes: vec![None],
 
             styles: vec![],
             capsules: vec![],
             dirties: HashSet::new(),
+            layout_roots: HashSet::new(),
+            layout_args: HashMap::new(),
             capsule_free_list: VecDeque::new(),
             allocator: Allocator::new(),
+            layout_mode: LayoutMode::default(),
+            pixel_snapping: PixelSnapping::default(),
+            damage: None,
+            layers: HashMap::new(),
+            last_stats: LayoutStats::default(),
+            #[cfg(feature = "profiling")]
+            profile: stats::LayoutProfile::default(),
+            content_providers: Providers::default(),
+            measured_within: HashMap::new(),
+            scroll_offsets: HashMap::new(),
+            overscrolls: HashMap::new(),
+            scrolled: Vec::new(),
+            scroll_extents: HashMap::new(),
+            gutters: HashMap::new(),
+            scrollbar_width: DEFAULT_SCROLLBAR_WIDTH,
+            baseline_grid: None,
+            tokens: Tokens::default(),
+            spatial: SpatialIndex::default(),
+            provider_widths: HashMap::new(),
+            content_ratios: HashMap::new(),
+            enlargements: HashMap::new(),
+            baselines: HashMap::new(),
+            width_overrides: HashMap::new(),
+            max_layout_rounds: DEFAULT_MAX_LAYOUT_ROUNDS,
+            unstable_frames: Vec::new(),
+            decorations: HashMap::new(),
+            next_decoration_id: 0,
+            strict: false,
+            diagnostics: Vec::new(),
         }
     }
 
+    #[inline]
+    pub fn layout_mode(&self) -> LayoutMode {
+        self.layout_mode
+    }
+
+    /// Switch the arithmetic used to distribute free space in flex layouts.
+    /// Every tree is re-laid out on the next `compute`.
+    pub fn set_layout_mode(&mut self, mode: LayoutMode) {
+        if self.layout_mode == mode {
+            return;
+        }
+        self.layout_mode = mode;
+        self.set_all_dirty();
+    }
+
+    #[inline]
+    pub fn pixel_snapping(&self) -> PixelSnapping {
+        self.pixel_snapping
+    }
+
+    /// Set how subpixel layout results are snapped to whole pixels.
+    /// Every tree is re-laid out on the next `compute`.
+    pub fn set_pixel_snapping(&mut self, snapping: PixelSnapping) {
+        if self.pixel_snapping == snapping {
+            return;
+        }
+        self.pixel_snapping = snapping;
+        self.set_all_dirty();
+    }
+
+    #[inline]
+    pub fn max_layout_rounds(&self) -> usize {
+        self.max_layout_rounds
+    }
+
+    /// Number of measure/layout rounds a `compute` may run (at least 1).
+    /// Extra rounds measure content providers again within the width the
+    /// layout actually gave them, when it is narrower than their content
+    /// (wrapped text getting taller...).
+    pub fn set_max_layout_rounds(&mut self, rounds: usize) {
+        self.max_layout_rounds = rounds.max(1);
+    }
+
+    #[inline]
+    pub fn scrollbar_width(&self) -> u32 {
+        self.scrollbar_width
+    }
+
+    /// Width of the scrollbars of `Overflow::Auto` frames, and of the gutter
+    /// they take off the content box.
+    pub fn set_scrollbar_width(&mut self, width: u32) {
+        if self.scrollbar_width == width {
+            return;
+        }
+        self.scrollbar_width = width;
+        let shown: Vec<_> = self.gutters.keys().copied().collect();
+        for frame_ref in shown {
+            self.set_dirty(frame_ref);
+        }
+    }
+
+    /// Vertical rhythm of dense UIs: the heights measured by content
+    /// providers (lines of text...) and the gaps of column flows are rounded
+    /// up to a multiple of `unit`. `None` or 0 turns it off (the default).
+    pub fn set_baseline_grid(&mut self, unit: Option<u32>) {
+        let unit = unit.filter(|unit| *unit > 0);
+        if self.baseline_grid == unit {
+            return;
+        }
+        self.baseline_grid = unit;
+
+        let affected: Vec<CapsuleRef> = self
+            .capsules
+            .iter()
+            .enumerate()
+            .filter_map(|(id, slot)| {
+                let capsule = slot.capsule.as_ref()?;
+                let cref = CapsuleRef {
+                    id,
+                    generation: slot.generation,
+                };
+                let style = self.styles.get(capsule.style_ref)?.as_ref()?;
+                let column_gap = style.flow == Direction::Column && style.gap > 0;
+                (column_gap || self.content_providers.contains(&cref)).then_some(cref)
+            })
+            .collect();
+        for frame_ref in affected {
+            self.set_dirty(frame_ref);
+        }
+    }
+
+    #[inline]
+    pub fn baseline_grid(&self) -> Option<u32> {
+        self.baseline_grid
+    }
+
+    /// `value` rounded up to the baseline grid, if there is one
+    fn on_grid(&self, value: u32) -> u32 {
+        match self.baseline_grid {
+            Some(unit) => value.div_ceil(unit) * unit,
+            None => value,
+        }
+    }
+
+    /// Sets a design token styles can refer to with `SizeSpec::Token` and
+    /// `Style::tokens`. The frames using it are laid out and drawn again.
+    ///
+    /// ```rust,ignore
+    /// root.set_token("spacing-m", 12);
+    /// root.set_token("accent", Color::Hex(0x4455eeFF));
+    /// ```
+    pub fn set_token(&mut self, name: impl Into<String>, value: impl Into<TokenValue>) {
+        let name = name.into();
+        if self.tokens.set(name.clone(), value.into()) {
+            self.invalidate_token(&name);
+        }
+    }
+
+    #[inline]
+    pub fn token(&self, name: &str) -> Option<TokenValue> {
+        self.tokens.get(name)
+    }
+
+    /// Returns whether the token existed. The styles using it fall back to
+    /// `SizeSpec::Auto`, `Color::transparent` and spacings of 0.
+    pub fn remove_token(&mut self, name: &str) -> bool {
+        // Users are found while the token still links them to it
+        self.invalidate_token(name);
+        self.tokens.remove(name)
+    }
+
+    /// Lays `frame_ref` out larger than its style says, without changing the
+    /// style: later updates of the style keep the enlargement. `None` takes
+    /// it back.
+    pub fn set_enlargement(&mut self, frame_ref: CapsuleRef, enlargement: Option<Enlargement>) {
+        let Some(style_ref) = self.get_capsule(frame_ref).map(|c| c.style_ref) else {
+            return;
+        };
+        let previous = match enlargement {
+            Some(enlargement) => self.enlargements.insert(style_ref, enlargement),
+            None => self.enlargements.remove(&style_ref),
+        };
+        if previous != enlargement {
+            self.set_dirty(frame_ref);
+        }
+    }
+
+    pub fn enlargement(&self, frame_ref: CapsuleRef) -> Option<Enlargement> {
+        let style_ref = self.get_capsule(frame_ref)?.style_ref;
+        self.enlargements.get(&style_ref).copied()
+    }
+
+    /// Marks the frames whose style depends on a token dirty
+    fn invalidate_token(&mut self, name: &str) {
+        let users: Vec<CapsuleRef> = self
+            .capsules
+            .iter()
+            .enumerate()
+            .filter_map(|(id, slot)| {
+                let capsule = slot.capsule.as_ref()?;
+                let style = self.styles.get(capsule.style_ref)?.as_ref()?;
+                self.tokens.is_used_by(style, name).then_some(CapsuleRef {
+                    id,
+                    generation: slot.generation,
+                })
+            })
+            .collect();
+
+        for frame_ref in users {
+            // Colors change without the layout changing
+            if let Some(rect) = self.get_rect(frame_ref) {
+                self.add_damage(rect);
+            }
+            self.set_dirty(frame_ref);
+        }
+    }
+
+    /// Frames whose size was still changing when the last `compute` ran out
+    /// of rounds, or kept alternating between the same widths.
+    #[inline]
+    pub fn unstable_frames(&self) -> &[CapsuleRef] {
+        &self.unstable_frames
+    }
+
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// In strict mode, `compute` records what it had to work around
+    /// (frames skipped, sizes taken as 0...) for `take_diagnostics`.
+    /// Meant for debugging: leaving it off costs nothing.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        if !strict {
+            self.diagnostics.clear();
+        }
+    }
+
+    /// Diagnostics recorded since the last call, in the order they happened
+    pub fn take_diagnostics(&mut self) -> Vec<LayoutDiagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
     #[inline]
     pub fn is_dirty(&self) -> bool {
         !self.dirties.is_empty()
@@ -275,6 +690,12 @@ impl Root {
         self.allocator.get_mut(index)
     }
 
+    /// Frees data that is not bound to any frame.
+    /// Returns whether there was data at `index`.
+    pub fn remove_binding(&mut self, index: DataRef) -> bool {
+        self.allocator.dealloc(index)
+    }
+
     pub fn unbind_data(&mut self, frame_ref: CapsuleRef) -> bool {
         if let Some(capsule) = self.get_capsule_mut(frame_ref) {
             if let Some(data_ref) = capsule.data_ref.take() {
@@ -284,6 +705,40 @@ impl Root {
         false
     }
 
+    /// Binds a frame to other data without freeing the data it had, which
+    /// may be bound to other frames. Returns the previous data.
+    pub fn rebind_data(&mut self, frame_ref: CapsuleRef, data: Option<DataRef>) -> Option<DataRef> {
+        let capsule = self.get_capsule_mut(frame_ref)?;
+        let previous = std::mem::replace(&mut capsule.data_ref, data);
+        self.set_content_dirty(frame_ref);
+        previous
+    }
+
+    /// Lets `provider` report the content size of a frame during measure.
+    /// It takes precedence over the frame's children and intrinsic sizes.
+    /// Call `invalidate_content` whenever what it measures changes.
+    pub fn set_content_provider(&mut self, frame_ref: CapsuleRef, provider: impl ContentProvider) {
+        if self.get_capsule(frame_ref).is_some() {
+            self.content_providers.insert(frame_ref, Box::new(provider));
+            self.set_content_dirty(frame_ref);
+        }
+    }
+
+    pub fn remove_content_provider(&mut self, frame_ref: CapsuleRef) -> bool {
+        let removed = self.content_providers.remove(&frame_ref).is_some();
+        self.provider_widths.remove(&frame_ref);
+        if removed {
+            self.set_content_dirty(frame_ref);
+        }
+        removed
+    }
+
+    /// Asks for the frame content to be measured again on the next compute
+    #[inline]
+    pub fn invalidate_content(&mut self, frame_ref: CapsuleRef) {
+        self.set_content_dirty(frame_ref);
+    }
+
     pub fn set_parent(&mut self, child_frame: Frame, new_parent_frame: Frame) {
         let child_ref = child_frame.get_ref();
 
@@ -295,7 +750,7 @@ impl Root {
                 // Remove the child from the old parent's children
                 old_parent_capsule.children.retain(|&c| c != child_ref);
             }
-            self.set_dirty(old_parent_ref); // Old parent's layout is now invalid
+            self.set_content_dirty(old_parent_ref); // Old parent's layout is now invalid
         }
 
         // Add child to new parent's list
@@ -309,7 +764,7 @@ impl Root {
             child_capsule.parent_ref = Some(new_parent_ref);
         }
 
-        self.set_dirty(new_parent_ref);
+        self.set_content_dirty(new_parent_ref);
     }
 
     fn internal_add_frame(
@@ -321,6 +776,7 @@ impl Root {
         let space = Space::zero();
 
         self.spaces.push(Some(space));
+        self.layout_boxes.push(None);
 
         let new_style_idx = self.styles.len();
         self.styles.push(Some(Style::default()));
@@ -373,29 +829,282 @@ impl Root {
     pub fn add_frame(&mut self, data: Option<DataRef>) -> Frame {
         self.internal_add_frame(None, data)
     }
+
+    /// Adds a child to `to` for each style (and data) of `children`, in
+    /// order. The parent is marked dirty once for all of them, where adding
+    /// them one by one walks the dirty chain for each new child.
+    pub fn add_frame_children(
+        &mut self,
+        to: &Frame,
+        children: impl IntoIterator<Item = (Style, Option<DataRef>)>,
+    ) -> Vec<Frame> {
+        let children = children.into_iter();
+        if let Some(parent) = self.get_capsule_mut(to.capsule_ref) {
+            parent.children.reserve(children.size_hint().0);
+        }
+
+        let frames: Vec<Frame> = children
+            .map(|(style, data)| {
+                let frame = self.internal_add_frame(Some(to.capsule_ref), data);
+                if let Some(frame_style) = self.get_style_mut(frame.capsule_ref) {
+                    *frame_style = style;
+                }
+                self.dirties.insert(frame.capsule_ref);
+                frame
+            })
+            .collect();
+
+        if !frames.is_empty() {
+            self.set_content_dirty(to.capsule_ref);
+        }
+        frames
+    }
 }
 
 impl Root {
-    pub fn hit_test(&self, x: i32, y: i32) -> Vec<CapsuleRef> {
-        let mut hits = Vec::new();
+    /// Attaches a decoration to a frame. The decoration is dropped (and its
+    /// data freed) with the frame. Returns `None` for dead frames.
+    pub fn add_decoration(
+        &mut self,
+        frame_ref: CapsuleRef,
+        decoration: Decoration,
+    ) -> Option<DecorationId> {
+        self.get_capsule(frame_ref)?;
+        let id = DecorationId(self.next_decoration_id);
+        self.next_decoration_id += 1;
+
+        self.decorations
+            .entry(frame_ref)
+            .or_default()
+            .push((id, decoration));
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(decoration.place(rect));
+        }
+        Some(id)
+    }
 
-        for (i, slot) in self.capsules.iter().enumerate() {
-            if let Some(caps) = &slot.capsule {
-                let space = self.spaces.get(caps.space_ref).and_then(|s| s.as_ref());
-                if let Some(fs) = space {
-                    let (w, h) = (fs.width.unwrap_or(0) as i32, fs.height.unwrap_or(0) as i32);
+    /// Replaces a decoration, keeping its frame.
+    /// Returns whether the decoration exists.
+    pub fn set_decoration(&mut self, id: DecorationId, decoration: Decoration) -> bool {
+        let Some((frame_ref, previous)) = self.take_decoration_slot(id, Some(decoration)) else {
+            return false;
+        };
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(previous.place(rect));
+            self.add_damage(decoration.place(rect));
+        }
+        true
+    }
 
-                    if x >= fs.x && x <= (fs.x + w) && y >= fs.y && y <= (fs.y + h) {
-                        hits.push(CapsuleRef {
-                            id: i,
-                            generation: slot.generation,
-                        });
-                    }
+    /// Detaches a decoration. Its data is left to the caller.
+    pub fn remove_decoration(&mut self, id: DecorationId) -> Option<Decoration> {
+        let (frame_ref, removed) = self.take_decoration_slot(id, None)?;
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(removed.place(rect));
+        }
+        Some(removed)
+    }
+
+    /// Decorations of a frame with where they land, in the order they were added.
+    /// Empty until the frame is laid out.
+    pub fn decorations(
+        &self,
+        frame_ref: CapsuleRef,
+    ) -> impl Iterator<Item = (DecorationId, Rect, &Decoration)> {
+        let rect = self.get_rect(frame_ref);
+        self.decorations
+            .get(&frame_ref)
+            .into_iter()
+            .flatten()
+            .filter_map(move |(id, decoration)| Some((*id, decoration.place(rect?), decoration)))
+    }
+
+    /// Replaces (or removes, with `None`) a decoration in place.
+    /// Returns its frame and the previous decoration.
+    fn take_decoration_slot(
+        &mut self,
+        id: DecorationId,
+        replacement: Option<Decoration>,
+    ) -> Option<(CapsuleRef, Decoration)> {
+        let (&frame_ref, list) = self
+            .decorations
+            .iter_mut()
+            .find(|(_, list)| list.iter().any(|(other, _)| *other == id))?;
+        let index = list.iter().position(|(other, _)| *other == id)?;
+
+        let previous = match replacement {
+            Some(decoration) => std::mem::replace(&mut list[index].1, decoration),
+            None => {
+                let (_, previous) = list.remove(index);
+                if list.is_empty() {
+                    self.decorations.remove(&frame_ref);
                 }
+                previous
+            }
+        };
+        Some((frame_ref, previous))
+    }
+
+    /// Repaints the decorations of a frame laid out at `rect`
+    fn damage_decorations(&mut self, frame_ref: CapsuleRef, rect: Rect) {
+        let placed: Vec<Rect> = self
+            .decorations
+            .get(&frame_ref)
+            .into_iter()
+            .flatten()
+            .map(|(_, decoration)| decoration.place(rect))
+            .collect();
+        for rect in placed {
+            self.add_damage(rect);
+        }
+    }
+}
+
+impl Root {
+    /// Creates a named layer: a top-level frame laid out against the
+    /// whole window, stacked according to `order` rather than creation order.
+    /// Frames outside of any layer are stacked as if they had order 0.
+    pub fn create_layer(&mut self, name: impl Into<String>, order: i32) -> Frame {
+        let frame = self.add_frame(None);
+        frame.update_style(self, |style| {
+            style.width = SizeSpec::Fill;
+            style.height = SizeSpec::Fill;
+            style.layout = LayoutStrategy::NoStrategy;
+        });
+
+        self.layers.insert(
+            frame.capsule_ref,
+            Layer {
+                name: name.into(),
+                order,
+            },
+        );
+        frame
+    }
+
+    /// Finds a layer frame by name
+    pub fn get_layer(&self, name: &str) -> Option<Frame> {
+        self.layers
+            .iter()
+            .find(|(_, layer)| layer.name == name)
+            .map(|(cref, _)| Frame::define(*cref))
+    }
+
+    /// Changes the stacking order of a layer.
+    /// Returns `false` if no layer has this name.
+    pub fn set_layer_order(&mut self, name: &str, order: i32) -> bool {
+        let Some((cref, layer)) = self.layers.iter_mut().find(|(_, l)| l.name == name) else {
+            return false;
+        };
+        layer.order = order;
+        let cref = *cref;
+        if let Some(rect) = self.get_rect(cref) {
+            self.add_damage(rect);
+        }
+        true
+    }
+
+    /// Every layer frame, from the bottom-most to the top-most.
+    /// Layers with the same order keep their creation order.
+    pub fn layers(&self) -> Vec<(Frame, &Layer)> {
+        let mut layers: Vec<_> = self
+            .layers
+            .iter()
+            .map(|(cref, layer)| (Frame::define(*cref), layer))
+            .collect();
+        layers.sort_by(|a, b| {
+            a.1.order
+                .cmp(&b.1.order)
+                .then(a.0.capsule_ref.cmp(&b.0.capsule_ref))
+        });
+        layers
+    }
+
+    /// Stacking order of the layer a frame belongs to (0 outside of layers)
+    pub fn layer_order(&self, frame_ref: CapsuleRef) -> i32 {
+        let mut current = frame_ref;
+        while let Some(capsule) = self.get_capsule(current) {
+            match capsule.parent_ref {
+                Some(parent_ref) => current = parent_ref,
+                None => break,
             }
         }
 
-        hits
+        self.layers.get(&current).map_or(0, |layer| layer.order)
+    }
+}
+
+impl Root {
+    /// Frames containing a point, parts cut by their ancestors and frames
+    /// under `PointerEvents::None` aside. Ordered top-most first: by layer,
+    /// then z-index, then tree order (children over their parent, later
+    /// siblings over earlier ones).
+    pub fn hit_test(&self, x: i32, y: i32) -> Vec<CapsuleRef> {
+        let mut hits: Vec<_> = self
+            .spatial
+            .at(x, y)
+            .into_iter()
+            .filter(|cref| self.clip_rect(*cref).is_none_or(|clip| clip.contains(x, y)))
+            .filter(|cref| self.receives_pointer(*cref))
+            .map(|cref| {
+                let z_index = self.get_style(cref).map_or(0, |style| style.z_index);
+                (
+                    (self.layer_order(cref), z_index, self.tree_path(cref)),
+                    cref,
+                )
+            })
+            .collect();
+        hits.sort_by(|a, b| b.cmp(a));
+        hits.into_iter().map(|(_, cref)| cref).collect()
+    }
+
+    /// The top-most frame containing a point, see `hit_test`
+    pub fn hit_test_top(&self, x: i32, y: i32) -> Option<CapsuleRef> {
+        self.hit_test(x, y).into_iter().next()
+    }
+
+    /// Position of a frame in a depth-first walk of the tree: the top-level
+    /// frame it is in, then its index among its siblings at each level.
+    /// Comparing paths compares positions in the walk.
+    fn tree_path(&self, frame_ref: CapsuleRef) -> (CapsuleRef, Vec<usize>) {
+        let mut indices = Vec::new();
+        let mut current = frame_ref;
+        while let Some(parent_ref) = self.get_capsule(current).and_then(|c| c.parent_ref) {
+            let index = self
+                .get_capsule(parent_ref)
+                .and_then(|parent| parent.children.iter().position(|c| *c == current))
+                .unwrap_or(0);
+            indices.push(index);
+            current = parent_ref;
+        }
+        indices.reverse();
+        (current, indices)
+    }
+
+    /// Neither the frame nor one of its ancestors has `PointerEvents::None`
+    fn receives_pointer(&self, frame_ref: CapsuleRef) -> bool {
+        let mut current = Some(frame_ref);
+        while let Some(cref) = current {
+            let Some(capsule) = self.get_capsule(cref) else {
+                return false;
+            };
+            if self
+                .style_of(capsule)
+                .is_some_and(|style| style.pointer_events == PointerEvents::None)
+            {
+                return false;
+            }
+            current = capsule.parent_ref;
+        }
+        true
+    }
+
+    /// Frames intersecting an area as of the last layout (the window, to
+    /// only draw what is visible...). Ordered by `CapsuleRef`.
+    pub fn frames_in(&self, area: Rect) -> Vec<CapsuleRef> {
+        let mut frames = self.spatial.within(area);
+        frames.sort();
+        frames
     }
 }
 
@@ -420,34 +1129,154 @@ impl Root {
         }
         None
     }
+
+    /// Style slot of a capsule. Stale or out of range indices give `None`.
+    /// Its design tokens are replaced by their values, and its enlargement
+    /// is applied.
+    fn style_of(&self, capsule: &Capsule) -> Option<Style> {
+        let mut style = (*self.styles.get(capsule.style_ref)?)?;
+        self.tokens.resolve(&mut style);
+        if let Some(enlargement) = self.enlargements.get(&capsule.style_ref) {
+            enlargement.apply(&mut style.width, &mut style.height, &mut style.padding);
+        }
+        if style.flow == Direction::Column {
+            style.gap = self.on_grid(style.gap);
+        }
+        Some(style)
+    }
+
+    /// Space slot of a capsule. Stale or out of range indices give `None`.
+    fn space_of(&self, capsule: &Capsule) -> Option<&Space> {
+        self.spaces.get(capsule.space_ref)?.as_ref()
+    }
+
+    fn get_style_mut(&mut self, frame_ref: CapsuleRef) -> Option<&mut Style> {
+        let style_ref = self.get_capsule(frame_ref)?.style_ref;
+        self.styles.get_mut(style_ref)?.as_mut()
+    }
 }
 
 impl Root {
+    /// The frame changed (its style...): its parent lays it out again,
+    /// and so on up to a layout boundary
     fn set_dirty(&mut self, capsule_ref: CapsuleRef) {
-        if !self.dirties.insert(capsule_ref) {
+        // A layout root now changes itself
+        let was_root = self.layout_roots.remove(&capsule_ref);
+        if !self.dirties.insert(capsule_ref) && !was_root {
             return;
         }
 
-        let mut current = self.get_capsule(capsule_ref);
-        while let Some(capsule) = current {
-            if let Some(parent_ref) = capsule.parent_ref {
-                if !self.dirties.insert(parent_ref) {
-                    break; // Parent already dirty
+        let mut current = capsule_ref;
+        while let Some(parent_ref) = self.get_capsule(current).and_then(|c| c.parent_ref) {
+            if self.is_layout_boundary(parent_ref) {
+                // Its size does not follow its content, its parent is left as is
+                if self.dirties.insert(parent_ref) {
+                    self.layout_roots.insert(parent_ref);
                 }
-                current = self.get_capsule(parent_ref);
-            } else {
-                break; // Reached the top
+                break;
+            }
+            if !self.dirties.insert(parent_ref) {
+                break; // Parent already dirty
             }
+            current = parent_ref;
         }
     }
+
+    /// Only what is inside the frame changed (its children, the content
+    /// measured by its provider, its scroll position...)
+    fn set_content_dirty(&mut self, capsule_ref: CapsuleRef) {
+        if !self.is_layout_boundary(capsule_ref) {
+            self.set_dirty(capsule_ref);
+        } else if self.dirties.insert(capsule_ref) {
+            self.layout_roots.insert(capsule_ref);
+        }
+    }
+
+    /// Frames whose box does not depend on their content (see
+    /// `Style::sized_by_parent`), already laid out once. They can be laid
+    /// out again on their own, in the space they were last given.
+    fn is_layout_boundary(&self, frame_ref: CapsuleRef) -> bool {
+        self.layout_args.contains_key(&frame_ref)
+            && !self.width_overrides.contains_key(&frame_ref)
+            && self.get_capsule(frame_ref).is_some_and(|capsule| {
+                capsule.parent_ref.is_some()
+                    && self
+                        .style_of(capsule)
+                        .is_some_and(|style| style.sized_by_parent())
+            })
+    }
 }
 
 impl Root {
-    pub fn compute(&mut self) {
+    /// Lays out every dirty tree and reports the work done.
+    /// Changes inside a frame whose size does not follow its content
+    /// (see `Style::sized_by_parent`) only lay that frame out again.
+    /// The same report is available afterwards through `last_stats`.
+    #[inline]
+    pub fn compute(&mut self) -> LayoutStats {
+        self.compute_with(&mut ())
+    }
+
+    /// Same as `compute`, handing `context` to every content provider
+    /// (a font system for text measurement, for instance).
+    pub fn compute_with(&mut self, context: &mut dyn Any) -> LayoutStats {
+        let started = std::time::Instant::now();
+        self.last_stats = LayoutStats::default();
+
         if self.dirties.is_empty() {
-            return;
+            self.last_stats.duration = started.elapsed();
+            return self.last_stats;
+        }
+        self.unstable_frames.clear();
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.computes += 1;
+            self.profile.dirty_frames += self.dirties.len();
+            self.profile.max_dirty_frames = self.profile.max_dirty_frames.max(self.dirties.len());
         }
 
+        // Widths each frame was re-measured within, and scrollbars each
+        // frame was laid out with, to detect oscillations
+        let mut tried_widths: HashMap<CapsuleRef, Vec<u32>> = HashMap::new();
+        let mut tried_gutters: HashMap<CapsuleRef, Vec<Gutters>> = HashMap::new();
+        loop {
+            self.last_stats.rounds += 1;
+            self.compute_round(context);
+
+            let pending = self.frames_to_remeasure(&mut tried_widths);
+            let toggled = self.gutters_to_toggle(&mut tried_gutters);
+            let clamped = self.clamp_scroll_offsets();
+            if pending.is_empty() && toggled.is_empty() && clamped.is_empty() {
+                break;
+            }
+            if self.last_stats.rounds >= self.max_layout_rounds {
+                self.unstable_frames
+                    .extend(pending.iter().map(|(frame_ref, _)| *frame_ref));
+                self.unstable_frames
+                    .extend(toggled.iter().map(|(frame_ref, _)| *frame_ref));
+                break;
+            }
+            for (frame_ref, width) in pending {
+                self.width_overrides.insert(frame_ref, width);
+                self.set_dirty(frame_ref);
+            }
+            for (frame_ref, gutters) in toggled {
+                self.set_gutters(frame_ref, gutters);
+                self.set_dirty(frame_ref);
+            }
+            for frame_ref in clamped {
+                self.set_content_dirty(frame_ref);
+            }
+        }
+        self.width_overrides.clear();
+
+        self.last_stats.unstable_frames = self.unstable_frames.len();
+        self.last_stats.duration = started.elapsed();
+        self.last_stats
+    }
+
+    /// Measures and lays out every dirty top-level tree and layout root once
+    fn compute_round(&mut self, context: &mut dyn Any) {
         // 1. Get the screen's dimensions from the root space (space[0])
         let (root_w, root_h) = {
             let root_space = self.spaces[0].unwrap();
@@ -486,19 +1315,211 @@ impl Root {
         for capsule_ref in dirty_top_level_capsules {
             // Start Pass 1: This computes the "desired" size for all nodes
             // in this tree, storing it in their `Space`.
-            self.compute_pass_1_measure(capsule_ref);
+            self.compute_pass_1_measure(capsule_ref, Some(root_w), context);
 
             // Start Pass 2: This gives each node its final position and size,
             // using the root dimensions as the available space.
-            self.compute_pass_2_layout(capsule_ref, 0, 0, root_w, root_h);
+            self.compute_pass_2_layout(capsule_ref, 0.0, 0.0, root_w as f32, root_h as f32, 1);
+        }
+
+        // 4. Lay out the layout roots the passes above did not reach, in the
+        // space they were last given. Outer ones first, they may move the
+        // inner ones.
+        let mut layout_roots: Vec<(CapsuleRef, LayoutArgs)> = self
+            .layout_roots
+            .iter()
+            .filter(|cref| {
+                // Reached through a dirty parent already
+                self.get_capsule(**cref)
+                    .and_then(|capsule| capsule.parent_ref)
+                    .is_some_and(|parent_ref| !self.dirties.contains(&parent_ref))
+            })
+            .filter_map(|cref| Some((*cref, *self.layout_args.get(cref)?)))
+            .collect();
+        layout_roots.sort_by_key(|(cref, args)| (args.depth, *cref));
+        for (capsule_ref, args) in layout_roots {
+            self.last_stats.subtrees += 1;
+            let available_w = self.measured_within.get(&capsule_ref).copied().flatten();
+            self.compute_pass_1_measure(capsule_ref, available_w, context);
+            self.compute_pass_2_layout(
+                capsule_ref,
+                args.x,
+                args.y,
+                args.width,
+                args.height,
+                args.depth,
+            );
         }
 
         // Clear dirties after compute
         self.dirties.clear();
+        self.layout_roots.clear();
+    }
+
+    /// Content providers laid out narrower than the content they reported,
+    /// with the width they should be measured within in another round.
+    /// Frames already measured within that width are left alone: their
+    /// content cannot get any narrower (a long word...). Frames coming back
+    /// to a width they were already tried with are oscillating, they are
+    /// reported as unstable instead.
+    fn frames_to_remeasure(
+        &mut self,
+        tried_widths: &mut HashMap<CapsuleRef, Vec<u32>>,
+    ) -> Vec<(CapsuleRef, u32)> {
+        let mut pending = Vec::new();
+
+        for (&frame_ref, &(max_width, content_w)) in &self.provider_widths {
+            let Some(rect) = self.get_content_rect(frame_ref) else {
+                continue;
+            };
+            if content_w <= rect.w || max_width == Some(rect.w) {
+                continue;
+            }
+
+            let tried = tried_widths.entry(frame_ref).or_default();
+            if tried.contains(&rect.w) {
+                self.unstable_frames.push(frame_ref);
+            } else {
+                tried.push(rect.w);
+                pending.push((frame_ref, rect.w));
+            }
+        }
+        pending
+    }
+
+    /// `Overflow::Auto` frames whose content stopped (or started) fitting
+    /// during the last round, with the scrollbars they need now. A frame
+    /// going back to scrollbars it was already laid out with keeps both
+    /// sets shown: it cuts no content, where hiding them may.
+    fn gutters_to_toggle(
+        &mut self,
+        tried_gutters: &mut HashMap<CapsuleRef, Vec<Gutters>>,
+    ) -> Vec<(CapsuleRef, Gutters)> {
+        let mut toggled = Vec::new();
+
+        for (&frame_ref, &(extent_w, extent_h)) in &self.scroll_extents {
+            if self
+                .get_style(frame_ref)
+                .is_none_or(|style| style.overflow != Overflow::Auto)
+            {
+                continue;
+            }
+            let Some(view) = self.get_content_rect(frame_ref) else {
+                continue;
+            };
+
+            // The view already lost the gutters of the current scrollbars: one
+            // scrollbar may keep the other one needed
+            let current = self.gutters.get(&frame_ref).copied().unwrap_or_default();
+            let overflows = |extent: f32, view: u32| extent.round() as u32 > view;
+            let needed = Gutters {
+                vertical: overflows(extent_h, view.h),
+                horizontal: overflows(extent_w, view.w),
+            };
+            if needed == current {
+                continue;
+            }
+
+            let tried = tried_gutters.entry(frame_ref).or_default();
+            if !tried.contains(&current) {
+                tried.push(current);
+            }
+            let next = if tried.contains(&needed) {
+                needed.union(current)
+            } else {
+                needed
+            };
+            if next != current {
+                tried.push(next);
+                toggled.push((frame_ref, next));
+            }
+        }
+        toggled
+    }
+
+    /// Brings back the scroll position of containers whose content shrank
+    /// within their new content size. Returns the containers that moved.
+    fn clamp_scroll_offsets(&mut self) -> Vec<CapsuleRef> {
+        let scrolled: Vec<_> = self.scroll_offsets.keys().copied().collect();
+        let mut clamped = Vec::new();
+        for frame_ref in scrolled {
+            let (x, y) = self.scroll_offset(frame_ref);
+            let (max_x, max_y) = self.max_scroll_offset(frame_ref);
+            if x > max_x || y > max_y {
+                self.scroll_offsets
+                    .insert(frame_ref, (x.min(max_x), y.min(max_y)));
+                self.mark_scrolled(frame_ref);
+                clamped.push(frame_ref);
+            }
+        }
+        clamped
+    }
+
+    fn set_gutters(&mut self, frame_ref: CapsuleRef, gutters: Gutters) {
+        if gutters == Gutters::default() {
+            self.gutters.remove(&frame_ref);
+        } else {
+            self.gutters.insert(frame_ref, gutters);
+        }
+        // The scrollbars show up (or go away) within the same box
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(rect);
+        }
+    }
+
+    /// Width and height the scrollbars of a frame take off its content box
+    fn gutter_size(&self, frame_ref: CapsuleRef) -> (u32, u32) {
+        let auto = self
+            .get_style(frame_ref)
+            .is_some_and(|style| style.overflow == Overflow::Auto);
+        match self.gutters.get(&frame_ref) {
+            Some(gutters) if auto => (
+                if gutters.vertical {
+                    self.scrollbar_width
+                } else {
+                    0
+                },
+                if gutters.horizontal {
+                    self.scrollbar_width
+                } else {
+                    0
+                },
+            ),
+            _ => (0, 0),
+        }
+    }
+
+    /// Statistics of the last `compute` call
+    #[inline]
+    pub fn last_stats(&self) -> LayoutStats {
+        self.last_stats
+    }
+
+    /// Counters of the layout hot paths since the root was created or
+    /// `reset_profile`, see `LayoutProfile`
+    #[cfg(feature = "profiling")]
+    pub fn profile(&self) -> stats::LayoutProfile {
+        self.profile
+    }
+
+    /// Starts counting again, before the work to profile
+    #[cfg(feature = "profiling")]
+    pub fn reset_profile(&mut self) {
+        self.profile = stats::LayoutProfile::default();
+    }
+}
+
+impl Root {
+    /// Size the top-level frames are laid out in (the window)
+    pub fn size(&self) -> (u32, u32) {
+        self.spaces[0].map_or((0, 0), |root_space| {
+            (
+                root_space.width.unwrap_or(0),
+                root_space.height.unwrap_or(0),
+            )
+        })
     }
-}
 
-impl Root {
     pub fn resize(&mut self, new_width: u32, new_height: u32) {
         let root_space = self.spaces[0]
             .as_mut()
@@ -507,6 +1528,11 @@ impl Root {
         root_space.width = Some(new_width);
         root_space.height = Some(new_height);
 
+        self.set_all_dirty();
+    }
+
+    /// Marks every top-level tree as dirty.
+    fn set_all_dirty(&mut self) {
         let top_level_capsules = self
             .capsules
             .iter()
@@ -539,6 +1565,14 @@ impl Root {
         };
 
         self.unbind_data(frame_ref);
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.damage_decorations(frame_ref, rect);
+        }
+        for (_, decoration) in self.decorations.remove(&frame_ref).unwrap_or_default() {
+            if let Some(data_ref) = decoration.data_ref {
+                self.allocator.dealloc(data_ref);
+            }
+        }
         for child_ref in capsule.children.clone() {
             self.remove_frame(child_ref); // This call is now safe
         }
@@ -546,13 +1580,39 @@ impl Root {
         if let Some(parent_ref) = capsule.parent_ref {
             if let Some(parent_capsule) = self.get_capsule_mut(parent_ref) {
                 parent_capsule.children.retain(|&c| c != frame_ref);
-                self.set_dirty(parent_ref);
+                self.set_content_dirty(parent_ref);
             }
         }
 
-        self.spaces[capsule.space_ref] = None;
-        self.styles[capsule.style_ref] = None;
+        if let Some(space) = self
+            .spaces
+            .get_mut(capsule.space_ref)
+            .and_then(Option::take)
+        {
+            self.add_damage(space.rect());
+        }
+        if let Some(layout_box) = self.layout_boxes.get_mut(capsule.space_ref) {
+            *layout_box = None;
+        }
+        if let Some(style) = self.styles.get_mut(capsule.style_ref) {
+            *style = None;
+        }
+        self.enlargements.remove(&capsule.style_ref);
         self.dirties.remove(&frame_ref);
+        self.layout_roots.remove(&frame_ref);
+        self.layout_args.remove(&frame_ref);
+        self.layers.remove(&frame_ref);
+        self.content_providers.remove(&frame_ref);
+        self.provider_widths.remove(&frame_ref);
+        self.content_ratios.remove(&frame_ref);
+        self.baselines.remove(&frame_ref);
+        self.measured_within.remove(&frame_ref);
+        self.scroll_offsets.remove(&frame_ref);
+        self.overscrolls.remove(&frame_ref);
+        self.scrolled.retain(|scrolled| *scrolled != frame_ref);
+        self.scroll_extents.remove(&frame_ref);
+        self.gutters.remove(&frame_ref);
+        self.spatial.remove(frame_ref);
 
         // NOTE: Get the slot, `take()` the capsule, and increment the generation
         let slot = &mut self.capsules[frame_ref.id];
@@ -565,77 +1625,237 @@ impl Root {
 }
 
 impl Root {
+    /// Size a frame got from the measure pass, 0 where it has none
+    fn measured_size(&mut self, frame_ref: CapsuleRef, space: Space) -> (f32, f32) {
+        if self.strict {
+            for (size, axis) in [
+                (space.width, Axis::Horizontal),
+                (space.height, Axis::Vertical),
+            ] {
+                if size.is_none() {
+                    self.diagnostics.push(LayoutDiagnostic::MissingMeasurement {
+                        frame: frame_ref,
+                        axis,
+                    });
+                }
+            }
+        }
+        (
+            space.width.unwrap_or(0) as f32,
+            space.height.unwrap_or(0) as f32,
+        )
+    }
+
+    fn report_skip(&mut self, frame_ref: CapsuleRef, pass: LayoutPass) {
+        if !self.strict {
+            return;
+        }
+        let reason = match self.get_capsule(frame_ref) {
+            None => SkipReason::DeadHandle,
+            Some(capsule) if self.style_of(capsule).is_none() => SkipReason::MissingStyle,
+            Some(_) => SkipReason::MissingSpace,
+        };
+        self.diagnostics.push(LayoutDiagnostic::SkippedCapsule {
+            frame: frame_ref,
+            pass,
+            reason,
+        });
+    }
+
     fn compute_pass_2_layout(
         &mut self,
         frame_ref: CapsuleRef,
-        given_x: i32,
-        given_y: i32,
-        given_width: u32,
-        given_height: u32,
+        given_x: f32,
+        given_y: f32,
+        given_width: f32,
+        given_height: f32,
+        depth: usize,
     ) {
         let (capsule, style, space_ref) = match self.get_capsule(frame_ref).and_then(|cap| {
             // Chain the getters. Get capsule, then its style.
-            let style = self.styles[cap.style_ref].as_ref()?;
-            Some((cap.clone(), style.clone(), cap.space_ref)) // Clone them
+            let style = self.style_of(cap)?;
+            Some((cap.clone(), style, cap.space_ref)) // Clone them
         }) {
             Some((cap, style, sref)) => (cap, style, sref),
-            None => return, // Dead handle or missing style, skip.
+            None => {
+                // Dead handle or missing style, skip.
+                self.report_skip(frame_ref, LayoutPass::Arrange);
+                return;
+            }
         };
 
-        let space = match self.spaces[space_ref].as_mut() {
-            Some(s) => s,
-            None => return, // This space was removed, skip.
+        let space = match self.spaces.get(space_ref).and_then(|s| s.as_ref()) {
+            Some(s) => *s,
+            None => {
+                // This space was removed, skip.
+                self.report_skip(frame_ref, LayoutPass::Arrange);
+                return;
+            }
         };
 
+        // Kept to lay the frame out again on its own
+        if capsule.parent_ref.is_some() && style.sized_by_parent() {
+            self.layout_args.insert(
+                frame_ref,
+                LayoutArgs {
+                    x: given_x,
+                    y: given_y,
+                    width: given_width,
+                    height: given_height,
+                    depth,
+                },
+            );
+        } else {
+            self.layout_args.remove(&frame_ref);
+        }
+
         // 1 - Determine My Final Size
         // Get my "desired" size from Pass 1
-        let desired_w = space.width.unwrap();
-        let desired_h = space.height.unwrap();
+        let (desired_w, desired_h) = self.measured_size(frame_ref, space);
 
         // `Pixel`, `Percent`, `Fill` are resolved against `given_width`.
         // `Fit` returns `None`, so we `unwrap_or` our desired size from Pass 1.
-        let final_w = style.width.resolve_size(given_width).unwrap_or(desired_w);
-        let final_h = style.height.resolve_size(given_height).unwrap_or(desired_h);
+        let mut final_w = style.width.resolve_size(given_width).unwrap_or(desired_w);
+        let mut final_h = style.height.resolve_size(given_height).unwrap_or(desired_h);
+
+        // `IntrinsicRatio` follows the other side, now that it is final
+        if let Some(&(ratio_w, ratio_h)) = self.content_ratios.get(&frame_ref) {
+            let inset_w = (style.padding.left + style.padding.right + style.border.size * 2) as f32;
+            let inset_h = (style.padding.top + style.padding.bottom + style.border.size * 2) as f32;
+            if style.height.is_intrinsic_ratio() && !style.width.is_intrinsic_ratio() {
+                final_h = inset_h + (final_w - inset_w).max(0.0) * ratio_h as f32 / ratio_w as f32;
+            } else if style.width.is_intrinsic_ratio() && !style.height.is_intrinsic_ratio() {
+                final_w = inset_w + (final_h - inset_h).max(0.0) * ratio_w as f32 / ratio_h as f32;
+            }
+        }
+
+        // An `Auto` size anchored to both edges stretches between them
+        if let Position::Absolute(insets) = style.position {
+            let stretched = |start: Offset, end: Offset, given: f32| {
+                (given - start.resolve(given) - end.resolve(given)).max(0.0)
+            };
+            if let (SizeSpec::Auto, Some(left), Some(right)) =
+                (style.width, insets.left, insets.right)
+            {
+                final_w = stretched(left, right, given_width);
+            }
+            if let (SizeSpec::Auto, Some(top), Some(bottom)) =
+                (style.height, insets.top, insets.bottom)
+            {
+                final_h = stretched(top, bottom, given_height);
+            }
+        }
 
         // 2 - Determine My Final Position
         // This is determined by *my* `Position` style.
         // The `given_x/y` are from my parent's layout flow.
         let (final_x, final_y) = match style.position {
             Position::Auto => (given_x, given_y),
+            // Where the flow put me, moved by a part of my own size
+            Position::Relative { dx, dy } => {
+                (given_x + dx.resolve(final_w), given_y + dy.resolve(final_h))
+            }
+            // Like `Fixed`, from whichever edge I am anchored to
+            Position::Absolute(insets) => {
+                let along =
+                    |start: Option<Offset>, end: Option<Offset>, given: f32, size: f32| match (
+                        start, end,
+                    ) {
+                        (Some(start), _) => start.resolve(given),
+                        (None, Some(end)) => given - end.resolve(given) - size,
+                        (None, None) => 0.0,
+                    };
+                (
+                    given_x + along(insets.left, insets.right, given_width, final_w),
+                    given_y + along(insets.top, insets.bottom, given_height, final_h),
+                )
+            }
             Position::Fixed { x, y } => {
                 // `Position::Fixed` is relative to the *parent's content box*,
                 // which is what `given_x/y` represent (for the *start* of the flow).
-                (given_x + x as i32, given_y + y as i32)
+                // Percentages use its size, `given_width/height`.
+                (
+                    given_x + x.resolve(given_width),
+                    given_y + y.resolve(given_height),
+                )
             }
         };
 
+        let mut layout_box = LayoutBox {
+            x: final_x,
+            y: final_y,
+            width: final_w,
+            height: final_h,
+        };
+
+        // The deterministic mode works on whole pixels only,
+        // so the box is snapped before children see it.
+        if self.layout_mode == LayoutMode::Deterministic {
+            layout_box = self.snapped(layout_box);
+        }
+        let LayoutBox {
+            x: final_x,
+            y: final_y,
+            width: final_w,
+            height: final_h,
+        } = layout_box;
+
         // if not dirty AND position/size hasn't changed, stop recursion.
         if !self.dirties.contains(&frame_ref)
-            && space.x == final_x
-            && space.y == final_y
-            && space.width == Some(final_w)
-            && space.height == Some(final_h)
+            && self.layout_boxes.get(space_ref).copied().flatten() == Some(layout_box)
         {
+            #[cfg(feature = "profiling")]
+            {
+                self.profile.arrange_skips += 1;
+            }
             return;
         }
 
         // 3 - Store My Final Space
-        space.x = final_x;
-        space.y = final_y;
-        space.width = Some(final_w);
-        space.height = Some(final_h);
+        // Decorations move with the frame, outside of its box
+        if self.decorations.contains_key(&frame_ref) {
+            let previous = self.layout_boxes.get(space_ref).copied().flatten();
+            if let Some(previous) = previous {
+                self.damage_decorations(frame_ref, self.snapped_rect(previous));
+            }
+            self.damage_decorations(frame_ref, self.snapped_rect(layout_box));
+        }
+        self.store_layout_box(frame_ref, space_ref, layout_box);
+        self.last_stats.nodes_arranged += 1;
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.arranged += 1;
+        }
+        self.last_stats.deepest_depth = self.last_stats.deepest_depth.max(depth);
 
         // 4 - Calculate My "Content Box" for My Children
-        let content_x = final_x + style.padding.left as i32 + style.border.size as i32;
-        let content_y = final_y + style.padding.top as i32 + style.border.size as i32;
-        let content_w = final_w
-            .saturating_sub(style.padding.left + style.padding.right + style.border.size * 2);
-        let content_h = final_h
-            .saturating_sub(style.padding.top + style.padding.bottom + style.border.size * 2);
+        let content_x = final_x + (style.padding.left + style.border.size) as f32;
+        let content_y = final_y + (style.padding.top + style.border.size) as f32;
+        // Shown scrollbars take their gutter off the content box
+        let (gutter_w, gutter_h) = self.gutter_size(frame_ref);
+        let content_w = (final_w
+            - (style.padding.left + style.padding.right + style.border.size * 2 + gutter_w) as f32)
+            .max(0.0);
+        let content_h = (final_h
+            - (style.padding.top + style.padding.bottom + style.border.size * 2 + gutter_h) as f32)
+            .max(0.0);
+
+        // Children of a scroll container move with its scroll position
+        let scrolls = style.overflow.scrolls();
+        let (content_x, content_y) = if scrolls {
+            let (scroll_x, scroll_y) = self.scroll_offset(frame_ref);
+            let (over_x, over_y) = self.overscroll(frame_ref);
+            (content_x - scroll_x - over_x, content_y - scroll_y - over_y)
+        } else {
+            (content_x, content_y)
+        };
 
         // 5 - Pre-pass: Analyze In-Flow Children for Flex 'Fill'
         // We need to know how many `Fill` children we have to divide space.
         let mut in_flow_children = Vec::new();
+        // (main axis base size, counts in base size, flex_grow, flex_shrink)
+        // of each in-flow child
+        let mut in_flow_factors = Vec::new();
         let mut total_base_w = 0.0;
         let mut total_base_h = 0.0;
 
@@ -647,34 +1867,51 @@ impl Root {
 
         for &child_ref in &capsule.children {
             let (child_style, child_space) = match self.get_capsule(child_ref).and_then(|cap| {
-                let style = self.styles[cap.style_ref].as_ref()?;
-                let space = self.spaces[cap.space_ref].as_ref()?;
-                Some((style, space))
+                let style = self.style_of(cap)?;
+                let space = self.space_of(cap)?;
+                Some((style, *space))
             }) {
                 Some((s, sp)) => (s, sp),
-                None => continue, // Dead handle or missing data, skip
+                None => continue, // Dead handle or missing data, reported when arranged
             };
 
-            if child_style.position == Position::Auto {
+            if child_style.position.in_flow() {
                 in_flow_children.push(child_ref);
-
-                let base_w = child_space.width.unwrap() as f32;
-                let base_h = child_space.height.unwrap() as f32;
-
+                // Missing sizes are reported when the child is arranged
                 let (child_desired_w, child_desired_h) = (
-                    child_space.width.unwrap() as f32,  // Use f32
-                    child_space.height.unwrap() as f32, // Use f32
+                    child_space.width.unwrap_or(0) as f32,
+                    child_space.height.unwrap_or(0) as f32,
                 );
+                let (main_base, main_spec) = match style.flow {
+                    Direction::Row => (child_desired_w as u32, child_style.width),
+                    Direction::Column => (child_desired_h as u32, child_style.height),
+                };
+                in_flow_factors.push((
+                    main_base,
+                    !main_spec.is_fill()
+                        && !main_spec.is_percent()
+                        && !main_spec.is_percent_of_remaining(),
+                    child_style.flex_grow,
+                    child_style.flex_shrink,
+                ));
+
+                let (base_w, base_h) = (child_desired_w, child_desired_h);
 
                 if style.flow == Direction::Row {
-                    // Add to total base size (respecting Fill/Percent)
-                    if !child_style.width.is_fill() && !child_style.width.is_percent() {
+                    // Add to total base size (respecting Fill/Percent/PercentOfRemaining)
+                    if !child_style.width.is_fill()
+                        && !child_style.width.is_percent()
+                        && !child_style.width.is_percent_of_remaining()
+                    {
                         total_base_w += child_desired_w;
                     }
                     total_grow_factor_w += child_style.flex_grow;
                     total_weighted_shrink_w += child_style.flex_shrink * base_w;
                 } else {
-                    if !child_style.height.is_fill() && !child_style.height.is_percent() {
+                    if !child_style.height.is_fill()
+                        && !child_style.height.is_percent()
+                        && !child_style.height.is_percent_of_remaining()
+                    {
                         total_base_h += child_desired_h;
                     }
                     total_grow_factor_h += child_style.flex_grow;
@@ -696,8 +1933,8 @@ impl Root {
             0
         } as f32;
 
-        let remaining_w = (content_w as f32) - total_base_w - total_gap_w;
-        let remaining_h = (content_h as f32) - total_base_h - total_gap_h;
+        let remaining_w = content_w - total_base_w - total_gap_w;
+        let remaining_h = content_h - total_base_h - total_gap_h;
 
         // These will store our "per-point" ratios
         let mut grow_per_factor_w = 0.0;
@@ -710,8 +1947,9 @@ impl Root {
             if total_grow_factor_w > 0.0 {
                 grow_per_factor_w = remaining_w / total_grow_factor_w;
             }
-        } else if remaining_w < 0.0 {
+        } else if remaining_w < 0.0 && !scrolls {
             // SHRINK LOGIC
+            // (the content of a scroll container keeps its size and overflows)
             let overflow_amount = -remaining_w; // e.g., 100px overflow
             if total_weighted_shrink_w > 0.0 {
                 // This is our "shrink multiplier"
@@ -723,13 +1961,60 @@ impl Root {
             if total_grow_factor_h > 0.0 {
                 grow_per_factor_h = remaining_h / total_grow_factor_h;
             }
-        } else if remaining_h < 0.0 {
+        } else if remaining_h < 0.0 && !scrolls {
             let overflow_amount = -remaining_h;
             if total_weighted_shrink_h > 0.0 {
                 shrink_ratio_h = overflow_amount / total_weighted_shrink_h;
             }
         }
 
+        // In deterministic mode, the main axis size of every in-flow child is
+        // resolved upfront with integer arithmetic.
+        let deterministic_main_sizes = if self.layout_mode == LayoutMode::Deterministic
+            && style.layout == LayoutStrategy::Flex
+        {
+            let (content_main, total_gap) = match style.flow {
+                Direction::Row => (content_w, total_gap_w as u32),
+                Direction::Column => (content_h, total_gap_h as u32),
+            };
+            let total_base: u32 = in_flow_factors
+                .iter()
+                .filter(|(_, counts, _, _)| *counts)
+                .map(|(base, _, _, _)| *base)
+                .sum();
+            let remaining = content_main as i64 - total_base as i64 - total_gap as i64;
+
+            let sizes: Vec<u32> = if remaining > 0 {
+                let weights: Vec<u64> = in_flow_factors
+                    .iter()
+                    .map(|(_, _, grow, _)| distribute::to_fixed(*grow))
+                    .collect();
+                distribute::largest_remainder(remaining as u32, &weights)
+                    .into_iter()
+                    .zip(&in_flow_factors)
+                    .map(|(share, (base, _, _, _))| base + share)
+                    .collect()
+            } else if remaining < 0 {
+                let weights: Vec<u64> = in_flow_factors
+                    .iter()
+                    .map(|(base, _, _, shrink)| distribute::to_fixed(*shrink) * *base as u64)
+                    .collect();
+                distribute::largest_remainder((-remaining) as u32, &weights)
+                    .into_iter()
+                    .zip(&in_flow_factors)
+                    .map(|(share, (base, _, _, _))| base.saturating_sub(share))
+                    .collect()
+            } else {
+                in_flow_factors
+                    .iter()
+                    .map(|(base, _, _, _)| *base)
+                    .collect()
+            };
+            Some(sizes)
+        } else {
+            None
+        };
+
         let (mut main_axis_offset, mut extra_gap) = (0.0, 0.0);
         let child_count = in_flow_children.len() as f32;
 
@@ -774,60 +2059,150 @@ impl Root {
             }
         }
 
+        // Extra main axis spacing inserted after each in-flow child
+        let mut extra_gaps = vec![extra_gap; in_flow_children.len()];
+
+        if self.layout_mode == LayoutMode::Deterministic {
+            let free = match style.flow {
+                Direction::Row if total_grow_factor_w == 0.0 => remaining_w,
+                Direction::Column if total_grow_factor_h == 0.0 => remaining_h,
+                _ => 0.0,
+            };
+            // `remaining_*` only holds whole pixels here
+            let free = free.max(0.0) as u32;
+            let n = in_flow_children.len();
+            extra_gaps = vec![0.0; n];
+            main_axis_offset = 0.0;
+
+            if free > 0 && n > 0 {
+                match style.justify_content {
+                    JustifyContent::Start => {}
+                    JustifyContent::Center => main_axis_offset = (free / 2) as f32,
+                    JustifyContent::End => main_axis_offset = free as f32,
+                    JustifyContent::SpaceBetween => {
+                        let slots = distribute::evenly(free, n - 1);
+                        for (gap, slot) in extra_gaps.iter_mut().zip(slots) {
+                            *gap = slot as f32;
+                        }
+                    }
+                    JustifyContent::SpaceAround => {
+                        // Each child owns a half gap on both sides
+                        let halves = distribute::evenly(free, n * 2);
+                        main_axis_offset = halves[0] as f32;
+                        for (gap, pair) in extra_gaps.iter_mut().zip(halves[1..].chunks(2)) {
+                            if let [after, before] = pair {
+                                *gap = (after + before) as f32;
+                            }
+                        }
+                    }
+                    JustifyContent::SpaceEvenly => {
+                        let slots = distribute::evenly(free, n + 1);
+                        main_axis_offset = slots[0] as f32;
+                        for (gap, slot) in extra_gaps.iter_mut().zip(&slots[1..n]) {
+                            *gap = *slot as f32;
+                        }
+                    }
+                }
+            }
+        }
+
         let mut current_x = content_x
             + if style.flow == Direction::Row {
-                main_axis_offset as i32
+                main_axis_offset
             } else {
-                0
+                0.0
             };
         let mut current_y = content_y
             + if style.flow == Direction::Column {
-                main_axis_offset as i32
+                main_axis_offset
             } else {
-                0
+                0.0
             };
+        let mut next_flow_index = 0;
 
         // 7 - Recurse and Arrange All Children
         let children_to_layout = capsule.children.clone();
 
+        // Baseline the children of a row line up on, from the top of the
+        // content box: the lowest of them
+        let row_baseline = if style.layout == LayoutStrategy::Flex
+            && style.flow == Direction::Row
+            && style.align_items == AlignItems::Baseline
+        {
+            children_to_layout
+                .iter()
+                .filter_map(|child_ref| {
+                    let cap = self.get_capsule(*child_ref)?;
+                    let child_style = self.style_of(cap)?;
+                    if !child_style.position.in_flow() {
+                        return None;
+                    }
+                    // Missing measurements are reported when the child is placed
+                    let desired_h = self.space_of(cap)?.height.unwrap_or(0) as f32;
+                    let height = match child_style.height {
+                        SizeSpec::Percent(_) | SizeSpec::PercentOfRemaining(_) => (content_h
+                            - (child_style.margin.top + child_style.margin.bottom) as f32)
+                            .max(0.0),
+                        _ => desired_h,
+                    };
+                    Some(self.row_baseline(*child_ref, &child_style, height))
+                })
+                .fold(0.0, f32::max)
+        } else {
+            0.0
+        };
+
         for child_ref in &children_to_layout {
             let (child_capsule, child_style, child_space) =
                 match self.get_capsule(*child_ref).and_then(|cap| {
-                    let style = self.styles[cap.style_ref].as_ref()?;
-                    let space = self.spaces[cap.space_ref].as_ref()?;
-                    Some((cap.clone(), style.clone(), space)) // Clone what we need
+                    let style = self.style_of(cap)?;
+                    let space = self.space_of(cap)?;
+                    Some((cap.clone(), style, space)) // Clone what we need
                 }) {
-                    Some((cap, style, space)) => (cap, style, space),
-                    None => continue, // Dead handle
+                    Some((cap, style, space)) => (cap, style, *space),
+                    None => {
+                        // Dead handle
+                        self.report_skip(*child_ref, LayoutPass::Arrange);
+                        continue;
+                    }
                 };
 
-            let (child_desired_w, child_desired_h) =
-                (child_space.width.unwrap(), child_space.height.unwrap());
+            let (child_desired_w, child_desired_h) = self.measured_size(*child_ref, child_space);
 
             match child_style.position {
-                Position::Fixed { .. } => {
+                Position::Fixed { .. } | Position::Absolute(_) => {
                     // This child is "out-of-flow". It's positioned relative
                     // to our content box, but doesn't affect `current_x/y`.
                     // Its `given_width/height` is our content box.
                     self.compute_pass_2_layout(
-                        *child_ref, content_x, // Base for fixed positioning
+                        *child_ref,
+                        content_x, // Base for fixed positioning
                         content_y, // Base for fixed positioning
-                        content_w, content_h,
+                        content_w,
+                        content_h,
+                        depth + 1,
                     );
                 }
-                Position::Auto => {
+                Position::Auto | Position::Relative { .. } => {
                     // This child is "in-flow".
+                    let flow_index = next_flow_index;
+                    next_flow_index += 1;
+
                     let (child_given_x, child_given_y, child_given_w, child_given_h);
-                    let base_w = child_desired_w as f32;
-                    let base_h = child_desired_h as f32;
+                    let base_w = child_desired_w;
+                    let base_h = child_desired_h;
 
-                    let m_left = child_style.margin.left as i32;
-                    let m_top = child_style.margin.top as i32;
+                    let m_left = child_style.margin.left as f32;
+                    let m_right = child_style.margin.right as f32;
+                    let m_top = child_style.margin.top as f32;
+                    let m_bottom = child_style.margin.bottom as f32;
 
                     match style.layout {
                         LayoutStrategy::Flex => match style.flow {
                             Direction::Row => {
-                                let final_child_w = if remaining_w > 0.0 {
+                                let final_child_w = if let Some(sizes) = &deterministic_main_sizes {
+                                    sizes[flow_index] as f32
+                                } else if remaining_w > 0.0 {
                                     base_w + (child_style.flex_grow * grow_per_factor_w)
                                 } else if remaining_w < 0.0 {
                                     let weighted_shrink = child_style.flex_shrink * base_w;
@@ -838,29 +2213,34 @@ impl Root {
 
                                 // Determine Height
                                 // Needed for AlignItems
+                                // Nothing is placed along the cross axis, so `PercentOfRemaining`
+                                // behaves like `Percent` here.
                                 let final_child_h = match child_style.height {
-                                    SizeSpec::Percent(_) => content_h.saturating_sub(
-                                        (m_top + child_style.margin.bottom as i32) as u32,
-                                    ),
+                                    SizeSpec::Percent(_) | SizeSpec::PercentOfRemaining(_) => {
+                                        (content_h - m_top - m_bottom).max(0.0)
+                                    }
                                     // If fit/auto, use the desired height from Pass 1
                                     _ => child_desired_h,
                                 };
 
                                 let align_offset = match style.align_items {
-                                    AlignItems::Start => 0,
+                                    AlignItems::Start => 0.0,
                                     AlignItems::End => {
                                         // Parent Height - Child Height - Margins
-                                        (content_h as i32)
-                                            - (final_child_h as i32)
-                                            - m_top
-                                            - (child_style.margin.bottom as i32)
+                                        content_h - final_child_h - m_top - m_bottom
                                     }
                                     AlignItems::Center => {
                                         // (Parent Height - Child Total Height) / 2
-                                        let child_total_h = (final_child_h as i32)
-                                            + m_top
-                                            + (child_style.margin.bottom as i32);
-                                        ((content_h as i32) - child_total_h) / 2
+                                        let child_total_h = final_child_h + m_top + m_bottom;
+                                        (content_h - child_total_h) / 2.0
+                                    }
+                                    AlignItems::Baseline => {
+                                        row_baseline
+                                            - self.row_baseline(
+                                                *child_ref,
+                                                &child_style,
+                                                final_child_h,
+                                            )
                                     }
                                 };
 
@@ -870,12 +2250,15 @@ impl Root {
 
                                 child_given_w = match child_style.width {
                                     SizeSpec::Percent(_) => content_w,
-                                    _ => final_child_w as u32,
+                                    SizeSpec::PercentOfRemaining(_) => remaining_w.max(0.0),
+                                    _ => final_child_w,
                                 };
                                 child_given_h = final_child_h;
                             }
                             Direction::Column => {
-                                let final_child_h = if remaining_h > 0.0 {
+                                let final_child_h = if let Some(sizes) = &deterministic_main_sizes {
+                                    sizes[flow_index] as f32
+                                } else if remaining_h > 0.0 {
                                     base_h + (child_style.flex_grow * grow_per_factor_h)
                                 } else if remaining_h < 0.0 {
                                     let weighted_shrink = child_style.flex_shrink * base_h;
@@ -886,25 +2269,18 @@ impl Root {
 
                                 // Determine Width
                                 let final_child_w = match child_style.width {
-                                    SizeSpec::Percent(_) => content_w.saturating_sub(
-                                        (m_left + child_style.margin.right as i32) as u32,
-                                    ),
+                                    SizeSpec::Percent(_) | SizeSpec::PercentOfRemaining(_) => {
+                                        (content_w - m_left - m_right).max(0.0)
+                                    }
                                     _ => child_desired_w,
                                 };
 
                                 let align_offset = match style.align_items {
-                                    AlignItems::Start => 0,
-                                    AlignItems::End => {
-                                        (content_w as i32)
-                                            - (final_child_w as i32)
-                                            - m_left
-                                            - (child_style.margin.right as i32)
-                                    }
+                                    AlignItems::Start | AlignItems::Baseline => 0.0,
+                                    AlignItems::End => content_w - final_child_w - m_left - m_right,
                                     AlignItems::Center => {
-                                        let child_total_w = (final_child_w as i32)
-                                            + m_left
-                                            + (child_style.margin.right as i32);
-                                        ((content_w as i32) - child_total_w) / 2
+                                        let child_total_w = final_child_w + m_left + m_right;
+                                        (content_w - child_total_w) / 2.0
                                     }
                                 };
 
@@ -914,7 +2290,8 @@ impl Root {
                                 child_given_w = final_child_w;
                                 child_given_h = match child_style.height {
                                     SizeSpec::Percent(_) => content_h,
-                                    _ => final_child_h as u32,
+                                    SizeSpec::PercentOfRemaining(_) => remaining_h.max(0.0),
+                                    _ => final_child_h,
                                 };
                             }
                         },
@@ -937,47 +2314,53 @@ impl Root {
                         child_given_y,
                         child_given_w,
                         child_given_h,
+                        depth + 1,
                     );
 
-                    let child_space_mut = match self.spaces[child_capsule.space_ref].as_mut() {
-                        Some(s) => s,
-                        None => continue, // This child's space was removed
+                    let Some(mut child_box) = self
+                        .layout_boxes
+                        .get(child_capsule.space_ref)
+                        .copied()
+                        .flatten()
+                    else {
+                        continue; // This child's space was removed
                     };
 
                     if style.layout == LayoutStrategy::Flex {
+                        let mut stretched = false;
                         if style.flow == Direction::Row && child_style.height.is_auto() {
-                            child_space_mut.height = Some(content_h);
+                            child_box.height = content_h;
+                            stretched = true;
                         }
                         if style.flow == Direction::Column && child_style.width.is_auto() {
-                            child_space_mut.width = Some(content_w);
+                            child_box.width = content_w;
+                            stretched = true;
+                        }
+                        if stretched {
+                            self.store_layout_box(*child_ref, child_capsule.space_ref, child_box);
                         }
                     }
 
                     // Update cursor for next in-flow item
                     match style.layout {
                         LayoutStrategy::Flex => {
-                            // NOTE: We need the sizes from the SPACE, because the child
+                            // NOTE: We need the sizes from the layout box, because the child
                             // might have updated them in the recursive call (e.g. if it was Auto/Fit)
-                            let (child_final_w, child_final_h) = {
-                                let s = self.spaces[child_capsule.space_ref].as_ref().unwrap();
-                                (s.width.unwrap(), s.height.unwrap())
-                            };
-
                             match style.flow {
                                 Direction::Row => {
                                     // Add standard gap + JustifyContent extra gap
-                                    current_x += child_final_w as i32
-                                        + child_style.margin.left as i32
-                                        + child_style.margin.right as i32
-                                        + style.gap as i32
-                                        + extra_gap as i32;
+                                    current_x += child_box.width
+                                        + m_left
+                                        + m_right
+                                        + style.gap as f32
+                                        + extra_gaps[flow_index];
                                 }
                                 Direction::Column => {
-                                    current_y += child_final_h as i32
-                                        + child_style.margin.top as i32
-                                        + child_style.margin.bottom as i32
-                                        + style.gap as i32
-                                        + extra_gap as i32;
+                                    current_y += child_box.height
+                                        + m_top
+                                        + m_bottom
+                                        + style.gap as f32
+                                        + extra_gaps[flow_index];
                                 }
                             }
                         }
@@ -986,6 +2369,370 @@ impl Root {
                 }
             }
         }
+
+        if scrolls {
+            self.store_scroll_extent(frame_ref, &children_to_layout, content_x, content_y);
+        }
+    }
+}
+
+impl Root {
+    /// Distance from the top margin edge of an in-flow child of a row,
+    /// `height` tall, to the baseline it lines up on: its first baseline,
+    /// or its bottom border edge without one
+    fn row_baseline(&self, child_ref: CapsuleRef, child_style: &Style, height: f32) -> f32 {
+        let margin = child_style.margin.top as f32;
+        match self.baselines.get(&child_ref) {
+            Some(baseline) => {
+                margin + (child_style.border.size + child_style.padding.top) as f32 + baseline
+            }
+            None => margin + height,
+        }
+    }
+
+    /// Remembers how far the children of a scroll container extend from
+    /// the origin of its (scrolled) content box
+    fn store_scroll_extent(
+        &mut self,
+        frame_ref: CapsuleRef,
+        children: &[CapsuleRef],
+        origin_x: f32,
+        origin_y: f32,
+    ) {
+        let mut extent = (0.0f32, 0.0f32);
+        for &child_ref in children {
+            let Some((child_box, margin)) = self.get_capsule(child_ref).and_then(|cap| {
+                let child_box = self.layout_boxes.get(cap.space_ref).copied().flatten()?;
+                Some((child_box, self.style_of(cap)?.margin))
+            }) else {
+                continue;
+            };
+            extent.0 = extent
+                .0
+                .max(child_box.x + child_box.width + margin.right as f32 - origin_x);
+            extent.1 = extent
+                .1
+                .max(child_box.y + child_box.height + margin.bottom as f32 - origin_y);
+        }
+        self.scroll_extents.insert(frame_ref, extent);
+    }
+
+    /// Scroll position of a scroll container, `(0, 0)` for other frames
+    pub fn scroll_offset(&self, frame_ref: CapsuleRef) -> (f32, f32) {
+        self.scroll_offsets
+            .get(&frame_ref)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Size of the content of a scroll container, as of the last layout
+    pub fn scroll_extent(&self, frame_ref: CapsuleRef) -> Option<(f32, f32)> {
+        self.scroll_extents.get(&frame_ref).copied()
+    }
+
+    /// Largest scroll position of a scroll container: the content
+    /// size past its content box
+    pub fn max_scroll_offset(&self, frame_ref: CapsuleRef) -> (f32, f32) {
+        match (
+            self.scroll_extent(frame_ref),
+            self.get_content_rect(frame_ref),
+        ) {
+            (Some((w, h)), Some(content)) => (
+                (w - content.w as f32).max(0.0),
+                (h - content.h as f32).max(0.0),
+            ),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Scrolls an `Overflow::Scroll` (or `Auto`) frame, clamped to its content.
+    /// Returns whether the position changed.
+    pub fn set_scroll_offset(&mut self, frame_ref: CapsuleRef, x: f32, y: f32) -> bool {
+        if self
+            .get_style(frame_ref)
+            .is_none_or(|style| !style.overflow.scrolls())
+        {
+            return false;
+        }
+
+        let (max_x, max_y) = self.max_scroll_offset(frame_ref);
+        let offset = (x.clamp(0.0, max_x), y.clamp(0.0, max_y));
+        if self.scroll_offset(frame_ref) == offset {
+            return false;
+        }
+
+        self.scroll_offsets.insert(frame_ref, offset);
+        self.mark_scrolled(frame_ref);
+        self.set_content_dirty(frame_ref);
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(rect);
+        }
+        true
+    }
+
+    /// First baseline of the content of a frame, from the top of its
+    /// content box, if it has one
+    pub fn baseline(&self, frame_ref: CapsuleRef) -> Option<f32> {
+        self.baselines.get(&frame_ref).copied()
+    }
+
+    /// Gives a frame the first baseline its parent lines it up on when the
+    /// parent is a row aligning its children on their baselines
+    /// (`AlignItems::Baseline`), from the top of its content box. Frames
+    /// with a content provider get the one it reports instead.
+    pub fn set_baseline(&mut self, frame_ref: CapsuleRef, baseline: Option<f32>) {
+        if self.baseline(frame_ref) == baseline {
+            return;
+        }
+        match baseline {
+            Some(baseline) => self.baselines.insert(frame_ref, baseline),
+            None => self.baselines.remove(&frame_ref),
+        };
+        self.set_dirty(frame_ref);
+    }
+
+    /// Distance a scroll container is pulled past the ends of its content,
+    /// `(0, 0)` unless `set_overscroll` was called
+    pub fn overscroll(&self, frame_ref: CapsuleRef) -> (f32, f32) {
+        self.overscrolls
+            .get(&frame_ref)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Draws the content of a scroll container as if scrolled `x, y` further
+    /// than its scroll position, past the ends of its content: negative
+    /// values pull it down and right, positive ones up and left. Unlike the
+    /// scroll position it is never clamped, and `take_scrolled` does not
+    /// report it. Returns whether it changed.
+    pub fn set_overscroll(&mut self, frame_ref: CapsuleRef, x: f32, y: f32) -> bool {
+        if self
+            .get_style(frame_ref)
+            .is_none_or(|style| !style.overflow.scrolls())
+            || self.overscroll(frame_ref) == (x, y)
+        {
+            return false;
+        }
+
+        if (x, y) == (0.0, 0.0) {
+            self.overscrolls.remove(&frame_ref);
+        } else {
+            self.overscrolls.insert(frame_ref, (x, y));
+        }
+        self.set_content_dirty(frame_ref);
+        if let Some(rect) = self.get_rect(frame_ref) {
+            self.add_damage(rect);
+        }
+        true
+    }
+
+    fn mark_scrolled(&mut self, frame_ref: CapsuleRef) {
+        if !self.scrolled.contains(&frame_ref) {
+            self.scrolled.push(frame_ref);
+        }
+    }
+
+    /// Scroll containers whose position changed since the last call,
+    /// scrolled or brought back by a layout, in the order they first moved
+    pub fn take_scrolled(&mut self) -> Vec<CapsuleRef> {
+        std::mem::take(&mut self.scrolled)
+    }
+
+    /// Scrolls by a distance, see `set_scroll_offset`
+    pub fn scroll_by(&mut self, frame_ref: CapsuleRef, dx: f32, dy: f32) -> bool {
+        let (x, y) = self.scroll_offset(frame_ref);
+        self.set_scroll_offset(frame_ref, x + dx, y + dy)
+    }
+
+    /// Scrollbars an `Overflow::Auto` frame shows as of the last layout,
+    /// in its gutters along the right and bottom edges of its padding box
+    pub fn scrollbars(&self, frame_ref: CapsuleRef) -> Scrollbars {
+        let (gutter_w, gutter_h) = self.gutter_size(frame_ref);
+        let (Some(rect), Some(view), Some(style)) = (
+            self.get_rect(frame_ref),
+            self.get_content_rect(frame_ref),
+            self.get_style(frame_ref),
+        ) else {
+            return Scrollbars::default();
+        };
+
+        // Padding box, the gutters sit along its right and bottom edges
+        let border = style.border.size;
+        let (left, top) = (
+            rect.x.saturating_add_unsigned(border),
+            rect.y.saturating_add_unsigned(border),
+        );
+        let (width, height) = (
+            rect.w.saturating_sub(border * 2),
+            rect.h.saturating_sub(border * 2),
+        );
+        let (extent_w, extent_h) = self.scroll_extent(frame_ref).unwrap_or_default();
+        let (offset_x, offset_y) = self.scroll_offset(frame_ref);
+        let min_len = self.scrollbar_width;
+
+        let vertical = (gutter_w > 0).then(|| {
+            let track = Rect::new(
+                left.saturating_add_unsigned(width.saturating_sub(gutter_w)),
+                top,
+                gutter_w.min(width),
+                height.saturating_sub(gutter_h),
+            );
+            let (start, len) =
+                scrollbar::thumb_span(track.h, view.h as f32, extent_h, offset_y, min_len);
+            Scrollbar {
+                track,
+                thumb: Rect::new(
+                    track.x,
+                    track.y.saturating_add_unsigned(start),
+                    track.w,
+                    len,
+                ),
+            }
+        });
+        let horizontal = (gutter_h > 0).then(|| {
+            let track = Rect::new(
+                left,
+                top.saturating_add_unsigned(height.saturating_sub(gutter_h)),
+                width.saturating_sub(gutter_w),
+                gutter_h.min(height),
+            );
+            let (start, len) =
+                scrollbar::thumb_span(track.w, view.w as f32, extent_w, offset_x, min_len);
+            Scrollbar {
+                track,
+                thumb: Rect::new(
+                    track.x.saturating_add_unsigned(start),
+                    track.y,
+                    len,
+                    track.h,
+                ),
+            }
+        });
+
+        Scrollbars {
+            vertical,
+            horizontal,
+        }
+    }
+
+    /// Converts a window position to a position relative to the content box
+    /// of a frame. Inside scroll containers, the position is in the scrolled
+    /// content, the space their children are laid out in.
+    /// Returns `None` for frames that are dead or not laid out yet.
+    pub fn to_local(&self, frame_ref: CapsuleRef, point: (f32, f32)) -> Option<(f32, f32)> {
+        let (origin_x, origin_y) = self.content_origin(frame_ref)?;
+        Some((point.0 - origin_x, point.1 - origin_y))
+    }
+
+    /// Converts a position relative to the content box of a frame (see
+    /// `to_local`) back to a window position.
+    pub fn to_global(&self, frame_ref: CapsuleRef, point: (f32, f32)) -> Option<(f32, f32)> {
+        let (origin_x, origin_y) = self.content_origin(frame_ref)?;
+        Some((point.0 + origin_x, point.1 + origin_y))
+    }
+
+    /// Window position of the local origin of a frame.
+    /// Frame rects already carry the offsets of their ancestors and the
+    /// scroll positions of the containers above them.
+    fn content_origin(&self, frame_ref: CapsuleRef) -> Option<(f32, f32)> {
+        let content = self.get_content_rect(frame_ref)?;
+        let (scroll_x, scroll_y) = match self.get_style(frame_ref)?.overflow.scrolls() {
+            true => {
+                let (scroll_x, scroll_y) = self.scroll_offset(frame_ref);
+                let (over_x, over_y) = self.overscroll(frame_ref);
+                (scroll_x + over_x, scroll_y + over_y)
+            }
+            false => (0.0, 0.0),
+        };
+        Some((content.x as f32 - scroll_x, content.y as f32 - scroll_y))
+    }
+
+    /// Area a frame is visible in: the padding boxes of its ancestors
+    /// that cut their children. `None` if no ancestor does.
+    pub fn clip_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        let mut clip: Option<Rect> = None;
+        let mut current = self.get_capsule(frame_ref)?.parent_ref;
+
+        while let Some(parent_ref) = current {
+            let capsule = self.get_capsule(parent_ref)?;
+            if let (Some(style), Some(rect)) = (self.style_of(capsule), self.get_rect(parent_ref))
+                && style.overflow != Overflow::Visible
+            {
+                // Scrollbars are not covered by the children
+                let border = style.border.size;
+                let (gutter_w, gutter_h) = self.gutter_size(parent_ref);
+                let padding_box = Rect {
+                    x: rect.x.saturating_add_unsigned(border),
+                    y: rect.y.saturating_add_unsigned(border),
+                    w: rect.w.saturating_sub(border * 2 + gutter_w),
+                    h: rect.h.saturating_sub(border * 2 + gutter_h),
+                };
+                clip = Some(match clip {
+                    Some(clip) => clip.intersection(&padding_box).unwrap_or_default(),
+                    None => padding_box,
+                });
+            }
+            current = capsule.parent_ref;
+        }
+
+        clip
+    }
+}
+
+impl Root {
+    /// Snaps every edge of a box to whole pixels.
+    fn snapped(&self, layout_box: LayoutBox) -> LayoutBox {
+        let snapping = self.pixel_snapping;
+        let left = snapping.left.apply(layout_box.x);
+        let top = snapping.top.apply(layout_box.y);
+        let right = snapping.right.apply(layout_box.x + layout_box.width);
+        let bottom = snapping.bottom.apply(layout_box.y + layout_box.height);
+
+        LayoutBox {
+            x: left as f32,
+            y: top as f32,
+            width: right.saturating_sub(left).max(0) as f32,
+            height: bottom.saturating_sub(top).max(0) as f32,
+        }
+    }
+
+    fn snapped_rect(&self, layout_box: LayoutBox) -> Rect {
+        let snapped = self.snapped(layout_box);
+        Rect::new(
+            snapped.x as i32,
+            snapped.y as i32,
+            snapped.width as u32,
+            snapped.height as u32,
+        )
+    }
+
+    /// Stores the subpixel box of a node along with its pixel-snapped `Space`.
+    fn store_layout_box(&mut self, frame_ref: CapsuleRef, space_ref: usize, layout_box: LayoutBox) {
+        let new_rect = self.snapped_rect(layout_box);
+        // `spaces` may hold Pass 1 measurements at this point,
+        // the previous layout box tells where the node really was.
+        let old_rect = self
+            .layout_boxes
+            .get(space_ref)
+            .copied()
+            .flatten()
+            .map(|previous| self.snapped_rect(previous));
+
+        if let Some(space) = self.spaces.get_mut(space_ref).and_then(|s| s.as_mut()) {
+            *space = new_rect.into();
+            self.spatial.insert(frame_ref, new_rect);
+
+            if old_rect != Some(new_rect) {
+                if let Some(old_rect) = old_rect {
+                    self.add_damage(old_rect);
+                }
+                self.add_damage(new_rect);
+            }
+        }
+
+        if let Some(slot) = self.layout_boxes.get_mut(space_ref) {
+            *slot = Some(layout_box);
+        }
     }
 }
 
@@ -993,73 +2740,213 @@ impl Root {
     pub fn get_style(&self, frame_ref: CapsuleRef) -> Option<Style> {
         self.get_capsule(frame_ref).and_then(|cap| {
             // Chain the getters. Get capsule, then its style.
-            let style = self.styles[cap.style_ref].as_ref()?;
-            Some(style.clone())
+            // Tokens are resolved
+            self.style_of(cap)
         })
     }
 
     pub fn get_space(&self, frame_ref: CapsuleRef) -> Option<Space> {
         self.get_capsule(frame_ref).and_then(|cap| {
             // Chain the getters. Get capsule, then its style.
-            let space = self.spaces[cap.space_ref].as_ref()?;
+            let space = self.space_of(cap)?;
             Some(space.clone())
         })
     }
+
+    /// Resolved rectangle of a frame. Only meaningful after `compute`.
+    pub fn get_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        self.get_space(frame_ref).map(|space| space.rect())
+    }
+
+    /// Resolved rectangle of a frame without its border, padding and
+    /// scrollbars. This is the box its children are laid out in.
+    pub fn get_content_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        let rect = self.get_rect(frame_ref)?;
+        let style = self.get_style(frame_ref)?;
+        let inset = |start: u32, end: u32| (start + style.border.size, end + style.border.size);
+        let (gutter_w, gutter_h) = self.gutter_size(frame_ref);
+        let (left, right) = inset(style.padding.left, style.padding.right + gutter_w);
+        let (top, bottom) = inset(style.padding.top, style.padding.bottom + gutter_h);
+
+        Some(Rect {
+            x: rect.x.saturating_add_unsigned(left),
+            y: rect.y.saturating_add_unsigned(top),
+            w: rect.w.saturating_sub(left + right),
+            h: rect.h.saturating_sub(top + bottom),
+        })
+    }
+}
+
+impl Root {
+    /// Mark an area as needing a repaint
+    pub fn add_damage(&mut self, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+        self.damage = Some(match self.damage {
+            Some(damage) => damage.union(&rect),
+            None => rect,
+        });
+    }
+
+    /// Area that changed (layout, style or removal) since the last call,
+    /// `None` if nothing needs to be repainted.
+    pub fn take_damage(&mut self) -> Option<Rect> {
+        self.damage.take()
+    }
 }
 
 impl Root {
     /// PASS 1 (Bottom-Up): Measure desired content size.
+    /// `available_w` is the width the parent can offer (top-down), so content
+    /// providers can wrap within it.
     /// Returns (desired_width, desired_height)
-    fn compute_pass_1_measure(&mut self, frame_ref: CapsuleRef) -> (u32, u32) {
+    fn compute_pass_1_measure(
+        &mut self,
+        frame_ref: CapsuleRef,
+        available_w: Option<u32>,
+        context: &mut dyn Any,
+    ) -> (u32, u32) {
         // if not dirty, reuse cached size.
         // Since dirty propagates UP, if we are NOT dirty, our children
-        // are definitely NOT dirty, so our content size is stable.
-        if !self.dirties.contains(&frame_ref) {
-            if let Some(space) = self
-                .get_capsule(frame_ref)
-                .and_then(|c| self.spaces[c.space_ref].as_ref())
-            {
+        // are NOT dirty either, or stop at a layout boundary whose size does
+        // not follow its content, so our content size is stable as long as
+        // it is measured within the same width.
+        let same_width = self.measured_within.get(&frame_ref) == Some(&available_w);
+        self.measured_within.insert(frame_ref, available_w);
+        let dirty = self.dirties.contains(&frame_ref);
+        if !dirty && !same_width {
+            // Measured again, its `Space` no longer holds its layout box:
+            // Pass 2 must not skip it
+            self.dirties.insert(frame_ref);
+        }
+        if !dirty && same_width {
+            if let Some(space) = self.get_capsule(frame_ref).and_then(|c| self.space_of(c)) {
                 if let (Some(w), Some(h)) = (space.width, space.height) {
+                    #[cfg(feature = "profiling")]
+                    {
+                        self.profile.measure_cache_hits += 1;
+                    }
                     return (w, h);
                 }
             }
         }
+        #[cfg(feature = "profiling")]
+        if !dirty {
+            self.profile.measure_cache_misses += 1;
+        }
 
         let (capsule, style) = match self.get_capsule(frame_ref).and_then(|cap| {
             // Chain the getters. Get capsule, then its style.
-            let style = self.styles[cap.style_ref].as_ref()?;
-            Some((cap.clone(), style.clone())) // Clone them
+            let style = self.style_of(cap)?;
+            Some((cap.clone(), style)) // Clone them
         }) {
             Some((cap, style)) => (cap, style),
-            None => return (0, 0), // Dead handle or missing style, skip.
+            None => {
+                // Dead handle or missing style, skip.
+                self.report_skip(frame_ref, LayoutPass::Measure);
+                return (0, 0);
+            }
+        };
+        self.last_stats.nodes_measured += 1;
+        #[cfg(feature = "profiling")]
+        {
+            self.profile.measured += 1;
+        }
+
+        // Width left for the content once this node is sized within `available_w`
+        let (gutter_w, gutter_h) = self.gutter_size(frame_ref);
+        let inset_w = style.padding.left + style.padding.right + style.border.size * 2 + gutter_w;
+        let content_available_w = match self.width_overrides.get(&frame_ref) {
+            // A previous round found out the width actually given to the content
+            Some(&width) => Some(width),
+            None => match style.width {
+                SizeSpec::Pixel(w) => Some(w),
+                SizeSpec::Percent(p) => available_w.map(|w| (w as f32 * p) as u32),
+                _ => available_w,
+            }
+            .map(|w| w.saturating_sub(inset_w)),
         };
 
         // 1 - Recurse and Measure "In-Flow" Children
-        // Children with `Position::Fixed` are "out-of-flow" and do not
+        // Children with `Position::Fixed` or `Absolute` are "out-of-flow" and do not
         // contribute to their parent's `FitContent` size.
         let mut in_flow_child_sizes = Vec::new();
+        // Rows lining up baselines are as tall as the most their children
+        // extend above and below them
+        let aligns_baselines = style.layout == LayoutStrategy::Flex
+            && style.flow == Direction::Row
+            && style.align_items == AlignItems::Baseline;
+        let mut baseline_extent = (0.0f32, 0.0f32);
         for &child_ref in &capsule.children {
             let child_style = match self
                 .get_capsule(child_ref)
-                .and_then(|cap| self.styles[cap.style_ref].as_ref())
+                .and_then(|cap| self.style_of(cap))
             {
-                Some(style) => style.clone(),
-                None => continue, // Dead handle or missing style
+                Some(style) => style,
+                None => {
+                    // Dead handle or missing style
+                    self.report_skip(child_ref, LayoutPass::Measure);
+                    continue;
+                }
             };
 
             // Recurse for all children
-            let (child_w, child_h) = self.compute_pass_1_measure(child_ref);
+            let child_available_w = content_available_w
+                .map(|w| w.saturating_sub(child_style.margin.left + child_style.margin.right));
+            let (child_w, child_h) =
+                self.compute_pass_1_measure(child_ref, child_available_w, context);
 
-            // Only "Auto" children participate in the parent's `Fit` sizing
-            if child_style.position == Position::Auto {
+            // Only in-flow children participate in the parent's `Fit` sizing
+            if child_style.position.in_flow() {
                 in_flow_child_sizes.push((child_w, child_h, child_style.margin));
+                if aligns_baselines {
+                    let above = self.row_baseline(child_ref, &child_style, child_h as f32);
+                    let below = (child_h + child_style.margin.top + child_style.margin.bottom)
+                        as f32
+                        - above;
+                    baseline_extent = (baseline_extent.0.max(above), baseline_extent.1.max(below));
+                }
             }
         }
 
         // 2 - Calculate This Node's "Content" Size
         let (mut content_w, mut content_h);
-
-        if !capsule.children.is_empty() {
+        let mut has_intrinsic_ratio = false;
+
+        if let Some(mut provider) = self.content_providers.remove(&frame_ref) {
+            // The provider declares the content itself
+            let inset = |start: u32, end: u32| start + end + style.border.size * 2;
+            let constraints = Constraints {
+                // Fit content wraps within the parent
+                max_width: content_available_w,
+                fixed_width: !matches!(style.width, SizeSpec::Fit | SizeSpec::Auto),
+                max_height: match style.height {
+                    SizeSpec::Pixel(h) => {
+                        Some(h.saturating_sub(inset(style.padding.top, style.padding.bottom)))
+                    }
+                    _ => None,
+                },
+            };
+            let data = capsule
+                .data_ref
+                .and_then(|data_ref| self.allocator.get_any_mut(data_ref));
+
+            (content_w, content_h) = provider.measure(constraints, data, context);
+            match provider.baseline() {
+                Some(baseline) => self.baselines.insert(frame_ref, baseline),
+                None => self.baselines.remove(&frame_ref),
+            };
+            #[cfg(feature = "profiling")]
+            {
+                self.profile.content_measures += 1;
+            }
+            content_h = self.on_grid(content_h);
+            self.content_providers.insert(frame_ref, provider);
+            self.provider_widths
+                .insert(frame_ref, (constraints.max_width, content_w));
+            has_intrinsic_ratio = true;
+        } else if !capsule.children.is_empty() {
             // Calculate content size based on children (if we are `Fit`)
             match style.layout {
                 LayoutStrategy::Flex => {
@@ -1074,11 +2961,15 @@ impl Root {
                                 content_w += style.gap * (in_flow_child_sizes.len() as u32 - 1);
                             }
                             // Height is max of child heights
-                            content_h = in_flow_child_sizes
-                                .iter()
-                                .map(|(_, h, m)| *h + m.top + m.bottom)
-                                .max()
-                                .unwrap_or(0);
+                            content_h = if aligns_baselines {
+                                (baseline_extent.0 + baseline_extent.1).ceil() as u32
+                            } else {
+                                in_flow_child_sizes
+                                    .iter()
+                                    .map(|(_, h, m)| *h + m.top + m.bottom)
+                                    .max()
+                                    .unwrap_or(0)
+                            };
                         }
                         Direction::Column => {
                             // Width is max of child widths
@@ -1115,28 +3006,80 @@ impl Root {
         } else {
             content_w = style.intrinsic_width.unwrap_or(0);
             content_h = style.intrinsic_height.unwrap_or(0);
+            has_intrinsic_ratio =
+                style.intrinsic_width.is_some() && style.intrinsic_height.is_some();
+
+            if self.strict {
+                let fits = |spec: SizeSpec| matches!(spec, SizeSpec::Fit | SizeSpec::Auto);
+                if fits(style.width) && style.intrinsic_width.is_none() {
+                    self.diagnostics.push(LayoutDiagnostic::UnresolvedFit {
+                        frame: frame_ref,
+                        axis: Axis::Horizontal,
+                    });
+                }
+                if fits(style.height) && style.intrinsic_height.is_none() {
+                    self.diagnostics.push(LayoutDiagnostic::UnresolvedFit {
+                        frame: frame_ref,
+                        axis: Axis::Vertical,
+                    });
+                }
+            }
+        }
+
+        let ratio = (has_intrinsic_ratio && content_w > 0 && content_h > 0)
+            .then_some((content_w, content_h));
+        match ratio {
+            Some(ratio) => self.content_ratios.insert(frame_ref, ratio),
+            None => self.content_ratios.remove(&frame_ref),
+        };
+
+        // A ratio side follows the content size of the other side when it is
+        // already known, otherwise it waits for Pass 2
+        let inset_h = style.padding.top + style.padding.bottom + style.border.size * 2 + gutter_h;
+        if let Some((ratio_w, ratio_h)) = ratio {
+            if style.height.is_intrinsic_ratio() && !style.width.is_intrinsic_ratio() {
+                let known_w = match style.width {
+                    SizeSpec::Fit | SizeSpec::Auto => Some(content_w),
+                    SizeSpec::Pixel(_) | SizeSpec::Percent(_) => content_available_w,
+                    _ => None,
+                };
+                content_h = known_w.map_or(0, |w| sizing::scale_by_ratio(w, ratio_h, ratio_w));
+            } else if style.width.is_intrinsic_ratio() && !style.height.is_intrinsic_ratio() {
+                let known_h = match style.height {
+                    SizeSpec::Fit | SizeSpec::Auto => Some(content_h),
+                    SizeSpec::Pixel(h) => Some(h.saturating_sub(inset_h)),
+                    _ => None,
+                };
+                content_w = known_h.map_or(0, |h| sizing::scale_by_ratio(h, ratio_w, ratio_h));
+            }
         }
 
         // 3 - Determine Final Desired Size Based on Style
-        // `Fill` and `Percent` have 0 desired size in Pass 1. They expand in Pass 2.
+        // `Fill`, `Percent` and `PercentOfRemaining` have 0 desired size in Pass 1.
+        // They expand in Pass 2.
         let desired_w = match style.width {
             SizeSpec::Pixel(w) => w,
-            SizeSpec::Fit | SizeSpec::Auto => {
-                content_w + style.padding.left + style.padding.right + style.border.size * 2
+            // Tokens are resolved with the style, unknown ones stand for `Auto`
+            SizeSpec::Fit | SizeSpec::Auto | SizeSpec::IntrinsicRatio | SizeSpec::Token(_) => {
+                content_w + inset_w
             }
-            SizeSpec::Fill | SizeSpec::Percent(_) => 0,
+            SizeSpec::Fill | SizeSpec::Percent(_) | SizeSpec::PercentOfRemaining(_) => 0,
         };
 
         let desired_h = match style.height {
             SizeSpec::Pixel(h) => h,
-            SizeSpec::Fit | SizeSpec::Auto => {
-                content_h + style.padding.top + style.padding.bottom + style.border.size * 2
+            SizeSpec::Fit | SizeSpec::Auto | SizeSpec::IntrinsicRatio | SizeSpec::Token(_) => {
+                content_h + inset_h
             }
-            SizeSpec::Fill | SizeSpec::Percent(_) => 0,
+            SizeSpec::Fill | SizeSpec::Percent(_) | SizeSpec::PercentOfRemaining(_) => 0,
         };
 
         // 4 - Store Result in Space
-        if let Some(space) = self.spaces[capsule.space_ref].as_mut() {
+        if let Some(space) = self
+            .spaces
+            .get_mut(capsule.space_ref)
+            .and_then(|s| s.as_mut())
+        {
             space.width = Some(desired_w);
             space.height = Some(desired_h);
         }
@@ -1242,7 +3185,15 @@ impl Root {
         };
 
         // 3 - Print This Node's Info
-        eprintln!("{branch_str}Capsule({})", num_s.paint(cref_str));
+        if let Some(layer) = self.layers.get(&cref) {
+            eprintln!(
+                "{branch_str}Capsule({}) {}",
+                num_s.paint(cref_str),
+                field.paint(format!("layer={:?} order={}", layer.name, layer.order))
+            );
+        } else {
+            eprintln!("{branch_str}Capsule({})", num_s.paint(cref_str));
+        }
 
         let info_indent = dim.paint(format!("{continue_str}"));
 
@@ -1293,5 +3244,69 @@ impl Root {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use crate::geometry::Rect;
+    use crate::tokens::Token;
+
+    #[test]
+    fn resize_keeps_clean_frames_laid_out() {
+        let mut root = Root::new(800, 600);
+        let column = root.add_frame(None);
+        column.update_style(&mut root, |style| {
+            style.width = SizeSpec::Fill;
+            style.height = SizeSpec::Fill;
+            style.flow = Direction::Column;
+        });
+        let row = root.add_frame_child(&column, None);
+        row.update_style(&mut root, |style| {
+            style.width = SizeSpec::Fill;
+            style.height = SizeSpec::Pixel(50);
+        });
+        let child = root.add_frame_child(&row, None);
+        child.update_style(&mut root, |style| {
+            style.width = SizeSpec::Pixel(100);
+            style.height = SizeSpec::Percent(1.0);
+        });
+        root.compute();
+        let rect = Rect::new(0, 0, 100, 50);
+        assert_eq!(root.get_rect(child.get_ref()), Some(rect));
+
+        root.resize(900, 600);
+        root.compute();
+        assert_eq!(root.get_rect(child.get_ref()), Some(rect));
+        assert_eq!(root.hit_test_top(50, 25), Some(child.get_ref()));
+    }
+
+    #[test]
+    fn spacing_tokens_lay_out_again_when_set() {
+        let mut root = Root::new(800, 600);
+        root.set_token("spacing-m", 12);
+        let row = root.add_frame(None);
+        row.update_style(&mut root, |style| {
+            style.width = SizeSpec::Fill;
+            style.height = SizeSpec::Pixel(50);
+            style.tokens.padding = Some(Token::new("spacing-m"));
+            style.tokens.gap = Some(Token::named(&String::from("spacing-m")));
+        });
+        let first = root.add_frame_child(&row, None);
+        let second = root.add_frame_child(&row, None);
+        for child in [&first, &second] {
+            child.update_style(&mut root, |style| {
+                style.width = SizeSpec::Pixel(10);
+                style.height = SizeSpec::Pixel(10);
+            });
+        }
+        root.compute();
+        assert_eq!(
+            root.get_rect(second.get_ref()),
+            Some(Rect::new(34, 12, 10, 10))
+        );
+
+        root.set_token("spacing-m", 4);
+        root.compute();
+        assert_eq!(
+            root.get_rect(second.get_ref()),
+            Some(Rect::new(18, 4, 10, 10))
+        );
+    }
 }
@@ -1,18 +1,18 @@
 /// A convenient macro to create a style.
+/// Nested fields can be set together with a block.
 /// ```rust,ignore
-/// let s = make_style!({
+/// let s = make_style! {
 ///     background_color: clr!(RED),
 ///     width: size!(fill),
+///     border: { size: 2, color: clr!(BLACK) },
 ///     ...
-/// });
+/// };
 /// ```
 #[macro_export]
 macro_rules! make_style {
-    ($($field:ident : $value:expr),* $(,)?) => {{
+    ($($fields:tt)*) => {{
         let mut style = $crate::Style::default();
-        $(
-            style.$field = $value;
-        )*
+        $crate::__style_fields!(style; $($fields)*);
         style
     }};
 }
@@ -24,27 +24,51 @@ macro_rules! make_style {
 /// style!(frame1, &mut root, {
 ///     background_color: clr!(RED),
 ///     width: size!(fill),
+///     padding: { left: 4, right: 4 },
 ///     ...
 /// });
 /// ```
 #[macro_export]
 macro_rules! style {
     ($elem:expr, $root:expr, {
-    $($field:ident : $value:expr),* $(,)?
+    $($fields:tt)*
     }) => {{
         $elem.update_style($root, |style_mut| {
-            $(
-                style_mut.$field = $value;
-            )*
+            $crate::__style_fields!(style_mut; $($fields)*);
         });
     }};
 }
 
+/// Assigns the `field: value` list of the style macros to `$style`.
+/// A block of fields sets the fields of a nested struct. The fields are
+/// checked by `__style_field!`, derived with `Style`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __style_fields {
+    ($style:ident $(. $path:ident)*;) => {};
+    ($style:ident; $field:ident : { $sub:ident : $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__style_field!(@block $style; $field $field { $sub : $($inner)* });
+        $crate::__style_fields!($style; $($($rest)*)?);
+    };
+    ($style:ident $(. $path:ident)*; $field:ident : { $sub:ident : $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__style_fields!($style $(. $path)* . $field; $sub : $($inner)*);
+        $crate::__style_fields!($style $(. $path)*; $($($rest)*)?);
+    };
+    ($style:ident $(. $path:ident)*; $field:ident : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__style_field!($style $(. $path)*; $field $field = $value);
+        $crate::__style_fields!($style $(. $path)*; $($($rest)*)?);
+    };
+}
+
+#[doc(hidden)]
+pub use edl_macro::unknown_style_field;
+
 /// A concise way to specify size constraints for width or height.
 ///
 /// This macro supports several modes:
 /// * **Fixed pixels:** `size!(100 px)` or `size!(100)`
 /// * **Percentage:** `size!(50 %)` (Relative to parent content box)
+/// * **Percentage of remaining:** `size!(30 % rem)` (Relative to the space left by fixed-size siblings)
 /// * **Fill:** `size!(fill)` (Takes all remaining space, like `flex-grow: 1`)
 /// * **Fit:** `size!(fit)` (Shrinks to fit the content size)
 /// * **Auto:** `size!(auto)` (Natural size, usually equivalent to fit)
@@ -54,6 +78,8 @@ macro_rules! style {
 /// let w = size!(fill);
 /// let h = size!(50 px);
 /// let w2 = size!(25 %);
+/// let h2 = size!(30 % rem);
+/// let h3 = size!(ratio);
 /// ```
 #[macro_export]
 macro_rules! size {
@@ -66,6 +92,12 @@ macro_rules! size {
     (auto) => {
         $crate::sizing::SizeSpec::Auto
     };
+    (ratio) => {
+        $crate::sizing::SizeSpec::IntrinsicRatio
+    };
+    ($val:literal % rem) => {
+        $crate::sizing::SizeSpec::PercentOfRemaining($val as f32 / 100f32)
+    };
     ($val:literal %) => {
         $crate::sizing::SizeSpec::Percent($val as f32 / 100f32)
     };
@@ -129,19 +161,44 @@ macro_rules! flow {
 /// * `auto` - The element is part of the standard layout flow.
 /// * `x, y` - The element is removed from the flow and positioned relative
 ///   to the parent's top-left corner (content box).
+///   Each coordinate is either pixels (possibly negative) or a percentage
+///   of the parent's content box (`80 %`).
 ///
 /// # Examples
 /// ```rust,ignore
 /// pos!(auto);       // Standard flow
 /// pos!(10, 50);     // Fixed at x:10, y:50
+/// pos!(-4, -4);     // Overflowing the top-left corner
+/// pos!(80 %, 10);   // 80% across the parent, 10px down
 /// ```
 #[macro_export]
 macro_rules! pos {
     (auto) => {
         $crate::position::Position::Auto
     };
+    ($x:literal %, $y:literal %) => {
+        $crate::position::Position::Fixed {
+            x: $crate::position::Offset::Percent($x as f32 / 100f32),
+            y: $crate::position::Offset::Percent($y as f32 / 100f32),
+        }
+    };
+    ($x:literal %, $y:expr) => {
+        $crate::position::Position::Fixed {
+            x: $crate::position::Offset::Percent($x as f32 / 100f32),
+            y: $crate::position::Offset::from($y),
+        }
+    };
+    ($x:expr, $y:literal %) => {
+        $crate::position::Position::Fixed {
+            x: $crate::position::Offset::from($x),
+            y: $crate::position::Offset::Percent($y as f32 / 100f32),
+        }
+    };
     ($x:expr, $y:expr) => {
-        $crate::position::Position::Fixed { x: $x, y: $y }
+        $crate::position::Position::Fixed {
+            x: $crate::position::Offset::from($x),
+            y: $crate::position::Offset::from($y),
+        }
     };
 }
 
@@ -181,12 +238,7 @@ macro_rules! clr {
 #[macro_export]
 macro_rules! rgb {
     ($r:expr, $g:expr, $b:expr) => {
-        $crate::color::Color {
-            r: $r,
-            g: $g,
-            b: $b,
-            a: 255,
-        }
+        $crate::color::Color::new($r, $g, $b, 255)
     };
 }
 
@@ -201,12 +253,7 @@ macro_rules! rgb {
 #[macro_export]
 macro_rules! rgba {
     ($r:expr, $g:expr, $b:expr, $a:expr) => {
-        $crate::color::Color {
-            r: $r,
-            g: $g,
-            b: $b,
-            a: $a,
-        }
+        $crate::color::Color::new($r, $g, $b, $a)
     };
 }
 
@@ -417,6 +464,7 @@ macro_rules! justify {
 /// * `start` - Aligns items to the start of the cross axis (Top or Left).
 /// * `center` - Aligns items to the center of the cross axis.
 /// * `end` - Aligns items to the end of the cross axis (Bottom or Right).
+/// * `baseline` - Lines up the text baselines of the items of a row.
 #[macro_export]
 macro_rules! align {
     (start) => {
@@ -428,4 +476,7 @@ macro_rules! align {
     (end) => {
         $crate::position::AlignItems::End
     };
+    (baseline) => {
+        $crate::position::AlignItems::Baseline
+    };
 }
@@ -1,13 +1,125 @@
+/// What happens to the children of a frame extending past its content box
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// They are drawn as is
+    #[default]
+    Visible,
+    /// They are cut at the padding box
+    Hidden,
+    /// They are cut at the padding box and can be scrolled
+    /// into view (see `Root::set_scroll_offset`)
+    Scroll,
+    /// Like `Scroll`, with scrollbars that only show up while the children
+    /// do not fit. A shown scrollbar takes a gutter off the content box
+    /// (see `Root::scrollbars`).
+    Auto,
+}
+
+impl Overflow {
+    /// Whether the children can be scrolled into view
+    #[inline]
+    pub fn scrolls(&self) -> bool {
+        matches!(self, Overflow::Scroll | Overflow::Auto)
+    }
+}
+
+/// Whether a frame can be under the pointer, see `Root::hit_test`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEvents {
+    #[default]
+    Auto,
+    /// The frame and its children are never hit, the pointer reaches what
+    /// is below them whatever their `z_index` (decorative overlays...)
+    None,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Position {
-    Fixed {
-        x: u32,
-        y: u32,
-    },
+    /// Out of the flow, its top-left corner at `x, y` in the parent's
+    /// content box
+    Fixed { x: Offset, y: Offset },
+    /// In the flow, then moved by `dx, dy`. Its siblings are placed as if
+    /// it had not moved. Percentages are fractions of its own size.
+    Relative { dx: Offset, dy: Offset },
+    /// Out of the flow, anchored to the edges of the parent's content box
+    /// it has an inset for, see `Insets`
+    Absolute(Insets),
     #[default]
     Auto,
 }
 
+impl Position {
+    /// Whether the element takes part in its parent's flow: it is placed
+    /// after its previous siblings and counts in the parent's `Fit` size
+    #[inline]
+    pub fn in_flow(&self) -> bool {
+        matches!(self, Position::Auto | Position::Relative { .. })
+    }
+}
+
+/// Distances of a `Position::Absolute` element from the edges of its
+/// parent's content box. Percentages are fractions of the content box
+/// size on the same axis.
+///
+/// An element with neither inset on an axis sits at the start of it. With
+/// both, an `Auto` size stretches from one inset to the other, and other
+/// sizes stay at the start inset.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Insets {
+    pub top: Option<Offset>,
+    pub right: Option<Offset>,
+    pub bottom: Option<Offset>,
+    pub left: Option<Offset>,
+}
+
+impl Insets {
+    /// The same inset from every edge
+    pub fn all(inset: impl Into<Offset>) -> Self {
+        let inset = Some(inset.into());
+        Self {
+            top: inset,
+            right: inset,
+            bottom: inset,
+            left: inset,
+        }
+    }
+}
+
+/// A coordinate of a `Position::Fixed` element, relative to the
+/// parent's content box, an inset of a `Position::Absolute` one or the
+/// shift of a `Position::Relative` one. Negative values go past the
+/// top/left edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offset {
+    /// A distance in pixels
+    Pixel(i32),
+    /// A fraction of the parent's content box size on the same axis.
+    /// 0.0 is the top/left edge, 1.0 the bottom/right one.
+    Percent(f32),
+}
+
+impl Default for Offset {
+    fn default() -> Self {
+        Offset::Pixel(0)
+    }
+}
+
+impl From<i32> for Offset {
+    fn from(px: i32) -> Self {
+        Offset::Pixel(px)
+    }
+}
+
+impl Offset {
+    #[inline]
+    pub(crate) fn resolve(&self, parent_value: f32) -> f32 {
+        match self {
+            Offset::Pixel(px) => *px as f32,
+            Offset::Percent(pct) => pct * parent_value,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum Direction {
     #[default]
@@ -24,21 +136,95 @@ pub enum LayoutStrategy {
     Grid,
 }
 
+/// Where the free space of a flex frame goes along its `flow`. Only
+/// applies while no in-flow child grows and the children do not overflow.
+/// The children out of the flow (see `Position::in_flow`: `Fixed` and
+/// `Absolute` ones) are left out.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum JustifyContent {
     #[default]
     Start,
     Center,
     End,
+    /// Between the children, none before the first or after the last
     SpaceBetween,
+    /// Around each child: edges get half the gap between two children
     SpaceAround,
+    /// Equal gaps between the children and at both edges
     SpaceEvenly,
 }
 
+/// Where each child of a flex frame sits across its `flow`, margins
+/// included. Children sized in percent fill the cross axis anyway.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum AlignItems {
     #[default]
     Start,
     Center,
     End,
+    /// In rows, the first baselines of the children line up (see
+    /// `Root::set_baseline`). Children without one line up their bottom
+    /// border edge instead. Columns place their children at the start.
+    Baseline,
+}
+
+/// Arithmetic used by the flex layout when distributing free space.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Grow/shrink and justify spacing are computed with `f32` ratios.
+    #[default]
+    Float,
+    /// Grow/shrink and justify spacing are computed in fixed-point and
+    /// leftover pixels are handed out with the largest-remainder method.
+    /// Results are stable across resizes and platforms.
+    Deterministic,
+}
+
+/// Rounding applied to a subpixel edge coordinate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round to the nearest pixel
+    #[default]
+    Round,
+    /// Round towards the top/left of the screen
+    Floor,
+    /// Round towards the bottom/right of the screen
+    Ceil,
+}
+
+impl Rounding {
+    #[inline]
+    pub fn apply(&self, value: f32) -> i32 {
+        match self {
+            Rounding::Round => value.round() as i32,
+            Rounding::Floor => value.floor() as i32,
+            Rounding::Ceil => value.ceil() as i32,
+        }
+    }
+}
+
+/// How the subpixel layout is snapped to whole pixels.
+///
+/// Each edge of a box is snapped independently, and the snapped size is
+/// derived from the snapped edges. Adjacent boxes sharing an edge therefore
+/// never overlap or leave a 1px seam as long as the facing edges use the
+/// same rounding.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PixelSnapping {
+    pub left: Rounding,
+    pub top: Rounding,
+    pub right: Rounding,
+    pub bottom: Rounding,
+}
+
+impl PixelSnapping {
+    /// Use the same rounding on every edge
+    pub fn all(rounding: Rounding) -> Self {
+        Self {
+            left: rounding,
+            top: rounding,
+            right: rounding,
+            bottom: rounding,
+        }
+    }
 }
@@ -0,0 +1,56 @@
+use crate::geometry::Rect;
+
+/// A scrollbar shown by an `Overflow::Auto` frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scrollbar {
+    /// Gutter the scrollbar takes, between the border and the padding
+    pub track: Rect,
+    /// Part of the track standing for the visible content
+    pub thumb: Rect,
+}
+
+/// Scrollbars of a frame, see `Root::scrollbars`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Scrollbars {
+    pub vertical: Option<Scrollbar>,
+    pub horizontal: Option<Scrollbar>,
+}
+
+/// Gutters an `Overflow::Auto` frame reserves, decided between layout rounds
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Gutters {
+    pub vertical: bool,
+    pub horizontal: bool,
+}
+
+impl Gutters {
+    pub(crate) fn union(self, other: Self) -> Self {
+        Self {
+            vertical: self.vertical || other.vertical,
+            horizontal: self.horizontal || other.horizontal,
+        }
+    }
+}
+
+/// Thumb of a track of `track_len`, for a viewport of `view` over content
+/// of `extent` scrolled by `offset`. Returns its start in the track and its
+/// length, never shorter than `min_len`.
+pub(crate) fn thumb_span(
+    track_len: u32,
+    view: f32,
+    extent: f32,
+    offset: f32,
+    min_len: u32,
+) -> (u32, u32) {
+    let extent = extent.max(view).max(1.0);
+    let len = ((track_len as f32 * view / extent).round() as u32)
+        .max(min_len)
+        .min(track_len);
+    let max_offset = extent - view;
+    let start = if max_offset > 0.0 {
+        ((track_len - len) as f32 * (offset / max_offset).clamp(0.0, 1.0)).round() as u32
+    } else {
+        0
+    };
+    (start, len)
+}
@@ -1,4 +1,4 @@
-use crate::color::Color;
+use crate::{color::Color, tokens::Token};
 
 /// Define dimension specification for a given element.
 /// These specification can either be dynamic or fixed.
@@ -17,6 +17,19 @@ pub enum SizeSpec {
     /// **percent**, a value starting by 0..1 - 0.0 being 0% and 1.0 is 100%.
     /// It takes the size of the parent and multiplies it by the defined scalar
     Percent(f32),
+    /// **percent of remaining**, a value starting by 0..1 like `Percent`.
+    /// Along the parent's main axis, it is resolved against the space left once
+    /// the fixed-size in-flow siblings (and gaps) are placed.
+    /// Anywhere else, it behaves like `Percent`.
+    PercentOfRemaining(f32),
+    /// **intrinsic ratio**, derives this side from the other one, keeping
+    /// the ratio of the frame's content (its content provider or
+    /// `intrinsic_width/height`). An image at `Percent(1.0)` wide gets its
+    /// height from its width. Without a known ratio, it behaves like `Fit`.
+    IntrinsicRatio,
+    /// **token**, the size held by a design token (see `Root::set_token`).
+    /// Unknown tokens behave like `Auto`.
+    Token(Token),
     #[default]
     /// **auto**, this element is sized-awared of its neighbors
     Auto,
@@ -40,17 +53,20 @@ impl std::fmt::Debug for SizeSpec {
             SizeSpec::Auto => write!(f, "auto"),
             SizeSpec::Pixel(px) => write!(f, "{}px", px),
             SizeSpec::Percent(p) => write!(f, "{}%", p * 100.0),
+            SizeSpec::PercentOfRemaining(p) => write!(f, "{}% rem", p * 100.0),
+            SizeSpec::IntrinsicRatio => write!(f, "ratio"),
+            SizeSpec::Token(token) => write!(f, "token({token:?})"),
         }
     }
 }
 
 impl SizeSpec {
-    pub(crate) fn resolve_size(&self, parent_value: u32) -> Option<u32> {
+    pub(crate) fn resolve_size(&self, parent_value: f32) -> Option<f32> {
         match self {
-            SizeSpec::Pixel(px) => Some(*px),
-            SizeSpec::Percent(pct) => Some((*pct * parent_value as f32) as u32),
+            SizeSpec::Pixel(px) => Some(*px as f32),
+            SizeSpec::Percent(pct) | SizeSpec::PercentOfRemaining(pct) => Some(*pct * parent_value),
             SizeSpec::Fill => Some(parent_value),
-            SizeSpec::Fit | SizeSpec::Auto => None,
+            SizeSpec::Fit | SizeSpec::Auto | SizeSpec::IntrinsicRatio | SizeSpec::Token(_) => None,
         }
     }
 
@@ -67,7 +83,7 @@ impl SizeSpec {
 
     pub fn percent(&self) -> f32 {
         match &self {
-            SizeSpec::Percent(e) => *e,
+            SizeSpec::Percent(e) | SizeSpec::PercentOfRemaining(e) => *e,
             _ => 0.0,
         }
     }
@@ -102,6 +118,21 @@ impl SizeSpec {
             _ => false,
         }
     }
+
+    #[inline]
+    pub fn is_percent_of_remaining(&self) -> bool {
+        matches!(self, SizeSpec::PercentOfRemaining(_))
+    }
+
+    #[inline]
+    pub fn is_intrinsic_ratio(&self) -> bool {
+        *self == SizeSpec::IntrinsicRatio
+    }
+}
+
+/// `value * numerator / denominator`, for `SizeSpec::IntrinsicRatio`
+pub(crate) fn scale_by_ratio(value: u32, numerator: u32, denominator: u32) -> u32 {
+    (value as u64 * numerator as u64 / denominator.max(1) as u64) as u32
 }
 
 // impl Default for SizeSpec {
@@ -111,7 +142,7 @@ impl SizeSpec {
 // }
 macro_rules! dimensioner {
     ($for:ident, $display: literal) => {
-        #[derive(Debug, Default, Clone, Copy)]
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
         pub struct $for {
             pub left: u32,
             pub right: u32,
@@ -159,6 +190,30 @@ macro_rules! dimensioner {
 dimensioner!(Padding, "Pad");
 dimensioner!(Margin, "Mar");
 
+/// How much larger than its style says a frame is laid out, see
+/// `Root::set_enlargement`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Enlargement {
+    /// Smallest width and height of the sides with a fixed size
+    pub min_size: u32,
+    /// Added to the padding of the style
+    pub extra_padding: Padding,
+}
+
+impl Enlargement {
+    pub(crate) fn apply(&self, width: &mut SizeSpec, height: &mut SizeSpec, padding: &mut Padding) {
+        for side in [width, height] {
+            if let SizeSpec::Pixel(px) = side {
+                *px = (*px).max(self.min_size);
+            }
+        }
+        padding.left += self.extra_padding.left;
+        padding.right += self.extra_padding.right;
+        padding.top += self.extra_padding.top;
+        padding.bottom += self.extra_padding.bottom;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Border {
     pub size: u32,
@@ -0,0 +1,171 @@
+//! Read-only copies of a laid out tree, see `Root::snapshot`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{CapsuleRef, Layer, Root, Space, Style, geometry::Rect};
+
+/// A frame as it was when the snapshot was taken
+#[derive(Debug, Clone)]
+pub struct SnapshotFrame {
+    pub parent: Option<CapsuleRef>,
+    pub children: Vec<CapsuleRef>,
+    /// Style with its tokens resolved
+    pub style: Style,
+    /// Computed space, `None` if the frame was never laid out
+    pub space: Option<Space>,
+    /// See `Root::get_content_rect`
+    pub content_rect: Option<Rect>,
+    /// See `Root::clip_rect`
+    pub clip: Option<Rect>,
+    pub scroll_offset: (f32, f32),
+    /// See `Root::layer_order`
+    pub layer_order: i32,
+}
+
+#[derive(Debug)]
+struct SnapshotData {
+    frames: HashMap<CapsuleRef, SnapshotFrame>,
+    /// Top-level frames, bottom-most layer first
+    top_level: Vec<CapsuleRef>,
+    layers: HashMap<CapsuleRef, Layer>,
+    size: (u32, u32),
+}
+
+/// Immutable copy of the frame hierarchy, styles and computed spaces of a
+/// `Root`. Clones share the same data, and it can be sent to other threads
+/// to be read while the tree keeps changing.
+#[derive(Debug, Clone)]
+pub struct LayoutSnapshot(Arc<SnapshotData>);
+
+impl Root {
+    /// Copies the tree as of the last `compute`, for readers on other
+    /// threads. Costs a walk of the whole tree: take one per frame at most.
+    pub fn snapshot(&self) -> LayoutSnapshot {
+        let mut frames = HashMap::with_capacity(self.capsules.len());
+        let mut top_level = Vec::new();
+
+        for (id, slot) in self.capsules.iter().enumerate() {
+            let Some(capsule) = &slot.capsule else {
+                continue;
+            };
+            let frame_ref = CapsuleRef {
+                id,
+                generation: slot.generation,
+            };
+            let Some(style) = self.style_of(capsule) else {
+                continue;
+            };
+            if capsule.parent_ref.is_none() {
+                top_level.push(frame_ref);
+            }
+
+            frames.insert(
+                frame_ref,
+                SnapshotFrame {
+                    parent: capsule.parent_ref,
+                    children: capsule.children.clone(),
+                    style,
+                    space: self.space_of(capsule).copied(),
+                    content_rect: self.get_content_rect(frame_ref),
+                    clip: self.clip_rect(frame_ref),
+                    scroll_offset: self.scroll_offset(frame_ref),
+                    layer_order: self.layer_order(frame_ref),
+                },
+            );
+        }
+
+        top_level.sort_by_key(|frame_ref| (self.layer_order(*frame_ref), *frame_ref));
+
+        LayoutSnapshot(Arc::new(SnapshotData {
+            frames,
+            top_level,
+            layers: self.layers.clone(),
+            size: self.size(),
+        }))
+    }
+}
+
+impl LayoutSnapshot {
+    /// Size the top-level frames were laid out in (the window)
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.0.size
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.frames.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.frames.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, frame_ref: CapsuleRef) -> Option<&SnapshotFrame> {
+        self.0.frames.get(&frame_ref)
+    }
+
+    /// Every frame, in no particular order
+    pub fn frames(&self) -> impl Iterator<Item = (CapsuleRef, &SnapshotFrame)> {
+        self.0
+            .frames
+            .iter()
+            .map(|(frame_ref, frame)| (*frame_ref, frame))
+    }
+
+    /// Top-level frames (layers included), from the bottom-most to the
+    /// top-most
+    #[inline]
+    pub fn top_level(&self) -> &[CapsuleRef] {
+        &self.0.top_level
+    }
+
+    /// Layer of a top-level frame, if it is one
+    #[inline]
+    pub fn layer(&self, frame_ref: CapsuleRef) -> Option<&Layer> {
+        self.0.layers.get(&frame_ref)
+    }
+
+    pub fn children(&self, frame_ref: CapsuleRef) -> &[CapsuleRef] {
+        self.get(frame_ref)
+            .map_or(&[], |frame| frame.children.as_slice())
+    }
+
+    pub fn get_style(&self, frame_ref: CapsuleRef) -> Option<Style> {
+        self.get(frame_ref).map(|frame| frame.style)
+    }
+
+    pub fn get_space(&self, frame_ref: CapsuleRef) -> Option<Space> {
+        self.get(frame_ref)?.space
+    }
+
+    pub fn get_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        self.get_space(frame_ref).map(|space| space.rect())
+    }
+
+    pub fn get_content_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        self.get(frame_ref)?.content_rect
+    }
+
+    pub fn clip_rect(&self, frame_ref: CapsuleRef) -> Option<Rect> {
+        self.get(frame_ref)?.clip
+    }
+
+    /// A frame and its descendants, depth first with parents before their
+    /// children
+    pub fn descendants(&self, frame_ref: CapsuleRef) -> Vec<CapsuleRef> {
+        let mut frames = Vec::new();
+        let mut stack = vec![frame_ref];
+        while let Some(current) = stack.pop() {
+            if self.get(current).is_none() {
+                continue;
+            }
+            frames.push(current);
+            stack.extend(self.children(current).iter().rev());
+        }
+        frames
+    }
+}
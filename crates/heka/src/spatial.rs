@@ -0,0 +1,125 @@
+//! Uniform grid over the laid out frames, so the frames at a point or in an
+//! area are found without going through every frame of the tree.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{CapsuleRef, geometry::Rect};
+
+/// Side of a grid cell, in pixels
+const CELL_SIZE: i32 = 128;
+
+/// Frames spanning more cells than this are kept aside and always checked,
+/// rather than registered in every cell they cover (scroll contents...)
+const MAX_CELLS: i64 = 256;
+
+#[derive(Debug, Default)]
+pub(crate) struct SpatialIndex {
+    cells: HashMap<(i32, i32), Vec<CapsuleRef>>,
+    large: HashSet<CapsuleRef>,
+    rects: HashMap<CapsuleRef, Rect>,
+}
+
+/// Range of cells a rectangle covers along one axis
+fn cell_span(start: i32, len: u32) -> (i32, i32) {
+    let end = start.saturating_add_unsigned(len.max(1)) - 1;
+    (start.div_euclid(CELL_SIZE), end.div_euclid(CELL_SIZE))
+}
+
+fn cells_of(rect: Rect) -> (std::ops::RangeInclusive<i32>, std::ops::RangeInclusive<i32>) {
+    let (x0, x1) = cell_span(rect.x, rect.w);
+    let (y0, y1) = cell_span(rect.y, rect.h);
+    (x0..=x1, y0..=y1)
+}
+
+impl SpatialIndex {
+    /// Moves a frame to where it was laid out
+    pub(crate) fn insert(&mut self, frame_ref: CapsuleRef, rect: Rect) {
+        if self.rects.get(&frame_ref) == Some(&rect) {
+            return;
+        }
+        self.remove(frame_ref);
+        if rect.is_empty() {
+            return;
+        }
+
+        let (columns, rows) = cells_of(rect);
+        let count =
+            (columns.end() - columns.start() + 1) as i64 * (rows.end() - rows.start() + 1) as i64;
+        if count > MAX_CELLS {
+            self.large.insert(frame_ref);
+        } else {
+            for row in rows {
+                for column in columns.clone() {
+                    self.cells.entry((column, row)).or_default().push(frame_ref);
+                }
+            }
+        }
+        self.rects.insert(frame_ref, rect);
+    }
+
+    pub(crate) fn remove(&mut self, frame_ref: CapsuleRef) {
+        let Some(rect) = self.rects.remove(&frame_ref) else {
+            return;
+        };
+        if self.large.remove(&frame_ref) {
+            return;
+        }
+
+        let (columns, rows) = cells_of(rect);
+        for row in rows {
+            for column in columns.clone() {
+                if let Some(cell) = self.cells.get_mut(&(column, row)) {
+                    cell.retain(|cref| *cref != frame_ref);
+                    if cell.is_empty() {
+                        self.cells.remove(&(column, row));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Frames whose rectangle contains the point
+    pub(crate) fn at(&self, x: i32, y: i32) -> Vec<CapsuleRef> {
+        let cell = (x.div_euclid(CELL_SIZE), y.div_euclid(CELL_SIZE));
+        self.cells
+            .get(&cell)
+            .into_iter()
+            .flatten()
+            .chain(&self.large)
+            .copied()
+            .filter(|cref| self.rects.get(cref).is_some_and(|rect| rect.contains(x, y)))
+            .collect()
+    }
+
+    /// Frames whose rectangle intersects `area`
+    pub(crate) fn within(&self, area: Rect) -> Vec<CapsuleRef> {
+        if area.is_empty() {
+            return Vec::new();
+        }
+
+        let (columns, rows) = cells_of(area);
+        let mut found: HashSet<CapsuleRef> = self
+            .large
+            .iter()
+            .copied()
+            .filter(|cref| {
+                self.rects
+                    .get(cref)
+                    .is_some_and(|rect| rect.intersects(&area))
+            })
+            .collect();
+        for row in rows {
+            for column in columns.clone() {
+                let Some(cell) = self.cells.get(&(column, row)) else {
+                    continue;
+                };
+                found.extend(cell.iter().filter(|cref| {
+                    self.rects
+                        .get(cref)
+                        .is_some_and(|rect| rect.intersects(&area))
+                }));
+            }
+        }
+        found.into_iter().collect()
+    }
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+/// What the last `Root::compute` call did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutStats {
+    /// Nodes measured in pass 1 (cached sizes are not counted)
+    pub nodes_measured: usize,
+    /// Nodes given a new position/size in pass 2
+    pub nodes_arranged: usize,
+    /// Wall time spent in `compute`
+    pub duration: Duration,
+    /// Deepest tree level arranged, top-level frames being at depth 1.
+    /// 0 when nothing was arranged.
+    pub deepest_depth: usize,
+    /// Dirty subtrees laid out on their own, without their ancestors
+    /// (see `Style::sized_by_parent`)
+    pub subtrees: usize,
+    /// Measure/layout rounds run, see `Root::set_max_layout_rounds`.
+    /// Nodes handled again in later rounds are counted again.
+    pub rounds: usize,
+    /// Frames that had not settled when the rounds stopped
+    pub unstable_frames: usize,
+}
+
+/// Work done by the layout hot paths, summed over the `compute` calls
+/// since the `Root` was created or `Root::reset_profile`. Divided by
+/// `computes`, it gives what an average compute does on a real tree.
+#[cfg(feature = "profiling")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutProfile {
+    /// `compute` calls with something dirty to lay out
+    pub computes: usize,
+    /// Frames measured in pass 1
+    pub measured: usize,
+    /// Frames whose measured size was reused, along with their subtree
+    pub measure_cache_hits: usize,
+    /// Clean frames measured anyway, the width they are measured within
+    /// changed
+    pub measure_cache_misses: usize,
+    /// Calls to the `measure` of content providers
+    pub content_measures: usize,
+    /// Frames arranged in pass 2
+    pub arranged: usize,
+    /// Clean frames left in place in pass 2, along with their subtree
+    pub arrange_skips: usize,
+    /// Dirty frames when the computes started, summed
+    pub dirty_frames: usize,
+    /// Most dirty frames a single compute started with
+    pub max_dirty_frames: usize,
+}
@@ -0,0 +1,218 @@
+//! Design tokens: named sizes and colors styles can refer to, see
+//! `Root::set_token`.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use crate::{
+    Style,
+    color::Color,
+    sizing::{Margin, Padding, SizeSpec},
+};
+
+/// Tokens referring to other tokens are followed this many times at most
+const MAX_TOKEN_DEPTH: usize = 8;
+
+/// Name of a design token, see `Root::set_token`
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Token(&'static str);
+
+impl Token {
+    pub const fn new(name: &'static str) -> Self {
+        Token(name)
+    }
+
+    /// A token whose name is only known at runtime, like one loaded from a
+    /// theme file. Each distinct name is kept until the program exits.
+    pub fn named(name: &str) -> Self {
+        static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+        let mut names = NAMES
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match names.get(name) {
+            Some(kept) => Token(kept),
+            None => {
+                let kept: &'static str = Box::leak(name.into());
+                names.insert(kept);
+                Token(kept)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl From<&'static str> for Token {
+    fn from(name: &'static str) -> Self {
+        Token(name)
+    }
+}
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Design tokens the fields of a style take their value from, instead of
+/// the value set on the style. Unknown colors (or color tokens holding a
+/// size) are transparent, spacing tokens not holding a pixel size give 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StyleTokens {
+    pub background: Option<Token>,
+    pub border: Option<Token>,
+    pub shadow: Option<Token>,
+    /// All four sides of the padding
+    pub padding: Option<Token>,
+    /// All four sides of the margin
+    pub margin: Option<Token>,
+    pub gap: Option<Token>,
+}
+
+/// Value of a design token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenValue {
+    Size(SizeSpec),
+    Color(Color),
+}
+
+impl From<u32> for TokenValue {
+    fn from(px: u32) -> Self {
+        TokenValue::Size(SizeSpec::Pixel(px))
+    }
+}
+
+impl From<SizeSpec> for TokenValue {
+    fn from(size: SizeSpec) -> Self {
+        TokenValue::Size(size)
+    }
+}
+
+impl From<Color> for TokenValue {
+    fn from(color: Color) -> Self {
+        TokenValue::Color(color)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Tokens {
+    values: HashMap<String, TokenValue>,
+}
+
+impl Tokens {
+    pub(crate) fn get(&self, name: &str) -> Option<TokenValue> {
+        self.values.get(name).copied()
+    }
+
+    /// Returns whether the value changed
+    pub(crate) fn set(&mut self, name: String, value: TokenValue) -> bool {
+        self.values.insert(name, value) != Some(value)
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// Size a token refers to. Unknown tokens (or tokens holding a color)
+    /// give `SizeSpec::Auto`.
+    pub(crate) fn size(&self, mut size: SizeSpec) -> SizeSpec {
+        for _ in 0..MAX_TOKEN_DEPTH {
+            let SizeSpec::Token(name) = size else {
+                return size;
+            };
+            size = match self.get(name.name()) {
+                Some(TokenValue::Size(value)) => value,
+                _ => return SizeSpec::Auto,
+            };
+        }
+        SizeSpec::Auto
+    }
+
+    /// Color of a token, `color` without one. Unknown tokens (or tokens
+    /// holding a size) give `Color::transparent`.
+    pub(crate) fn color(&self, token: Option<Token>, color: Color) -> Color {
+        let Some(token) = token else {
+            return color;
+        };
+        match self.get(token.name()) {
+            Some(TokenValue::Color(value)) => value,
+            _ => Color::transparent,
+        }
+    }
+
+    /// Pixels of a spacing token, `px` without one. Tokens not holding a
+    /// pixel size give 0.
+    fn pixels(&self, token: Option<Token>, px: u32) -> u32 {
+        let Some(token) = token else {
+            return px;
+        };
+        match self.size(SizeSpec::Token(token)) {
+            SizeSpec::Pixel(value) => value,
+            _ => 0,
+        }
+    }
+
+    /// Whether `token` is `name`, or refers to it through other tokens
+    fn depends_on(&self, token: Token, name: &str) -> bool {
+        let mut token = token.name();
+        for _ in 0..MAX_TOKEN_DEPTH {
+            if token == name {
+                return true;
+            }
+            token = match self.get(token) {
+                Some(TokenValue::Size(SizeSpec::Token(next))) => next.name(),
+                _ => return false,
+            };
+        }
+        false
+    }
+
+    /// Replaces the tokens of a style by their values
+    pub(crate) fn resolve(&self, style: &mut Style) {
+        if style_tokens(style).next().is_none() {
+            return;
+        }
+        style.width = self.size(style.width);
+        style.height = self.size(style.height);
+        let tokens = style.tokens;
+        style.background_color = self.color(tokens.background, style.background_color);
+        style.border.color = self.color(tokens.border, style.border.color);
+        style.shadow.color = self.color(tokens.shadow, style.shadow.color);
+        if tokens.padding.is_some() {
+            style.padding = Padding::all(self.pixels(tokens.padding, 0));
+        }
+        if tokens.margin.is_some() {
+            style.margin = Margin::all(self.pixels(tokens.margin, 0));
+        }
+        style.gap = self.pixels(tokens.gap, style.gap);
+    }
+
+    /// Whether a style changes with the token `name`
+    pub(crate) fn is_used_by(&self, style: &Style, name: &str) -> bool {
+        style_tokens(style).any(|token| self.depends_on(token, name))
+    }
+}
+
+/// Tokens a style refers to
+fn style_tokens(style: &Style) -> impl Iterator<Item = Token> {
+    let size = |spec: SizeSpec| match spec {
+        SizeSpec::Token(name) => Some(name),
+        _ => None,
+    };
+    [
+        size(style.width),
+        size(style.height),
+        style.tokens.background,
+        style.tokens.border,
+        style.tokens.shadow,
+        style.tokens.padding,
+        style.tokens.margin,
+        style.tokens.gap,
+    ]
+    .into_iter()
+    .flatten()
+}